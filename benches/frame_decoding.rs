@@ -0,0 +1,79 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::path::{Path, PathBuf};
+use timsrust::{
+    readers::{FrameReader, MetadataReader, TdfBlob},
+    writers::TdfWriter,
+    Frame,
+};
+
+const FRAME_COUNT: usize = 20;
+const SCAN_COUNT: usize = 1000;
+const PEAKS_PER_SCAN: usize = 100;
+
+/// Write a run of dense type 2 frames, so that decoding the binary data
+/// dominates the time to read a frame.
+fn write_synthetic_run() -> PathBuf {
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test.d");
+    let global_metadata = MetadataReader::new(&source).unwrap().global_metadata;
+    let run = std::env::temp_dir().join("timsrust_frame_decoding_bench.d");
+    let _ = std::fs::remove_dir_all(&run);
+    let mut writer = TdfWriter::create(&run, &global_metadata).unwrap();
+    for index in 0..FRAME_COUNT {
+        let scan_offsets: Vec<usize> =
+            (0..=SCAN_COUNT).map(|scan| scan * PEAKS_PER_SCAN).collect();
+        let tof_indices: Vec<u32> = (0..SCAN_COUNT * PEAKS_PER_SCAN)
+            .map(|peak| (peak % PEAKS_PER_SCAN * 997 + index) as u32)
+            .collect();
+        let intensities: Vec<u32> = (0..SCAN_COUNT * PEAKS_PER_SCAN)
+            .map(|peak| (peak * 7919 % 100_000) as u32)
+            .collect();
+        let frame = Frame {
+            scan_offsets,
+            tof_indices,
+            intensities,
+            index: index + 1,
+            rt_in_seconds: index as f64,
+            ..Default::default()
+        };
+        writer.write_frame(&frame).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+/// The values of a dense frame, with their bytes in four planes as in the
+/// decompressed data of a type 2 blob.
+fn synthetic_blob() -> TdfBlob {
+    let values: Vec<u32> = (0..SCAN_COUNT * PEAKS_PER_SCAN)
+        .map(|peak| (peak * 7919 % 100_000) as u32)
+        .collect();
+    let bytes = (0..4)
+        .flat_map(|plane| values.iter().map(move |v| v.to_le_bytes()[plane]))
+        .collect();
+    TdfBlob::new(bytes).unwrap()
+}
+
+fn criterion_benchmark_frame_decoding(c: &mut Criterion) {
+    let run = write_synthetic_run();
+    let frame_reader = FrameReader::new(&run).unwrap();
+    let mut group = c.benchmark_group("frame-decoding");
+    group.sample_size(20);
+    group.bench_function("decode dense type 2 frame", |b| {
+        b.iter(|| frame_reader.get(black_box(0)).unwrap())
+    });
+    let blob = synthetic_blob();
+    group.bench_function("decode dense type 2 blob", |b| {
+        b.iter(|| black_box(&blob).decode())
+    });
+    group.bench_function("decode dense type 2 blob per value", |b| {
+        b.iter(|| {
+            let blob = black_box(&blob);
+            (0..blob.len())
+                .map(|index| blob.get(index).unwrap())
+                .collect::<Vec<u32>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark_frame_decoding);
+criterion_main!(benches);
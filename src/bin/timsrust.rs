@@ -0,0 +1,374 @@
+//! Command line interface to summarize, convert and subset timsTOF runs.
+//!
+//! All subcommands are thin wrappers around the library:
+//!
+//! * `info`: a summary of the metadata and frames of a run
+//! * `convert`: see [timsrust::convert]
+//! * `xic`: extracted ion chromatograms of a list of targets
+//! * `subset`: see [timsrust::writers::RunSubsetter]
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+use timsrust::{
+    convert::{
+        to_imzml, to_mgf, to_mzml, to_parquet, ImzMLOptions, MgfOptions,
+        MzMLOptions, ParquetOptions,
+    },
+    processing::chromatogram::{XicExtractor, XicTarget},
+    readers::{FrameReader, MetadataReader, TimsTofPath},
+    writers::RunSubsetter,
+    MSLevel,
+};
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+fn cli() -> Command {
+    let run = || {
+        Arg::new("run")
+            .required(true)
+            .value_parser(value_parser!(PathBuf))
+            .help("The .d folder (or analysis.tdf) of the run")
+    };
+    let output = || {
+        Arg::new("output")
+            .short('o')
+            .long("output")
+            .value_parser(value_parser!(PathBuf))
+    };
+    let ms_level = || {
+        Arg::new("ms-level")
+            .long("ms-level")
+            .value_parser(["1", "2"])
+            .help("Only use frames of this MS level")
+    };
+    Command::new("timsrust")
+        .about("Read, convert and subset Bruker timsTOF data")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("info")
+                .about("Summarize the metadata and frames of a run")
+                .arg(run()),
+        )
+        .subcommand(
+            Command::new("convert")
+                .about("Convert a run to mzML, MGF, imzML or Parquet")
+                .arg(run())
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .required(true)
+                        .value_parser(["mzml", "mgf", "imzml", "parquet"]),
+                )
+                .arg(output().help(
+                    "Defaults to the run path with the extension of the format",
+                ))
+                .arg(ms_level()),
+        )
+        .subcommand(
+            Command::new("xic")
+                .about("Extract ion chromatograms of the targets in a TSV file")
+                .long_about(
+                    "Extract ion chromatograms of the targets in a TSV file.\n\n\
+                     The file needs a header with an `mz` column. Optional \
+                     columns are `id`, `tolerance_ppm`, `rt_start`, `rt_end`, \
+                     `im_start`, `im_end`, `ms_level` and `isolation_mz` \
+                     (for MS2 targets). Chromatograms are \
+                     written as TSV with the columns `id`, `rt` and \
+                     `intensity`.",
+                )
+                .arg(run())
+                .arg(
+                    Arg::new("targets")
+                        .long("targets")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("tolerance-ppm")
+                        .long("tolerance-ppm")
+                        .default_value("20")
+                        .value_parser(value_parser!(f64))
+                        .help("For targets without a `tolerance_ppm`"),
+                )
+                .arg(output().help("Defaults to stdout")),
+        )
+        .subcommand(
+            Command::new("subset")
+                .about("Copy a subset of the frames of a run to a new run")
+                .arg(run())
+                .arg(
+                    Arg::new("output")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("The .d folder to write, which may not exist"),
+                )
+                .arg(
+                    Arg::new("rt-start")
+                        .long("rt-start")
+                        .value_parser(value_parser!(f64))
+                        .help("Retention time in seconds (inclusive)"),
+                )
+                .arg(
+                    Arg::new("rt-end")
+                        .long("rt-end")
+                        .value_parser(value_parser!(f64))
+                        .help("Retention time in seconds (exclusive)"),
+                )
+                .arg(ms_level())
+                .arg(
+                    Arg::new("maldi-region")
+                        .long("maldi-region")
+                        .help("Only MALDI frames of this region (e.g. R00)"),
+                ),
+        )
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    let result = match matches.subcommand() {
+        Some(("info", args)) => info(args),
+        Some(("convert", args)) => convert(args),
+        Some(("xic", args)) => xic(args),
+        Some(("subset", args)) => subset(args),
+        _ => unreachable!("A subcommand is required"),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run_path(args: &ArgMatches) -> &PathBuf {
+    args.get_one::<PathBuf>("run").expect("The run is required")
+}
+
+fn ms_level(args: &ArgMatches) -> Option<MSLevel> {
+    args.get_one::<String>("ms-level")
+        .map(|level| match level.as_str() {
+            "1" => MSLevel::MS1,
+            _ => MSLevel::MS2,
+        })
+}
+
+fn info(args: &ArgMatches) -> CliResult {
+    let path = TimsTofPath::new(run_path(args))?;
+    let metadata = MetadataReader::new(&path)?;
+    let reader = FrameReader::new(&path)?;
+    let mut ms1_frames = 0;
+    let mut ms2_frames = 0;
+    for index in 0..reader.len() {
+        match reader.get_frame_without_coordinates(index)?.ms_level {
+            MSLevel::MS1 => ms1_frames += 1,
+            MSLevel::MS2 => ms2_frames += 1,
+            MSLevel::Unknown => {},
+        }
+    }
+    let global = &metadata.global_metadata;
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let instrument: Vec<&str> =
+        [&global.instrument_vendor, &global.instrument_name]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+    let mut out = io::stdout().lock();
+    writeln!(out, "path\t{}", path.as_ref().display())?;
+    writeln!(out, "instrument\t{}", instrument.join(" "))?;
+    writeln!(
+        out,
+        "acquisition_date\t{}",
+        text(&global.acquisition_date_time)
+    )?;
+    writeln!(out, "acquisition\t{:?}", reader.get_acquisition())?;
+    writeln!(out, "maldi\t{}", reader.is_maldi())?;
+    writeln!(out, "compression_type\t{}", metadata.compression_type)?;
+    writeln!(out, "frames\t{}", reader.len())?;
+    writeln!(out, "ms1_frames\t{ms1_frames}")?;
+    writeln!(out, "ms2_frames\t{ms2_frames}")?;
+    writeln!(out, "rt\t{}\t{}", metadata.lower_rt, metadata.upper_rt)?;
+    writeln!(out, "mz\t{}\t{}", metadata.lower_mz, metadata.upper_mz)?;
+    writeln!(out, "im\t{}\t{}", metadata.lower_im, metadata.upper_im)?;
+    Ok(())
+}
+
+fn convert(args: &ArgMatches) -> CliResult {
+    let path = run_path(args);
+    let output = args.get_one::<PathBuf>("output").cloned();
+    let format = args.get_one::<String>("to").map(String::as_str);
+    // mzML and MGF only contain MS2 spectra
+    if let Some(format @ ("mzml" | "mgf")) = format {
+        if ms_level(args) == Some(MSLevel::MS1) {
+            return Err(
+                format!("--ms-level 1 is not supported for {format}").into()
+            );
+        }
+    }
+    let written = match format {
+        Some("mzml") => to_mzml(
+            path,
+            &MzMLOptions {
+                output,
+                ..Default::default()
+            },
+        )?,
+        Some("mgf") => to_mgf(
+            path,
+            &MgfOptions {
+                output,
+                ..Default::default()
+            },
+        )?,
+        Some("imzml") => {
+            let default = ImzMLOptions::default();
+            to_imzml(
+                path,
+                &ImzMLOptions {
+                    output,
+                    ms_level: ms_level(args).unwrap_or(default.ms_level),
+                    ..default
+                },
+            )?
+            .0
+        },
+        _ => to_parquet(
+            path,
+            &ParquetOptions {
+                output,
+                ms_level: ms_level(args),
+                ..Default::default()
+            },
+        )?,
+    };
+    println!("{}", written.display());
+    Ok(())
+}
+
+/// Targets and their ids from a TSV file with a header.
+fn read_targets(
+    path: &PathBuf,
+    tolerance_ppm: f64,
+) -> Result<(Vec<String>, Vec<XicTarget>), Box<dyn Error>> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header = lines.next().ok_or("The targets file is empty")??;
+    let columns: Vec<&str> = header.split('\t').map(str::trim).collect();
+    let column = |name: &str| columns.iter().position(|&x| x == name);
+    let mz_column = column("mz").ok_or("The targets file has no mz column")?;
+    let mut ids = vec![];
+    let mut targets = vec![];
+    for (line_index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let field = |name: &str| {
+            column(name)
+                .and_then(|index| fields.get(index).copied())
+                .filter(|field| !field.is_empty())
+        };
+        let number = |name: &str| -> Result<Option<f64>, Box<dyn Error>> {
+            field(name)
+                .map(|field| {
+                    field.parse::<f64>().map_err(|_| {
+                        format!(
+                            "Invalid {name} `{field}` on line {}",
+                            line_index + 2
+                        )
+                        .into()
+                    })
+                })
+                .transpose()
+        };
+        let mz = fields
+            .get(mz_column)
+            .and_then(|field| field.parse::<f64>().ok())
+            .ok_or_else(|| format!("Invalid mz on line {}", line_index + 2))?;
+        let range = |start: Option<f64>, end: Option<f64>| match (start, end) {
+            (None, None) => None,
+            (start, end) => {
+                Some(start.unwrap_or(f64::MIN)..end.unwrap_or(f64::MAX))
+            },
+        };
+        targets.push(XicTarget {
+            im_range: range(number("im_start")?, number("im_end")?),
+            rt_range: range(number("rt_start")?, number("rt_end")?),
+            isolation_mz: number("isolation_mz")?,
+            ms_level: match field("ms_level") {
+                None | Some("1") => MSLevel::MS1,
+                Some("2") => MSLevel::MS2,
+                Some(level) => {
+                    return Err(format!(
+                        "Invalid ms_level `{level}` on line {}",
+                        line_index + 2
+                    )
+                    .into())
+                },
+            },
+            ..XicTarget::new(
+                mz,
+                number("tolerance_ppm")?.unwrap_or(tolerance_ppm),
+            )
+        });
+        ids.push(field("id").map_or_else(|| format!("{mz}"), String::from));
+    }
+    Ok((ids, targets))
+}
+
+fn xic(args: &ArgMatches) -> CliResult {
+    let (ids, targets) = read_targets(
+        args.get_one::<PathBuf>("targets")
+            .expect("Targets are required"),
+        *args
+            .get_one::<f64>("tolerance-ppm")
+            .expect("The tolerance has a default"),
+    )?;
+    let chromatograms = XicExtractor::new(run_path(args))?.extract(&targets)?;
+    let mut out: Box<dyn Write> = match args.get_one::<PathBuf>("output") {
+        Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    writeln!(out, "id\trt\tintensity")?;
+    for (id, chromatogram) in ids.iter().zip(chromatograms.iter()) {
+        for (rt, intensity) in
+            chromatogram.rt.iter().zip(chromatogram.intensity.iter())
+        {
+            writeln!(out, "{id}\t{rt}\t{intensity}")?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn subset(args: &ArgMatches) -> CliResult {
+    let mut subsetter = RunSubsetter::new(run_path(args))?;
+    let rt_start = args.get_one::<f64>("rt-start").copied();
+    let rt_end = args.get_one::<f64>("rt-end").copied();
+    if rt_start.is_some() | rt_end.is_some() {
+        subsetter = subsetter
+            .rt_range(rt_start.unwrap_or(f64::MIN)..rt_end.unwrap_or(f64::MAX));
+    }
+    if let Some(ms_level) = ms_level(args) {
+        subsetter = subsetter.ms_level(ms_level);
+    }
+    if let Some(region) = args.get_one::<String>("maldi-region") {
+        subsetter = subsetter.maldi_region(region);
+    }
+    let written = subsetter.write(
+        args.get_one::<PathBuf>("output")
+            .expect("The output is required"),
+    )?;
+    println!("{}", written.display());
+    Ok(())
+}
@@ -0,0 +1,471 @@
+//! One-call conversions of complete runs to generic file formats.
+//!
+//! Each conversion wires together the appropriate reader, the domain
+//! converters, optional processing and a writer. All options have sensible
+//! defaults, so `to_mzml(path, &MzMLOptions::default())` is a complete
+//! conversion. The performance relevant knobs are:
+//!
+//! * binary precision and zlib compression of mzML arrays (32-bit floats
+//!   and no compression write fastest, 64-bit m/z values keep full
+//!   accuracy),
+//! * centroiding of imzML pixel spectra (centroided spectra are much
+//!   smaller),
+//! * row group size and compression codec of Parquet tables (larger row
+//!   groups compress better, but need more memory while writing).
+//!
+//! Frames and spectra are decoded in parallel; writing is sequential.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use parquet::errors::ParquetError;
+use rayon::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data_processing::centroid::{Centroider, CentroidingConfig},
+    domain_converters::{ConvertableDomain, Tof2MzConverter},
+    imaging::LaserNormalization,
+    io::{
+        readers::{
+            CancellationToken, FrameReader, FrameReaderError, MetadataReader,
+            MetadataReaderError, ProgressCallback, ProgressTracker,
+            SampleSheet, SpectrumReader, SpectrumReaderConfig,
+            SpectrumReaderError, TimsTofPath, TimsTofPathError,
+            TimsTofPathLike,
+        },
+        writers::{
+            ImagingSpectrum, ImzMLWriter, ImzMLWriterConfig, MGFWriter,
+            MGFWriterConfig, MzMLWriter, MzMLWriterConfig, ParquetCompression,
+            PeakParquetWriter,
+        },
+    },
+    ms_data::{Frame, FrameQuality, MSLevel, NormalizationMode, Spectrum},
+};
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MzMLOptions {
+    /// Defaults to the run path with an `mzML` extension
+    pub output: Option<PathBuf>,
+    pub spectrum_reader_config: SpectrumReaderConfig,
+    pub writer_config: MzMLWriterConfig,
+}
+
+/// Convert all MS2 spectra of a run to mzML. Spectra are written in the
+/// order of the reader as soon as their batch is read.
+///
+/// By default, the file is written next to the run
+/// (e.g. `sample.d` -> `sample.mzML`). Returns the path of the mzML file.
+pub fn to_mzml(
+    path: impl TimsTofPathLike,
+    options: &MzMLOptions,
+) -> Result<PathBuf, ConversionError> {
+    let path = path.to_timstof_path()?;
+    let output = output_path(&path, options.output.as_deref(), "mzML");
+    let reader = SpectrumReader::build()
+        .with_path(&path)
+        .with_config(options.spectrum_reader_config)
+        .finalize()?;
+    let mut writer = MzMLWriter::new(options.writer_config).stream(
+        BufWriter::new(File::create(&output)?),
+        &run_id(&path),
+        reader.len(),
+    )?;
+    for_each_spectrum(&reader, |spectrum| writer.write_spectrum(spectrum))?;
+    writer.finish()?;
+    Ok(output)
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MgfOptions {
+    /// Defaults to the run path with an `mgf` extension
+    pub output: Option<PathBuf>,
+    pub spectrum_reader_config: SpectrumReaderConfig,
+    pub writer_config: MGFWriterConfig,
+}
+
+/// Convert the merged MS2 spectra of all DDA precursors of a run to MGF,
+/// annotated with the 1/K0 and CCS of their precursor.
+///
+/// By default, the file is written next to the run
+/// (e.g. `sample.d` -> `sample.mgf`). Returns the path of the MGF file.
+pub fn to_mgf(
+    path: impl TimsTofPathLike,
+    options: &MgfOptions,
+) -> Result<PathBuf, ConversionError> {
+    let path = path.to_timstof_path()?;
+    let output = output_path(&path, options.output.as_deref(), "mgf");
+    let reader = SpectrumReader::build()
+        .with_path(&path)
+        .with_config(options.spectrum_reader_config)
+        .finalize()?;
+    let mgf_writer = MGFWriter::new(options.writer_config);
+    let run_id = run_id(&path);
+    let mut writer = BufWriter::new(File::create(&output)?);
+    for_each_spectrum(&reader, |spectrum| {
+        mgf_writer.write_spectrum(&mut writer, &run_id, spectrum)
+    })?;
+    writer.flush()?;
+    Ok(output)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ImzMLOptions {
+    /// Defaults to the run path with an `imzML` extension. The `.ibd`
+    /// file is written next to it.
+    pub output: Option<PathBuf>,
+    /// Centroid the summed spectrum of every pixel (`None` keeps all TOF
+    /// bins).
+    pub centroiding: Option<CentroidingConfig>,
+    /// The MS level of the frames to image.
+    pub ms_level: MSLevel,
+    /// How the intensities of every frame are normalized before they are
+    /// summed into its pixel spectrum.
+    pub normalization: NormalizationMode,
+    /// Normalize every pixel spectrum by the laser settings of its frame.
+    /// Pixels without these settings are written without peaks and flagged
+    /// with [FrameQuality::MISSING_LASER_SETTINGS].
+    pub laser_normalization: LaserNormalization,
+    /// Labels that are joined onto the pixels and written as user
+    /// parameters of their spectra.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub sample_sheet: Option<SampleSheet>,
+    pub writer_config: ImzMLWriterConfig,
+    /// The number of frames that are decoded in parallel before their
+    /// spectra are written.
+    pub batch_size: usize,
+    /// Called with the progress of decoding frames.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub progress: Option<ProgressCallback>,
+    /// Aborts the conversion with [FrameReaderError::Cancelled].
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for ImzMLOptions {
+    fn default() -> Self {
+        Self {
+            output: None,
+            centroiding: Some(CentroidingConfig::default()),
+            ms_level: MSLevel::MS1,
+            normalization: NormalizationMode::default(),
+            laser_normalization: LaserNormalization::default(),
+            sample_sheet: None,
+            writer_config: ImzMLWriterConfig::default(),
+            batch_size: 64,
+            progress: None,
+            cancellation: None,
+        }
+    }
+}
+
+/// Convert a MALDI imaging run to a processed imzML file.
+///
+/// Every frame with MALDI info and the requested MS level is summed over
+/// all scans into the spectrum of its pixel; frames of the same pixel are
+/// summed into one spectrum. Frames are decoded in batches of pixels and
+/// their spectra are written to the `.ibd` file right away, in order of
+/// their pixels.
+///
+/// By default, the files are written next to the run (e.g. `sample.d` ->
+/// `sample.imzML` and `sample.ibd`). Returns the paths of the imzML and
+/// ibd files.
+pub fn to_imzml(
+    path: impl TimsTofPathLike,
+    options: &ImzMLOptions,
+) -> Result<(PathBuf, PathBuf), ConversionError> {
+    let path = path.to_timstof_path()?;
+    let imzml_path = output_path(&path, options.output.as_deref(), "imzML");
+    let ibd_path = imzml_path.with_extension("ibd");
+    let mz_converter = MetadataReader::new(&path)?.mz_converter;
+    let mut builder = FrameReader::build()
+        .with_path(&path)
+        .with_normalization(options.normalization);
+    if let Some(sample_sheet) = &options.sample_sheet {
+        builder = builder.with_sample_sheet(sample_sheet.clone());
+    }
+    let reader = builder.finalize()?;
+    if !reader.is_maldi() {
+        return Err(ConversionError::NotMaldi);
+    }
+    // The frames of every pixel, ordered by (y, x)
+    let mut pixels: BTreeMap<(i32, i32), Vec<usize>> = BTreeMap::new();
+    for index in 0..reader.len() {
+        let Ok(frame) = reader.get_frame_without_coordinates(index) else {
+            continue;
+        };
+        if let Some(info) = frame.maldi_info.as_ref() {
+            if frame.ms_level == options.ms_level {
+                pixels
+                    .entry((info.pixel_y, info.pixel_x))
+                    .or_default()
+                    .push(index);
+            }
+        }
+    }
+    let pixels: Vec<((i32, i32), Vec<usize>)> = pixels.into_iter().collect();
+    let x_min = pixels.iter().map(|&((_, x), _)| x).min().unwrap_or(0);
+    let y_min = pixels.iter().map(|&((y, _), _)| y).min().unwrap_or(0);
+    let tracker = ProgressTracker::new(
+        options.progress.as_ref(),
+        options.cancellation.as_ref(),
+        pixels.iter().map(|(_, indices)| indices.len()).sum(),
+    );
+    let mut writer = ImzMLWriter::new(options.writer_config)
+        .stream(BufWriter::new(File::create(&ibd_path)?), &run_id(&path))?;
+    for batch in pixels.chunks(options.batch_size.max(1)) {
+        let spectra = batch
+            .par_iter()
+            .map(|((y, x), indices)| {
+                let mut summed: BTreeMap<u32, f64> = BTreeMap::new();
+                let mut quality = FrameQuality::default();
+                let mut sample_labels = None;
+                let mut normalized = true;
+                for &index in indices {
+                    let frame = tracker.track(|| reader.get(index))?;
+                    quality.insert(frame.quality);
+                    sample_labels =
+                        sample_labels.or_else(|| frame.sample_labels.clone());
+                    match options
+                        .laser_normalization
+                        .factor(frame.maldi_info.as_ref())
+                    {
+                        Some(factor) => sum_frame(&mut summed, &frame, factor),
+                        None => normalized = false,
+                    }
+                }
+                let (mz_values, intensities) = if normalized {
+                    pixel_spectrum(summed, &mz_converter, options.centroiding)
+                } else {
+                    // Written without peaks, so that it is not mistaken
+                    // for a normalized spectrum
+                    quality.insert(FrameQuality::MISSING_LASER_SETTINGS);
+                    (vec![], vec![])
+                };
+                let spectrum = ImagingSpectrum {
+                    x: (x - x_min + 1) as u32,
+                    y: (y - y_min + 1) as u32,
+                    ms_level: options.ms_level,
+                    mz_values,
+                    intensities,
+                    quality: quality.bits(),
+                    sample_labels,
+                };
+                Ok(spectrum)
+            })
+            .collect::<Result<Vec<ImagingSpectrum>, FrameReaderError>>()?;
+        for spectrum in spectra.iter() {
+            writer.write_spectrum(spectrum)?;
+        }
+    }
+    writer.finish(BufWriter::new(File::create(&imzml_path)?))?;
+    Ok((imzml_path, ibd_path))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ParquetOptions {
+    /// Defaults to the run path with a `parquet` extension
+    pub output: Option<PathBuf>,
+    /// Only convert frames of this MS level (`None` converts all frames).
+    pub ms_level: Option<MSLevel>,
+    /// The number of peaks per row group.
+    pub row_group_size: usize,
+    pub compression: ParquetCompression,
+    /// The number of frames that are decoded in parallel before writing.
+    pub batch_size: usize,
+    /// Labels that are joined onto the frames and written as additional
+    /// `sample` columns (see [PeakParquetWriter]).
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub sample_sheet: Option<SampleSheet>,
+    /// Called with the progress of decoding frames.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub progress: Option<ProgressCallback>,
+    /// Aborts the conversion with [FrameReaderError::Cancelled].
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for ParquetOptions {
+    fn default() -> Self {
+        Self {
+            output: None,
+            ms_level: None,
+            row_group_size: 1 << 20,
+            compression: ParquetCompression::default(),
+            batch_size: 64,
+            sample_sheet: None,
+            progress: None,
+            cancellation: None,
+        }
+    }
+}
+
+/// Convert all peaks of a run to a Parquet table with the columns
+/// `frame, scan, tof, intensity, quality, rt, mz, mobility` (and the
+/// `sample` columns of [ParquetOptions::sample_sheet]).
+///
+/// By default, the file is written next to the run
+/// (e.g. `sample.d` -> `sample.parquet`). Returns the path of the file.
+pub fn to_parquet(
+    path: impl TimsTofPathLike,
+    options: &ParquetOptions,
+) -> Result<PathBuf, ConversionError> {
+    let path = path.to_timstof_path()?;
+    let output = output_path(&path, options.output.as_deref(), "parquet");
+    let metadata = MetadataReader::new(&path)?;
+    let output_file = BufWriter::new(File::create(&output)?);
+    let (reader, mut writer) = match &options.sample_sheet {
+        Some(sample_sheet) => (
+            FrameReader::build()
+                .with_path(&path)
+                .with_sample_sheet(sample_sheet.clone())
+                .finalize()?,
+            PeakParquetWriter::with_sample_labels(
+                output_file,
+                metadata.mz_converter,
+                metadata.im_converter,
+                options.row_group_size,
+                options.compression,
+                sample_sheet.columns(),
+            )?,
+        ),
+        None => (
+            FrameReader::new(&path)?,
+            PeakParquetWriter::new(
+                output_file,
+                metadata.mz_converter,
+                metadata.im_converter,
+                options.row_group_size,
+                options.compression,
+            )?,
+        ),
+    };
+    let indices: Vec<usize> = (0..reader.len())
+        .filter(|&index| match options.ms_level {
+            Some(ms_level) => reader
+                .get_frame_without_coordinates(index)
+                .map(|frame| frame.ms_level == ms_level)
+                .unwrap_or(false),
+            None => true,
+        })
+        .collect();
+    let tracker = ProgressTracker::new(
+        options.progress.as_ref(),
+        options.cancellation.as_ref(),
+        indices.len(),
+    );
+    for batch in indices.chunks(options.batch_size.max(1)) {
+        let frames = batch
+            .par_iter()
+            .map(|&index| tracker.track(|| reader.get(index)))
+            .collect::<Result<Vec<Frame>, _>>()?;
+        for frame in frames.iter() {
+            writer.write_frame(frame)?;
+        }
+    }
+    writer.close()?;
+    Ok(output)
+}
+
+/// The number of spectra that are read in parallel before they are
+/// written.
+const SPECTRUM_BATCH_SIZE: usize = 256;
+
+/// Read all spectra of a reader in parallel batches and write them in
+/// order, so that only a single batch is kept in memory.
+fn for_each_spectrum(
+    reader: &SpectrumReader,
+    mut write: impl FnMut(&Spectrum) -> io::Result<()>,
+) -> Result<(), ConversionError> {
+    let indices: Vec<usize> = (0..reader.len()).collect();
+    for batch in indices.chunks(SPECTRUM_BATCH_SIZE) {
+        let spectra = batch
+            .par_iter()
+            .map(|&index| reader.get(index))
+            .collect::<Result<Vec<Spectrum>, _>>()?;
+        for spectrum in spectra.iter() {
+            write(spectrum)?;
+        }
+    }
+    Ok(())
+}
+
+/// Add the (normalized) intensities of a frame, scaled by `factor`, to
+/// the intensities per TOF index of its pixel.
+fn sum_frame(summed: &mut BTreeMap<u32, f64>, frame: &Frame, factor: f64) {
+    for (peak, &tof) in frame.tof_indices.iter().enumerate() {
+        *summed.entry(tof).or_default() +=
+            frame.normalized_intensity(peak) * factor;
+    }
+}
+
+/// The spectrum of the summed intensities of a pixel, optionally
+/// centroided.
+fn pixel_spectrum(
+    summed: BTreeMap<u32, f64>,
+    mz_converter: &Tof2MzConverter,
+    centroiding: Option<CentroidingConfig>,
+) -> (Vec<f64>, Vec<f64>) {
+    let (tof_values, intensities): (Vec<f64>, Vec<f64>) = match centroiding {
+        Some(config) => {
+            let tof_indices: Vec<u32> = summed.keys().cloned().collect();
+            let intensities: Vec<f64> = summed.values().cloned().collect();
+            Centroider::new(config, *mz_converter)
+                .centroid_scan(&tof_indices, &intensities)
+        },
+        None => summed
+            .into_iter()
+            .map(|(tof, intensity)| (tof as f64, intensity))
+            .unzip(),
+    };
+    let mz_values = tof_values
+        .iter()
+        .map(|&tof| mz_converter.convert(tof))
+        .collect();
+    (mz_values, intensities)
+}
+
+fn output_path(
+    path: &TimsTofPath,
+    output: Option<&Path>,
+    extension: &str,
+) -> PathBuf {
+    match output {
+        Some(output) => output.to_path_buf(),
+        None => path.as_ref().with_extension(extension),
+    }
+}
+
+fn run_id(path: &TimsTofPath) -> String {
+    path.as_ref()
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionError {
+    #[error("{0}")]
+    IO(#[from] io::Error),
+    #[error("{0}")]
+    TimsTofPathError(#[from] TimsTofPathError),
+    #[error("{0}")]
+    SpectrumReaderError(#[from] SpectrumReaderError),
+    #[error("{0}")]
+    FrameReaderError(#[from] FrameReaderError),
+    #[error("{0}")]
+    MetadataReaderError(#[from] MetadataReaderError),
+    #[error("{0}")]
+    ParquetError(#[from] ParquetError),
+    #[error("Run does not contain MALDI imaging data")]
+    NotMaldi,
+}
@@ -0,0 +1,12 @@
+//! Processing of raw TimsTOF data (e.g. centroiding)
+
+#[cfg(feature = "tdf")]
+pub mod binning;
+pub mod centroid;
+#[cfg(feature = "tdf")]
+pub mod chromatogram;
+pub mod deconvolution;
+pub mod features;
+pub mod signal;
+#[cfg(feature = "tdf")]
+pub mod targeted;
@@ -0,0 +1,378 @@
+//! Rasterization of peaks onto retention time × m/z or ion mobility × m/z
+//! grids, e.g. to visualize a run as a heatmap or as input of ML models.
+//!
+//! A [HeatmapBinner] is a [FrameVisitor], so frames are streamed through
+//! the parallel reading engine of a [FrameReader] and only the grid (at most
+//! once per worker thread) is kept in memory.
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain_converters::{ConvertableDomain, Scan2ImConverter, Tof2MzConverter},
+    io::readers::{FrameReader, FrameReaderError, FrameVisitor},
+    ms_data::{Frame, MSLevel, Metadata},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum BinScale {
+    #[default]
+    Linear,
+    /// Bins of equal width on a log scale, i.e. of a constant relative
+    /// (ppm) width. Only positive values can be binned.
+    Log,
+}
+
+/// Equally sized bins covering the half-open range `[start, end)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct BinAxis {
+    pub start: f64,
+    pub end: f64,
+    pub bins: usize,
+    pub scale: BinScale,
+}
+
+impl BinAxis {
+    pub fn linear(start: f64, end: f64, bins: usize) -> Self {
+        Self {
+            start,
+            end,
+            bins,
+            scale: BinScale::Linear,
+        }
+    }
+
+    pub fn log(start: f64, end: f64, bins: usize) -> Self {
+        Self {
+            start,
+            end,
+            bins,
+            scale: BinScale::Log,
+        }
+    }
+
+    /// The bin of a value, if it lies within the axis.
+    pub fn bin(&self, value: f64) -> Option<usize> {
+        let position = match self.scale {
+            BinScale::Linear => (value - self.start) / (self.end - self.start),
+            BinScale::Log => {
+                (value / self.start).ln() / (self.end / self.start).ln()
+            },
+        };
+        let bin = position * self.bins as f64;
+        if (0.0..self.bins as f64).contains(&bin) {
+            Some(bin as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The `bins + 1` boundaries of all bins.
+    pub fn edges(&self) -> Vec<f64> {
+        (0..=self.bins)
+            .map(|edge| {
+                let fraction = edge as f64 / self.bins as f64;
+                match self.scale {
+                    BinScale::Linear => {
+                        self.start + fraction * (self.end - self.start)
+                    },
+                    BinScale::Log => {
+                        self.start * (self.end / self.start).powf(fraction)
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// The center of every bin (the geometric center on a log scale).
+    pub fn centers(&self) -> Vec<f64> {
+        self.edges()
+            .windows(2)
+            .map(|edges| match self.scale {
+                BinScale::Linear => (edges[0] + edges[1]) / 2.0,
+                BinScale::Log => (edges[0] * edges[1]).sqrt(),
+            })
+            .collect()
+    }
+}
+
+/// The dimension of the rows of a [Heatmap]; columns are always m/z.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum BinDimension {
+    /// Retention time in seconds
+    #[default]
+    RetentionTime,
+    /// Ion mobility (1/K0)
+    IonMobility,
+}
+
+/// How the intensities of all peaks in a bin are combined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum BinAggregation {
+    #[default]
+    Sum,
+    Max,
+}
+
+impl BinAggregation {
+    fn add(&self, value: &mut f64, intensity: f64) {
+        match self {
+            Self::Sum => *value += intensity,
+            Self::Max => *value = value.max(intensity),
+        }
+    }
+}
+
+/// A 2D grid of binned intensities.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Heatmap {
+    pub dimension: BinDimension,
+    pub rows: BinAxis,
+    pub mz: BinAxis,
+    /// Row-major, with `mz.bins` columns per row (empty bins are 0)
+    pub values: Vec<f64>,
+}
+
+impl Heatmap {
+    pub fn new(dimension: BinDimension, rows: BinAxis, mz: BinAxis) -> Self {
+        Self {
+            dimension,
+            rows,
+            mz,
+            values: vec![0.0; rows.bins * mz.bins],
+        }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<f64> {
+        if column >= self.mz.bins {
+            return None;
+        }
+        self.values.get(row * self.mz.bins + column).copied()
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[f64]> {
+        self.values
+            .get(row * self.mz.bins..(row + 1) * self.mz.bins)
+    }
+}
+
+/// Bins the peaks of all frames of a single MS level onto a [Heatmap].
+///
+/// Ion mobilities are converted with the converter of every frame (see
+/// [Frame::im_converter]) when it has one. Intensities are not corrected.
+#[derive(Clone, Debug)]
+pub struct HeatmapBinner {
+    heatmap: Heatmap,
+    aggregation: BinAggregation,
+    ms_level: MSLevel,
+    mz_converter: Tof2MzConverter,
+    im_converter: Scan2ImConverter,
+}
+
+impl HeatmapBinner {
+    /// Sum the intensities of the MS1 frames.
+    pub fn new(
+        dimension: BinDimension,
+        rows: BinAxis,
+        mz: BinAxis,
+        metadata: &Metadata,
+    ) -> Self {
+        Self {
+            heatmap: Heatmap::new(dimension, rows, mz),
+            aggregation: BinAggregation::default(),
+            ms_level: MSLevel::MS1,
+            mz_converter: metadata.mz_converter,
+            im_converter: metadata.im_converter,
+        }
+    }
+
+    pub fn with_aggregation(self, aggregation: BinAggregation) -> Self {
+        Self {
+            aggregation,
+            ..self
+        }
+    }
+
+    pub fn with_ms_level(self, ms_level: MSLevel) -> Self {
+        Self { ms_level, ..self }
+    }
+
+    /// Whether the peaks of a frame can end up in the heatmap, based on
+    /// its metadata only.
+    pub fn keeps(&self, frame: &Frame) -> bool {
+        self.frame_filter()(frame)
+    }
+
+    fn frame_filter(&self) -> impl Fn(&Frame) -> bool + Send + Sync {
+        let ms_level = self.ms_level;
+        let dimension = self.heatmap.dimension;
+        let rows = self.heatmap.rows;
+        move |frame| {
+            (frame.ms_level == ms_level)
+                & match dimension {
+                    BinDimension::RetentionTime => {
+                        rows.bin(frame.rt_in_seconds).is_some()
+                    },
+                    BinDimension::IonMobility => true,
+                }
+        }
+    }
+
+    /// Bin all frames of a reader that are kept, without decoding the
+    /// others.
+    pub fn bin(
+        self,
+        frame_reader: &FrameReader,
+    ) -> Result<Heatmap, FrameReaderError> {
+        frame_reader.visit_filtered(self.frame_filter(), self)
+    }
+
+    fn add_peaks(
+        &mut self,
+        row: usize,
+        tof_indices: &[u32],
+        intensities: &[u32],
+    ) {
+        let columns = self.heatmap.mz.bins;
+        for (&tof_index, &intensity) in tof_indices.iter().zip(intensities) {
+            let mz = self.mz_converter.convert(tof_index);
+            if let Some(column) = self.heatmap.mz.bin(mz) {
+                self.aggregation.add(
+                    &mut self.heatmap.values[row * columns + column],
+                    intensity as f64,
+                );
+            }
+        }
+    }
+}
+
+impl FrameVisitor for HeatmapBinner {
+    type Output = Heatmap;
+
+    fn fork(&self) -> Self {
+        let heatmap = &self.heatmap;
+        Self {
+            heatmap: Heatmap::new(heatmap.dimension, heatmap.rows, heatmap.mz),
+            aggregation: self.aggregation,
+            ms_level: self.ms_level,
+            mz_converter: self.mz_converter,
+            im_converter: self.im_converter,
+        }
+    }
+
+    fn visit_frame(&mut self, frame: &Frame) {
+        if !self.keeps(frame) {
+            return;
+        }
+        match self.heatmap.dimension {
+            BinDimension::RetentionTime => {
+                if let Some(row) = self.heatmap.rows.bin(frame.rt_in_seconds) {
+                    self.add_peaks(row, &frame.tof_indices, &frame.intensities);
+                }
+            },
+            BinDimension::IonMobility => {
+                let im_converter =
+                    frame.im_converter.unwrap_or(self.im_converter);
+                for scan in frame.scans() {
+                    let mobility = im_converter.convert(scan.scan_index as f64);
+                    if let Some(row) = self.heatmap.rows.bin(mobility) {
+                        self.add_peaks(row, scan.tof_indices, scan.intensities);
+                    }
+                }
+            },
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (value, other) in
+            self.heatmap.values.iter_mut().zip(other.heatmap.values)
+        {
+            self.aggregation.add(value, other);
+        }
+    }
+
+    fn end_run(self) -> Heatmap {
+        self.heatmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bins_linear_and_log_axes() {
+        let axis = BinAxis::linear(0.0, 10.0, 5);
+        assert_eq!(axis.bin(0.0), Some(0));
+        assert_eq!(axis.bin(9.99), Some(4));
+        assert_eq!(axis.bin(10.0), None);
+        assert_eq!(axis.bin(-0.1), None);
+        assert_eq!(axis.centers(), vec![1.0, 3.0, 5.0, 7.0, 9.0]);
+        let axis = BinAxis::log(100.0, 10000.0, 2);
+        assert_eq!(axis.bin(999.0), Some(0));
+        assert_eq!(axis.bin(1001.0), Some(1));
+        assert_eq!(axis.bin(0.0), None);
+        assert_eq!(axis.bin(-5.0), None);
+        let edges = axis.edges();
+        assert!((edges[1] - 1000.0).abs() < 1e-9);
+        assert_eq!(axis.bin(f64::NAN), None);
+    }
+
+    #[test]
+    fn bins_frames() {
+        let metadata = Metadata {
+            // m/z = tof_index², 1/K0 = 1.5 - 0.1 * scan
+            mz_converter: Tof2MzConverter::from_boundaries(0.0, 10000.0, 100),
+            im_converter: Scan2ImConverter::from_boundaries(0.5, 1.5, 10),
+            ..Default::default()
+        };
+        let frame = Frame {
+            scan_offsets: vec![0, 2, 3],
+            tof_indices: vec![10, 60, 61],
+            intensities: vec![1, 2, 3],
+            rt_in_seconds: 5.0,
+            ms_level: MSLevel::MS1,
+            ..Default::default()
+        };
+        let mz = BinAxis::linear(0.0, 5000.0, 2);
+        let mut binner = HeatmapBinner::new(
+            BinDimension::RetentionTime,
+            BinAxis::linear(0.0, 10.0, 2),
+            mz,
+            &metadata,
+        );
+        binner.visit_frame(&frame);
+        assert_eq!(binner.end_run().values, vec![0.0, 0.0, 1.0, 5.0]);
+        let mut binner = HeatmapBinner::new(
+            BinDimension::IonMobility,
+            BinAxis::linear(1.3, 1.6, 2),
+            mz,
+            &metadata,
+        )
+        .with_aggregation(BinAggregation::Max);
+        binner.visit_frame(&frame);
+        let mut other = binner.fork();
+        other.visit_frame(&Frame {
+            intensities: vec![4, 1, 1],
+            ..frame.clone()
+        });
+        binner.merge(other);
+        let heatmap = binner.end_run();
+        assert_eq!(heatmap.row(0), Some(&[0.0, 3.0][..]));
+        assert_eq!(heatmap.row(1), Some(&[4.0, 2.0][..]));
+        binner = HeatmapBinner::new(
+            BinDimension::RetentionTime,
+            BinAxis::linear(0.0, 10.0, 2),
+            mz,
+            &metadata,
+        )
+        .with_ms_level(MSLevel::MS2);
+        binner.visit_frame(&frame);
+        assert!(binner.end_run().values.iter().all(|&x| x == 0.0));
+    }
+}
@@ -0,0 +1,228 @@
+//! Centroiding of profile TOF data.
+//!
+//! Neighbouring TOF bins within a single scan are collapsed into one peak
+//! with an intensity-weighted TOF (and m/z) and a summed intensity.
+
+use rayon::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain_converters::{ConvertableDomain, Tof2MzConverter},
+    ms_data::{Frame, MSLevel},
+};
+
+/// The maximum distance between two neighbouring TOF bins of a peak.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum CentroidingTolerance {
+    /// Maximum distance in parts per million of the m/z value.
+    Ppm(f64),
+    /// Maximum distance in TOF bins.
+    TofBins(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CentroidingConfig {
+    pub tolerance: CentroidingTolerance,
+    /// Centroids with a lower summed intensity are discarded.
+    pub min_intensity: f64,
+}
+
+impl Default for CentroidingConfig {
+    fn default() -> Self {
+        Self {
+            tolerance: CentroidingTolerance::TofBins(1),
+            min_intensity: 0.0,
+        }
+    }
+}
+
+/// A frame in which every scan is centroided.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CentroidedFrame {
+    pub index: usize,
+    pub rt_in_seconds: f64,
+    pub ms_level: MSLevel,
+    pub scan_offsets: Vec<usize>,
+    pub tof_centroids: Vec<f64>,
+    pub mz_values: Vec<f64>,
+    pub intensities: Vec<f64>,
+}
+
+impl CentroidedFrame {
+    pub fn len(&self) -> usize {
+        self.intensities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intensities.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Centroider {
+    config: CentroidingConfig,
+    mz_converter: Tof2MzConverter,
+}
+
+impl Centroider {
+    pub fn new(
+        config: CentroidingConfig,
+        mz_converter: Tof2MzConverter,
+    ) -> Self {
+        Self {
+            config,
+            mz_converter,
+        }
+    }
+
+    /// Centroid a single scan, given its (sorted) TOF indices and
+    /// intensities. Returns the intensity-weighted TOF centroids and the
+    /// summed intensities. Unsorted TOF indices only merge peaks that are
+    /// adjacent and within tolerance.
+    pub fn centroid_scan<T: Copy + Into<f64>>(
+        &self,
+        tof_indices: &[u32],
+        intensities: &[T],
+    ) -> (Vec<f64>, Vec<f64>) {
+        let mut tof_centroids: Vec<f64> = vec![];
+        let mut summed_intensities: Vec<f64> = vec![];
+        let mut start = 0;
+        while start < tof_indices.len() {
+            let mut end = start + 1;
+            while (end < tof_indices.len())
+                && self.are_neighbours(tof_indices[end - 1], tof_indices[end])
+            {
+                end += 1;
+            }
+            let mut weighted_tof = 0.0;
+            let mut summed_intensity = 0.0;
+            for index in start..end {
+                let intensity: f64 = intensities[index].into();
+                weighted_tof += tof_indices[index] as f64 * intensity;
+                summed_intensity += intensity;
+            }
+            if (summed_intensity > 0.0)
+                & (summed_intensity >= self.config.min_intensity)
+            {
+                tof_centroids.push(weighted_tof / summed_intensity);
+                summed_intensities.push(summed_intensity);
+            }
+            start = end;
+        }
+        (tof_centroids, summed_intensities)
+    }
+
+    pub fn centroid_frame(&self, frame: &Frame) -> CentroidedFrame {
+        let mut centroided = CentroidedFrame {
+            index: frame.index,
+            rt_in_seconds: frame.rt_in_seconds,
+            ms_level: frame.ms_level,
+            ..Default::default()
+        };
+        if frame.scan_offsets.is_empty() {
+            return centroided;
+        }
+        centroided.scan_offsets.push(0);
+        for scan in frame.scan_offsets.windows(2) {
+            let (tof_centroids, intensities) = self.centroid_scan(
+                &frame.tof_indices[scan[0]..scan[1]],
+                &frame.intensities[scan[0]..scan[1]],
+            );
+            centroided.tof_centroids.extend(tof_centroids);
+            centroided.intensities.extend(intensities);
+            centroided.scan_offsets.push(centroided.intensities.len());
+        }
+        centroided.mz_values = centroided
+            .tof_centroids
+            .iter()
+            .map(|&tof| self.mz_converter.convert(tof))
+            .collect();
+        centroided
+    }
+
+    /// Centroid multiple frames in parallel.
+    pub fn centroid_frames(&self, frames: &[Frame]) -> Vec<CentroidedFrame> {
+        frames
+            .par_iter()
+            .map(|frame| self.centroid_frame(frame))
+            .collect()
+    }
+
+    fn are_neighbours(&self, tof: u32, next_tof: u32) -> bool {
+        match self.config.tolerance {
+            CentroidingTolerance::TofBins(bins) => {
+                next_tof.abs_diff(tof) <= bins
+            },
+            CentroidingTolerance::Ppm(ppm) => {
+                let mz = self.mz_converter.convert(tof);
+                let next_mz = self.mz_converter.convert(next_tof);
+                (next_mz - mz).abs() / mz * 1e6 <= ppm
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_neighbouring_tof_bins() {
+        let centroider = Centroider::new(
+            CentroidingConfig::default(),
+            Tof2MzConverter::from_boundaries(100.0, 1000.0, 1000),
+        );
+        let (tof_centroids, intensities) = centroider
+            .centroid_scan(&[10, 11, 12, 20, 22, 23], &[1, 2, 1, 5, 3, 1]);
+        assert_eq!(tof_centroids, vec![11.0, 20.0, 22.25]);
+        assert_eq!(intensities, vec![4.0, 5.0, 4.0]);
+    }
+
+    #[test]
+    fn accepts_unsorted_tof_indices() {
+        let centroider = Centroider::new(
+            CentroidingConfig::default(),
+            Tof2MzConverter::from_boundaries(100.0, 1000.0, 1000),
+        );
+        let (tof_centroids, intensities) =
+            centroider.centroid_scan(&[20, 10, 11, 5], &[1, 1, 1, 1]);
+        assert_eq!(tof_centroids, vec![20.0, 10.5, 5.0]);
+        assert_eq!(intensities, vec![1.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn merges_within_ppm_tolerance() {
+        let mz_converter =
+            Tof2MzConverter::from_boundaries(100.0, 1000.0, 1000);
+        let config = CentroidingConfig {
+            tolerance: CentroidingTolerance::Ppm(10000.0),
+            min_intensity: 3.0,
+        };
+        let centroider = Centroider::new(config, mz_converter);
+        let (tof_centroids, intensities) =
+            centroider.centroid_scan(&[500, 501, 502, 600], &[1, 1, 1, 2]);
+        assert_eq!(tof_centroids, vec![501.0]);
+        assert_eq!(intensities, vec![3.0]);
+    }
+
+    #[test]
+    fn keeps_scan_offsets_aligned() {
+        let centroider = Centroider::new(
+            CentroidingConfig::default(),
+            Tof2MzConverter::from_boundaries(100.0, 1000.0, 1000),
+        );
+        let frame = Frame {
+            scan_offsets: vec![0, 2, 2, 5],
+            tof_indices: vec![10, 11, 5, 6, 9],
+            intensities: vec![1, 1, 2, 2, 4],
+            ..Default::default()
+        };
+        let centroided = centroider.centroid_frame(&frame);
+        assert_eq!(centroided.scan_offsets, vec![0, 1, 1, 3]);
+        assert_eq!(centroided.tof_centroids, vec![10.5, 5.5, 9.0]);
+        assert_eq!(centroided.mz_values.len(), 3);
+    }
+}
@@ -0,0 +1,421 @@
+//! Total-ion (TIC) and base-peak (BPC) chromatograms.
+//!
+//! Chromatograms are computed from the `SummedIntensities` and
+//! `MaxIntensity` columns of the `Frames` table when they are available,
+//! so no frames need to be decoded. Otherwise, all selected frames are
+//! decoded. All SQL errors other than missing columns are returned.
+//!
+//! Extracted-ion chromatograms (XICs) of many targets are computed in a
+//! single pass over the frames with an [XicExtractor].
+
+use std::{collections::BTreeMap, ops::Range};
+
+use rayon::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain_converters::{
+        scan_bounds, tof_bounds, Scan2ImConverter, Tof2MzConverter,
+    },
+    io::readers::{
+        file_readers::sql_reader::{SqlReader, SqlReaderError},
+        FrameReader, FrameReaderError, MetadataReader, MetadataReaderError,
+        TimsTofPathLike,
+    },
+    ms_data::{Frame, MSLevel, Metadata},
+};
+
+/// The intensity of every selected frame, in order of retention time.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Chromatogram {
+    /// Retention times in seconds
+    pub rt: Vec<f64>,
+    pub intensity: Vec<f64>,
+}
+
+impl Chromatogram {
+    pub fn len(&self) -> usize {
+        self.rt.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rt.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ChromatogramKind {
+    /// The summed intensity of each frame
+    #[default]
+    Tic,
+    /// The highest intensity of each frame
+    Bpc,
+}
+
+/// The frames that contribute to a chromatogram.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ChromatogramFilter {
+    #[default]
+    All,
+    MsLevel(MSLevel),
+    /// MS2 frames of a single DIA window group
+    WindowGroup(u8),
+}
+
+impl ChromatogramFilter {
+    fn keeps(&self, frame: &Frame) -> bool {
+        match self {
+            Self::All => true,
+            Self::MsLevel(ms_level) => frame.ms_level == *ms_level,
+            Self::WindowGroup(window_group) => {
+                (frame.ms_level == MSLevel::MS2)
+                    & (frame.window_group == *window_group)
+            },
+        }
+    }
+}
+
+/// Computes chromatograms of a run.
+#[derive(Debug)]
+pub struct ChromatogramExtractor {
+    frame_reader: FrameReader,
+    summed_intensities: Option<Vec<f64>>,
+    max_intensities: Option<Vec<f64>>,
+}
+
+impl ChromatogramExtractor {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, ChromatogramError> {
+        let frame_reader = FrameReader::new(&path)?;
+        let sql_reader = SqlReader::open(&path)?;
+        // Missing columns and values are computed by decoding frames
+        let read_column = |column: &str| {
+            let values = sql_reader
+                .read_optional_column_from_table::<f64>(
+                    column,
+                    "Frames",
+                    frame_reader.len(),
+                )?
+                .into_iter()
+                .collect::<Option<Vec<f64>>>()
+                .filter(|values| values.len() >= frame_reader.len());
+            Ok::<_, SqlReaderError>(values)
+        };
+        let extractor = Self {
+            summed_intensities: read_column("SummedIntensities")?,
+            max_intensities: read_column("MaxIntensity")?,
+            frame_reader,
+        };
+        Ok(extractor)
+    }
+
+    /// Whether chromatograms of `kind` are computed without decoding
+    /// frames.
+    pub fn uses_frame_table(&self, kind: ChromatogramKind) -> bool {
+        match kind {
+            ChromatogramKind::Tic => self.summed_intensities.is_some(),
+            ChromatogramKind::Bpc => self.max_intensities.is_some(),
+        }
+    }
+
+    pub fn tic(
+        &self,
+        filter: ChromatogramFilter,
+    ) -> Result<Chromatogram, ChromatogramError> {
+        self.chromatogram(ChromatogramKind::Tic, filter)
+    }
+
+    pub fn bpc(
+        &self,
+        filter: ChromatogramFilter,
+    ) -> Result<Chromatogram, ChromatogramError> {
+        self.chromatogram(ChromatogramKind::Bpc, filter)
+    }
+
+    pub fn chromatogram(
+        &self,
+        kind: ChromatogramKind,
+        filter: ChromatogramFilter,
+    ) -> Result<Chromatogram, ChromatogramError> {
+        let (indices, rt): (Vec<usize>, Vec<f64>) = self
+            .frames()?
+            .into_iter()
+            .filter(|(_, frame)| filter.keeps(frame))
+            .map(|(index, frame)| (index, frame.rt_in_seconds))
+            .unzip();
+        let column = match kind {
+            ChromatogramKind::Tic => &self.summed_intensities,
+            ChromatogramKind::Bpc => &self.max_intensities,
+        };
+        let intensity: Vec<f64> = match column {
+            Some(values) => {
+                indices.iter().map(|&index| values[index]).collect()
+            },
+            None => indices
+                .par_iter()
+                .map(|&index| {
+                    let frame = self.frame_reader.get(index)?;
+                    let intensities = frame.intensities.iter();
+                    Ok(match kind {
+                        ChromatogramKind::Tic => {
+                            intensities.map(|&x| x as f64).sum()
+                        },
+                        ChromatogramKind::Bpc => {
+                            intensities.max().map_or(0.0, |&x| x as f64)
+                        },
+                    })
+                })
+                .collect::<Result<Vec<f64>, FrameReaderError>>()?,
+        };
+        let mut points: Vec<(f64, f64)> =
+            rt.into_iter().zip(intensity).collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (rt, intensity) = points.into_iter().unzip();
+        Ok(Chromatogram { rt, intensity })
+    }
+
+    /// A chromatogram of every DIA window group, by window group.
+    pub fn per_window_group(
+        &self,
+        kind: ChromatogramKind,
+    ) -> Result<BTreeMap<u8, Chromatogram>, ChromatogramError> {
+        let mut window_groups: Vec<u8> = self
+            .frames()?
+            .into_iter()
+            .map(|(_, frame)| frame)
+            .filter(|frame| {
+                (frame.ms_level == MSLevel::MS2) & (frame.window_group > 0)
+            })
+            .map(|frame| frame.window_group)
+            .collect();
+        window_groups.sort();
+        window_groups.dedup();
+        window_groups
+            .into_iter()
+            .map(|window_group| {
+                let filter = ChromatogramFilter::WindowGroup(window_group);
+                Ok((window_group, self.chromatogram(kind, filter)?))
+            })
+            .collect()
+    }
+
+    /// All frames (without peaks) with their index in the frame reader.
+    fn frames(&self) -> Result<Vec<(usize, Frame)>, FrameReaderError> {
+        (0..self.frame_reader.len())
+            .map(|index| {
+                self.frame_reader
+                    .get_frame_without_coordinates(index)
+                    .map(|frame| (index, frame))
+            })
+            .collect()
+    }
+}
+
+/// An ion to extract a chromatogram of.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct XicTarget {
+    pub mz: f64,
+    pub tolerance_ppm: f64,
+    /// Only sum peaks within this 1/K0 range, converted to scans with the
+    /// calibration of every frame (`None` sums all scans)
+    pub im_range: Option<Range<f64>>,
+    /// Only include frames within this retention time range in seconds
+    /// (`None` includes all frames)
+    pub rt_range: Option<Range<f64>>,
+    pub ms_level: MSLevel,
+    /// Only include MS2 frames of this DIA window group (`None` includes
+    /// frames of all window groups)
+    pub window_group: Option<u8>,
+    /// For MS2 targets, only sum scans whose isolation window contains this
+    /// (precursor) m/z (`None` sums all scans)
+    pub isolation_mz: Option<f64>,
+}
+
+impl XicTarget {
+    pub fn new(mz: f64, tolerance_ppm: f64) -> Self {
+        Self {
+            mz,
+            tolerance_ppm,
+            im_range: None,
+            rt_range: None,
+            ms_level: MSLevel::MS1,
+            window_group: None,
+            isolation_mz: None,
+        }
+    }
+
+    fn keeps(&self, frame: &Frame) -> bool {
+        (frame.ms_level == self.ms_level)
+            & self
+                .rt_range
+                .as_ref()
+                .is_none_or(|range| range.contains(&frame.rt_in_seconds))
+            & self
+                .window_group
+                .is_none_or(|window_group| frame.window_group == window_group)
+    }
+}
+
+/// A target converted to (inclusive) TOF bounds and the converter of the
+/// scan bounds of frames without their own calibration.
+#[derive(Clone, Debug, PartialEq)]
+struct XicBounds {
+    tof_lower: f64,
+    tof_upper: f64,
+    im_range: Option<Range<f64>>,
+    im_converter: Scan2ImConverter,
+    isolation_mz: Option<f64>,
+}
+
+impl XicBounds {
+    /// The scans of `frame` within the ion mobility range of the target,
+    /// converted with the calibration of the frame.
+    fn scan_bounds(&self, frame: &Frame) -> (usize, usize) {
+        let im_converter = frame.im_converter.unwrap_or(self.im_converter);
+        match &self.im_range {
+            Some(im_range) => scan_bounds(&im_converter, im_range),
+            None => (0, usize::MAX),
+        }
+    }
+
+    /// The summed (normalized) intensity of the peaks of the target.
+    fn sum_intensities(&self, frame: &Frame) -> f64 {
+        let (scan_start, scan_end) = self.scan_bounds(frame);
+        (scan_start..scan_end.min(frame.scan_count()))
+            .filter(|&scan_index| {
+                self.isolation_mz.is_none_or(|mz| {
+                    frame.quadrupole_settings.isolates(scan_index, mz)
+                })
+            })
+            .filter_map(|scan_index| frame.scan(scan_index))
+            .map(|scan| {
+                let lower = scan
+                    .tof_indices
+                    .partition_point(|&tof| (tof as f64) < self.tof_lower);
+                let upper = scan
+                    .tof_indices
+                    .partition_point(|&tof| (tof as f64) <= self.tof_upper);
+                let offset = frame.scan_offsets[scan.scan_index];
+                (offset + lower..offset + upper)
+                    .map(|index| frame.normalized_intensity(index))
+                    .sum::<f64>()
+            })
+            .sum()
+    }
+}
+
+/// Extracts the chromatograms of many targets in a single pass over the
+/// frames.
+///
+/// The TOF indices within each scan are sorted, so peaks of a target are
+/// found by binary search.
+#[derive(Debug)]
+pub struct XicExtractor {
+    frame_reader: FrameReader,
+    mz_converter: Tof2MzConverter,
+    im_converter: Scan2ImConverter,
+}
+
+impl XicExtractor {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, ChromatogramError> {
+        let metadata = MetadataReader::new(&path)?;
+        let frame_reader = FrameReader::new(&path)?;
+        Ok(Self::from_frame_reader(frame_reader, &metadata))
+    }
+
+    /// An extractor that reads frames with the settings of `frame_reader`.
+    /// Intensities are normalized if `frame_reader` normalizes frames.
+    pub fn from_frame_reader(
+        frame_reader: FrameReader,
+        metadata: &Metadata,
+    ) -> Self {
+        Self {
+            frame_reader,
+            mz_converter: metadata.mz_converter,
+            im_converter: metadata.im_converter,
+        }
+    }
+
+    pub fn frame_reader(&self) -> &FrameReader {
+        &self.frame_reader
+    }
+
+    /// The chromatogram of every target, in the order of the targets. A
+    /// chromatogram contains all frames that the target includes, also
+    /// if the ion was not found in them.
+    pub fn extract(
+        &self,
+        targets: &[XicTarget],
+    ) -> Result<Vec<Chromatogram>, ChromatogramError> {
+        let bounds: Vec<XicBounds> =
+            targets.iter().map(|target| self.bounds(target)).collect();
+        let frames: Vec<(usize, Frame)> = (0..self.frame_reader.len())
+            .map(|index| {
+                self.frame_reader
+                    .get_frame_without_coordinates(index)
+                    .map(|frame| (index, frame))
+            })
+            .collect::<Result<_, _>>()?;
+        let frame_targets: Vec<(usize, f64, Vec<usize>)> = frames
+            .into_iter()
+            .filter_map(|(index, frame)| {
+                let target_indices: Vec<usize> = (0..targets.len())
+                    .filter(|&target| targets[target].keeps(&frame))
+                    .collect();
+                match target_indices.is_empty() {
+                    true => None,
+                    false => Some((index, frame.rt_in_seconds, target_indices)),
+                }
+            })
+            .collect();
+        let intensities: Vec<Vec<f64>> = frame_targets
+            .par_iter()
+            .map(|(index, _, target_indices)| {
+                let frame = self.frame_reader.get(*index)?;
+                Ok(target_indices
+                    .iter()
+                    .map(|&target| bounds[target].sum_intensities(&frame))
+                    .collect())
+            })
+            .collect::<Result<_, FrameReaderError>>()?;
+        let mut chromatograms = vec![Chromatogram::default(); targets.len()];
+        for ((_, rt, target_indices), intensities) in
+            frame_targets.iter().zip(intensities)
+        {
+            for (&target, intensity) in target_indices.iter().zip(intensities) {
+                chromatograms[target].rt.push(*rt);
+                chromatograms[target].intensity.push(intensity);
+            }
+        }
+        Ok(chromatograms)
+    }
+
+    fn bounds(&self, target: &XicTarget) -> XicBounds {
+        let (tof_lower, tof_upper) =
+            tof_bounds(&self.mz_converter, target.mz, target.tolerance_ppm);
+        XicBounds {
+            tof_lower,
+            tof_upper,
+            im_range: target.im_range.clone(),
+            im_converter: self.im_converter,
+            isolation_mz: match target.ms_level {
+                MSLevel::MS2 => target.isolation_mz,
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChromatogramError {
+    #[error("{0}")]
+    FrameReaderError(#[from] FrameReaderError),
+    #[error("{0}")]
+    SqlReaderError(#[from] SqlReaderError),
+    #[error("{0}")]
+    MetadataReaderError(#[from] MetadataReaderError),
+}
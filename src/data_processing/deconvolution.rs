@@ -0,0 +1,347 @@
+//! Charge-state deconvolution of centroided spectra.
+//!
+//! Peaks are grouped into isotope clusters of a single charge state. A
+//! cluster consists of peaks that are spaced by the isotope distance over
+//! the charge in m/z and, if peaks have an ion mobility, that coelute in
+//! mobility. The monoisotopic peak and charge are chosen by comparing the
+//! observed isotope pattern with that of averagine.
+
+use rayon::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain_converters::{ConvertableDomain, Scan2ImConverter},
+    ms_data::Spectrum,
+};
+
+use super::centroid::CentroidedFrame;
+
+pub(super) const PROTON_MASS: f64 = 1.007276;
+/// The mass difference between the 13C and 12C isotopes
+pub(super) const ISOTOPE_SPACING: f64 = 1.0033548;
+/// The mean number of heavy isotopes per Dalton of an averagine peptide,
+/// i.e. the Poisson parameter of its isotope distribution is mass / 1800.
+const AVERAGINE_ISOTOPES_PER_DALTON: f64 = 1.0 / 1800.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct DeconvolutionConfig {
+    /// The maximum m/z deviation of an isotope peak from its expected m/z
+    pub tolerance_ppm: f64,
+    /// The maximum ion mobility (1/K0) difference of an isotope peak from
+    /// the most intense peak of the cluster
+    pub im_tolerance: f64,
+    pub min_charge: usize,
+    pub max_charge: usize,
+    /// Clusters with fewer peaks are discarded
+    pub min_peaks: usize,
+    /// Clusters whose pattern is less similar (cosine) to averagine are
+    /// discarded
+    pub min_score: f64,
+}
+
+impl Default for DeconvolutionConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_ppm: 10.0,
+            im_tolerance: 0.02,
+            min_charge: 1,
+            max_charge: 6,
+            min_peaks: 2,
+            min_score: 0.8,
+        }
+    }
+}
+
+/// A centroided peak, optionally with an ion mobility.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DeconvolutionPeak {
+    pub mz: f64,
+    pub intensity: f64,
+    pub im: Option<f64>,
+}
+
+impl DeconvolutionPeak {
+    /// All peaks of a (centroided) spectrum, without ion mobility.
+    pub fn from_spectrum(spectrum: &Spectrum) -> Vec<Self> {
+        spectrum
+            .mz_values
+            .iter()
+            .zip(&spectrum.intensities)
+            .map(|(&mz, &intensity)| Self {
+                mz,
+                intensity,
+                im: None,
+            })
+            .collect()
+    }
+
+    /// All peaks of a centroided frame, with the ion mobility of their
+    /// scan.
+    pub fn from_centroided_frame(
+        frame: &CentroidedFrame,
+        im_converter: &Scan2ImConverter,
+    ) -> Vec<Self> {
+        let mut peaks = Vec::with_capacity(frame.len());
+        for (scan, offsets) in frame.scan_offsets.windows(2).enumerate() {
+            let im = im_converter.convert(scan as f64);
+            for index in offsets[0]..offsets[1] {
+                peaks.push(Self {
+                    mz: frame.mz_values[index],
+                    intensity: frame.intensities[index],
+                    im: Some(im),
+                });
+            }
+        }
+        peaks
+    }
+}
+
+/// The isotope peaks of a single charge state of an analyte.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct IsotopeCluster {
+    pub monoisotopic_mz: f64,
+    /// The neutral monoisotopic mass
+    pub monoisotopic_mass: f64,
+    pub charge: usize,
+    /// The summed intensity of all peaks
+    pub intensity: f64,
+    /// The intensity-weighted ion mobility of all peaks, if they have one
+    pub im: Option<f64>,
+    /// The indices of the peaks, from the monoisotopic peak upwards
+    pub peaks: Vec<usize>,
+    /// The cosine similarity of the isotope pattern with averagine
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Deconvoluter {
+    config: DeconvolutionConfig,
+}
+
+impl Deconvoluter {
+    pub fn new(config: DeconvolutionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Group peaks into isotope clusters, starting from the most intense
+    /// peak. Every peak belongs to at most one cluster; peaks that belong
+    /// to none are not reported.
+    pub fn deconvolute(
+        &self,
+        peaks: &[DeconvolutionPeak],
+    ) -> Vec<IsotopeCluster> {
+        let mut by_mz: Vec<usize> = (0..peaks.len()).collect();
+        by_mz.sort_by(|&a, &b| peaks[a].mz.total_cmp(&peaks[b].mz));
+        let mut by_intensity = by_mz.clone();
+        by_intensity.sort_by(|&a, &b| {
+            peaks[b].intensity.total_cmp(&peaks[a].intensity)
+        });
+        let mut used = vec![false; peaks.len()];
+        let mut clusters = vec![];
+        for seed in by_intensity {
+            if used[seed] {
+                continue;
+            }
+            let best = (self.config.min_charge..=self.config.max_charge)
+                .filter(|&charge| charge > 0)
+                .filter_map(|charge| {
+                    self.cluster(peaks, &by_mz, &used, seed, charge)
+                })
+                .max_by(|a, b| a.score.total_cmp(&b.score));
+            if let Some(cluster) = best {
+                for &peak in cluster.peaks.iter() {
+                    used[peak] = true;
+                }
+                clusters.push(cluster);
+            }
+        }
+        clusters
+            .sort_by(|a, b| a.monoisotopic_mz.total_cmp(&b.monoisotopic_mz));
+        clusters
+    }
+
+    /// Deconvolute multiple spectra in parallel.
+    pub fn deconvolute_spectra(
+        &self,
+        spectra: &[Spectrum],
+    ) -> Vec<Vec<IsotopeCluster>> {
+        spectra
+            .par_iter()
+            .map(|spectrum| {
+                self.deconvolute(&DeconvolutionPeak::from_spectrum(spectrum))
+            })
+            .collect()
+    }
+
+    /// The best cluster of a charge that contains `seed`.
+    fn cluster(
+        &self,
+        peaks: &[DeconvolutionPeak],
+        by_mz: &[usize],
+        used: &[bool],
+        seed: usize,
+        charge: usize,
+    ) -> Option<IsotopeCluster> {
+        let spacing = ISOTOPE_SPACING / charge as f64;
+        let find = |isotope: i32| {
+            let mz = peaks[seed].mz + isotope as f64 * spacing;
+            self.find_peak(peaks, by_mz, used, mz, peaks[seed].im)
+        };
+        let mut lower = vec![];
+        while let Some(peak) = find(-(lower.len() as i32) - 1) {
+            lower.push(peak);
+        }
+        let mut chain: Vec<usize> = lower.into_iter().rev().collect();
+        let seed_position = chain.len();
+        chain.push(seed);
+        let mut isotope = 1;
+        while let Some(peak) = find(isotope) {
+            chain.push(peak);
+            isotope += 1;
+        }
+        if chain.len() < self.config.min_peaks {
+            return None;
+        }
+        (0..=seed_position)
+            .filter(|&start| chain.len() - start >= self.config.min_peaks)
+            .map(|start| self.score(peaks, &chain[start..], charge))
+            .filter(|cluster| cluster.score >= self.config.min_score)
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+    }
+
+    /// The most intense unused peak within tolerance of `mz` and `im`.
+    fn find_peak(
+        &self,
+        peaks: &[DeconvolutionPeak],
+        by_mz: &[usize],
+        used: &[bool],
+        mz: f64,
+        im: Option<f64>,
+    ) -> Option<usize> {
+        let delta = mz * self.config.tolerance_ppm / 1e6;
+        let start = by_mz.partition_point(|&peak| peaks[peak].mz < mz - delta);
+        by_mz[start..]
+            .iter()
+            .take_while(|&&peak| peaks[peak].mz <= mz + delta)
+            .filter(|&&peak| !used[peak])
+            .filter(|&&peak| match (im, peaks[peak].im) {
+                (Some(im), Some(peak_im)) => {
+                    (peak_im - im).abs() <= self.config.im_tolerance
+                },
+                _ => true,
+            })
+            .copied()
+            .max_by(|&a, &b| peaks[a].intensity.total_cmp(&peaks[b].intensity))
+    }
+
+    fn score(
+        &self,
+        peaks: &[DeconvolutionPeak],
+        chain: &[usize],
+        charge: usize,
+    ) -> IsotopeCluster {
+        let monoisotopic_mz = peaks[chain[0]].mz;
+        let monoisotopic_mass = (monoisotopic_mz - PROTON_MASS) * charge as f64;
+        let expected = averagine_distribution(monoisotopic_mass, chain.len());
+        let observed: Vec<f64> =
+            chain.iter().map(|&peak| peaks[peak].intensity).collect();
+        let intensity: f64 = observed.iter().sum();
+        let im = chain
+            .iter()
+            .map(|&peak| peaks[peak].im.map(|im| im * peaks[peak].intensity))
+            .sum::<Option<f64>>()
+            .map(|weighted| weighted / intensity);
+        IsotopeCluster {
+            monoisotopic_mz,
+            monoisotopic_mass,
+            charge,
+            intensity,
+            im,
+            peaks: chain.to_vec(),
+            score: cosine_similarity(&observed, &expected),
+        }
+    }
+}
+
+/// The relative intensities of the first isotopes of averagine with a
+/// monoisotopic `mass`, approximated by a Poisson distribution.
+pub(super) fn averagine_distribution(
+    mass: f64,
+    isotope_count: usize,
+) -> Vec<f64> {
+    let lambda = mass.max(0.0) * AVERAGINE_ISOTOPES_PER_DALTON;
+    let mut probability = (-lambda).exp();
+    let mut distribution = Vec::with_capacity(isotope_count);
+    for isotope in 0..isotope_count {
+        distribution.push(probability);
+        probability *= lambda / (isotope + 1) as f64;
+    }
+    distribution
+}
+
+pub(super) fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f64 = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b: f64 = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if (norm_a == 0.0) | (norm_b == 0.0) {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The isotope peaks of averagine with a monoisotopic mass and charge.
+    fn isotope_peaks(
+        mass: f64,
+        charge: usize,
+        scale: f64,
+        im: Option<f64>,
+    ) -> Vec<DeconvolutionPeak> {
+        let mz = mass / charge as f64 + PROTON_MASS;
+        averagine_distribution(mass, 4)
+            .into_iter()
+            .enumerate()
+            .map(|(isotope, intensity)| DeconvolutionPeak {
+                mz: mz + isotope as f64 * ISOTOPE_SPACING / charge as f64,
+                intensity: scale * intensity,
+                im,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_charge_and_monoisotopic_peak() {
+        let mut peaks = isotope_peaks(2000.0, 2, 100.0, None);
+        peaks.extend(isotope_peaks(1500.0, 3, 50.0, None));
+        peaks.push(DeconvolutionPeak {
+            mz: 700.0,
+            intensity: 10.0,
+            im: None,
+        });
+        let clusters = Deconvoluter::default().deconvolute(&peaks);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].charge, 3);
+        assert!((clusters[0].monoisotopic_mass - 1500.0).abs() < 1e-6);
+        assert_eq!(clusters[1].charge, 2);
+        assert!((clusters[1].monoisotopic_mass - 2000.0).abs() < 1e-6);
+        assert_eq!(clusters[1].peaks, vec![0, 1, 2, 3]);
+        assert!(clusters[1].score > 0.99);
+    }
+
+    #[test]
+    fn separates_clusters_by_mobility() {
+        let mut peaks = isotope_peaks(1000.0, 1, 100.0, Some(0.8));
+        peaks.extend(isotope_peaks(1001.00335, 1, 100.0, Some(1.2)));
+        let clusters = Deconvoluter::default().deconvolute(&peaks);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].peaks, vec![0, 1, 2, 3]);
+        assert_eq!(clusters[0].im, Some(0.8));
+        assert_eq!(clusters[1].im, Some(1.2));
+    }
+}
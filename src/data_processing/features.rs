@@ -0,0 +1,469 @@
+//! Detection of LC-IM-m/z features in centroided MS1 frames.
+//!
+//! A reference implementation in three steps:
+//!
+//! 1. The peaks of every frame are merged over neighbouring scans into
+//!    mobility peaks at the apex of their mobility profile.
+//! 2. Mobility peaks of consecutive frames with the same m/z and mobility
+//!    are linked into traces over retention time.
+//! 3. Traces whose apexes coelute in retention time and mobility are
+//!    grouped into isotope clusters, of which the charge and monoisotopic
+//!    trace are chosen by comparison with averagine (see
+//!    [deconvolution](super::deconvolution)).
+
+use rayon::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::domain_converters::Scan2ImConverter;
+
+use super::{
+    centroid::CentroidedFrame,
+    deconvolution::{
+        averagine_distribution, cosine_similarity, DeconvolutionConfig,
+        DeconvolutionPeak, ISOTOPE_SPACING, PROTON_MASS,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FeatureFinderConfig {
+    /// The m/z and mobility tolerances of peaks of the same trace and
+    /// the isotope settings
+    pub deconvolution: DeconvolutionConfig,
+    /// The maximum number of consecutive frames a trace may be missing in
+    pub max_gap: usize,
+    /// Traces over fewer frames are discarded
+    pub min_frames: usize,
+    /// The maximum retention time difference (in seconds) between the
+    /// apexes of isotope traces of a feature
+    pub rt_tolerance: f64,
+}
+
+impl Default for FeatureFinderConfig {
+    fn default() -> Self {
+        Self {
+            deconvolution: DeconvolutionConfig::default(),
+            max_gap: 1,
+            min_frames: 3,
+            rt_tolerance: 2.0,
+        }
+    }
+}
+
+/// The extent of a feature in retention time (seconds) and mobility (1/K0).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FeatureBoundary {
+    pub rt_start: f64,
+    pub rt_end: f64,
+    pub im_start: f64,
+    pub im_end: f64,
+}
+
+impl FeatureBoundary {
+    fn union(&self, other: &Self) -> Self {
+        Self {
+            rt_start: self.rt_start.min(other.rt_start),
+            rt_end: self.rt_end.max(other.rt_end),
+            im_start: self.im_start.min(other.im_start),
+            im_end: self.im_end.max(other.im_end),
+        }
+    }
+}
+
+/// An analyte detected over retention time, ion mobility and m/z.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Feature {
+    /// The monoisotopic m/z
+    pub mz: f64,
+    pub charge: usize,
+    /// The retention time (in seconds) at which the monoisotopic trace is
+    /// most intense
+    pub rt_apex: f64,
+    /// The ion mobility (1/K0) at which the monoisotopic trace is most
+    /// intense
+    pub im_apex: f64,
+    /// The summed intensity of all isotope traces
+    pub intensity: f64,
+    pub boundary: FeatureBoundary,
+    pub isotope_count: usize,
+    /// The cosine similarity of the isotope pattern with averagine
+    pub score: f64,
+}
+
+/// A peak of a single frame, merged over scans.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MobilityPeak {
+    mz: f64,
+    im: f64,
+    intensity: f64,
+}
+
+/// A mobility peak traced over consecutive frames.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Trace {
+    /// The position of the frame of the last peak
+    last_frame: usize,
+    /// The m/z and mobility of the last peak
+    mz: f64,
+    im: f64,
+    points: Vec<(f64, MobilityPeak)>,
+}
+
+/// A trace summarized by its apex.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TracePeak {
+    mz: f64,
+    rt_apex: f64,
+    im_apex: f64,
+    intensity: f64,
+    boundary: FeatureBoundary,
+}
+
+impl Trace {
+    fn summarize(&self) -> TracePeak {
+        let (rt_apex, apex) = self
+            .points
+            .iter()
+            .max_by(|a, b| a.1.intensity.total_cmp(&b.1.intensity))
+            .copied()
+            .expect("Traces are never empty");
+        let intensity: f64 = self.points.iter().map(|x| x.1.intensity).sum();
+        let weighted_mz: f64 =
+            self.points.iter().map(|x| x.1.mz * x.1.intensity).sum();
+        let fold = |f: fn(f64, f64) -> f64,
+                    value: fn(&(f64, MobilityPeak)) -> f64| {
+            self.points.iter().map(value).reduce(f).unwrap_or(0.0)
+        };
+        TracePeak {
+            mz: weighted_mz / intensity,
+            rt_apex,
+            im_apex: apex.im,
+            intensity,
+            boundary: FeatureBoundary {
+                rt_start: fold(f64::min, |x| x.0),
+                rt_end: fold(f64::max, |x| x.0),
+                im_start: fold(f64::min, |x| x.1.im),
+                im_end: fold(f64::max, |x| x.1.im),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeatureFinder {
+    config: FeatureFinderConfig,
+}
+
+impl FeatureFinder {
+    pub fn new(config: FeatureFinderConfig) -> Self {
+        Self { config }
+    }
+
+    /// Detect features in centroided MS1 frames (in any order), sorted by
+    /// monoisotopic m/z.
+    pub fn find_features(
+        &self,
+        frames: &[CentroidedFrame],
+        im_converter: &Scan2ImConverter,
+    ) -> Vec<Feature> {
+        let mut frames: Vec<&CentroidedFrame> = frames.iter().collect();
+        frames.sort_by(|a, b| a.rt_in_seconds.total_cmp(&b.rt_in_seconds));
+        let mobility_peaks: Vec<Vec<MobilityPeak>> = frames
+            .par_iter()
+            .map(|frame| {
+                self.mobility_peaks(&DeconvolutionPeak::from_centroided_frame(
+                    frame,
+                    im_converter,
+                ))
+            })
+            .collect();
+        let rts: Vec<f64> = frames.iter().map(|x| x.rt_in_seconds).collect();
+        let traces: Vec<TracePeak> = self
+            .trace(&rts, mobility_peaks)
+            .iter()
+            .filter(|trace| trace.points.len() >= self.config.min_frames)
+            .map(|trace| trace.summarize())
+            .collect();
+        self.group_isotopes(&traces)
+    }
+
+    /// Merge the peaks of a frame that are within tolerance of the most
+    /// intense peak in m/z and mobility, starting from the most intense.
+    fn mobility_peaks(&self, peaks: &[DeconvolutionPeak]) -> Vec<MobilityPeak> {
+        let tolerance = &self.config.deconvolution;
+        let mut by_mz: Vec<usize> = (0..peaks.len()).collect();
+        by_mz.sort_by(|&a, &b| peaks[a].mz.total_cmp(&peaks[b].mz));
+        let mut by_intensity = by_mz.clone();
+        by_intensity.sort_by(|&a, &b| {
+            peaks[b].intensity.total_cmp(&peaks[a].intensity)
+        });
+        let mut used = vec![false; peaks.len()];
+        let mut mobility_peaks = vec![];
+        for seed in by_intensity {
+            if used[seed] {
+                continue;
+            }
+            let (mz, im) = (peaks[seed].mz, peaks[seed].im.unwrap_or(0.0));
+            let delta = mz * tolerance.tolerance_ppm / 1e6;
+            let start = by_mz.partition_point(|&x| peaks[x].mz < mz - delta);
+            let mut intensity = 0.0;
+            let mut weighted_mz = 0.0;
+            for &peak in by_mz[start..]
+                .iter()
+                .take_while(|&&x| peaks[x].mz <= mz + delta)
+            {
+                let peak_im = peaks[peak].im.unwrap_or(0.0);
+                if used[peak] || ((peak_im - im).abs() > tolerance.im_tolerance)
+                {
+                    continue;
+                }
+                used[peak] = true;
+                intensity += peaks[peak].intensity;
+                weighted_mz += peaks[peak].mz * peaks[peak].intensity;
+            }
+            mobility_peaks.push(MobilityPeak {
+                mz: weighted_mz / intensity,
+                im,
+                intensity,
+            });
+        }
+        mobility_peaks
+    }
+
+    /// Link the mobility peaks of all frames into traces. Every peak
+    /// extends the trace with the closest m/z that is within tolerance and
+    /// was not extended in the same frame, or starts a new trace.
+    fn trace(&self, rts: &[f64], frames: Vec<Vec<MobilityPeak>>) -> Vec<Trace> {
+        let tolerance = &self.config.deconvolution;
+        let mut finished = vec![];
+        let mut active: Vec<Trace> = vec![];
+        for (position, mut peaks) in frames.into_iter().enumerate() {
+            let (alive, retired) = active.into_iter().partition(|trace| {
+                position - trace.last_frame <= self.config.max_gap + 1
+            });
+            active = alive;
+            finished.extend::<Vec<Trace>>(retired);
+            active.sort_by(|a, b| a.mz.total_cmp(&b.mz));
+            peaks.sort_by(|a, b| b.intensity.total_cmp(&a.intensity));
+            let active_count = active.len();
+            for peak in peaks {
+                let delta = peak.mz * tolerance.tolerance_ppm / 1e6;
+                let start = active[..active_count]
+                    .partition_point(|trace| trace.mz < peak.mz - delta);
+                let closest = (start..active_count)
+                    .take_while(|&x| active[x].mz <= peak.mz + delta)
+                    .filter(|&x| active[x].last_frame < position)
+                    .filter(|&x| {
+                        (active[x].im - peak.im).abs() <= tolerance.im_tolerance
+                    })
+                    .min_by(|&a, &b| {
+                        (active[a].mz - peak.mz)
+                            .abs()
+                            .total_cmp(&(active[b].mz - peak.mz).abs())
+                    });
+                let trace = match closest {
+                    Some(x) => &mut active[x],
+                    None => {
+                        active.push(Trace::default());
+                        active.last_mut().expect("A trace was just added")
+                    },
+                };
+                trace.last_frame = position;
+                trace.mz = peak.mz;
+                trace.im = peak.im;
+                trace.points.push((rts[position], peak));
+            }
+        }
+        finished.extend(active);
+        finished
+    }
+
+    /// Group coeluting traces into isotope clusters, starting from the
+    /// most intense trace.
+    fn group_isotopes(&self, traces: &[TracePeak]) -> Vec<Feature> {
+        let config = &self.config.deconvolution;
+        let mut by_mz: Vec<usize> = (0..traces.len()).collect();
+        by_mz.sort_by(|&a, &b| traces[a].mz.total_cmp(&traces[b].mz));
+        let mut by_intensity = by_mz.clone();
+        by_intensity.sort_by(|&a, &b| {
+            traces[b].intensity.total_cmp(&traces[a].intensity)
+        });
+        let mut used = vec![false; traces.len()];
+        let mut features = vec![];
+        for seed in by_intensity {
+            if used[seed] {
+                continue;
+            }
+            let best = (config.min_charge..=config.max_charge)
+                .filter(|&charge| charge > 0)
+                .filter_map(|charge| {
+                    self.cluster(traces, &by_mz, &used, seed, charge)
+                })
+                .max_by(|a, b| a.1.score.total_cmp(&b.1.score));
+            if let Some((chain, feature)) = best {
+                for trace in chain {
+                    used[trace] = true;
+                }
+                features.push(feature);
+            }
+        }
+        features.sort_by(|a, b| a.mz.total_cmp(&b.mz));
+        features
+    }
+
+    /// The best isotope cluster of a charge that contains `seed`.
+    fn cluster(
+        &self,
+        traces: &[TracePeak],
+        by_mz: &[usize],
+        used: &[bool],
+        seed: usize,
+        charge: usize,
+    ) -> Option<(Vec<usize>, Feature)> {
+        let config = &self.config.deconvolution;
+        let spacing = ISOTOPE_SPACING / charge as f64;
+        let find = |isotope: i32| {
+            let mz = traces[seed].mz + isotope as f64 * spacing;
+            let delta = mz * config.tolerance_ppm / 1e6;
+            let start = by_mz.partition_point(|&x| traces[x].mz < mz - delta);
+            by_mz[start..]
+                .iter()
+                .take_while(|&&x| traces[x].mz <= mz + delta)
+                .filter(|&&x| !used[x])
+                .filter(|&&x| {
+                    ((traces[x].rt_apex - traces[seed].rt_apex).abs()
+                        <= self.config.rt_tolerance)
+                        && ((traces[x].im_apex - traces[seed].im_apex).abs()
+                            <= config.im_tolerance)
+                })
+                .copied()
+                .max_by(|&a, &b| {
+                    traces[a].intensity.total_cmp(&traces[b].intensity)
+                })
+        };
+        let mut lower = vec![];
+        while let Some(trace) = find(-(lower.len() as i32) - 1) {
+            lower.push(trace);
+        }
+        let mut chain: Vec<usize> = lower.into_iter().rev().collect();
+        let seed_position = chain.len();
+        chain.push(seed);
+        let mut isotope = 1;
+        while let Some(trace) = find(isotope) {
+            chain.push(trace);
+            isotope += 1;
+        }
+        (0..=seed_position)
+            .filter(|&start| chain.len() - start >= config.min_peaks)
+            .map(|start| {
+                let chain = chain[start..].to_vec();
+                let feature = feature(traces, &chain, charge);
+                (chain, feature)
+            })
+            .filter(|(_, feature)| feature.score >= config.min_score)
+            .max_by(|a, b| a.1.score.total_cmp(&b.1.score))
+    }
+}
+
+fn feature(traces: &[TracePeak], chain: &[usize], charge: usize) -> Feature {
+    let monoisotopic = &traces[chain[0]];
+    let mass = (monoisotopic.mz - PROTON_MASS) * charge as f64;
+    let observed: Vec<f64> =
+        chain.iter().map(|&x| traces[x].intensity).collect();
+    let boundary = chain
+        .iter()
+        .map(|&x| traces[x].boundary)
+        .reduce(|a, b| a.union(&b))
+        .expect("Chains are never empty");
+    Feature {
+        mz: monoisotopic.mz,
+        charge,
+        rt_apex: monoisotopic.rt_apex,
+        im_apex: monoisotopic.im_apex,
+        intensity: observed.iter().sum(),
+        boundary,
+        isotope_count: chain.len(),
+        score: cosine_similarity(
+            &observed,
+            &averagine_distribution(mass, chain.len()),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain_converters::ConvertableDomain;
+
+    /// A frame with the first isotopes of an averagine analyte of `mass`
+    /// and `charge`, spread over three scans around `scan`.
+    fn frame(
+        rt: f64,
+        analytes: &[(f64, usize, usize, f64)],
+    ) -> CentroidedFrame {
+        let mut scans: Vec<Vec<(f64, f64)>> = vec![vec![]; 20];
+        for &(mass, charge, scan, scale) in analytes {
+            let mz = mass / charge as f64 + PROTON_MASS;
+            for (isotope, intensity) in
+                averagine_distribution(mass, 3).into_iter().enumerate()
+            {
+                let isotope_mz =
+                    mz + isotope as f64 * ISOTOPE_SPACING / charge as f64;
+                for (offset, factor) in [(0, 0.5), (1, 1.0), (2, 0.5)] {
+                    scans[scan + offset - 1]
+                        .push((isotope_mz, scale * factor * intensity));
+                }
+            }
+        }
+        let mut frame = CentroidedFrame {
+            rt_in_seconds: rt,
+            scan_offsets: vec![0],
+            ..Default::default()
+        };
+        for mut peaks in scans {
+            peaks.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for (mz, intensity) in peaks {
+                frame.mz_values.push(mz);
+                frame.intensities.push(intensity);
+            }
+            frame.scan_offsets.push(frame.mz_values.len());
+        }
+        frame.tof_centroids = vec![0.0; frame.mz_values.len()];
+        frame
+    }
+
+    #[test]
+    fn finds_features() {
+        let im_converter = Scan2ImConverter::from_boundaries(0.6, 1.6, 1000);
+        let profile = [0.2, 0.6, 1.0, 0.6, 0.2];
+        let frames: Vec<CentroidedFrame> = profile
+            .iter()
+            .enumerate()
+            .map(|(index, &scale)| {
+                frame(
+                    index as f64,
+                    &[(1500.0, 2, 5, 100.0 * scale), (2400.0, 3, 12, scale)],
+                )
+            })
+            .collect();
+        let features =
+            FeatureFinder::default().find_features(&frames, &im_converter);
+        assert_eq!(features.len(), 2);
+        let feature = &features[0];
+        assert_eq!(feature.charge, 2);
+        assert!((feature.mz - (1500.0 / 2.0 + PROTON_MASS)).abs() < 1e-6);
+        assert!(feature.score > 0.99);
+        let feature = &features[1];
+        assert_eq!(feature.charge, 3);
+        assert!((feature.mz - (2400.0 / 3.0 + PROTON_MASS)).abs() < 1e-6);
+        assert_eq!(feature.rt_apex, 2.0);
+        assert_eq!(feature.boundary.rt_start, 0.0);
+        assert_eq!(feature.boundary.rt_end, 4.0);
+        assert_eq!(feature.isotope_count, 3);
+        assert!((feature.im_apex - im_converter.convert(12.0)).abs() < 1e-9);
+        assert!(feature.score > 0.99);
+    }
+}
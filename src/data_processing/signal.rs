@@ -0,0 +1,389 @@
+//! Smoothing and baseline correction of TOF intensity profiles.
+//!
+//! Profiles are dense: every TOF bin between the first and last peak of a
+//! scan (or of a summed frame) has an intensity. Processed profiles can be
+//! converted back into peaks (e.g. for [centroiding](super::centroid)).
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::ms_data::Frame;
+
+/// The intensities of consecutive TOF bins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TofProfile {
+    /// The TOF index of the first bin
+    pub first_tof_index: u32,
+    pub intensities: Vec<f64>,
+}
+
+impl TofProfile {
+    /// The profile of a single scan, given its TOF indices and
+    /// intensities. Duplicate TOF indices are summed.
+    pub fn from_scan(tof_indices: &[u32], intensities: &[u32]) -> Self {
+        let (Some(&min), Some(&max)) =
+            (tof_indices.iter().min(), tof_indices.iter().max())
+        else {
+            return Self::default();
+        };
+        let mut profile = Self {
+            first_tof_index: min,
+            intensities: vec![0.0; (max - min) as usize + 1],
+        };
+        for (&tof, &intensity) in tof_indices.iter().zip(intensities) {
+            profile.intensities[(tof - min) as usize] += intensity as f64;
+        }
+        profile
+    }
+
+    /// The profile of all scans of a frame summed, e.g. the spectrum of a
+    /// MALDI spot.
+    pub fn from_frame(frame: &Frame) -> Self {
+        Self::from_scan(&frame.tof_indices, &frame.intensities)
+    }
+
+    pub fn len(&self) -> usize {
+        self.intensities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intensities.is_empty()
+    }
+
+    pub fn tof_index(&self, bin: usize) -> u32 {
+        self.first_tof_index + bin as u32
+    }
+
+    /// The bins with a (rounded) intensity above zero as sorted TOF
+    /// indices and intensities.
+    pub fn to_peaks(&self) -> (Vec<u32>, Vec<u32>) {
+        let mut tof_indices = vec![];
+        let mut intensities = vec![];
+        for (bin, &intensity) in self.intensities.iter().enumerate() {
+            let rounded = intensity.round();
+            if rounded > 0.0 {
+                tof_indices.push(self.tof_index(bin));
+                intensities.push(rounded as u32);
+            }
+        }
+        (tof_indices, intensities)
+    }
+}
+
+/// A Savitzky-Golay filter, i.e. a moving least-squares polynomial fit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SavitzkyGolay {
+    /// The number of bins on each side of the smoothed bin
+    pub half_window: usize,
+    /// Must be lower than the window size (2 * half_window + 1)
+    pub polynomial_order: usize,
+}
+
+impl Default for SavitzkyGolay {
+    fn default() -> Self {
+        Self {
+            half_window: 3,
+            polynomial_order: 2,
+        }
+    }
+}
+
+impl SavitzkyGolay {
+    /// Smooth `intensities`. Bins closer than `half_window` to an edge are
+    /// fitted with an asymmetric window of the same size.
+    pub fn smooth(&self, intensities: &[f64]) -> Vec<f64> {
+        let length = intensities.len();
+        let window = 2 * self.half_window + 1;
+        if (length < window) | (self.polynomial_order >= window) {
+            return intensities.to_vec();
+        }
+        let central = self.coefficients(self.half_window);
+        let edge: Vec<Vec<f64>> = (0..self.half_window)
+            .map(|position| self.coefficients(position))
+            .collect();
+        (0..length)
+            .map(|bin| {
+                if bin < self.half_window {
+                    dot(&edge[bin], &intensities[..window])
+                } else if bin + self.half_window >= length {
+                    // Mirrored, the bin is as far from the end of the
+                    // window as it is from the end of the profile
+                    let values = intensities[length - window..].iter().rev();
+                    dot(&edge[length - 1 - bin], values)
+                } else {
+                    let start = bin - self.half_window;
+                    dot(&central, &intensities[start..start + window])
+                }
+            })
+            .collect()
+    }
+
+    /// The weights of all bins of a window when evaluating the fit at
+    /// bin `position` of the window.
+    fn coefficients(&self, position: usize) -> Vec<f64> {
+        let window = 2 * self.half_window + 1;
+        let order = self.polynomial_order + 1;
+        let offsets: Vec<f64> =
+            (0..window).map(|x| x as f64 - position as f64).collect();
+        // The fit evaluated at offset 0 is the constant term, i.e. the
+        // first row of (A^T A)^-1 A^T for the Vandermonde matrix A.
+        let mut normal: Vec<Vec<f64>> = (0..order)
+            .map(|row| {
+                (0..order)
+                    .map(|column| {
+                        offsets
+                            .iter()
+                            .map(|x| x.powi((row + column) as i32))
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut unit = vec![0.0; order];
+        unit[0] = 1.0;
+        let solution = solve(&mut normal, unit);
+        offsets
+            .iter()
+            .map(|x| {
+                solution
+                    .iter()
+                    .enumerate()
+                    .map(|(power, s)| s * x.powi(power as i32))
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+fn dot<'a>(
+    coefficients: &[f64],
+    values: impl IntoIterator<Item = &'a f64>,
+) -> f64 {
+    coefficients.iter().zip(values).map(|(c, x)| c * x).sum()
+}
+
+/// Solve a small symmetric positive definite system by Gaussian
+/// elimination with partial pivoting.
+fn solve(matrix: &mut [Vec<f64>], mut rhs: Vec<f64>) -> Vec<f64> {
+    let size = rhs.len();
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|&a, &b| {
+                matrix[a][column].abs().total_cmp(&matrix[b][column].abs())
+            })
+            .expect("The system is not empty");
+        matrix.swap(column, pivot);
+        rhs.swap(column, pivot);
+        for row in column + 1..size {
+            let (pivot_rows, rows) = matrix.split_at_mut(row);
+            let pivot_row = &pivot_rows[column];
+            let factor = rows[0][column] / pivot_row[column];
+            for (value, pivot_value) in rows[0][column..size]
+                .iter_mut()
+                .zip(&pivot_row[column..size])
+            {
+                *value -= factor * pivot_value;
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+    let mut solution = vec![0.0; size];
+    for row in (0..size).rev() {
+        let known: f64 =
+            (row + 1..size).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (rhs[row] - known) / matrix[row][row];
+    }
+    solution
+}
+
+/// The estimation of the baseline that is subtracted from a profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum BaselineCorrection {
+    /// A morphological opening (moving minimum followed by moving
+    /// maximum). Peaks narrower than the window are kept.
+    TopHat { half_window: usize },
+    /// A rolling ball: the opening is additionally smoothed with a moving
+    /// average, which avoids steps in the baseline.
+    RollingBall {
+        half_window: usize,
+        smoothing_half_window: usize,
+    },
+}
+
+impl BaselineCorrection {
+    pub fn baseline(&self, intensities: &[f64]) -> Vec<f64> {
+        match *self {
+            Self::TopHat { half_window } => opening(intensities, half_window),
+            Self::RollingBall {
+                half_window,
+                smoothing_half_window,
+            } => moving_average(
+                &opening(intensities, half_window),
+                smoothing_half_window,
+            ),
+        }
+    }
+
+    /// Subtract the baseline, clamping at zero.
+    pub fn correct(&self, intensities: &[f64]) -> Vec<f64> {
+        intensities
+            .iter()
+            .zip(self.baseline(intensities))
+            .map(|(x, baseline)| (x - baseline).max(0.0))
+            .collect()
+    }
+}
+
+fn opening(intensities: &[f64], half_window: usize) -> Vec<f64> {
+    let eroded = moving_extreme(intensities, half_window, |a, b| a <= b);
+    moving_extreme(&eroded, half_window, |a, b| a >= b)
+}
+
+/// The moving minimum or maximum (with a monotonic queue), depending on
+/// whether `keep(a, b)` means that `a` is at least as extreme as `b`.
+fn moving_extreme(
+    values: &[f64],
+    half_window: usize,
+    keep: fn(f64, f64) -> bool,
+) -> Vec<f64> {
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    let mut extremes = Vec::with_capacity(values.len());
+    let mut next = 0;
+    for bin in 0..values.len() {
+        while (next < values.len()) & (next <= bin + half_window) {
+            while queue.back().is_some_and(|&x| keep(values[next], values[x])) {
+                queue.pop_back();
+            }
+            queue.push_back(next);
+            next += 1;
+        }
+        while queue.front().is_some_and(|&x| x + half_window < bin) {
+            queue.pop_front();
+        }
+        extremes.push(values[queue[0]]);
+    }
+    extremes
+}
+
+fn moving_average(values: &[f64], half_window: usize) -> Vec<f64> {
+    let mut cumulative = Vec::with_capacity(values.len() + 1);
+    cumulative.push(0.0);
+    for value in values {
+        cumulative.push(cumulative.last().unwrap_or(&0.0) + value);
+    }
+    (0..values.len())
+        .map(|bin| {
+            let start = bin.saturating_sub(half_window);
+            let end = (bin + half_window + 1).min(values.len());
+            (cumulative[end] - cumulative[start]) / (end - start) as f64
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SignalConfig {
+    pub smoothing: Option<SavitzkyGolay>,
+    pub baseline: Option<BaselineCorrection>,
+}
+
+/// Smooths profiles and then subtracts their baseline.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SignalProcessor {
+    config: SignalConfig,
+}
+
+impl SignalProcessor {
+    pub fn new(config: SignalConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn process(&self, profile: &TofProfile) -> TofProfile {
+        let mut intensities = match &self.config.smoothing {
+            Some(smoothing) => smoothing.smooth(&profile.intensities),
+            None => profile.intensities.clone(),
+        };
+        if let Some(baseline) = &self.config.baseline {
+            intensities = baseline.correct(&intensities);
+        }
+        TofProfile {
+            first_tof_index: profile.first_tof_index,
+            intensities,
+        }
+    }
+
+    /// Process a single scan of a frame.
+    pub fn process_scan(&self, frame: &Frame, scan: usize) -> TofProfile {
+        match frame.scan(scan) {
+            Some(scan) => self.process(&TofProfile::from_scan(
+                scan.tof_indices,
+                scan.intensities,
+            )),
+            None => TofProfile::default(),
+        }
+    }
+
+    /// Process the sum of all scans of a frame.
+    pub fn process_frame(&self, frame: &Frame) -> TofProfile {
+        self.process(&TofProfile::from_frame(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-6, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn builds_profiles() {
+        let profile = TofProfile::from_scan(&[12, 10, 12], &[1, 2, 3]);
+        assert_eq!(profile.first_tof_index, 10);
+        assert_eq!(profile.intensities, vec![2.0, 0.0, 4.0]);
+        assert_eq!(profile.to_peaks(), (vec![10, 12], vec![2, 4]));
+    }
+
+    #[test]
+    fn savitzky_golay_preserves_polynomials() {
+        let quadratic: Vec<f64> =
+            (0..12).map(|x| (x * x) as f64 - 3.0 * x as f64).collect();
+        let smoothing = SavitzkyGolay::default();
+        assert_close(&smoothing.smooth(&quadratic), &quadratic);
+        let spike = [0.0, 0.0, 0.0, 0.0, 0.0, 7.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let smoothed = SavitzkyGolay {
+            half_window: 2,
+            polynomial_order: 0,
+        }
+        .smooth(&spike);
+        let expected = [0.0, 0.0, 0.0, 1.4, 1.4, 1.4, 1.4, 1.4, 0.0, 0.0, 0.0];
+        assert_close(&smoothed, &expected);
+    }
+
+    #[test]
+    fn removes_baseline() {
+        let mut intensities = vec![10.0; 20];
+        intensities[8] += 50.0;
+        intensities[9] += 100.0;
+        let top_hat = BaselineCorrection::TopHat { half_window: 3 };
+        let corrected = top_hat.correct(&intensities);
+        assert_eq!(corrected[9], 100.0);
+        assert_eq!(corrected[8], 50.0);
+        assert_eq!(corrected.iter().sum::<f64>(), 150.0);
+        let rolling_ball = BaselineCorrection::RollingBall {
+            half_window: 3,
+            smoothing_half_window: 1,
+        };
+        let corrected = rolling_ball.correct(&intensities);
+        assert_eq!(corrected[9], 100.0);
+        assert_eq!(corrected[2], 0.0);
+    }
+}
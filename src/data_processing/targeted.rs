@@ -0,0 +1,236 @@
+//! Targeted extraction of fragment chromatograms from diaPASEF runs.
+//!
+//! Every target of a transition list (e.g. from a spectral library) is
+//! matched to the DIA windows that isolate its precursor within its ion
+//! mobility range. For each match, the chromatograms of all fragments are
+//! extracted from the MS2 frames of that window group. All chromatograms
+//! of all targets are extracted in a single pass with an [XicExtractor].
+
+use std::{collections::BTreeMap, ops::Range};
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain_converters::{ConvertableDomain, Scan2ImConverter},
+    io::readers::{
+        DiaWindow, DiaWindowIndex, FrameReader, FrameReaderError,
+        MetadataReader, TimsTofPathLike,
+    },
+    ms_data::MSLevel,
+};
+
+use super::chromatogram::{
+    Chromatogram, ChromatogramError, XicExtractor, XicTarget,
+};
+
+/// A precursor with its fragments, e.g. an entry of a spectral library.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TargetedPrecursor {
+    pub id: String,
+    pub precursor_mz: f64,
+    pub fragment_mz: Vec<f64>,
+    /// Retention time in seconds
+    pub rt_range: Range<f64>,
+    /// Ion mobility (1/K0)
+    pub im_range: Range<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TargetedConfig {
+    pub precursor_tolerance_ppm: f64,
+    pub fragment_tolerance_ppm: f64,
+    /// Whether to also extract the MS1 chromatogram of the precursor
+    pub include_precursor: bool,
+}
+
+impl Default for TargetedConfig {
+    fn default() -> Self {
+        Self {
+            precursor_tolerance_ppm: 20.0,
+            fragment_tolerance_ppm: 20.0,
+            include_precursor: true,
+        }
+    }
+}
+
+/// The chromatograms of a target in a single DIA window.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct XicGroup {
+    /// The position of the target in the transition list
+    pub target: usize,
+    pub window: DiaWindow,
+    /// The ion mobility range of the target that the window covers
+    pub im_range: Range<f64>,
+    /// The MS1 chromatogram of the precursor (see
+    /// [TargetedConfig::include_precursor])
+    pub precursor: Option<Chromatogram>,
+    /// The chromatogram of every fragment, in the order of the target
+    pub fragments: Vec<Chromatogram>,
+}
+
+/// Extracts the fragment chromatograms of a transition list.
+#[derive(Debug)]
+pub struct TargetedExtractor {
+    xic_extractor: XicExtractor,
+    window_index: DiaWindowIndex,
+    im_converter: Scan2ImConverter,
+    /// The distinct converters of the frames of every window group
+    window_converters: BTreeMap<u8, Vec<Scan2ImConverter>>,
+    config: TargetedConfig,
+}
+
+impl TargetedExtractor {
+    pub fn new(
+        path: impl TimsTofPathLike,
+        config: TargetedConfig,
+    ) -> Result<Self, ChromatogramError> {
+        let metadata = MetadataReader::new(&path)?;
+        let frame_reader = FrameReader::build()
+            .with_path(&path)
+            .with_dia_windows(true)
+            .finalize()?;
+        let window_index =
+            frame_reader.dia_window_index().cloned().unwrap_or_default();
+        let window_converters = window_index
+            .window_groups()
+            .into_iter()
+            .map(|window_group| {
+                let mut converters: Vec<Scan2ImConverter> = vec![];
+                for &index in window_index.frames_for_window(window_group) {
+                    let converter = frame_reader
+                        .get_frame_without_coordinates(index)?
+                        .im_converter
+                        .unwrap_or(metadata.im_converter);
+                    if !converters.contains(&converter) {
+                        converters.push(converter);
+                    }
+                }
+                Ok((window_group, converters))
+            })
+            .collect::<Result<_, FrameReaderError>>()?;
+        let extractor = Self {
+            xic_extractor: XicExtractor::from_frame_reader(
+                frame_reader,
+                &metadata,
+            ),
+            window_index,
+            im_converter: metadata.im_converter,
+            window_converters,
+            config,
+        };
+        Ok(extractor)
+    }
+
+    pub fn window_index(&self) -> &DiaWindowIndex {
+        &self.window_index
+    }
+
+    /// The windows that isolate the precursor of `target`, with the part of
+    /// the ion mobility range of the target that they cover. If the frames
+    /// of a window group have different calibrations, the range covers the
+    /// window in any of them.
+    pub fn windows_for(
+        &self,
+        target: &TargetedPrecursor,
+    ) -> Vec<(DiaWindow, Range<f64>)> {
+        self.window_index
+            .windows()
+            .iter()
+            .filter(|window| {
+                (window.lower_mz() <= target.precursor_mz)
+                    & (target.precursor_mz <= window.upper_mz())
+                    & (window.scan_start < window.scan_end)
+            })
+            .filter_map(|window| {
+                let converters = self
+                    .window_converters
+                    .get(&window.window_group)
+                    .filter(|converters| !converters.is_empty())
+                    .map_or(std::slice::from_ref(&self.im_converter), |x| x);
+                converters
+                    .iter()
+                    .filter_map(|im_converter| {
+                        // Higher scans have a lower mobility
+                        let lower = im_converter
+                            .convert((window.scan_end - 1) as f64)
+                            .max(target.im_range.start);
+                        let upper = im_converter
+                            .convert(window.scan_start as f64)
+                            .min(target.im_range.end);
+                        (lower <= upper).then_some((lower, upper))
+                    })
+                    .reduce(|(lower, upper), (other_lower, other_upper)| {
+                        (lower.min(other_lower), upper.max(other_upper))
+                    })
+                    .map(|(lower, upper)| (*window, lower..upper))
+            })
+            .collect()
+    }
+
+    /// The chromatograms of every window of every target, in the order of
+    /// the targets. Targets without a window have no groups.
+    pub fn extract(
+        &self,
+        targets: &[TargetedPrecursor],
+    ) -> Result<Vec<XicGroup>, ChromatogramError> {
+        let mut xic_targets = vec![];
+        let mut precursors = vec![];
+        let mut groups = vec![];
+        for (index, target) in targets.iter().enumerate() {
+            let windows = self.windows_for(target);
+            if windows.is_empty() {
+                continue;
+            }
+            let precursor = self.config.include_precursor.then(|| {
+                xic_targets.push(XicTarget {
+                    im_range: Some(target.im_range.clone()),
+                    rt_range: Some(target.rt_range.clone()),
+                    ..XicTarget::new(
+                        target.precursor_mz,
+                        self.config.precursor_tolerance_ppm,
+                    )
+                });
+                xic_targets.len() - 1
+            });
+            for (window, im_range) in windows {
+                let first_fragment = xic_targets.len();
+                xic_targets.extend(target.fragment_mz.iter().map(|&mz| {
+                    XicTarget {
+                        im_range: Some(im_range.clone()),
+                        rt_range: Some(target.rt_range.clone()),
+                        ms_level: MSLevel::MS2,
+                        window_group: Some(window.window_group),
+                        isolation_mz: Some(target.precursor_mz),
+                        ..XicTarget::new(mz, self.config.fragment_tolerance_ppm)
+                    }
+                }));
+                precursors.push(precursor);
+                groups.push((
+                    index,
+                    window,
+                    im_range,
+                    first_fragment..xic_targets.len(),
+                ));
+            }
+        }
+        let chromatograms = self.xic_extractor.extract(&xic_targets)?;
+        let groups = groups
+            .into_iter()
+            .zip(precursors)
+            .map(|((target, window, im_range, fragments), precursor)| {
+                XicGroup {
+                    target,
+                    window,
+                    im_range,
+                    precursor: precursor.map(|x| chromatograms[x].clone()),
+                    fragments: chromatograms[fragments].to_vec(),
+                }
+            })
+            .collect();
+        Ok(groups)
+    }
+}
@@ -1,14 +1,35 @@
 //! Allows conversions between domains (e.g. Time of Flight and m/z)
-mod frame_to_rt;
-mod scan_to_im;
-mod tof_to_mz;
+pub use timsrust_4d_types::domain_converters::*;
 
-pub use frame_to_rt::Frame2RtConverter;
-pub use scan_to_im::Scan2ImConverter;
-pub use tof_to_mz::Tof2MzConverter;
+#[cfg(feature = "tdf")]
+use std::ops::Range;
 
-/// Convert from one domain (e.g. Time of Flight) to another (m/z).
-pub trait ConvertableDomain {
-    fn convert<T: Into<f64> + Copy>(&self, value: T) -> f64;
-    fn invert<T: Into<f64> + Copy>(&self, value: T) -> f64;
+/// The TOF range `[lower, upper]` of all m/z values within `tolerance_ppm`
+/// of `mz`.
+#[cfg(feature = "tdf")]
+pub(crate) fn tof_bounds(
+    mz_converter: &Tof2MzConverter,
+    mz: f64,
+    tolerance_ppm: f64,
+) -> (f64, f64) {
+    let delta = mz * tolerance_ppm / 1e6;
+    (
+        mz_converter.invert(mz - delta),
+        mz_converter.invert(mz + delta),
+    )
+}
+
+/// The half-open scan range `[start, end)` of all scans whose ion mobility
+/// (1/K0) lies within `im_range`.
+#[cfg(feature = "tdf")]
+pub(crate) fn scan_bounds(
+    im_converter: &Scan2ImConverter,
+    im_range: &Range<f64>,
+) -> (usize, usize) {
+    // Higher scans usually have a lower mobility, so order the bounds
+    let scan_a = im_converter.invert(im_range.start);
+    let scan_b = im_converter.invert(im_range.end);
+    let scan_start = scan_a.min(scan_b).ceil().max(0.0) as usize;
+    let scan_end = scan_a.max(scan_b).floor().max(-1.0) + 1.0;
+    (scan_start, scan_end as usize)
 }
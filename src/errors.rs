@@ -1,6 +1,16 @@
+#[cfg(feature = "convert")]
+use crate::conversion::ConversionError;
 #[cfg(feature = "tdf")]
-use crate::io::readers::{
-    FrameReaderError, MetadataReaderError, QuadrupoleSettingsReaderError,
+use crate::{
+    data_processing::chromatogram::ChromatogramError,
+    imaging::MaldiImagingError,
+    io::index::RunIndexError,
+    io::readers::{
+        FrameReaderError, MetadataReaderError, MultiFrameReaderError,
+        QcReaderError, QuadrupoleSettingsReaderError,
+    },
+    io::writers::{RunSubsetterError, TdfWriterError},
+    validation::ValidationError,
 };
 use crate::{io::readers::PrecursorReaderError, readers::SpectrumReaderError};
 
@@ -20,4 +30,31 @@ pub enum TimsRustError {
     #[cfg(feature = "tdf")]
     #[error("{0}")]
     QuadrupoleSettingsReaderError(#[from] QuadrupoleSettingsReaderError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    MultiFrameReaderError(#[from] MultiFrameReaderError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    QcReaderError(#[from] QcReaderError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    MaldiImagingError(#[from] MaldiImagingError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    RunIndexError(#[from] RunIndexError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    TdfWriterError(#[from] TdfWriterError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    RunSubsetterError(#[from] RunSubsetterError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    ChromatogramError(#[from] ChromatogramError),
+    #[cfg(feature = "tdf")]
+    #[error("{0}")]
+    ValidationError(#[from] ValidationError),
+    #[cfg(feature = "convert")]
+    #[error("{0}")]
+    ConversionError(#[from] ConversionError),
 }
@@ -0,0 +1,63 @@
+//! Imaging utilities for MALDI-TIMS-MSI data.
+//!
+//! Frames of a MALDI imaging run are arranged on a pixel grid through their
+//! [MaldiInfo](crate::ms_data::MaldiInfo). This module builds that grid and
+//! extracts 2D images from it.
+
+mod image;
+mod ion_images;
+mod msms;
+mod normalization;
+mod pixel_grid;
+mod roi;
+mod stats;
+
+pub use image::*;
+pub use ion_images::*;
+pub use msms::*;
+pub use normalization::*;
+pub use pixel_grid::*;
+pub use roi::*;
+pub use stats::*;
+
+use crate::{
+    io::readers::{FrameReaderError, MetadataReaderError},
+    ms_data::Frame,
+};
+
+/// Sum the (normalized) intensities of all peaks within a scan range
+/// `[scan_start, scan_end)` whose TOF index lies within
+/// `[tof_lower, tof_upper]`.
+pub(crate) fn sum_intensities(
+    frame: &Frame,
+    scan_start: usize,
+    scan_end: usize,
+    tof_lower: f64,
+    tof_upper: f64,
+) -> f64 {
+    if frame.scan_offsets.is_empty() {
+        return 0.0;
+    }
+    let last_scan = frame.scan_offsets.len() - 1;
+    let start = frame.scan_offsets[scan_start.min(last_scan)];
+    let end = frame.scan_offsets[scan_end.min(last_scan)];
+    (start..end)
+        .filter(|&peak| {
+            let tof = frame.tof_indices[peak] as f64;
+            (tof >= tof_lower) & (tof <= tof_upper)
+        })
+        .map(|peak| frame.normalized_intensity(peak))
+        .sum()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MaldiImagingError {
+    #[error("{0}")]
+    FrameReaderError(#[from] FrameReaderError),
+    #[error("{0}")]
+    MetadataReaderError(#[from] MetadataReaderError),
+    #[error("Dataset does not contain MALDI imaging data")]
+    NotMaldi,
+    #[error("Invalid precursor window {0}")]
+    InvalidWindow(usize),
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::PixelGrid;
+
+/// A 2D image aligned to a [PixelGrid], stored row-major.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IonImage {
+    pub x_min: i32,
+    pub y_min: i32,
+    pub width: usize,
+    pub height: usize,
+    pub intensities: Vec<f64>,
+}
+
+impl IonImage {
+    /// An empty (all zero) image covering the full grid.
+    pub fn new(grid: &PixelGrid) -> Self {
+        Self {
+            x_min: grid.x_min(),
+            y_min: grid.y_min(),
+            width: grid.width(),
+            height: grid.height(),
+            intensities: vec![0.0; grid.len()],
+        }
+    }
+
+    /// The intensity at pixel `(x, y)`, if it lies on the image.
+    pub fn get(&self, x: i32, y: i32) -> Option<f64> {
+        if (x < self.x_min) | (y < self.y_min) {
+            return None;
+        }
+        let column = (x - self.x_min) as usize;
+        let row = (y - self.y_min) as usize;
+        if (column >= self.width) | (row >= self.height) {
+            return None;
+        }
+        Some(self.intensities[row * self.width + column])
+    }
+}
+
+/// An image of a [PixelGrid] that all threads update at once.
+///
+/// Every pixel stores the bits of an `f64` that is updated with a
+/// compare-and-swap loop, so a single image is shared between threads
+/// without locks and its memory does not grow with the number of threads.
+#[derive(Debug)]
+pub(crate) struct AtomicImage {
+    intensities: Vec<AtomicU64>,
+}
+
+impl AtomicImage {
+    pub(crate) fn new(grid: &PixelGrid) -> Self {
+        Self {
+            intensities: (0..grid.len())
+                .map(|_| AtomicU64::new(0.0f64.to_bits()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn add(&self, pixel: usize, value: f64) {
+        if value != 0.0 {
+            self.update(pixel, |intensity| intensity + value);
+        }
+    }
+
+    pub(crate) fn max(&self, pixel: usize, value: f64) {
+        self.update(pixel, |intensity| intensity.max(value));
+    }
+
+    fn update(&self, pixel: usize, f: impl Fn(f64) -> f64) {
+        // The closure always returns Some, so the update cannot fail
+        let _ = self.intensities[pixel].fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |bits| Some(f(f64::from_bits(bits)).to_bits()),
+        );
+    }
+
+    pub(crate) fn into_image(self, grid: &PixelGrid) -> IonImage {
+        IonImage {
+            intensities: self
+                .intensities
+                .into_iter()
+                .map(|bits| f64::from_bits(bits.into_inner()))
+                .collect(),
+            ..IonImage::new(grid)
+        }
+    }
+}
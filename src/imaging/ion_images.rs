@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    domain_converters::{
+        scan_bounds, tof_bounds, ConvertableDomain, Scan2ImConverter,
+        Tof2MzConverter,
+    },
+    io::readers::{FrameReader, MetadataReader, TimsTofPathLike},
+    ms_data::{Frame, MSLevel},
+};
+
+use super::{
+    sum_intensities, AtomicImage, IonImage, LaserNormalization,
+    MaldiImagingError, PixelGrid, Roi,
+};
+
+/// An ion to image, optionally restricted to a mobility window.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IonTarget {
+    pub mz: f64,
+    pub tolerance_ppm: f64,
+    /// Only peaks with an ion mobility (1/K0) within this range contribute,
+    /// converted to scans with the calibration of every frame
+    pub im_range: Option<Range<f64>>,
+}
+
+impl IonTarget {
+    pub fn new(mz: f64, tolerance_ppm: f64) -> Self {
+        Self {
+            mz,
+            tolerance_ppm,
+            im_range: None,
+        }
+    }
+
+    pub fn with_im_range(self, im_range: Range<f64>) -> Self {
+        Self {
+            im_range: Some(im_range),
+            ..self
+        }
+    }
+}
+
+/// The averaged MS1 spectrum of a region of interest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoiSpectrum {
+    pub mz_values: Vec<f64>,
+    /// The summed intensity of every m/z, divided by `pixel_count`
+    pub intensities: Vec<f64>,
+    /// The number of pixels in the region with at least one MS1 frame
+    pub pixel_count: usize,
+}
+
+/// Ion images and region-of-interest spectra of the MS1 frames of a MALDI
+/// imaging run.
+///
+/// Frames are routed to the pixel grid through their MALDI info. All frames
+/// of a pixel are summed, using the normalized intensities if the
+/// [FrameReader] normalizes them.
+#[derive(Debug)]
+pub struct MaldiImaging {
+    frame_reader: FrameReader,
+    mz_converter: Tof2MzConverter,
+    im_converter: Scan2ImConverter,
+    grid: PixelGrid,
+    ms1_frames: Vec<bool>,
+    normalization: LaserNormalization,
+}
+
+impl MaldiImaging {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, MaldiImagingError> {
+        let frame_reader = FrameReader::new(&path)?;
+        let metadata = MetadataReader::new(&path)?;
+        Self::from_frame_reader(
+            frame_reader,
+            metadata.mz_converter,
+            metadata.im_converter,
+        )
+    }
+
+    pub fn from_frame_reader(
+        frame_reader: FrameReader,
+        mz_converter: Tof2MzConverter,
+        im_converter: Scan2ImConverter,
+    ) -> Result<Self, MaldiImagingError> {
+        if !frame_reader.is_maldi() {
+            return Err(MaldiImagingError::NotMaldi);
+        }
+        let frames = (0..frame_reader.len())
+            .map(|index| frame_reader.get_frame_without_coordinates(index))
+            .collect::<Result<Vec<Frame>, _>>()?;
+        let grid = PixelGrid::from_frames(frames.iter().enumerate());
+        let ms1_frames = frames
+            .iter()
+            .map(|frame| frame.ms_level == MSLevel::MS1)
+            .collect();
+        let imaging = Self {
+            frame_reader,
+            mz_converter,
+            im_converter,
+            grid,
+            ms1_frames,
+            normalization: LaserNormalization::default(),
+        };
+        Ok(imaging)
+    }
+
+    /// Normalize the intensities of every frame by its laser settings
+    /// before they are summed into images and spectra.
+    pub fn with_laser_normalization(
+        self,
+        normalization: LaserNormalization,
+    ) -> Self {
+        Self {
+            normalization,
+            ..self
+        }
+    }
+
+    pub fn laser_normalization(&self) -> LaserNormalization {
+        self.normalization
+    }
+
+    pub fn pixel_grid(&self) -> &PixelGrid {
+        &self.grid
+    }
+
+    pub fn ion_image(
+        &self,
+        target: &IonTarget,
+    ) -> Result<IonImage, MaldiImagingError> {
+        let mut images = self.ion_images(std::slice::from_ref(target))?;
+        Ok(images.remove(0))
+    }
+
+    /// Extract the images of multiple ions in a single pass over the MS1
+    /// frames.
+    pub fn ion_images(
+        &self,
+        targets: &[IonTarget],
+    ) -> Result<Vec<IonImage>, MaldiImagingError> {
+        let tof_ranges: Vec<(f64, f64)> = targets
+            .iter()
+            .map(|target| {
+                tof_bounds(&self.mz_converter, target.mz, target.tolerance_ppm)
+            })
+            .collect();
+        let frame_indices = self.ms1_frame_indices(0..self.grid.len());
+        // All threads add to the same images
+        let images: Vec<AtomicImage> = targets
+            .iter()
+            .map(|_| AtomicImage::new(&self.grid))
+            .collect();
+        frame_indices
+            .par_iter()
+            .try_for_each(|&(frame_index, pixel)| {
+                let frame = self.frame_reader.get(frame_index)?;
+                let Some(factor) =
+                    self.normalization.factor(frame.maldi_info.as_ref())
+                else {
+                    return Ok(());
+                };
+                let im_converter =
+                    frame.im_converter.unwrap_or(self.im_converter);
+                for ((image, target), &(tof_lower, tof_upper)) in
+                    images.iter().zip(targets).zip(&tof_ranges)
+                {
+                    let (scan_start, scan_end) = match &target.im_range {
+                        Some(im_range) => scan_bounds(&im_converter, im_range),
+                        None => (0, usize::MAX),
+                    };
+                    let intensity = sum_intensities(
+                        &frame, scan_start, scan_end, tof_lower, tof_upper,
+                    );
+                    image.add(pixel, factor * intensity);
+                }
+                Ok::<_, MaldiImagingError>(())
+            })?;
+        Ok(images
+            .into_iter()
+            .map(|image| image.into_image(&self.grid))
+            .collect())
+    }
+
+    /// The MS1 spectrum of all pixels within `roi`, averaged over the
+    /// pixels that have MS1 frames that can be normalized.
+    pub fn roi_spectrum(
+        &self,
+        roi: &Roi,
+    ) -> Result<RoiSpectrum, MaldiImagingError> {
+        let pixels = (0..self.grid.len()).filter(|&pixel| {
+            let (x, y) = self.grid.pixel_coordinates(pixel);
+            roi.contains(x, y)
+        });
+        let frame_indices = self.ms1_frame_indices(pixels);
+        let empty = || (HashMap::new(), HashSet::new());
+        let (summed, pixels) = frame_indices
+            .par_iter()
+            .try_fold(
+                empty,
+                |(mut summed, mut pixels), &(frame_index, pixel)| {
+                    let frame = self.frame_reader.get(frame_index)?;
+                    let Some(factor) =
+                        self.normalization.factor(frame.maldi_info.as_ref())
+                    else {
+                        return Ok((summed, pixels));
+                    };
+                    for (peak, &tof) in frame.tof_indices.iter().enumerate() {
+                        *summed.entry(tof).or_insert(0.0) +=
+                            factor * frame.normalized_intensity(peak);
+                    }
+                    pixels.insert(pixel);
+                    Ok::<_, MaldiImagingError>((summed, pixels))
+                },
+            )
+            .try_reduce(
+                empty,
+                |(mut summed, mut pixels), (other, other_pixels)| {
+                    for (tof, intensity) in other {
+                        *summed.entry(tof).or_insert(0.0) += intensity;
+                    }
+                    pixels.extend(other_pixels);
+                    Ok((summed, pixels))
+                },
+            )?;
+        let pixel_count = pixels.len();
+        let mut peaks: Vec<(u32, f64)> = summed.into_iter().collect();
+        peaks.sort_by_key(|&(tof, _)| tof);
+        let spectrum = RoiSpectrum {
+            mz_values: peaks
+                .iter()
+                .map(|&(tof, _)| self.mz_converter.convert(tof))
+                .collect(),
+            intensities: peaks
+                .iter()
+                .map(|&(_, intensity)| intensity / pixel_count as f64)
+                .collect(),
+            pixel_count,
+        };
+        Ok(spectrum)
+    }
+
+    /// All MS1 frames of the given pixels, paired with their pixel and
+    /// sorted by pixel.
+    fn ms1_frame_indices(
+        &self,
+        pixels: impl Iterator<Item = usize>,
+    ) -> Vec<(usize, usize)> {
+        pixels
+            .flat_map(|pixel| {
+                self.grid
+                    .frames_at(pixel)
+                    .iter()
+                    .filter(|&&frame_index| self.ms1_frames[frame_index])
+                    .map(move |&frame_index| (frame_index, pixel))
+            })
+            .collect()
+    }
+}
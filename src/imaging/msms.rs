@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    domain_converters::{tof_bounds, ConvertableDomain, Tof2MzConverter},
+    io::readers::{FrameReader, MetadataReader, TimsTofPathLike},
+    ms_data::{Frame, MSLevel},
+};
+
+use super::{
+    sum_intensities, AtomicImage, IonImage, LaserNormalization,
+    MaldiImagingError, PixelGrid,
+};
+
+/// The quadrupole isolation window of an MS/MS imaging run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PrecursorWindow {
+    pub isolation_mz: f64,
+    pub isolation_width: f64,
+    pub collision_energy: f64,
+}
+
+impl PrecursorWindow {
+    pub fn lower_mz(&self) -> f64 {
+        self.isolation_mz - self.isolation_width / 2.0
+    }
+
+    pub fn upper_mz(&self) -> f64 {
+        self.isolation_mz + self.isolation_width / 2.0
+    }
+}
+
+/// A fragment ion to image within a precursor window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FragmentTarget {
+    /// Index into [MaldiMsMsImaging::precursor_windows]
+    pub window: usize,
+    pub mz: f64,
+    pub tolerance_ppm: f64,
+}
+
+/// The scans of an MS2 frame that were acquired with a precursor window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct WindowSegment {
+    frame_index: usize,
+    scan_start: usize,
+    scan_end: usize,
+}
+
+/// Fragment-ion and isolation purity images for MALDI MS/MS imaging.
+///
+/// MS2 frames are routed to the pixel grid through their MALDI info and to
+/// a precursor window through their quadrupole settings. Only the scans
+/// that were isolated with a window contribute to its images. Intensities
+/// are normalized if the [FrameReader] normalizes them.
+#[derive(Debug)]
+pub struct MaldiMsMsImaging {
+    frame_reader: FrameReader,
+    mz_converter: Tof2MzConverter,
+    grid: PixelGrid,
+    windows: Vec<PrecursorWindow>,
+    window_segments: Vec<Vec<WindowSegment>>,
+    ms1_frames: Vec<usize>,
+    normalization: LaserNormalization,
+}
+
+impl MaldiMsMsImaging {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, MaldiImagingError> {
+        let frame_reader = FrameReader::new(&path)?;
+        let metadata = MetadataReader::new(&path)?;
+        Self::from_frame_reader(frame_reader, metadata.mz_converter)
+    }
+
+    pub fn from_frame_reader(
+        frame_reader: FrameReader,
+        mz_converter: Tof2MzConverter,
+    ) -> Result<Self, MaldiImagingError> {
+        if !frame_reader.is_maldi() {
+            return Err(MaldiImagingError::NotMaldi);
+        }
+        let frames = (0..frame_reader.len())
+            .map(|index| frame_reader.get_frame_without_coordinates(index))
+            .collect::<Result<Vec<Frame>, _>>()?;
+        let grid = PixelGrid::from_frames(frames.iter().enumerate());
+        let (windows, window_segments) = group_precursor_windows(&frames);
+        let ms1_frames = frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| {
+                (frame.ms_level == MSLevel::MS1) & frame.maldi_info.is_some()
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let imaging = Self {
+            frame_reader,
+            mz_converter,
+            grid,
+            windows,
+            window_segments,
+            ms1_frames,
+            normalization: LaserNormalization::default(),
+        };
+        Ok(imaging)
+    }
+
+    /// Normalize the intensities of every frame by its laser settings
+    /// before they are summed into images.
+    pub fn with_laser_normalization(
+        self,
+        normalization: LaserNormalization,
+    ) -> Self {
+        Self {
+            normalization,
+            ..self
+        }
+    }
+
+    pub fn laser_normalization(&self) -> LaserNormalization {
+        self.normalization
+    }
+
+    pub fn pixel_grid(&self) -> &PixelGrid {
+        &self.grid
+    }
+
+    /// All distinct precursor windows, sorted by isolation m/z, isolation
+    /// width and collision energy.
+    pub fn precursor_windows(&self) -> &[PrecursorWindow] {
+        &self.windows
+    }
+
+    /// The indices of all precursor windows whose isolation range
+    /// contains `mz`.
+    pub fn windows_containing(&self, mz: f64) -> Vec<usize> {
+        self.windows
+            .iter()
+            .enumerate()
+            .filter(|(_, window)| {
+                (window.lower_mz() <= mz) & (mz <= window.upper_mz())
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn fragment_image(
+        &self,
+        window: usize,
+        fragment_mz: f64,
+        tolerance_ppm: f64,
+    ) -> Result<IonImage, MaldiImagingError> {
+        let target = FragmentTarget {
+            window,
+            mz: fragment_mz,
+            tolerance_ppm,
+        };
+        let mut images = self.fragment_images(&[target])?;
+        Ok(images.remove(0))
+    }
+
+    /// Extract the images of multiple fragments in a single pass over the
+    /// MS2 frames.
+    pub fn fragment_images(
+        &self,
+        targets: &[FragmentTarget],
+    ) -> Result<Vec<IonImage>, MaldiImagingError> {
+        let mut frame_targets: HashMap<usize, Vec<(usize, WindowSegment)>> =
+            HashMap::new();
+        for (target_index, target) in targets.iter().enumerate() {
+            let segments = self
+                .window_segments
+                .get(target.window)
+                .ok_or(MaldiImagingError::InvalidWindow(target.window))?;
+            for segment in segments {
+                frame_targets
+                    .entry(segment.frame_index)
+                    .or_default()
+                    .push((target_index, *segment));
+            }
+        }
+        let tof_bounds: Vec<(f64, f64)> = targets
+            .iter()
+            .map(|target| {
+                tof_bounds(&self.mz_converter, target.mz, target.tolerance_ppm)
+            })
+            .collect();
+        let frame_indices: Vec<usize> = frame_targets.keys().cloned().collect();
+        // All threads add to the same images without waiting on each other
+        let images: Vec<AtomicImage> = targets
+            .iter()
+            .map(|_| AtomicImage::new(&self.grid))
+            .collect();
+        frame_indices.par_iter().try_for_each(|&frame_index| {
+            let pixel = match self.grid.frame_pixel(frame_index) {
+                Some(pixel) => pixel,
+                None => return Ok(()),
+            };
+            let frame = self.frame_reader.get(frame_index)?;
+            let Some(factor) =
+                self.normalization.factor(frame.maldi_info.as_ref())
+            else {
+                return Ok(());
+            };
+            for (target_index, segment) in &frame_targets[&frame_index] {
+                let (tof_lower, tof_upper) = tof_bounds[*target_index];
+                let intensity = sum_intensities(
+                    &frame,
+                    segment.scan_start,
+                    segment.scan_end,
+                    tof_lower,
+                    tof_upper,
+                );
+                images[*target_index].add(pixel, factor * intensity);
+            }
+            Ok::<_, MaldiImagingError>(())
+        })?;
+        Ok(images
+            .into_iter()
+            .map(|image| image.into_image(&self.grid))
+            .collect())
+    }
+
+    /// The isolation purity of a precursor window at every pixel.
+    ///
+    /// Purity is the fraction of the MS1 intensity within the isolation
+    /// window that falls within `tolerance_ppm` of `precursor_mz`, measured
+    /// in the MS1 frames of each pixel. Pixels without MS1 signal in the
+    /// window have a purity of 0.
+    pub fn purity_image(
+        &self,
+        window: usize,
+        precursor_mz: f64,
+        tolerance_ppm: f64,
+    ) -> Result<IonImage, MaldiImagingError> {
+        let precursor_window = self
+            .windows
+            .get(window)
+            .ok_or(MaldiImagingError::InvalidWindow(window))?;
+        let (precursor_lower, precursor_upper) =
+            tof_bounds(&self.mz_converter, precursor_mz, tolerance_ppm);
+        let window_lower =
+            self.mz_converter.invert(precursor_window.lower_mz());
+        let window_upper =
+            self.mz_converter.invert(precursor_window.upper_mz());
+        let precursor_image = AtomicImage::new(&self.grid);
+        let window_image = AtomicImage::new(&self.grid);
+        self.ms1_frames.par_iter().try_for_each(|&frame_index| {
+            let pixel = match self.grid.frame_pixel(frame_index) {
+                Some(pixel) => pixel,
+                None => return Ok(()),
+            };
+            let frame = self.frame_reader.get(frame_index)?;
+            let Some(factor) =
+                self.normalization.factor(frame.maldi_info.as_ref())
+            else {
+                return Ok(());
+            };
+            let scan_end = frame.scan_offsets.len();
+            let precursor_intensity = sum_intensities(
+                &frame,
+                0,
+                scan_end,
+                precursor_lower,
+                precursor_upper,
+            );
+            let window_intensity = sum_intensities(
+                &frame,
+                0,
+                scan_end,
+                window_lower,
+                window_upper,
+            );
+            precursor_image.add(pixel, factor * precursor_intensity);
+            window_image.add(pixel, factor * window_intensity);
+            Ok::<_, MaldiImagingError>(())
+        })?;
+        let mut purity = precursor_image.into_image(&self.grid);
+        let window_image = window_image.into_image(&self.grid);
+        for (value, total) in
+            purity.intensities.iter_mut().zip(window_image.intensities)
+        {
+            *value = if total > 0.0 { *value / total } else { 0.0 };
+        }
+        Ok(purity)
+    }
+}
+
+/// Collect the distinct precursor windows of all MS2 MALDI frames and the
+/// frame segments that belong to each of them.
+fn group_precursor_windows(
+    frames: &[Frame],
+) -> (Vec<PrecursorWindow>, Vec<Vec<WindowSegment>>) {
+    let mut groups: HashMap<(u64, u64, u64), Vec<WindowSegment>> =
+        HashMap::new();
+    for (frame_index, frame) in frames.iter().enumerate() {
+        if (frame.ms_level != MSLevel::MS2) | frame.maldi_info.is_none() {
+            continue;
+        }
+        let quad = &frame.quadrupole_settings;
+        for i in 0..quad.len() {
+            let key = (
+                quad.isolation_mz[i].to_bits(),
+                quad.isolation_width[i].to_bits(),
+                quad.collision_energy[i].to_bits(),
+            );
+            groups.entry(key).or_default().push(WindowSegment {
+                frame_index,
+                scan_start: quad.scan_starts[i],
+                scan_end: quad.scan_ends[i],
+            });
+        }
+    }
+    let mut groups: Vec<(PrecursorWindow, Vec<WindowSegment>)> = groups
+        .into_iter()
+        .map(|((mz, width, energy), segments)| {
+            let window = PrecursorWindow {
+                isolation_mz: f64::from_bits(mz),
+                isolation_width: f64::from_bits(width),
+                collision_energy: f64::from_bits(energy),
+            };
+            (window, segments)
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        a.0.isolation_mz
+            .total_cmp(&b.0.isolation_mz)
+            .then(a.0.isolation_width.total_cmp(&b.0.isolation_width))
+            .then(a.0.collision_energy.total_cmp(&b.0.collision_energy))
+    });
+    groups.into_iter().unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::ms_data::{MaldiInfo, QuadrupoleSettings};
+
+    fn msms_frame(isolation_mz: f64, scan_end: usize) -> Frame {
+        Frame {
+            ms_level: MSLevel::MS2,
+            quadrupole_settings: Arc::new(QuadrupoleSettings {
+                scan_starts: vec![0],
+                scan_ends: vec![scan_end],
+                isolation_mz: vec![isolation_mz],
+                isolation_width: vec![1.0],
+                collision_energy: vec![30.0],
+                ..Default::default()
+            }),
+            maldi_info: Some(MaldiInfo::default()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn groups_frames_by_precursor_window() {
+        let frames = vec![
+            msms_frame(600.0, 10),
+            msms_frame(500.0, 10),
+            Frame::default(),
+            msms_frame(600.0, 12),
+        ];
+        let (windows, segments) = group_precursor_windows(&frames);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].isolation_mz, 500.0);
+        assert_eq!(windows[1].lower_mz(), 599.5);
+        assert_eq!(segments[0].len(), 1);
+        assert_eq!(segments[1][1].frame_index, 3);
+        assert_eq!(segments[1][1].scan_end, 12);
+    }
+
+    #[test]
+    fn sorts_windows_with_the_same_center_by_width() {
+        let mut wide = msms_frame(500.0, 10);
+        Arc::make_mut(&mut wide.quadrupole_settings).isolation_width =
+            vec![4.0];
+        let frames = vec![wide, msms_frame(500.0, 10)];
+        let (windows, _) = group_precursor_windows(&frames);
+        let widths: Vec<f64> = windows
+            .iter()
+            .map(|window| window.isolation_width)
+            .collect();
+        assert_eq!(widths, vec![1.0, 4.0]);
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::ms_data::MaldiInfo;
+
+/// Normalization of pixel intensities by the laser settings of their
+/// frames.
+///
+/// Intensities scale with the number of laser shots (and the laser power),
+/// which can vary across a slide. Frames without the required laser
+/// settings cannot be normalized and are skipped by images and spectra.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum LaserNormalization {
+    #[default]
+    None,
+    /// Divide by the number of laser shots
+    LaserShots,
+    /// Divide by the number of laser shots and the laser power
+    LaserShotsAndPower,
+}
+
+impl LaserNormalization {
+    /// The factor that the intensities of a frame are multiplied with, or
+    /// `None` if the frame lacks the (positive) laser settings to normalize
+    /// it.
+    pub fn factor(&self, maldi_info: Option<&MaldiInfo>) -> Option<f64> {
+        if *self == Self::None {
+            return Some(1.0);
+        }
+        let info = maldi_info?;
+        let shots = info.laser_shots.filter(|&shots| shots > 0)? as f64;
+        match self {
+            Self::LaserShotsAndPower => {
+                let power = info.laser_power.filter(|&power| power > 0.0)?;
+                Some(1.0 / (shots * power))
+            },
+            _ => Some(1.0 / shots),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_by_laser_settings() {
+        let info = MaldiInfo {
+            laser_shots: Some(200),
+            laser_power: Some(50.0),
+            ..Default::default()
+        };
+        assert_eq!(LaserNormalization::None.factor(Some(&info)), Some(1.0));
+        assert_eq!(
+            LaserNormalization::LaserShots.factor(Some(&info)),
+            Some(0.005)
+        );
+        assert_eq!(
+            LaserNormalization::LaserShotsAndPower.factor(Some(&info)),
+            Some(0.0001)
+        );
+        assert_eq!(LaserNormalization::None.factor(None), Some(1.0));
+        assert_eq!(LaserNormalization::LaserShots.factor(None), None);
+        let info = MaldiInfo {
+            laser_shots: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(LaserNormalization::LaserShots.factor(Some(&info)), None);
+        let info = MaldiInfo {
+            laser_shots: Some(200),
+            laser_power: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            LaserNormalization::LaserShotsAndPower.factor(Some(&info)),
+            None
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    io::readers::{FrameReader, FrameReaderError},
+    ms_data::Frame,
+};
+
+/// The pixel grid of a MALDI imaging run.
+///
+/// Maps every pixel to the frames that were acquired at that position.
+/// Frames are referred to by their 0-based index in the [FrameReader].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PixelGrid {
+    x_min: i32,
+    y_min: i32,
+    width: usize,
+    height: usize,
+    pixel_frames: Vec<Vec<usize>>,
+    frame_pixels: Vec<Option<usize>>,
+}
+
+impl PixelGrid {
+    /// Build a grid from frames, where each frame is paired with its
+    /// 0-based index in the reader. Frames without MALDI info are ignored.
+    pub fn from_frames<'a>(
+        frames: impl IntoIterator<Item = (usize, &'a Frame)>,
+    ) -> Self {
+        let positions: Vec<(usize, i32, i32)> = frames
+            .into_iter()
+            .filter_map(|(index, frame)| {
+                let maldi = frame.maldi_info.as_ref()?;
+                Some((index, maldi.pixel_x, maldi.pixel_y))
+            })
+            .collect();
+        if positions.is_empty() {
+            return Self::default();
+        }
+        let x_min = positions.iter().map(|x| x.1).min().unwrap_or(0);
+        let x_max = positions.iter().map(|x| x.1).max().unwrap_or(0);
+        let y_min = positions.iter().map(|x| x.2).min().unwrap_or(0);
+        let y_max = positions.iter().map(|x| x.2).max().unwrap_or(0);
+        let width = (x_max - x_min) as usize + 1;
+        let height = (y_max - y_min) as usize + 1;
+        let frame_count = positions.iter().map(|x| x.0).max().unwrap_or(0) + 1;
+        let mut pixel_frames = vec![vec![]; width * height];
+        let mut frame_pixels = vec![None; frame_count];
+        for (index, x, y) in positions {
+            let pixel = (y - y_min) as usize * width + (x - x_min) as usize;
+            pixel_frames[pixel].push(index);
+            frame_pixels[index] = Some(pixel);
+        }
+        Self {
+            x_min,
+            y_min,
+            width,
+            height,
+            pixel_frames,
+            frame_pixels,
+        }
+    }
+
+    /// Build a grid from the frame metadata of a reader, without decoding
+    /// any peak data.
+    pub fn from_frame_reader(
+        frame_reader: &FrameReader,
+    ) -> Result<Self, FrameReaderError> {
+        let frames = (0..frame_reader.len())
+            .map(|index| frame_reader.get_frame_without_coordinates(index))
+            .collect::<Result<Vec<Frame>, _>>()?;
+        Ok(Self::from_frames(frames.iter().enumerate()))
+    }
+
+    pub fn x_min(&self) -> i32 {
+        self.x_min
+    }
+
+    pub fn y_min(&self) -> i32 {
+        self.y_min
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The total number of pixels (including pixels without frames).
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The row-major index of pixel `(x, y)`, if it lies on the grid.
+    pub fn pixel_index(&self, x: i32, y: i32) -> Option<usize> {
+        if (x < self.x_min) | (y < self.y_min) {
+            return None;
+        }
+        let column = (x - self.x_min) as usize;
+        let row = (y - self.y_min) as usize;
+        if (column >= self.width) | (row >= self.height) {
+            return None;
+        }
+        Some(row * self.width + column)
+    }
+
+    /// The `(x, y)` coordinates of a row-major pixel index.
+    pub fn pixel_coordinates(&self, pixel_index: usize) -> (i32, i32) {
+        let column = (pixel_index % self.width) as i32;
+        let row = (pixel_index / self.width) as i32;
+        (self.x_min + column, self.y_min + row)
+    }
+
+    /// All frames acquired at a pixel.
+    pub fn frames_at(&self, pixel_index: usize) -> &[usize] {
+        self.pixel_frames
+            .get(pixel_index)
+            .map(|frames| frames.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The pixel at which a frame was acquired.
+    pub fn frame_pixel(&self, frame_index: usize) -> Option<usize> {
+        *self.frame_pixels.get(frame_index)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ms_data::MaldiInfo;
+
+    fn maldi_frame(x: i32, y: i32) -> Frame {
+        Frame {
+            maldi_info: Some(MaldiInfo {
+                pixel_x: x,
+                pixel_y: y,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn maps_frames_to_pixels() {
+        let frames = [
+            maldi_frame(10, 5),
+            maldi_frame(12, 5),
+            Frame::default(),
+            maldi_frame(12, 6),
+            maldi_frame(12, 6),
+        ];
+        let grid = PixelGrid::from_frames(frames.iter().enumerate());
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid.pixel_index(12, 6), Some(5));
+        assert_eq!(grid.pixel_index(13, 6), None);
+        assert_eq!(grid.pixel_coordinates(5), (12, 6));
+        assert_eq!(grid.frames_at(5), &[3, 4]);
+        assert_eq!(grid.frames_at(1), &[] as &[usize]);
+        assert_eq!(grid.frame_pixel(1), Some(2));
+        assert_eq!(grid.frame_pixel(2), None);
+    }
+}
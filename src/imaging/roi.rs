@@ -0,0 +1,77 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// A region of interest on a pixel grid, in pixel coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Roi {
+    /// All pixels with `x_min <= x <= x_max` and `y_min <= y <= y_max`
+    Rectangle {
+        x_min: i32,
+        y_min: i32,
+        x_max: i32,
+        y_max: i32,
+    },
+    /// All pixels whose center lies within the polygon through the
+    /// `(x, y)` vertices. The polygon is closed implicitly.
+    Polygon(Vec<(f64, f64)>),
+}
+
+impl Roi {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        match self {
+            Self::Rectangle {
+                x_min,
+                y_min,
+                x_max,
+                y_max,
+            } => {
+                (*x_min..=*x_max).contains(&x) & (*y_min..=*y_max).contains(&y)
+            },
+            Self::Polygon(vertices) => {
+                polygon_contains(vertices, x as f64, y as f64)
+            },
+        }
+    }
+}
+
+/// Even-odd rule: a point is inside if a ray from it crosses the edges of
+/// the polygon an odd number of times.
+fn polygon_contains(vertices: &[(f64, f64)], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut previous = match vertices.last() {
+        Some(&vertex) => vertex,
+        None => return false,
+    };
+    for &(x_i, y_i) in vertices {
+        let (x_j, y_j) = previous;
+        if ((y_i > y) != (y_j > y))
+            & (x < (x_j - x_i) * (y - y_i) / (y_j - y_i) + x_i)
+        {
+            inside = !inside;
+        }
+        previous = (x_i, y_i);
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_pixels() {
+        let rectangle = Roi::Rectangle {
+            x_min: 1,
+            y_min: 1,
+            x_max: 2,
+            y_max: 3,
+        };
+        assert!(rectangle.contains(2, 3));
+        assert!(!rectangle.contains(0, 2));
+        let triangle = Roi::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]);
+        assert!(triangle.contains(2, 2));
+        assert!(!triangle.contains(8, 8));
+        assert!(!Roi::Polygon(vec![]).contains(0, 0));
+    }
+}
@@ -0,0 +1,199 @@
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    io::readers::{FrameReader, TimsTofPathLike},
+    ms_data::{Frame, MSLevel},
+};
+
+use super::{AtomicImage, IonImage, MaldiImagingError, PixelGrid};
+
+/// Per-pixel statistics and a run-level summary of a MALDI imaging run,
+/// for acquisition QC.
+///
+/// All images are aligned to the [PixelGrid] of the run and only include
+/// MS1 frames. Intensities are not normalized.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaldiStats {
+    /// The summed intensity of all peaks of every pixel
+    pub tic: IonImage,
+    /// The number of peaks of every pixel
+    pub peak_counts: IonImage,
+    /// The intensity of the most intense peak of every pixel
+    pub max_intensity: IonImage,
+    pub summary: MaldiRunSummary,
+}
+
+/// A summary of the acquisition of a MALDI imaging run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaldiRunSummary {
+    /// The number of frames of all MS levels
+    pub frame_count: usize,
+    /// The number of pixels of the grid with at least one frame
+    pub acquired_pixels: usize,
+    /// The `(x, y)` coordinates of all pixels of the grid without frames
+    pub missing_pixels: Vec<(i32, i32)>,
+    /// The time between the first and the last frame in seconds
+    pub acquisition_duration: f64,
+    /// `None` if no frame has a laser power
+    pub laser_power: Option<LaserPowerDrift>,
+}
+
+/// The laser power of all frames that have one, in acquisition order.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LaserPowerDrift {
+    pub first: f64,
+    pub last: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// The least squares slope of the laser power over the retention time,
+    /// per second
+    pub slope: f64,
+}
+
+impl LaserPowerDrift {
+    /// `points` are `(rt_in_seconds, laser_power)` in acquisition order.
+    fn from_points(points: &[(f64, f64)]) -> Option<Self> {
+        let first = points.first()?.1;
+        let last = points.last()?.1;
+        let count = points.len() as f64;
+        let mean_rt = points.iter().map(|&(rt, _)| rt).sum::<f64>() / count;
+        let mean = points.iter().map(|&(_, power)| power).sum::<f64>() / count;
+        let (covariance, variance) = points.iter().fold(
+            (0.0, 0.0),
+            |(covariance, variance), &(rt, power)| {
+                (
+                    covariance + (rt - mean_rt) * (power - mean),
+                    variance + (rt - mean_rt).powi(2),
+                )
+            },
+        );
+        let drift = Self {
+            first,
+            last,
+            min: points.iter().map(|x| x.1).fold(f64::INFINITY, f64::min),
+            max: points.iter().map(|x| x.1).fold(f64::NEG_INFINITY, f64::max),
+            mean,
+            slope: if variance > 0.0 {
+                covariance / variance
+            } else {
+                0.0
+            },
+        };
+        Some(drift)
+    }
+}
+
+impl MaldiRunSummary {
+    fn from_frames(frames: &[Frame], grid: &PixelGrid) -> Self {
+        let missing_pixels: Vec<(i32, i32)> = (0..grid.len())
+            .filter(|&pixel| grid.frames_at(pixel).is_empty())
+            .map(|pixel| grid.pixel_coordinates(pixel))
+            .collect();
+        let rts = frames.iter().map(|frame| frame.rt_in_seconds);
+        let first_rt = rts.clone().fold(f64::INFINITY, f64::min);
+        let last_rt = rts.fold(f64::NEG_INFINITY, f64::max);
+        let laser_powers: Vec<(f64, f64)> = frames
+            .iter()
+            .filter_map(|frame| {
+                let power = frame.maldi_info.as_ref()?.laser_power?;
+                Some((frame.rt_in_seconds, power))
+            })
+            .collect();
+        Self {
+            frame_count: frames.len(),
+            acquired_pixels: grid.len() - missing_pixels.len(),
+            missing_pixels,
+            acquisition_duration: (last_rt - first_rt).max(0.0),
+            laser_power: LaserPowerDrift::from_points(&laser_powers),
+        }
+    }
+}
+
+impl MaldiStats {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, MaldiImagingError> {
+        Self::from_frame_reader(&FrameReader::new(path)?)
+    }
+
+    /// Compute all images in a single parallel pass over the MS1 frames.
+    pub fn from_frame_reader(
+        frame_reader: &FrameReader,
+    ) -> Result<Self, MaldiImagingError> {
+        if !frame_reader.is_maldi() {
+            return Err(MaldiImagingError::NotMaldi);
+        }
+        let frames = (0..frame_reader.len())
+            .map(|index| frame_reader.get_frame_without_coordinates(index))
+            .collect::<Result<Vec<Frame>, _>>()?;
+        let grid = PixelGrid::from_frames(frames.iter().enumerate());
+        let ms1_frames: Vec<(usize, usize)> = frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| frame.ms_level == MSLevel::MS1)
+            .filter_map(|(index, _)| Some((index, grid.frame_pixel(index)?)))
+            .collect();
+        let tic = AtomicImage::new(&grid);
+        let peak_counts = AtomicImage::new(&grid);
+        let max_intensity = AtomicImage::new(&grid);
+        ms1_frames
+            .par_iter()
+            .try_for_each(|&(frame_index, pixel)| {
+                let frame = frame_reader.get(frame_index)?;
+                tic.add(
+                    pixel,
+                    frame.intensities.iter().map(|&x| x as f64).sum::<f64>(),
+                );
+                peak_counts.add(pixel, frame.intensities.len() as f64);
+                let pixel_max =
+                    frame.intensities.iter().max().copied().unwrap_or(0);
+                max_intensity.max(pixel, pixel_max as f64);
+                Ok::<_, MaldiImagingError>(())
+            })?;
+        Ok(Self {
+            tic: tic.into_image(&grid),
+            peak_counts: peak_counts.into_image(&grid),
+            max_intensity: max_intensity.into_image(&grid),
+            summary: MaldiRunSummary::from_frames(&frames, &grid),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ms_data::MaldiInfo;
+
+    fn maldi_frame(x: i32, y: i32, rt: f64, laser_power: Option<f64>) -> Frame {
+        Frame {
+            rt_in_seconds: rt,
+            maldi_info: Some(MaldiInfo {
+                pixel_x: x,
+                pixel_y: y,
+                laser_power,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summarizes_acquisition() {
+        let frames = vec![
+            maldi_frame(0, 0, 1.0, Some(50.0)),
+            maldi_frame(1, 0, 2.0, None),
+            maldi_frame(1, 1, 3.0, Some(48.0)),
+            maldi_frame(1, 1, 5.0, Some(46.0)),
+        ];
+        let grid = PixelGrid::from_frames(frames.iter().enumerate());
+        let summary = MaldiRunSummary::from_frames(&frames, &grid);
+        assert_eq!(summary.frame_count, 4);
+        assert_eq!(summary.acquired_pixels, 3);
+        assert_eq!(summary.missing_pixels, vec![(0, 1)]);
+        assert_eq!(summary.acquisition_duration, 4.0);
+        let drift = summary.laser_power.unwrap();
+        assert_eq!((drift.first, drift.last), (50.0, 46.0));
+        assert_eq!((drift.min, drift.max, drift.mean), (46.0, 50.0, 48.0));
+        assert_eq!(drift.slope, -1.0);
+        assert_eq!(LaserPowerDrift::from_points(&[]), None);
+    }
+}
@@ -1,4 +1,6 @@
 //! Handles all input and output
 
+#[cfg(feature = "tdf")]
+pub mod index;
 pub mod readers;
 pub mod writers;
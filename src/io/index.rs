@@ -0,0 +1,627 @@
+//! A persistent index of all peaks in a run.
+//!
+//! The index maps bins of TOF (m/z) and scan (ion mobility) to the peak
+//! ranges of every frame and scan with signal in that bin. It is built with
+//! a single pass over the run and stored next to the `.d` folder (e.g.
+//! `sample.d.timsrust_idx`), so later extractions only need to decode the
+//! frames that can contain signal.
+//!
+//! The index file records the size and modification time of
+//! `analysis.tdf` and `analysis.tdf_bin`; if either changed, the index is
+//! considered stale and rebuilt by [RunIndex::open_or_build]. It also
+//! stores the TOF -> m/z converter and the Scan -> 1/K0 converter of every
+//! frame, so a loaded index does not need to read the run.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    domain_converters::{ConvertableDomain, Scan2ImConverter, Tof2MzConverter},
+    io::readers::{
+        FrameReader, FrameReaderError, FrameVisitor, MetadataReader,
+        MetadataReaderError, TimsTofPath, TimsTofPathError, TimsTofPathLike,
+    },
+    ms_data::Frame,
+};
+
+pub const INDEX_EXTENSION: &str = "timsrust_idx";
+pub const INDEX_VERSION: u32 = 3;
+const MAGIC: &[u8; 8] = b"TRSIDX\0\0";
+
+/// The bin sizes of a [RunIndex].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunIndexConfig {
+    pub tof_bin_width: u32,
+    pub scan_bin_width: u32,
+}
+
+impl RunIndexConfig {
+    /// Whether both bin widths are greater than 0.
+    fn is_valid(&self) -> bool {
+        (self.tof_bin_width > 0) & (self.scan_bin_width > 0)
+    }
+}
+
+impl Default for RunIndexConfig {
+    fn default() -> Self {
+        Self {
+            tof_bin_width: 1024,
+            scan_bin_width: 32,
+        }
+    }
+}
+
+/// A contiguous range of peaks in a single scan of a frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IndexEntry {
+    /// 0-based index of the frame in a [FrameReader]
+    pub frame_index: u32,
+    pub scan: u32,
+    /// Start of the peak range within the frame
+    pub peak_start: u32,
+    /// End (exclusive) of the peak range within the frame
+    pub peak_end: u32,
+}
+
+/// Size and modification time of the files an index was built from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DatasetFingerprint {
+    pub tdf_size: u64,
+    pub tdf_modified: u64,
+    pub tdf_bin_size: u64,
+    pub tdf_bin_modified: u64,
+}
+
+impl DatasetFingerprint {
+    pub fn from_path(
+        path: impl TimsTofPathLike,
+    ) -> Result<Self, RunIndexError> {
+        let path = path.to_timstof_path()?;
+        let (tdf_size, tdf_modified) = file_fingerprint(path.tdf()?)?;
+        let (tdf_bin_size, tdf_bin_modified) =
+            file_fingerprint(path.tdf_bin()?)?;
+        Ok(Self {
+            tdf_size,
+            tdf_modified,
+            tdf_bin_size,
+            tdf_bin_modified,
+        })
+    }
+}
+
+fn file_fingerprint(path: impl AsRef<Path>) -> Result<(u64, u64), io::Error> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    Ok((metadata.len(), modified))
+}
+
+/// An index of all peaks in a run, binned by TOF and scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunIndex {
+    config: RunIndexConfig,
+    fingerprint: DatasetFingerprint,
+    tof_bin_count: u32,
+    scan_bin_count: u32,
+    bin_offsets: Vec<u64>,
+    entries: Vec<IndexEntry>,
+    mz_converter: Tof2MzConverter,
+    /// The Scan -> 1/K0 converter of every frame, by reader position
+    im_converters: Vec<Scan2ImConverter>,
+}
+
+impl RunIndex {
+    /// Load the index stored next to a run, or build (and store) it if it
+    /// is missing, stale or was built with a different config or version.
+    pub fn open_or_build(
+        path: impl TimsTofPathLike,
+        config: RunIndexConfig,
+    ) -> Result<Self, RunIndexError> {
+        match Self::load(&path) {
+            Ok(index) if index.config == config => return Ok(index),
+            Ok(_)
+            | Err(RunIndexError::Stale)
+            | Err(RunIndexError::VersionMismatch(_))
+            | Err(RunIndexError::InvalidFormat) => {},
+            Err(RunIndexError::IO(error))
+                if error.kind() == io::ErrorKind::NotFound => {},
+            Err(error) => return Err(error),
+        }
+        let index = Self::build(&path, config)?;
+        index.save(&path)?;
+        Ok(index)
+    }
+
+    /// Build the index with a single parallel pass over all frames.
+    pub fn build(
+        path: impl TimsTofPathLike,
+        config: RunIndexConfig,
+    ) -> Result<Self, RunIndexError> {
+        if !config.is_valid() {
+            return Err(RunIndexError::InvalidConfig(config));
+        }
+        let fingerprint = DatasetFingerprint::from_path(&path)?;
+        let metadata = MetadataReader::new(&path)?;
+        let frame_reader = FrameReader::new(&path)?;
+        // Frame Ids need not be contiguous, so map them to reader positions
+        let frame_indices = (0..frame_reader.len())
+            .map(|index| {
+                let frame =
+                    frame_reader.get_frame_without_coordinates(index)?;
+                Ok((frame.index, index as u32))
+            })
+            .collect::<Result<HashMap<usize, u32>, FrameReaderError>>()?;
+        let im_converters =
+            frame_im_converters(&frame_reader, metadata.im_converter)?;
+        let collector = EntryCollector {
+            config,
+            frame_indices: &frame_indices,
+            entries: vec![],
+        };
+        let mut binned_entries = frame_reader.visit(collector)?;
+        binned_entries.sort_unstable();
+        let tof_bin_count = binned_entries
+            .iter()
+            .map(|(bin, _)| bin.0 + 1)
+            .max()
+            .unwrap_or(0);
+        let scan_bin_count = binned_entries
+            .iter()
+            .map(|(bin, _)| bin.1 + 1)
+            .max()
+            .unwrap_or(0);
+        let bin_count = tof_bin_count as usize * scan_bin_count as usize;
+        let mut bin_offsets = vec![0; bin_count + 1];
+        for ((tof_bin, scan_bin), _) in binned_entries.iter() {
+            let bin = *tof_bin as usize * scan_bin_count as usize
+                + *scan_bin as usize;
+            bin_offsets[bin + 1] += 1;
+        }
+        for bin in 0..bin_count {
+            bin_offsets[bin + 1] += bin_offsets[bin];
+        }
+        let entries = binned_entries.into_iter().map(|(_, x)| x).collect();
+        let index = Self {
+            config,
+            fingerprint,
+            tof_bin_count,
+            scan_bin_count,
+            bin_offsets,
+            entries,
+            mz_converter: metadata.mz_converter,
+            im_converters,
+        };
+        Ok(index)
+    }
+
+    /// Load the index stored next to a run.
+    ///
+    /// Fails with [RunIndexError::Stale] if the run changed since the
+    /// index was built.
+    pub fn load(path: impl TimsTofPathLike) -> Result<Self, RunIndexError> {
+        let timstof_path = path.to_timstof_path()?;
+        let mut bytes = vec![];
+        File::open(index_path(&timstof_path))?.read_to_end(&mut bytes)?;
+        let mut reader = ByteReader { bytes: &bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(RunIndexError::InvalidFormat);
+        }
+        let version = reader.u32()?;
+        if version != INDEX_VERSION {
+            return Err(RunIndexError::VersionMismatch(version));
+        }
+        let fingerprint = DatasetFingerprint {
+            tdf_size: reader.u64()?,
+            tdf_modified: reader.u64()?,
+            tdf_bin_size: reader.u64()?,
+            tdf_bin_modified: reader.u64()?,
+        };
+        if fingerprint != DatasetFingerprint::from_path(&timstof_path)? {
+            return Err(RunIndexError::Stale);
+        }
+        let config = RunIndexConfig {
+            tof_bin_width: reader.u32()?,
+            scan_bin_width: reader.u32()?,
+        };
+        if !config.is_valid() {
+            return Err(RunIndexError::InvalidFormat);
+        }
+        let tof_bin_count = reader.u32()?;
+        let scan_bin_count = reader.u32()?;
+        let entry_count = reader.u64()? as usize;
+        let bin_count = (tof_bin_count as usize)
+            .checked_mul(scan_bin_count as usize)
+            .ok_or(RunIndexError::InvalidFormat)?;
+        // The converters follow the entries: two m/z coefficients, the
+        // number of frames and two 1/K0 coefficients per frame
+        let min_len = (bin_count + 1)
+            .checked_mul(8)
+            .zip(entry_count.checked_mul(16))
+            .and_then(|(offsets, entries)| offsets.checked_add(entries))
+            .and_then(|len| len.checked_add(24))
+            .ok_or(RunIndexError::InvalidFormat)?;
+        if reader.bytes.len() < min_len {
+            return Err(RunIndexError::InvalidFormat);
+        }
+        let bin_offsets = (0..bin_count + 1)
+            .map(|_| reader.u64())
+            .collect::<Result<Vec<u64>, _>>()?;
+        let entries = (0..entry_count)
+            .map(|_| {
+                Ok(IndexEntry {
+                    frame_index: reader.u32()?,
+                    scan: reader.u32()?,
+                    peak_start: reader.u32()?,
+                    peak_end: reader.u32()?,
+                })
+            })
+            .collect::<Result<Vec<IndexEntry>, RunIndexError>>()?;
+        let mz_intercept = reader.f64()?;
+        let mz_slope = reader.f64()?;
+        let mz_converter = Tof2MzConverter::from_linear(mz_intercept, mz_slope);
+        let frame_count = reader.u64()? as usize;
+        if frame_count.checked_mul(16) != Some(reader.bytes.len()) {
+            return Err(RunIndexError::InvalidFormat);
+        }
+        let im_converters = (0..frame_count)
+            .map(|_| {
+                let intercept = reader.f64()?;
+                let slope = reader.f64()?;
+                Ok(Scan2ImConverter::from_linear(intercept, slope))
+            })
+            .collect::<Result<Vec<Scan2ImConverter>, RunIndexError>>()?;
+        if !valid_bins(tof_bin_count, scan_bin_count, &bin_offsets, entry_count)
+            || entries
+                .iter()
+                .any(|entry| entry.frame_index as usize >= frame_count)
+        {
+            return Err(RunIndexError::InvalidFormat);
+        }
+        let index = Self {
+            config,
+            fingerprint,
+            tof_bin_count,
+            scan_bin_count,
+            bin_offsets,
+            entries,
+            mz_converter,
+            im_converters,
+        };
+        Ok(index)
+    }
+
+    /// Store the index next to a run.
+    ///
+    /// The index is written to a temporary file that replaces the index
+    /// file once it is complete, so an interrupted save leaves no partial
+    /// index behind.
+    pub fn save(
+        &self,
+        path: impl TimsTofPathLike,
+    ) -> Result<(), RunIndexError> {
+        let path = path.to_timstof_path()?;
+        let index_path = index_path(&path);
+        let mut temporary_path = index_path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        let temporary_path = PathBuf::from(temporary_path);
+        self.write(File::create(&temporary_path)?)?;
+        fs::rename(&temporary_path, &index_path)?;
+        Ok(())
+    }
+
+    fn write(&self, file: File) -> Result<(), RunIndexError> {
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&INDEX_VERSION.to_le_bytes())?;
+        for value in [
+            self.fingerprint.tdf_size,
+            self.fingerprint.tdf_modified,
+            self.fingerprint.tdf_bin_size,
+            self.fingerprint.tdf_bin_modified,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for value in [
+            self.config.tof_bin_width,
+            self.config.scan_bin_width,
+            self.tof_bin_count,
+            self.scan_bin_count,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for offset in self.bin_offsets.iter() {
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+        for entry in self.entries.iter() {
+            for value in [
+                entry.frame_index,
+                entry.scan,
+                entry.peak_start,
+                entry.peak_end,
+            ] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        for value in [self.mz_converter.intercept(), self.mz_converter.slope()]
+        {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&(self.im_converters.len() as u64).to_le_bytes())?;
+        for im_converter in self.im_converters.iter() {
+            for value in [im_converter.intercept(), im_converter.slope()] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        Ok(())
+    }
+
+    pub fn config(&self) -> RunIndexConfig {
+        self.config
+    }
+
+    pub fn fingerprint(&self) -> DatasetFingerprint {
+        self.fingerprint
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All entries in bins that overlap a TOF range and a scan range (both
+    /// inclusive). Entries can contain peaks outside the requested ranges.
+    pub fn query_tof_scan(
+        &self,
+        tof_range: (u32, u32),
+        scan_range: (u32, u32),
+    ) -> Vec<IndexEntry> {
+        let mut result = vec![];
+        if self.entries.is_empty() {
+            return result;
+        }
+        let tof_bins = (tof_range.0 / self.config.tof_bin_width)
+            ..=(tof_range.1 / self.config.tof_bin_width)
+                .min(self.tof_bin_count - 1);
+        let scan_bins = (scan_range.0 / self.config.scan_bin_width)
+            ..=(scan_range.1 / self.config.scan_bin_width)
+                .min(self.scan_bin_count - 1);
+        for tof_bin in tof_bins {
+            for scan_bin in scan_bins.clone() {
+                let bin = (tof_bin * self.scan_bin_count + scan_bin) as usize;
+                let start = self.bin_offsets[bin] as usize;
+                let end = self.bin_offsets[bin + 1] as usize;
+                result.extend(self.entries[start..end].iter().filter(
+                    |entry| {
+                        (entry.scan >= scan_range.0)
+                            & (entry.scan <= scan_range.1)
+                    },
+                ));
+            }
+        }
+        result.sort_unstable();
+        result
+    }
+
+    /// All entries that can contain peaks within an m/z range and an
+    /// (inverse) ion mobility range, converted to scans with the
+    /// calibration of every frame.
+    pub fn query(
+        &self,
+        mz_range: (f64, f64),
+        im_range: (f64, f64),
+    ) -> Vec<IndexEntry> {
+        let tof_range = (
+            self.mz_converter.invert(mz_range.0).max(0.0).floor() as u32,
+            self.mz_converter.invert(mz_range.1).max(0.0).ceil() as u32,
+        );
+        let scan_ranges: Vec<(u32, u32)> = self
+            .im_converters
+            .iter()
+            .map(|im_converter| scan_range(im_converter, im_range))
+            .collect();
+        // Bins are shared by all frames, so query the scans of any of them
+        let Some(scan_range) = scan_ranges
+            .iter()
+            .copied()
+            .reduce(|x, y| (x.0.min(y.0), x.1.max(y.1)))
+        else {
+            return vec![];
+        };
+        let mut entries = self.query_tof_scan(tof_range, scan_range);
+        entries.retain(|entry| {
+            scan_ranges.get(entry.frame_index as usize).is_none_or(
+                |&(start, end)| (entry.scan >= start) & (entry.scan <= end),
+            )
+        });
+        entries
+    }
+
+    /// The sorted, deduplicated frame indices that can contain peaks within
+    /// an m/z range and an ion mobility range.
+    pub fn frames_for(
+        &self,
+        mz_range: (f64, f64),
+        im_range: (f64, f64),
+    ) -> Vec<usize> {
+        let mut frames: Vec<usize> = self
+            .query(mz_range, im_range)
+            .iter()
+            .map(|entry| entry.frame_index as usize)
+            .collect();
+        frames.dedup();
+        frames
+    }
+}
+
+/// The Scan -> 1/K0 converter of every frame of a reader. Frames without
+/// their own calibration use `im_converter`.
+fn frame_im_converters(
+    frame_reader: &FrameReader,
+    im_converter: Scan2ImConverter,
+) -> Result<Vec<Scan2ImConverter>, FrameReaderError> {
+    (0..frame_reader.len())
+        .map(|index| {
+            let frame = frame_reader.get_frame_without_coordinates(index)?;
+            Ok(frame.im_converter.unwrap_or(im_converter))
+        })
+        .collect()
+}
+
+/// The (inclusive) scans within an ion mobility range.
+fn scan_range(
+    im_converter: &Scan2ImConverter,
+    im_range: (f64, f64),
+) -> (u32, u32) {
+    // Low scans usually have high ion mobilities
+    let first = im_converter.invert(im_range.0);
+    let second = im_converter.invert(im_range.1);
+    (
+        first.min(second).max(0.0).floor() as u32,
+        first.max(second).max(0.0).ceil() as u32,
+    )
+}
+
+/// Whether the bin offsets delimit all entries: they start at 0, never
+/// decrease and end at the number of entries. Entries need bins.
+fn valid_bins(
+    tof_bin_count: u32,
+    scan_bin_count: u32,
+    bin_offsets: &[u64],
+    entry_count: usize,
+) -> bool {
+    let has_bins = tof_bin_count > 0 && scan_bin_count > 0;
+    (has_bins || entry_count == 0)
+        && bin_offsets.first() == Some(&0)
+        && bin_offsets.last() == Some(&(entry_count as u64))
+        && bin_offsets.windows(2).all(|x| x[0] <= x[1])
+}
+
+/// The location of the index file of a run.
+pub fn index_path(path: &TimsTofPath) -> PathBuf {
+    let mut file_name: OsString = path.as_ref().as_os_str().to_owned();
+    file_name.push(".");
+    file_name.push(INDEX_EXTENSION);
+    PathBuf::from(file_name)
+}
+
+#[derive(Debug)]
+struct EntryCollector<'a> {
+    config: RunIndexConfig,
+    /// The reader position of every frame Id
+    frame_indices: &'a HashMap<usize, u32>,
+    entries: Vec<((u32, u32), IndexEntry)>,
+}
+
+impl FrameVisitor for EntryCollector<'_> {
+    type Output = Vec<((u32, u32), IndexEntry)>;
+
+    fn fork(&self) -> Self {
+        Self {
+            config: self.config,
+            frame_indices: self.frame_indices,
+            entries: vec![],
+        }
+    }
+
+    fn visit_frame(&mut self, frame: &Frame) {
+        let Some(&frame_index) = self.frame_indices.get(&frame.index) else {
+            return;
+        };
+        for (scan, offsets) in frame.scan_offsets.windows(2).enumerate() {
+            let scan_bin = scan as u32 / self.config.scan_bin_width;
+            let mut start = offsets[0];
+            while start < offsets[1] {
+                let tof_bin =
+                    frame.tof_indices[start] / self.config.tof_bin_width;
+                let mut end = start + 1;
+                while (end < offsets[1])
+                    && (frame.tof_indices[end] / self.config.tof_bin_width
+                        == tof_bin)
+                {
+                    end += 1;
+                }
+                let entry = IndexEntry {
+                    frame_index,
+                    scan: scan as u32,
+                    peak_start: start as u32,
+                    peak_end: end as u32,
+                };
+                self.entries.push(((tof_bin, scan_bin), entry));
+                start = end;
+            }
+        }
+    }
+
+    fn merge(&mut self, mut other: Self) {
+        self.entries.append(&mut other.entries);
+    }
+
+    fn end_run(self) -> Self::Output {
+        self.entries
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], RunIndexError> {
+        if self.bytes.len() < count {
+            return Err(RunIndexError::InvalidFormat);
+        }
+        let (head, tail) = self.bytes.split_at(count);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, RunIndexError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("Length is 4")))
+    }
+
+    fn u64(&mut self) -> Result<u64, RunIndexError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("Length is 8")))
+    }
+
+    fn f64(&mut self) -> Result<f64, RunIndexError> {
+        let bytes = self.take(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().expect("Length is 8")))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RunIndexError {
+    #[error("{0}")]
+    IO(#[from] io::Error),
+    #[error("{0}")]
+    FrameReaderError(#[from] FrameReaderError),
+    #[error("{0}")]
+    MetadataReaderError(#[from] MetadataReaderError),
+    #[error("{0}")]
+    TimsTofPathError(#[from] TimsTofPathError),
+    #[error("Index file is not valid")]
+    InvalidFormat,
+    #[error("Bin widths of {0:?} must be greater than 0")]
+    InvalidConfig(RunIndexConfig),
+    #[error("Index version {0} is not supported")]
+    VersionMismatch(u32),
+    #[error("Index is older than the dataset")]
+    Stale,
+}
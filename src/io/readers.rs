@@ -1,20 +1,52 @@
+mod dataset_lock;
 pub(crate) mod file_readers;
 #[cfg(feature = "tdf")]
 mod frame_reader;
 #[cfg(feature = "tdf")]
 mod metadata_reader;
+#[cfg(feature = "tdf")]
+mod mobility_calibration_reader;
+#[cfg(feature = "tdf")]
+mod multi_frame_reader;
 mod precursor_reader;
 #[cfg(feature = "tdf")]
+mod qc_reader;
+#[cfg(feature = "tdf")]
 mod quad_settings_reader;
+mod sample_sheet;
 mod spectrum_reader;
 mod timstof;
 
+pub use dataset_lock::*;
+pub use file_readers::tdf_blob_reader::{TdfBlob, TdfBlobError};
 #[cfg(feature = "tdf")]
 pub use frame_reader::*;
 #[cfg(feature = "tdf")]
 pub use metadata_reader::*;
+#[cfg(feature = "tdf")]
+pub use mobility_calibration_reader::*;
+#[cfg(feature = "tdf")]
+pub use multi_frame_reader::*;
 pub use precursor_reader::*;
 #[cfg(feature = "tdf")]
+pub use qc_reader::*;
+#[cfg(feature = "tdf")]
 pub use quad_settings_reader::*;
+pub use sample_sheet::*;
 pub use spectrum_reader::*;
 pub use timstof::*;
+
+// All readers can be shared between threads (e.g. in an `Arc`) without
+// locking: after construction they only hold memory maps and decoded
+// tables, never an SQLite connection.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SpectrumReader>();
+    assert_send_sync::<PrecursorReader>();
+    #[cfg(feature = "tdf")]
+    assert_send_sync::<FrameReader>();
+    #[cfg(feature = "tdf")]
+    assert_send_sync::<MultiFrameReader>();
+    #[cfg(feature = "tdf")]
+    assert_send_sync::<QcReader>();
+};
@@ -0,0 +1,66 @@
+use std::{fs, path::PathBuf};
+
+use super::{TimsTofPath, TimsTofPathError, TimsTofPathLike};
+
+/// Signs that a dataset is still opened for writing, e.g. by the
+/// acquisition software on the instrument PC.
+///
+/// While a dataset is locked, readers should use safe mode (see
+/// [FrameReaderBuilder::with_safe_mode](super::FrameReaderBuilder::with_safe_mode)).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatasetLockState {
+    /// `analysis.tdf-wal` exists, i.e. SQLite has an open write-ahead log
+    pub sqlite_wal: bool,
+    /// `analysis.tdf-shm` exists
+    pub sqlite_shm: bool,
+    /// `analysis.tdf-journal` exists, i.e. a write transaction is pending
+    pub sqlite_journal: bool,
+    /// Lock files (`*.lock`, `*.lck`) within the dataset folder
+    pub lock_files: Vec<PathBuf>,
+}
+
+impl DatasetLockState {
+    pub fn from_path(
+        path: impl TimsTofPathLike,
+    ) -> Result<Self, TimsTofPathError> {
+        let path = path.to_timstof_path()?;
+        let mut state = Self::default();
+        #[cfg(feature = "tdf")]
+        if let Ok(tdf) = path.tdf() {
+            let sidecar = |suffix: &str| {
+                let mut sidecar = tdf.clone().into_os_string();
+                sidecar.push(suffix);
+                PathBuf::from(sidecar).exists()
+            };
+            state.sqlite_wal = sidecar("-wal");
+            state.sqlite_shm = sidecar("-shm");
+            state.sqlite_journal = sidecar("-journal");
+        }
+        for entry in fs::read_dir(&path)?.flatten() {
+            let file_path = entry.path();
+            let is_lock_file = file_path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| {
+                    extension.eq_ignore_ascii_case("lock")
+                        | extension.eq_ignore_ascii_case("lck")
+                })
+                .unwrap_or(false);
+            if is_lock_file {
+                state.lock_files.push(file_path);
+            }
+        }
+        state.lock_files.sort();
+        Ok(state)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.sqlite_wal | self.sqlite_journal | !self.lock_files.is_empty()
+    }
+}
+
+impl TimsTofPath {
+    pub fn lock_state(&self) -> Result<DatasetLockState, TimsTofPathError> {
+        DatasetLockState::from_path(self)
+    }
+}
@@ -1,26 +1,43 @@
+pub mod error_log;
 pub mod frame_groups;
+pub mod frame_msms;
 pub mod frames;
 pub mod maldi;
 pub mod metadata;
 pub mod pasef_frame_msms;
 pub mod precursors;
+pub mod prm;
+pub mod properties;
 pub mod quad_settings;
+pub mod schema;
+pub mod segments;
+pub mod tims_calibration;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
-use rusqlite::{types::FromSql, Connection};
+use rusqlite::{types::FromSql, Connection, OpenFlags};
 
 use crate::readers::{TimsTofPathError, TimsTofPathLike};
 
+use schema::SqlTableSchema;
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug)]
 pub struct SqlReader {
     connection: Connection,
 }
 
 impl SqlReader {
+    /// Open `analysis.tdf` read-only, so that a database that is still
+    /// being written by the acquisition software is never modified.
+    /// Concurrent writes are waited for instead of failing immediately.
     pub fn open(path: impl TimsTofPathLike) -> Result<Self, SqlReaderError> {
         let path = path.to_timstof_path()?;
-        let connection = Connection::open(&path.tdf()?)?;
+        let flags =
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let connection = Connection::open_with_flags(&path.tdf()?, flags)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
         Ok(Self { connection })
     }
 
@@ -38,18 +55,75 @@ impl SqlReader {
         let result = rows.collect::<Result<Vec<_>, _>>()?;
         Ok(result)
     }
+
+    /// Read a column that is not present in all schema versions. If it
+    /// doesn't exist, all `row_count` values are read as `None`; all other
+    /// errors are returned.
+    pub fn read_optional_column_from_table<T: FromSql + Clone>(
+        &self,
+        column_name: &str,
+        table_name: &str,
+        row_count: usize,
+    ) -> Result<Vec<Option<T>>, SqlReaderError> {
+        let exists = self
+            .table_columns(table_name)?
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(column_name));
+        match exists {
+            true => self.read_column_from_table(column_name, table_name),
+            false => Ok(vec![None; row_count]),
+        }
+    }
+
+    /// Check if a table with the given name is present in the database.
+    pub fn has_table(&self, table_name: &str) -> bool {
+        let query =
+            "SELECT name FROM sqlite_master WHERE type='table' AND name=?1";
+        self.connection
+            .prepare(query)
+            .and_then(|mut stmt| stmt.query_row([table_name], |_| Ok(true)))
+            .unwrap_or(false)
+    }
+
+    /// Read all rows of an optional table. Returns an empty Vec if any of
+    /// `tables` doesn't exist or if the table is empty.
+    pub fn read_optional_table<T: ReadableSqlTable>(
+        &self,
+        tables: &[&str],
+    ) -> Result<Vec<T>, SqlReaderError> {
+        if !tables.iter().all(|table| self.has_table(table)) {
+            return Ok(Vec::new());
+        }
+        match T::from_sql_reader(self) {
+            Err(SqlReaderError::SqlError(
+                rusqlite::Error::QueryReturnedNoRows,
+            )) => Ok(Vec::new()),
+            result => result,
+        }
+    }
 }
 
 pub trait ReadableSqlTable {
     fn get_sql_query() -> String;
 
+    /// Tables with a schema are read with a query that is adapted to the
+    /// columns of the file instead of [Self::get_sql_query].
+    fn schema() -> Option<SqlTableSchema> {
+        None
+    }
+
     fn from_sql_row(row: &rusqlite::Row) -> Self;
 
     fn from_sql_reader(reader: &SqlReader) -> Result<Vec<Self>, SqlReaderError>
     where
         Self: Sized,
     {
-        let query = Self::get_sql_query();
+        let query = match Self::schema() {
+            Some(schema) => {
+                schema.adapted_query(&reader.table_columns(schema.table)?)
+            },
+            None => Self::get_sql_query(),
+        };
         let mut stmt = reader.connection.prepare(&query)?;
         let rows = stmt.query_map([], |row| Ok(Self::from_sql_row(row)))?;
         let result = rows.collect::<Result<Vec<_>, _>>()?;
@@ -0,0 +1,24 @@
+//! Errors that were logged by the acquisition software.
+
+use super::{ParseDefault, ReadableSqlTable};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SqlErrorLog {
+    pub frame: usize,
+    pub scan: Option<usize>,
+    pub message: String,
+}
+
+impl ReadableSqlTable for SqlErrorLog {
+    fn get_sql_query() -> String {
+        "SELECT Frame, Scan, Message FROM ErrorLog".to_string()
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            frame: row.parse_default(0),
+            scan: row.get(1).ok().flatten(),
+            message: row.parse_default(2),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! MS/MS frame information from Bruker TDF files.
+//!
+//! Reads the `FrameMsMsInfo` table, which holds the isolation settings of
+//! MS/MS frames that were not acquired with PASEF or diaPASEF (MsMsType 2),
+//! e.g. the fragmentation frames of MALDI MS/MS imaging runs.
+
+use super::{ParseDefault, ReadableSqlTable, SqlReader, SqlReaderError};
+
+/// Isolation settings of a single MS/MS frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SqlFrameMsMs {
+    /// Frame ID (corresponds to Frame.Id)
+    pub frame: usize,
+    /// Frame ID of the parent MS1 frame (0 if unknown)
+    pub parent: usize,
+    /// Isolation m/z of the quadrupole
+    pub trigger_mass: f64,
+    /// Isolation width of the quadrupole
+    pub isolation_width: f64,
+    /// Charge of the precursor (0 if unknown)
+    pub precursor_charge: usize,
+    /// Collision energy
+    pub collision_energy: f64,
+}
+
+impl ReadableSqlTable for SqlFrameMsMs {
+    fn get_sql_query() -> String {
+        "SELECT Frame, Parent, TriggerMass, IsolationWidth, PrecursorCharge, CollisionEnergy FROM FrameMsMsInfo".to_string()
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            frame: row.parse_default(0),
+            parent: row.parse_default(1),
+            trigger_mass: row.parse_default(2),
+            isolation_width: row.parse_default(3),
+            precursor_charge: row.parse_default(4),
+            collision_energy: row.parse_default(5),
+        }
+    }
+}
+
+impl SqlReader {
+    /// Read all MS/MS frame info entries.
+    /// Returns an empty Vec if the table doesn't exist or is empty.
+    pub fn read_frame_msms_info(
+        &self,
+    ) -> Result<Vec<SqlFrameMsMs>, SqlReaderError> {
+        if !self.has_table("FrameMsMsInfo") {
+            return Ok(Vec::new());
+        }
+        match SqlFrameMsMs::from_sql_reader(self) {
+            Err(SqlReaderError::SqlError(
+                rusqlite::Error::QueryReturnedNoRows,
+            )) => Ok(Vec::new()),
+            result => result,
+        }
+    }
+}
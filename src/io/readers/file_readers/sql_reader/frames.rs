@@ -3,7 +3,26 @@
 //! Reads frame-level metadata including retention time, MS level, scan counts,
 //! and peak information from the `Frames` table in Bruker TimsTOF data files.
 
-use super::{ParseDefault, ReadableSqlTable};
+use super::{
+    schema::{SqlColumn, SqlTableSchema},
+    ParseDefault, ReadableSqlTable,
+};
+
+/// `ScanMode`, `NumPeaks` and `AccumulationTime` are absent in some
+/// schema versions and default to 0.
+pub const FRAMES_SCHEMA: SqlTableSchema = SqlTableSchema {
+    table: "Frames",
+    columns: &[
+        SqlColumn::required(&["Id"]),
+        SqlColumn::optional(&["ScanMode"]),
+        SqlColumn::required(&["MsMsType"]),
+        SqlColumn::optional(&["NumPeaks"]),
+        SqlColumn::required(&["Time"]),
+        SqlColumn::required(&["NumScans"]),
+        SqlColumn::required(&["TimsId"]),
+        SqlColumn::optional(&["AccumulationTime"]),
+    ],
+};
 
 /// Raw frame metadata from the Frames SQLite table.
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -20,7 +39,11 @@ pub struct SqlFrame {
 
 impl ReadableSqlTable for SqlFrame {
     fn get_sql_query() -> String {
-        "SELECT Id, ScanMode, MsMsType, NumPeaks, Time, NumScans, TimsId, AccumulationTime FROM Frames".to_string()
+        FRAMES_SCHEMA.query()
+    }
+
+    fn schema() -> Option<SqlTableSchema> {
+        Some(FRAMES_SCHEMA)
     }
 
     fn from_sql_row(row: &rusqlite::Row) -> Self {
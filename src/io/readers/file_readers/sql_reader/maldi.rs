@@ -29,7 +29,28 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
-use super::{ParseDefault, ReadableSqlTable, SqlReader, SqlReaderError};
+use super::{
+    schema::{SqlColumn, SqlTableSchema},
+    ParseDefault, ReadableSqlTable, SqlReader, SqlReaderError,
+};
+
+/// Older timsControl versions name the physical positions `MotorPositionX`
+/// and `MotorPositionY`, the repetition rate `LaserFrequency` and the shot
+/// count `LaserShots`.
+pub const MALDI_FRAME_INFO_SCHEMA: SqlTableSchema = SqlTableSchema {
+    table: "MaldiFrameInfo",
+    columns: &[
+        SqlColumn::required(&["Frame"]),
+        SqlColumn::optional(&["SpotName"]),
+        SqlColumn::required(&["XIndexPos"]),
+        SqlColumn::required(&["YIndexPos"]),
+        SqlColumn::optional(&["PositionX", "MotorPositionX"]),
+        SqlColumn::optional(&["PositionY", "MotorPositionY"]),
+        SqlColumn::optional(&["LaserPower"]),
+        SqlColumn::optional(&["LaserRepRate", "LaserFrequency"]),
+        SqlColumn::optional(&["NumLaserShots", "LaserShots"]),
+    ],
+};
 
 /// MALDI frame information from MaldiFrameInfo table.
 /// Contains spatial coordinates for imaging mass spectrometry.
@@ -57,9 +78,11 @@ pub struct SqlMaldiFrameInfo {
 
 impl ReadableSqlTable for SqlMaldiFrameInfo {
     fn get_sql_query() -> String {
-        "SELECT Frame, SpotName, XIndexPos, YIndexPos, PositionX, PositionY, \
-         LaserPower, LaserRepRate, NumLaserShots FROM MaldiFrameInfo"
-            .to_string()
+        MALDI_FRAME_INFO_SCHEMA.query()
+    }
+
+    fn schema() -> Option<SqlTableSchema> {
+        Some(MALDI_FRAME_INFO_SCHEMA)
     }
 
     fn from_sql_row(row: &rusqlite::Row) -> Self {
@@ -81,12 +104,7 @@ impl SqlReader {
     /// Check if this TDF file contains MALDI imaging data by checking
     /// for the MaldiFrameInfo table.
     pub fn has_maldi_info(&self) -> bool {
-        let query =
-            "SELECT name FROM sqlite_master WHERE type='table' AND name='MaldiFrameInfo'";
-        self.connection
-            .prepare(query)
-            .and_then(|mut stmt| stmt.query_row([], |_| Ok(true)))
-            .unwrap_or(false)
+        self.has_table("MaldiFrameInfo")
     }
 
     /// Read all MALDI frame info entries.
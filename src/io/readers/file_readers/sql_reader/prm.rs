@@ -0,0 +1,68 @@
+//! prm-PASEF targets from Bruker TDF files.
+//!
+//! The `PrmTargets` table lists the scheduled precursors of a prm-PASEF
+//! run and `PrmFrameMsMsInfo` the isolation windows of each frame
+//! (MsMsType 10) together with the target they measure.
+
+use super::{ParseDefault, ReadableSqlTable};
+
+/// A single isolation window of a prm-PASEF frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SqlPrmFrameMsMs {
+    pub frame: usize,
+    pub scan_start: usize,
+    pub scan_end: usize,
+    pub isolation_mz: f64,
+    pub isolation_width: f64,
+    pub collision_energy: f64,
+    /// Target ID (corresponds to PrmTargets.Id)
+    pub target: usize,
+}
+
+impl ReadableSqlTable for SqlPrmFrameMsMs {
+    fn get_sql_query() -> String {
+        "SELECT Frame, ScanNumBegin, ScanNumEnd, IsolationMz, IsolationWidth, CollisionEnergy, Target FROM PrmFrameMsMsInfo".to_string()
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            frame: row.parse_default(0),
+            scan_start: row.parse_default(1),
+            scan_end: row.parse_default(2),
+            isolation_mz: row.parse_default(3),
+            isolation_width: row.parse_default(4),
+            collision_energy: row.parse_default(5),
+            target: row.parse_default(6),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SqlPrmTarget {
+    pub id: usize,
+    pub external_id: String,
+    pub rt: f64,
+    pub im: f64,
+    pub mz: f64,
+    /// Charge of the target (0 if unknown)
+    pub charge: usize,
+    pub description: String,
+}
+
+impl ReadableSqlTable for SqlPrmTarget {
+    fn get_sql_query() -> String {
+        "SELECT Id, ExternalId, Time, OneOverK0, MonoisotopicMz, Charge, Description FROM PrmTargets".to_string()
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.parse_default(0),
+            external_id: row.parse_default(1),
+            rt: row.parse_default(2),
+            im: row.parse_default(3),
+            mz: row.parse_default(4),
+            charge: row.parse_default(5),
+            description: row.parse_default(6),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Per-frame instrument properties from Bruker TDF files.
+//!
+//! The `Properties` table stores values that changed for a frame. Their
+//! names are taken from the `PropertyDefinitions` table.
+
+use rusqlite::types::Value;
+
+use super::{ParseDefault, ReadableSqlTable};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SqlProperty {
+    pub frame: usize,
+    pub name: String,
+    pub value: Value,
+}
+
+impl ReadableSqlTable for SqlProperty {
+    fn get_sql_query() -> String {
+        "SELECT p.Frame, d.PermanentName, p.Value FROM Properties AS p \
+         JOIN PropertyDefinitions AS d ON p.Property = d.Id"
+            .to_string()
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            frame: row.parse_default(0),
+            name: row.parse_default(1),
+            value: row.get(2).unwrap_or(Value::Null),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Column introspection of the tables of Bruker TDF files.
+//!
+//! Bruker has added, removed and renamed columns of e.g. the `Frames` and
+//! `MaldiFrameInfo` tables across timsControl versions. Tables with a
+//! [SqlTableSchema] are read with a query that is adapted to the columns
+//! of the file: every column is selected by the first of its names that
+//! exists, and optional columns without any of their names are selected
+//! as `NULL`, so that their fields are defaulted.
+
+use std::collections::BTreeMap;
+
+use crate::ms_data::{GlobalMetadata, SchemaWarning, TdfSchema};
+
+use super::{
+    frames::FRAMES_SCHEMA, maldi::MALDI_FRAME_INFO_SCHEMA, SqlReader,
+    SqlReaderError,
+};
+
+/// A column of a [SqlTableSchema].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SqlColumn {
+    /// The current name first, followed by names of other schema versions
+    pub names: &'static [&'static str],
+    /// Whether reading fails if the column is missing
+    pub required: bool,
+}
+
+impl SqlColumn {
+    pub const fn required(names: &'static [&'static str]) -> Self {
+        Self {
+            names,
+            required: true,
+        }
+    }
+
+    pub const fn optional(names: &'static [&'static str]) -> Self {
+        Self {
+            names,
+            required: false,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+}
+
+/// The columns of a table, in the order in which they are selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SqlTableSchema {
+    pub table: &'static str,
+    pub columns: &'static [SqlColumn],
+}
+
+impl SqlTableSchema {
+    /// The query with the current names of all columns.
+    pub fn query(&self) -> String {
+        let columns: Vec<&str> =
+            self.columns.iter().map(|column| column.name()).collect();
+        format!("SELECT {} FROM {}", columns.join(", "), self.table)
+    }
+
+    /// The query adapted to the `available` columns of the table. Missing
+    /// required columns are selected by their current name, so that the
+    /// query fails.
+    pub fn adapted_query(&self, available: &[String]) -> String {
+        let columns: Vec<&str> = self
+            .columns
+            .iter()
+            .map(|column| match self.available_name(column, available) {
+                Some(name) => name,
+                None if column.required => column.name(),
+                None => "NULL",
+            })
+            .collect();
+        format!("SELECT {} FROM {}", columns.join(", "), self.table)
+    }
+
+    /// The current names of all optional columns that are missing.
+    pub fn missing_columns(&self, available: &[String]) -> Vec<&'static str> {
+        self.columns
+            .iter()
+            .filter(|column| !column.required)
+            .filter(|column| self.available_name(column, available).is_none())
+            .map(|column| column.name())
+            .collect()
+    }
+
+    fn available_name(
+        &self,
+        column: &SqlColumn,
+        available: &[String],
+    ) -> Option<&'static str> {
+        column.names.iter().copied().find(|name| {
+            available
+                .iter()
+                .any(|available| available.eq_ignore_ascii_case(name))
+        })
+    }
+}
+
+/// All tables that are read with an adapted query.
+const ADAPTED_TABLES: [SqlTableSchema; 2] =
+    [FRAMES_SCHEMA, MALDI_FRAME_INFO_SCHEMA];
+
+impl SqlReader {
+    pub fn table_names(&self) -> Result<Vec<String>, SqlReaderError> {
+        let mut stmt = self.connection.prepare(
+            "SELECT name FROM sqlite_master WHERE type='table' ORDER BY name",
+        )?;
+        let names = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(names)
+    }
+
+    /// The names of all columns of a table (empty if it doesn't exist).
+    pub fn table_columns(
+        &self,
+        table_name: &str,
+    ) -> Result<Vec<String>, SqlReaderError> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let columns = stmt
+            .query_map([table_name], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(columns)
+    }
+
+    /// The columns of all tables and a warning for every optional column
+    /// of an existing table that is defaulted.
+    pub fn detect_schema(
+        &self,
+        global_metadata: &GlobalMetadata,
+    ) -> Result<TdfSchema, SqlReaderError> {
+        let tables: BTreeMap<String, Vec<String>> = self
+            .table_names()?
+            .into_iter()
+            .map(|table| Ok((table.clone(), self.table_columns(&table)?)))
+            .collect::<Result<_, SqlReaderError>>()?;
+        let warnings = ADAPTED_TABLES
+            .iter()
+            .filter_map(|schema| {
+                let available = tables.get(schema.table)?;
+                Some(schema.missing_columns(available).into_iter().map(
+                    |column| SchemaWarning {
+                        table: schema.table.to_string(),
+                        column: column.to_string(),
+                    },
+                ))
+            })
+            .flatten()
+            .collect();
+        let version = global_metadata
+            .schema_version_major
+            .zip(global_metadata.schema_version_minor);
+        Ok(TdfSchema {
+            version,
+            tables,
+            warnings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: SqlTableSchema = SqlTableSchema {
+        table: "Frames",
+        columns: &[
+            SqlColumn::required(&["Id"]),
+            SqlColumn::optional(&["NumLaserShots", "LaserShots"]),
+            SqlColumn::optional(&["AccumulationTime"]),
+        ],
+    };
+
+    #[test]
+    fn adapts_queries_to_available_columns() {
+        assert_eq!(
+            SCHEMA.query(),
+            "SELECT Id, NumLaserShots, AccumulationTime FROM Frames"
+        );
+        let available = vec!["Id".to_string(), "LaserShots".to_string()];
+        assert_eq!(
+            SCHEMA.adapted_query(&available),
+            "SELECT Id, LaserShots, NULL FROM Frames"
+        );
+        assert_eq!(
+            SCHEMA.missing_columns(&available),
+            vec!["AccumulationTime"]
+        );
+        let available = vec!["LaserShots".to_string()];
+        assert_eq!(
+            SCHEMA.adapted_query(&available),
+            "SELECT Id, LaserShots, NULL FROM Frames"
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Acquisition segments from Bruker TDF files.
+//!
+//! A run is acquired in one or more segments, e.g. a calibration segment
+//! followed by the actual measurement.
+
+use super::{ParseDefault, ReadableSqlTable};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SqlSegment {
+    pub id: usize,
+    pub first_frame: usize,
+    pub last_frame: usize,
+    pub is_calibration_segment: bool,
+}
+
+impl ReadableSqlTable for SqlSegment {
+    fn get_sql_query() -> String {
+        "SELECT Id, FirstFrame, LastFrame, IsCalibrationSegment FROM Segments"
+            .to_string()
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.parse_default(0),
+            first_frame: row.parse_default(1),
+            last_frame: row.parse_default(2),
+            is_calibration_segment: row.parse_default(3),
+        }
+    }
+}
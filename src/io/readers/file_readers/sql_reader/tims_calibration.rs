@@ -0,0 +1,56 @@
+//! Scan -> 1/K0 calibrations from Bruker TDF files.
+//!
+//! Frames refer to a row of the `TimsCalibration` table through their
+//! `TimsCalibration` column. Every row has a `ModelType` and the
+//! coefficients `C0` to `C9` of that model.
+
+use super::{
+    schema::{SqlColumn, SqlTableSchema},
+    ParseDefault, ReadableSqlTable,
+};
+
+pub const TIMS_CALIBRATION_SCHEMA: SqlTableSchema = SqlTableSchema {
+    table: "TimsCalibration",
+    columns: &[
+        SqlColumn::required(&["Id"]),
+        SqlColumn::optional(&["ModelType"]),
+        SqlColumn::optional(&["C0"]),
+        SqlColumn::optional(&["C1"]),
+        SqlColumn::optional(&["C2"]),
+        SqlColumn::optional(&["C3"]),
+        SqlColumn::optional(&["C4"]),
+        SqlColumn::optional(&["C5"]),
+        SqlColumn::optional(&["C6"]),
+        SqlColumn::optional(&["C7"]),
+        SqlColumn::optional(&["C8"]),
+        SqlColumn::optional(&["C9"]),
+    ],
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SqlTimsCalibration {
+    pub id: usize,
+    pub model_type: i64,
+    /// `C0` to `C9`
+    pub coefficients: [f64; 10],
+}
+
+impl ReadableSqlTable for SqlTimsCalibration {
+    fn get_sql_query() -> String {
+        TIMS_CALIBRATION_SCHEMA.query()
+    }
+
+    fn schema() -> Option<SqlTableSchema> {
+        Some(TIMS_CALIBRATION_SCHEMA)
+    }
+
+    fn from_sql_row(row: &rusqlite::Row) -> Self {
+        Self {
+            id: row.parse_default(0),
+            model_type: row.parse_default(1),
+            coefficients: std::array::from_fn(|index| {
+                row.parse_default(index + 2)
+            }),
+        }
+    }
+}
@@ -1,9 +1,11 @@
 mod tdf_blobs;
 
 use memmap2::Mmap;
+use std::borrow::Cow;
 use std::fs::File;
 use std::io;
 pub use tdf_blobs::*;
+#[cfg(feature = "minitdf")]
 use zstd::decode_all;
 
 use crate::readers::{TimsTofFileType, TimsTofPathError, TimsTofPathLike};
@@ -18,37 +20,162 @@ pub struct TdfBlobReader {
 
 impl TdfBlobReader {
     pub fn new(path: impl TimsTofPathLike) -> Result<Self, TdfBlobReaderError> {
-        let bin_file_reader = TdfBinFileReader::new(path)?;
+        let bin_file_reader = TdfBinFileReader::new(path, false)?;
+        let reader = Self { bin_file_reader };
+        Ok(reader)
+    }
+
+    /// Read blobs with regular file reads instead of memory mapping the
+    /// binary file. This is slower, but cannot crash the process when the
+    /// file is truncated by another program while it is being read.
+    #[cfg(feature = "tdf")]
+    pub fn new_safe(
+        path: impl TimsTofPathLike,
+    ) -> Result<Self, TdfBlobReaderError> {
+        let bin_file_reader = TdfBinFileReader::new(path, true)?;
         let reader = Self { bin_file_reader };
         Ok(reader)
     }
 
+    #[cfg(feature = "minitdf")]
     pub fn get(&self, offset: usize) -> Result<TdfBlob, TdfBlobReaderError> {
+        let raw_blob = self.get_raw(offset)?;
+        let bytes = decode_all(raw_blob.data.as_ref())
+            .map_err(|_| TdfBlobReaderError::Decompression)?;
+        let blob = TdfBlob::new(bytes)?;
+        Ok(blob)
+    }
+
+    /// The (still compressed) data of the blob at `offset` and the scan
+    /// count of its header.
+    pub fn get_raw(
+        &self,
+        offset: usize,
+    ) -> Result<RawTdfBlob<'_>, TdfBlobReaderError> {
         let offset = self.bin_file_reader.global_file_offset + offset;
         let byte_count = self
             .bin_file_reader
-            .get_byte_count(offset)
+            .get_byte_count(offset)?
             .ok_or(TdfBlobReaderError::InvalidOffset(offset))?;
+        let scan_count = self
+            .bin_file_reader
+            .get_scan_count(offset)?
+            .ok_or(TdfBlobReaderError::CorruptData)?;
         let data = self
             .bin_file_reader
-            .get_data(offset, byte_count)
+            .get_data(offset, byte_count)?
             .ok_or(TdfBlobReaderError::CorruptData)?;
-        let bytes =
-            decode_all(data).map_err(|_| TdfBlobReaderError::Decompression)?;
-        let blob = TdfBlob::new(bytes)?;
-        Ok(blob)
+        Ok(RawTdfBlob { scan_count, data })
+    }
+
+    /// The size of the binary file in bytes.
+    #[cfg(feature = "tdf")]
+    pub fn file_size(&self) -> usize {
+        self.bin_file_reader.file_size
+    }
+
+    /// Whether the blob at `offset` is completely contained in the binary
+    /// file, without decompressing it.
+    #[cfg(feature = "tdf")]
+    pub fn is_complete(&self, offset: usize) -> bool {
+        let offset = self.bin_file_reader.global_file_offset + offset;
+        match self.bin_file_reader.get_byte_count(offset) {
+            Ok(Some(byte_count)) => {
+                (byte_count >= HEADER_SIZE * U32_SIZE)
+                    & (offset + byte_count <= self.file_size())
+            },
+            _ => false,
+        }
+    }
+}
+
+/// A blob as it is stored in the binary file, without its header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawTdfBlob<'a> {
+    pub scan_count: usize,
+    pub data: Cow<'a, [u8]>,
+}
+
+#[derive(Debug)]
+enum TdfBinStorage {
+    Mapped(Mmap),
+    /// Read with positional reads, so threads never wait for each other
+    File(File),
+}
+
+impl TdfBinStorage {
+    /// The bytes from `start` to `end`, or `None` if they are not within
+    /// the file.
+    fn get(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Result<Option<Cow<'_, [u8]>>, io::Error> {
+        match self {
+            Self::Mapped(mmap) => Ok(mmap.get(start..end).map(Cow::Borrowed)),
+            Self::File(file) => {
+                let Some(byte_count) = end.checked_sub(start) else {
+                    return Ok(None);
+                };
+                let mut buffer = vec![0; byte_count];
+                match read_exact_at(file, &mut buffer, start as u64) {
+                    Ok(()) => Ok(Some(Cow::Owned(buffer))),
+                    Err(error)
+                        if error.kind() == io::ErrorKind::UnexpectedEof =>
+                    {
+                        Ok(None)
+                    },
+                    Err(error) => Err(error),
+                }
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(
+    file: &File,
+    buffer: &mut [u8],
+    offset: u64,
+) -> Result<(), io::Error> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buffer, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(
+    file: &File,
+    mut buffer: &mut [u8],
+    mut offset: u64,
+) -> Result<(), io::Error> {
+    use std::os::windows::fs::FileExt;
+    while !buffer.is_empty() {
+        match file.seek_read(buffer, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(count) => {
+                buffer = &mut buffer[count..];
+                offset += count as u64;
+            },
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+            Err(error) => return Err(error),
+        }
     }
+    Ok(())
 }
 
 #[derive(Debug)]
 struct TdfBinFileReader {
-    mmap: Mmap,
+    storage: TdfBinStorage,
     global_file_offset: usize,
+    #[cfg(feature = "tdf")]
+    file_size: usize,
 }
 
 impl TdfBinFileReader {
     // TODO parse compression1
-    fn new(path: impl TimsTofPathLike) -> Result<Self, TdfBlobReaderError> {
+    fn new(
+        path: impl TimsTofPathLike,
+        safe: bool,
+    ) -> Result<Self, TdfBlobReaderError> {
         let path = path.to_timstof_path()?;
         let bin_path = match path.file_type() {
             #[cfg(feature = "tdf")]
@@ -57,36 +184,52 @@ impl TdfBinFileReader {
             TimsTofFileType::MiniTDF => path.ms2_bin()?,
         };
         let file = File::open(bin_path)?;
-        let mmap = unsafe { Mmap::map(&file)? };
+        #[cfg(feature = "tdf")]
+        let file_size = file.metadata()?.len() as usize;
+        let storage = if safe {
+            TdfBinStorage::File(file)
+        } else {
+            TdfBinStorage::Mapped(unsafe { Mmap::map(&file)? })
+        };
         let reader = Self {
-            mmap,
+            storage,
             global_file_offset: 0,
+            #[cfg(feature = "tdf")]
+            file_size,
         };
         Ok(reader)
     }
 
-    fn get_byte_count(&self, offset: usize) -> Option<usize> {
-        let start = offset as usize;
-        let end = start + U32_SIZE as usize;
-        let raw_byte_count = self.mmap.get(start..end)?;
-        let byte_count =
-            u32::from_le_bytes(raw_byte_count.try_into().ok()?) as usize;
-        Some(byte_count)
+    fn get_byte_count(
+        &self,
+        offset: usize,
+    ) -> Result<Option<usize>, io::Error> {
+        self.get_u32(offset)
     }
 
-    // fn get_scan_count(&self, offset: usize) -> Option<usize> {
-    //     let start = (offset + U32_SIZE) as usize;
-    //     let end = start + U32_SIZE as usize;
-    //     let raw_scan_count = self.mmap.get(start..end)?;
-    //     let scan_count =
-    //         u32::from_le_bytes(raw_scan_count.try_into().ok()?) as usize;
-    //     Some(scan_count)
-    // }
+    fn get_scan_count(
+        &self,
+        offset: usize,
+    ) -> Result<Option<usize>, io::Error> {
+        self.get_u32(offset + U32_SIZE)
+    }
 
-    fn get_data(&self, offset: usize, byte_count: usize) -> Option<&[u8]> {
+    fn get_u32(&self, start: usize) -> Result<Option<usize>, io::Error> {
+        let end = start + U32_SIZE;
+        let value = self.storage.get(start, end)?.and_then(|bytes| {
+            Some(u32::from_le_bytes(bytes.as_ref().try_into().ok()?) as usize)
+        });
+        Ok(value)
+    }
+
+    fn get_data(
+        &self,
+        offset: usize,
+        byte_count: usize,
+    ) -> Result<Option<Cow<'_, [u8]>>, io::Error> {
         let start = offset + HEADER_SIZE * U32_SIZE;
         let end = offset + byte_count;
-        self.mmap.get(start..end)
+        self.storage.get(start, end)
     }
 }
 
@@ -16,13 +16,30 @@ impl TdfBlob {
 
     #[cfg(feature = "minitdf")]
     pub fn get_all(&self) -> Vec<u32> {
-        (0..self.len())
-            .map(|index| self.get(index).expect(
-                "When iterating over the length of a tdf blob, you cannot go out of bounds"
-            ))
+        self.decode()
+    }
+
+    /// Decode all values at once.
+    ///
+    /// The bytes of the values are stored in four consecutive planes.
+    /// Walking these planes in lockstep needs no bounds checks per value,
+    /// which allows the compiler to vectorize the loop. This is much faster
+    /// than calling [TdfBlob::get] for every value.
+    pub fn decode(&self) -> Vec<u32> {
+        let (b1, rest) = self.bytes.split_at(self.len());
+        let (b2, rest) = rest.split_at(self.len());
+        let (b3, b4) = rest.split_at(self.len());
+        b1.iter()
+            .zip(b2)
+            .zip(b3)
+            .zip(b4)
+            .map(|(((&b1, &b2), &b3), &b4)| {
+                u32::from_le_bytes([b1, b2, b3, b4])
+            })
             .collect()
     }
 
+    #[cfg(feature = "tdf")]
     pub fn get(&self, index: usize) -> Option<u32> {
         if index >= self.len() {
             None
@@ -36,6 +53,7 @@ impl TdfBlob {
         }
     }
 
+    #[cfg(feature = "tdf")]
     fn concatenate_bytes(b1: u8, b2: u8, b3: u8, b4: u8) -> u32 {
         b1 as u32
             | ((b2 as u32) << 8)
@@ -56,3 +74,26 @@ impl TdfBlob {
 #[derive(Debug, thiserror::Error)]
 #[error("Length {0} is not a multiple of {BLOB_TYPE_SIZE}")]
 pub struct TdfBlobError(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tdf")]
+    #[test]
+    fn decode_matches_get() {
+        let values: Vec<u32> = vec![0, 1, 255, 256, 70000, u32::MAX, 12345678];
+        let bytes: Vec<u8> = (0..BLOB_TYPE_SIZE)
+            .flat_map(|plane| {
+                values.iter().map(move |value| value.to_le_bytes()[plane])
+            })
+            .collect();
+        let blob = TdfBlob::new(bytes).unwrap();
+        let expected: Vec<u32> = (0..blob.len())
+            .map(|index| blob.get(index).unwrap())
+            .collect();
+        assert_eq!(expected, values);
+        assert_eq!(blob.decode(), values);
+        assert_eq!(TdfBlob::default().decode(), Vec::<u32>::new());
+    }
+}
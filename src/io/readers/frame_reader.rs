@@ -9,9 +9,20 @@
 //!
 //! - Decompression of Bruker's proprietary TDF binary format
 //! - MALDI-TIMS-MSI support with pixel coordinates
-//! - Parallel frame reading for performance
-//! - DIA window metadata for data-independent acquisition
+//! - Parallel frame reading for performance, with progress reporting and
+//!   cancellation
+//! - DIA window metadata for data-independent acquisition, indexed by
+//!   [DiaWindowIndex]
+//! - Isolation settings of (MALDI) MS/MS frames from `FrameMsMsInfo`
+//! - prm-PASEF targets and diagonal (synchro-PASEF) window schemes
 //! - Full ion mobility (TIMS) data reconstruction
+//! - User-defined per-frame computations through [FrameVisitor]
+//! - Representative frame subsets for previews through [FrameReader::sample]
+//! - Selective loading of metadata and frames through [FrameReaderBuilder]
+//! - Safe reading of datasets that are still being acquired
+//! - Custom decoders for other compression types through [BlobDecompressor]
+//! - Normalized intensities through [FrameReaderBuilder::with_normalization]
+//! - An LRU cache of decoded frames through [FrameReader::get_cached]
 //!
 //! # Example
 //!
@@ -33,26 +44,69 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
-use std::sync::Arc;
-
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+mod builder;
+mod cache;
+mod decompression;
+mod dia_windows;
+mod prm;
+mod progress;
+mod quality;
+mod recovery;
+mod sampling;
+mod truncation;
+mod visitor;
+
+use std::{collections::HashMap, sync::Arc};
+
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    ParallelIterator,
+};
 #[cfg(feature = "timscompress")]
 use timscompress::reader::CompressedTdfBlobReader;
 
-use crate::ms_data::{AcquisitionType, Frame, MaldiInfo, MSLevel, QuadrupoleSettings};
+use crate::domain_converters::MobilityCalibration;
+use crate::ms_data::{
+    AcquisitionType, Frame, MSLevel, MaldiInfo, MsMsTypeKind,
+    NormalizationMode, PrmTarget, QuadrupoleSettings, ScanMode,
+};
+use crate::utils::parallel::min_split_len;
 
 use super::{
     file_readers::{
         sql_reader::{
-            frame_groups::SqlWindowGroup, frames::SqlFrame, maldi::SqlMaldiFrameInfo,
-            ReadableSqlTable, SqlReader, SqlReaderError,
+            frame_groups::SqlWindowGroup, frame_msms::SqlFrameMsMs,
+            frames::SqlFrame, maldi::SqlMaldiFrameInfo,
+            pasef_frame_msms::SqlPasefFrameMsMs, ReadableSqlTable, SqlReader,
+            SqlReaderError,
         },
-        tdf_blob_reader::{TdfBlob, TdfBlobReader, TdfBlobReaderError},
+        tdf_blob_reader::{TdfBlobReader, TdfBlobReaderError},
     },
-    MetadataReader, MetadataReaderError, QuadrupoleSettingsReader,
-    QuadrupoleSettingsReaderError, TimsTofPathLike,
+    MetadataReader, MetadataReaderError, MobilityCalibrationReader,
+    QuadrupoleSettingsReader, QuadrupoleSettingsReaderError, TimsTofPath,
+    TimsTofPathError, TimsTofPathLike,
 };
 
+pub use builder::FrameReaderBuilder;
+pub use cache::FrameCache;
+pub use decompression::*;
+pub use dia_windows::*;
+use prm::PrmSchedule;
+pub(crate) use progress::ProgressTracker;
+pub use progress::{CancellationToken, ProgressCallback, ReadProgress};
+pub use recovery::*;
+pub use sampling::*;
+pub use truncation::*;
+pub use visitor::*;
+
+/// Reads the frames of a TDF run.
+///
+/// All tables are read when the reader is constructed, after which the
+/// SQLite database is closed again. A single reader is `Send + Sync` and can
+/// be shared between threads (e.g. in an `Arc`). By default, frames are
+/// decoded from a memory map of `analysis.tdf_bin` without any locking. In
+/// safe mode, `analysis.tdf_bin` is read with positional reads that need no
+/// lock either; only a frame cache is guarded by a lock.
 #[derive(Debug)]
 pub struct FrameReader {
     tdf_bin_reader: TdfBlobReader,
@@ -62,53 +116,97 @@ pub struct FrameReader {
     acquisition: AcquisitionType,
     offsets: Vec<usize>,
     dia_windows: Option<Vec<Arc<QuadrupoleSettings>>>,
+    dia_window_index: Option<DiaWindowIndex>,
     compression_type: u8,
     #[cfg(feature = "timscompress")]
     scan_count: usize,
     /// Whether this is MALDI imaging data
     is_maldi: bool,
+    policy: ReaderPolicy,
+    truncation: Option<TruncationReport>,
+    quadrupole_crop: bool,
+    decompressor: Option<Arc<dyn BlobDecompressor>>,
+    normalization: NormalizationMode,
+    cache: Option<FrameCache>,
+    prm_targets: Vec<Arc<PrmTarget>>,
+    mobility_calibration: MobilityCalibration,
+    progress: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl FrameReader {
     pub fn new(path: impl TimsTofPathLike) -> Result<Self, FrameReaderError> {
-        let compression_type =
-            match MetadataReader::new(&path)?.compression_type {
-                2 => 2,
-                #[cfg(feature = "timscompress")]
-                3 => 3,
-                compression_type => {
-                    return Err(FrameReaderError::CompressionTypeError(
-                        compression_type,
-                    ))
-                },
-            };
+        Self::build().with_path(path).finalize()
+    }
+
+    pub fn build() -> FrameReaderBuilder {
+        FrameReaderBuilder::default()
+    }
+
+    fn from_builder(
+        path: TimsTofPath,
+        builder: &FrameReaderBuilder,
+    ) -> Result<Self, FrameReaderError> {
+        let metadata = MetadataReader::new(&path)?;
+        let compression_type = metadata.compression_type;
+        let decompressor =
+            builder.decompressors.get_or_builtin(compression_type);
+        match (compression_type, &decompressor) {
+            (_, Some(_)) => {},
+            #[cfg(feature = "timscompress")]
+            (3, None) => {},
+            (compression_type, None) => {
+                return Err(FrameReaderError::CompressionTypeError(
+                    compression_type,
+                ))
+            },
+        }
 
         let tdf_sql_reader = SqlReader::open(&path)?;
         let sql_frames = SqlFrame::from_sql_reader(&tdf_sql_reader)?;
-        
+
         // Load MALDI info if present (for imaging MS data)
-        let maldi_info = tdf_sql_reader.read_maldi_frame_info()?;
+        let maldi_info = if builder.maldi {
+            tdf_sql_reader.read_maldi_frame_info()?
+        } else {
+            vec![]
+        };
         let is_maldi = !maldi_info.is_empty();
-        let maldi_map: std::collections::HashMap<usize, SqlMaldiFrameInfo> = maldi_info
-            .into_iter()
-            .map(|m| (m.frame, m))
-            .collect();
-        
-        let tdf_bin_reader = TdfBlobReader::new(&path)?;
+        let maldi_map: HashMap<usize, SqlMaldiFrameInfo> =
+            maldi_info.into_iter().map(|m| (m.frame, m)).collect();
+        let mut msms_settings = get_msms_quadrupole_settings(
+            &sql_frames,
+            tdf_sql_reader.read_frame_msms_info()?,
+        );
+
+        let tdf_bin_reader = if builder.uses_safe_mode(&path) {
+            TdfBlobReader::new_safe(&path)?
+        } else {
+            TdfBlobReader::new(&path)?
+        };
+        let truncation = match decompressor {
+            Some(_) => {
+                truncation::detect_truncation(&sql_frames, &tdf_bin_reader)
+            },
+            None => None,
+        };
+        let readable_frame_count = truncation
+            .as_ref()
+            .map_or(sql_frames.len(), |x| x.readable_frame_count);
         #[cfg(feature = "timscompress")]
         let compressed_reader = CompressedTdfBlobReader::new(&path)
             .ok_or_else(|| FrameReaderError::TimscompressError)?;
-        let acquisition = if sql_frames.iter().any(|x| x.msms_type == 8) {
-            AcquisitionType::DDAPASEF
-        } else if sql_frames.iter().any(|x| x.msms_type == 9) {
-            AcquisitionType::DIAPASEF
-        } else {
-            AcquisitionType::Unknown
-        };
+        let mut acquisition =
+            AcquisitionType::from_frame_types(sql_frames.iter().map(|x| {
+                (
+                    ScanMode::from_raw(x.scan_mode),
+                    MsMsTypeKind::from_raw(x.msms_type),
+                )
+            }));
         // TODO should be refactored out to quadrupole reader
         let mut window_groups = vec![0; sql_frames.len()];
         let quadrupole_settings;
-        if acquisition == AcquisitionType::DIAPASEF {
+        if (acquisition == AcquisitionType::DIAPASEF) & builder.dia_windows {
             for window_group in
                 SqlWindowGroup::from_sql_reader(&tdf_sql_reader)?
             {
@@ -120,23 +218,60 @@ impl FrameReader {
             quadrupole_settings = vec![];
         }
         // TODO move Arc to quad settings reader?
-        let quadrupole_settings = quadrupole_settings
-            .into_iter()
-            .map(|x| Arc::new(x))
+        let quadrupole_settings: Vec<Arc<QuadrupoleSettings>> =
+            quadrupole_settings.into_iter().map(Arc::new).collect();
+        if dia_windows::is_diagonal(&quadrupole_settings) {
+            acquisition = AcquisitionType::DiagonalDIAPASEF;
+        }
+        let mut prm_schedule = match acquisition {
+            AcquisitionType::PRMPASEF => PrmSchedule::read(&tdf_sql_reader)?,
+            _ => PrmSchedule::default(),
+        };
+        msms_settings
+            .extend(std::mem::take(&mut prm_schedule.quadrupole_settings));
+        if acquisition == AcquisitionType::DDAPASEF {
+            msms_settings.extend(get_pasef_quadrupole_settings(
+                tdf_sql_reader.read_optional_table::<SqlPasefFrameMsMs>(&[
+                    "PasefFrameMsMsInfo",
+                ])?,
+            ));
+        }
+        let selected: Vec<usize> = (0..readable_frame_count)
+            .filter(|&index| builder.keeps_frame(&sql_frames[index]))
             .collect();
-        let frames = (0..sql_frames.len())
-            .into_par_iter()
-            .map(|index| {
+        let mut frames: Vec<Frame> = selected
+            .par_iter()
+            .map(|&index| {
                 get_frame_without_data(
                     index,
                     &sql_frames,
                     acquisition,
                     &window_groups,
                     &quadrupole_settings,
+                    &msms_settings,
                     &maldi_map,
                 )
             })
             .collect();
+        let mobility_calibration = MobilityCalibrationReader::from_sql_reader(
+            &tdf_sql_reader,
+            metadata.im_converter,
+        )?;
+        for frame in frames.iter_mut() {
+            frame.im_converter =
+                Some(mobility_calibration.frame_converter(frame.index));
+        }
+        prm_schedule.attach_targets(&mut frames);
+        if let Some(sample_sheet) = &builder.sample_sheet {
+            sample_sheet.join(&mut frames);
+        }
+        quality::assign_quality(
+            &mut frames,
+            &selected,
+            &sql_frames,
+            &tdf_sql_reader,
+            is_maldi,
+        );
         #[cfg(feature = "timscompress")]
         let scan_count = sql_frames
             .iter()
@@ -144,22 +279,44 @@ impl FrameReader {
             .max()
             .expect("Frame table cannot be empty")
             as usize;
-        let offsets = sql_frames.iter().map(|x| x.binary_offset).collect();
+        let offsets = selected
+            .iter()
+            .map(|&index| sql_frames[index].binary_offset)
+            .collect();
+        let dia_windows = match acquisition {
+            AcquisitionType::DIAPASEF | AcquisitionType::DiagonalDIAPASEF
+                if builder.dia_windows =>
+            {
+                Some(quadrupole_settings)
+            },
+            _ => None,
+        };
+        let dia_window_index = dia_windows
+            .as_ref()
+            .map(|window_groups| DiaWindowIndex::new(window_groups, &frames));
         let reader = Self {
             tdf_bin_reader,
             frames,
             acquisition,
             offsets,
-            dia_windows: match acquisition {
-                AcquisitionType::DIAPASEF => Some(quadrupole_settings),
-                _ => None,
-            },
+            dia_windows,
+            dia_window_index,
             compression_type,
             #[cfg(feature = "timscompress")]
             compressed_reader,
             #[cfg(feature = "timscompress")]
             scan_count,
             is_maldi,
+            policy: builder.policy,
+            truncation,
+            quadrupole_crop: builder.quadrupole_crop,
+            decompressor,
+            normalization: builder.normalization,
+            cache: builder.cache_capacity_in_bytes.map(FrameCache::new),
+            prm_targets: prm_schedule.targets,
+            mobility_calibration,
+            progress: builder.progress.clone(),
+            cancellation: builder.cancellation.clone(),
         };
         Ok(reader)
     }
@@ -169,15 +326,21 @@ impl FrameReader {
         self.offsets[index]
     }
 
+    /// Decode all frames that satisfy `predicate` in parallel. Progress is
+    /// reported to the [ProgressCallback] of this reader and frames are
+    /// not decoded anymore once its [CancellationToken] is cancelled.
     pub fn parallel_filter<'a, F: Fn(&Frame) -> bool + Sync + Send + 'a>(
         &'a self,
         predicate: F,
     ) -> impl ParallelIterator<Item = Result<Frame, FrameReaderError>> + 'a
     {
-        (0..self.len())
+        let indices: Vec<usize> = (0..self.len())
+            .filter(|&x| predicate(&self.frames[x]))
+            .collect();
+        let tracker = self.progress_tracker(indices.len());
+        indices
             .into_par_iter()
-            .filter(move |x| predicate(&self.frames[*x]))
-            .map(move |x| self.get(x))
+            .map(move |x| tracker.track(|| self.get(x)))
     }
 
     pub fn filter<'a, F: Fn(&Frame) -> bool + Sync + Send + 'a>(
@@ -189,41 +352,96 @@ impl FrameReader {
             .map(move |x| self.get(x))
     }
 
+    /// Run a [FrameVisitor] over all frames in parallel.
+    pub fn visit<V: FrameVisitor>(
+        &self,
+        visitor: V,
+    ) -> Result<V::Output, FrameReaderError> {
+        self.visit_filtered(|_| true, visitor)
+    }
+
+    /// Run a [FrameVisitor] in parallel over all frames that satisfy
+    /// `predicate`. The predicate only has access to frame metadata.
+    pub fn visit_filtered<V, F>(
+        &self,
+        predicate: F,
+        mut visitor: V,
+    ) -> Result<V::Output, FrameReaderError>
+    where
+        V: FrameVisitor,
+        F: Fn(&Frame) -> bool + Sync + Send,
+    {
+        visitor.begin_run(&self.run_info());
+        let indices: Vec<usize> = (0..self.len())
+            .filter(|&index| predicate(&self.frames[index]))
+            .collect();
+        let tracker = self.progress_tracker(indices.len());
+        // Every split forks a visitor, so bound the splits to one per thread
+        let min_len = min_split_len(indices.len());
+        let merged = indices
+            .into_par_iter()
+            .with_min_len(min_len)
+            .try_fold(
+                || visitor.fork(),
+                |mut worker, index| {
+                    let frame = tracker.track(|| self.get(index))?;
+                    worker.visit_frame(&frame);
+                    Ok::<V, FrameReaderError>(worker)
+                },
+            )
+            .try_reduce(
+                || visitor.fork(),
+                |mut worker, other| {
+                    worker.merge(other);
+                    Ok(worker)
+                },
+            )?;
+        visitor.merge(merged);
+        Ok(visitor.end_run())
+    }
+
+    fn progress_tracker(&self, total: usize) -> ProgressTracker<'_> {
+        ProgressTracker::new(
+            self.progress.as_ref(),
+            self.cancellation.as_ref(),
+            total,
+        )
+    }
+
+    pub fn run_info(&self) -> RunInfo {
+        RunInfo {
+            frame_count: self.len(),
+            acquisition_type: self.acquisition,
+            is_maldi: self.is_maldi,
+        }
+    }
+
     pub fn get_dia_windows(&self) -> Option<Vec<Arc<QuadrupoleSettings>>> {
         self.dia_windows.clone()
     }
 
+    /// Read a frame with all its data. Intensities are normalized
+    /// according to the [NormalizationMode] of this reader.
     pub fn get(&self, index: usize) -> Result<Frame, FrameReaderError> {
-        match self.compression_type {
-            2 => self.get_from_compression_type_2(index),
-            #[cfg(feature = "timscompress")]
-            3 => self.get_from_compression_type_3(index),
-            _ => Err(FrameReaderError::CompressionTypeError(
-                self.compression_type,
-            )),
-        }
+        let mut frame = if let Some(decompressor) = &self.decompressor {
+            self.get_with_decompressor(index, decompressor.as_ref())?
+        } else {
+            match self.compression_type {
+                #[cfg(feature = "timscompress")]
+                3 => self.get_from_compression_type_3(index)?,
+                _ => {
+                    return Err(FrameReaderError::CompressionTypeError(
+                        self.compression_type,
+                    ))
+                },
+            }
+        };
+        frame.normalize(self.normalization);
+        Ok(frame)
     }
 
-    fn get_from_compression_type_2(
-        &self,
-        index: usize,
-    ) -> Result<Frame, FrameReaderError> {
-        // NOTE: get does it by 0-offsetting the vec, not by Frame index!!!
-        let mut frame = self.get_frame_without_coordinates(index)?;
-        let offset = self.get_binary_offset(index);
-        let blob = self.tdf_bin_reader.get(offset)?;
-        let scan_count: usize =
-            blob.get(0).ok_or(FrameReaderError::CorruptFrame)? as usize;
-        let peak_count: usize = (blob.len() - scan_count) / 2;
-        frame.scan_offsets = read_scan_offsets(scan_count, peak_count, &blob)?;
-        frame.intensities = read_intensities(scan_count, peak_count, &blob)?;
-        frame.tof_indices = read_tof_indices(
-            scan_count,
-            peak_count,
-            &blob,
-            &frame.scan_offsets,
-        )?;
-        Ok(frame)
+    pub fn normalization(&self) -> NormalizationMode {
+        self.normalization
     }
 
     #[cfg(feature = "timscompress")]
@@ -244,6 +462,31 @@ impl FrameReader {
         Ok(frame)
     }
 
+    /// Which scans of an MS2 frame were isolated by the quadrupole, if only
+    /// those should be decoded.
+    fn quadrupole_scans(
+        &self,
+        frame: &Frame,
+        scan_count: usize,
+    ) -> Option<Vec<bool>> {
+        let quad = &frame.quadrupole_settings;
+        if !self.quadrupole_crop
+            | (frame.ms_level != MSLevel::MS2)
+            | (quad.len() == 0)
+        {
+            return None;
+        }
+        let mut kept_scans = vec![false; scan_count];
+        for (&start, &end) in quad.scan_starts.iter().zip(&quad.scan_ends) {
+            for kept in
+                kept_scans.iter_mut().take(end.min(scan_count)).skip(start)
+            {
+                *kept = true;
+            }
+        }
+        Some(kept_scans)
+    }
+
     pub fn get_frame_without_coordinates(
         &self,
         index: usize,
@@ -282,60 +525,76 @@ impl FrameReader {
     pub fn is_maldi(&self) -> bool {
         self.is_maldi
     }
-}
 
-fn read_scan_offsets(
-    scan_count: usize,
-    peak_count: usize,
-    blob: &TdfBlob,
-) -> Result<Vec<usize>, FrameReaderError> {
-    let mut scan_offsets: Vec<usize> = Vec::with_capacity(scan_count + 1);
-    scan_offsets.push(0);
-    for scan_index in 0..scan_count - 1 {
-        let index = scan_index + 1;
-        let scan_size: usize =
-            (blob.get(index).ok_or(FrameReaderError::CorruptFrame)? / 2)
-                as usize;
-        scan_offsets.push(scan_offsets[scan_index] + scan_size);
+    /// The Scan -> 1/K0 calibration of every frame, which is also attached
+    /// to frames as [Frame::im_converter].
+    pub fn mobility_calibration(&self) -> &MobilityCalibration {
+        &self.mobility_calibration
     }
-    scan_offsets.push(peak_count);
-    Ok(scan_offsets)
 }
 
-fn read_intensities(
-    scan_count: usize,
-    peak_count: usize,
-    blob: &TdfBlob,
-) -> Result<Vec<u32>, FrameReaderError> {
-    let mut intensities: Vec<u32> = Vec::with_capacity(peak_count);
-    for peak_index in 0..peak_count {
-        let index: usize = scan_count + 1 + 2 * peak_index;
-        intensities
-            .push(blob.get(index).ok_or(FrameReaderError::CorruptFrame)?);
-    }
-    Ok(intensities)
+/// Build the quadrupole settings of MS/MS frames listed in `FrameMsMsInfo`.
+///
+/// These frames isolate a single precursor over all scans, so each setting
+/// spans the full scan range of its frame.
+fn get_msms_quadrupole_settings(
+    sql_frames: &[SqlFrame],
+    sql_frame_msms: Vec<SqlFrameMsMs>,
+) -> HashMap<usize, Arc<QuadrupoleSettings>> {
+    let scan_counts: HashMap<usize, usize> = sql_frames
+        .iter()
+        .map(|frame| (frame.id, frame.scan_count as usize))
+        .collect();
+    sql_frame_msms
+        .into_iter()
+        .map(|msms| {
+            let quad = QuadrupoleSettings {
+                index: msms.frame,
+                scan_starts: vec![0],
+                scan_ends: vec![*scan_counts.get(&msms.frame).unwrap_or(&0)],
+                isolation_mz: vec![msms.trigger_mass],
+                isolation_width: vec![msms.isolation_width],
+                collision_energy: vec![msms.collision_energy],
+            };
+            (msms.frame, Arc::new(quad))
+        })
+        .collect()
 }
 
-fn read_tof_indices(
-    scan_count: usize,
-    peak_count: usize,
-    blob: &TdfBlob,
-    scan_offsets: &Vec<usize>,
-) -> Result<Vec<u32>, FrameReaderError> {
-    let mut tof_indices: Vec<u32> = Vec::with_capacity(peak_count);
-    for scan_index in 0..scan_count {
-        let start_offset: usize = scan_offsets[scan_index];
-        let end_offset: usize = scan_offsets[scan_index + 1];
-        let mut current_sum: u32 = 0;
-        for peak_index in start_offset..end_offset {
-            let index = scan_count + 2 * peak_index;
-            let tof_index: u32 =
-                blob.get(index).ok_or(FrameReaderError::CorruptFrame)?;
-            current_sum += tof_index;
-            tof_indices.push(current_sum - 1);
-        }
+/// Build the quadrupole settings of DDA-PASEF MS2 frames from
+/// `PasefFrameMsMsInfo`, with a window per isolated precursor in order of
+/// their scans.
+fn get_pasef_quadrupole_settings(
+    sql_pasef_frames: Vec<SqlPasefFrameMsMs>,
+) -> HashMap<usize, Arc<QuadrupoleSettings>> {
+    let mut windows: HashMap<usize, Vec<SqlPasefFrameMsMs>> = HashMap::new();
+    for pasef_frame in sql_pasef_frames {
+        windows
+            .entry(pasef_frame.frame)
+            .or_default()
+            .push(pasef_frame);
     }
-    Ok(tof_indices)
+    windows
+        .into_iter()
+        .map(|(frame, mut windows)| {
+            windows.sort_by_key(|window| window.scan_start);
+            let quad = QuadrupoleSettings {
+                index: frame,
+                scan_starts: windows.iter().map(|x| x.scan_start).collect(),
+                scan_ends: windows.iter().map(|x| x.scan_end).collect(),
+                isolation_mz: windows.iter().map(|x| x.isolation_mz).collect(),
+                isolation_width: windows
+                    .iter()
+                    .map(|x| x.isolation_width)
+                    .collect(),
+                collision_energy: windows
+                    .iter()
+                    .map(|x| x.collision_energy)
+                    .collect(),
+            };
+            (frame, Arc::new(quad))
+        })
+        .collect()
 }
 
 fn get_frame_without_data(
@@ -344,23 +603,36 @@ fn get_frame_without_data(
     acquisition: AcquisitionType,
     window_groups: &Vec<u8>,
     quadrupole_settings: &Vec<Arc<QuadrupoleSettings>>,
-    maldi_map: &std::collections::HashMap<usize, SqlMaldiFrameInfo>,
+    msms_settings: &HashMap<usize, Arc<QuadrupoleSettings>>,
+    maldi_map: &HashMap<usize, SqlMaldiFrameInfo>,
 ) -> Frame {
     let mut frame: Frame = Frame::default();
     let sql_frame = &sql_frames[index];
     frame.index = sql_frame.id;
     frame.ms_level = MSLevel::read_from_msms_type(sql_frame.msms_type);
+    frame.msms_type = sql_frame.msms_type;
+    frame.scan_mode = sql_frame.scan_mode;
     frame.rt_in_seconds = sql_frame.rt;
     frame.acquisition_type = acquisition;
-    frame.intensity_correction_factor = 1.0 / sql_frame.accumulation_time;
-    if (acquisition == AcquisitionType::DIAPASEF)
-        & (frame.ms_level == MSLevel::MS2)
+    // A missing AccumulationTime leaves the intensities uncorrected
+    frame.intensity_correction_factor = if sql_frame.accumulation_time > 0.0 {
+        1.0 / sql_frame.accumulation_time
+    } else {
+        1.0
+    };
+    if matches!(
+        acquisition,
+        AcquisitionType::DIAPASEF | AcquisitionType::DiagonalDIAPASEF
+    ) & (frame.ms_level == MSLevel::MS2)
+        & !quadrupole_settings.is_empty()
     {
         // TODO should be refactored out to quadrupole reader
         let window_group = window_groups[index];
         frame.window_group = window_group;
         frame.quadrupole_settings =
             quadrupole_settings[window_group as usize - 1].clone();
+    } else if let Some(quad) = msms_settings.get(&sql_frame.id) {
+        frame.quadrupole_settings = quad.clone();
     }
     // Attach MALDI info if present (frame IDs are 1-based)
     if let Some(maldi) = maldi_map.get(&sql_frame.id) {
@@ -381,7 +653,6 @@ fn get_frame_without_data(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
 
     #[test]
     fn attaches_maldi_metadata_when_present() {
@@ -415,6 +686,7 @@ mod tests {
             AcquisitionType::DDAPASEF,
             &vec![0],
             &vec![Arc::new(QuadrupoleSettings::default())],
+            &HashMap::new(),
             &maldi_map,
         );
 
@@ -429,6 +701,7 @@ mod tests {
         assert_eq!(maldi.laser_shots, Some(50));
         assert_eq!(frame.index, 1);
         assert_eq!(frame.ms_level, MSLevel::MS1);
+        assert_eq!(frame.msms_type, 0);
     }
 
     #[test]
@@ -448,12 +721,53 @@ mod tests {
             &vec![0],
             &vec![Arc::new(QuadrupoleSettings::default())],
             &HashMap::new(),
+            &HashMap::new(),
         );
 
         assert!(frame.maldi_info.is_none());
         assert_eq!(frame.index, 2);
         assert_eq!(frame.ms_level, MSLevel::MS2);
     }
+
+    #[test]
+    fn attaches_msms_quadrupole_settings() {
+        let sql_frames = vec![SqlFrame {
+            id: 3,
+            msms_type: 2,
+            scan_count: 100,
+            accumulation_time: 50.0,
+            ..Default::default()
+        }];
+        let msms_settings = get_msms_quadrupole_settings(
+            &sql_frames,
+            vec![SqlFrameMsMs {
+                frame: 3,
+                trigger_mass: 760.58,
+                isolation_width: 1.5,
+                collision_energy: 35.0,
+                ..Default::default()
+            }],
+        );
+
+        let frame = get_frame_without_data(
+            0,
+            &sql_frames,
+            AcquisitionType::Unknown,
+            &vec![0],
+            &vec![],
+            &msms_settings,
+            &HashMap::new(),
+        );
+
+        assert_eq!(frame.ms_level, MSLevel::MS2);
+        let quad = &frame.quadrupole_settings;
+        assert_eq!(quad.index, 3);
+        assert_eq!(quad.scan_starts, vec![0]);
+        assert_eq!(quad.scan_ends, vec![100]);
+        assert_eq!(quad.isolation_mz, vec![760.58]);
+        assert_eq!(quad.isolation_width, vec![1.5]);
+        assert_eq!(quad.collision_energy, vec![35.0]);
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -475,6 +789,14 @@ pub enum FrameReaderError {
     QuadrupoleSettingsReaderError(#[from] QuadrupoleSettingsReaderError),
     #[error("Index out of bounds")]
     IndexOutOfBounds,
+    #[error("Reading was cancelled")]
+    Cancelled,
     #[error("Compression type {0} not understood")]
     CompressionTypeError(u8),
+    #[error("No path provided")]
+    NoPath,
+    #[error("{0}")]
+    TimsTofPathError(#[from] TimsTofPathError),
+    #[error("{0}")]
+    BlobDecompressorError(#[from] BlobDecompressorError),
 }
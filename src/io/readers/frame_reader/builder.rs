@@ -0,0 +1,229 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    io::readers::{
+        builder_path, file_readers::sql_reader::frames::SqlFrame,
+        resolve_builder_path, BuilderPath, SampleSheet, TimsTofPath,
+        TimsTofPathLike,
+    },
+    ms_data::{MSLevel, NormalizationMode},
+};
+
+use super::{
+    BlobDecompressor, CancellationToken, DecompressorRegistry, FrameReader,
+    FrameReaderError, ProgressCallback, ReaderPolicy,
+};
+
+/// Configures which metadata a [FrameReader] loads and which frames it
+/// exposes.
+///
+/// By default everything is loaded, which is what [FrameReader::new] does.
+#[derive(Debug, Clone)]
+pub struct FrameReaderBuilder {
+    path: Option<BuilderPath>,
+    pub(super) dia_windows: bool,
+    pub(super) maldi: bool,
+    safe_mode: Option<bool>,
+    frame_range: Option<Range<usize>>,
+    ms_level: Option<MSLevel>,
+    pub(super) sample_sheet: Option<SampleSheet>,
+    pub(super) policy: ReaderPolicy,
+    pub(super) quadrupole_crop: bool,
+    pub(super) decompressors: DecompressorRegistry,
+    pub(super) normalization: NormalizationMode,
+    pub(super) cache_capacity_in_bytes: Option<usize>,
+    pub(super) progress: Option<ProgressCallback>,
+    pub(super) cancellation: Option<CancellationToken>,
+}
+
+impl Default for FrameReaderBuilder {
+    fn default() -> Self {
+        Self {
+            path: None,
+            dia_windows: true,
+            maldi: true,
+            safe_mode: None,
+            frame_range: None,
+            ms_level: None,
+            sample_sheet: None,
+            policy: ReaderPolicy::default(),
+            quadrupole_crop: false,
+            decompressors: DecompressorRegistry::default(),
+            normalization: NormalizationMode::default(),
+            cache_capacity_in_bytes: None,
+            progress: None,
+            cancellation: None,
+        }
+    }
+}
+
+impl FrameReaderBuilder {
+    /// The run to read. The path is only resolved by [Self::finalize],
+    /// which returns the error of an invalid path.
+    pub fn with_path(&self, path: impl TimsTofPathLike) -> Self {
+        let path = Some(builder_path(path));
+        Self {
+            path,
+            ..self.clone()
+        }
+    }
+
+    /// Whether to load the DIA window groups and their quadrupole settings.
+    /// Without them, DIA MS2 frames have default quadrupole settings.
+    pub fn with_dia_windows(&self, dia_windows: bool) -> Self {
+        Self {
+            dia_windows,
+            ..self.clone()
+        }
+    }
+
+    /// Whether to load the MALDI imaging tables.
+    pub fn with_maldi(&self, maldi: bool) -> Self {
+        Self {
+            maldi,
+            ..self.clone()
+        }
+    }
+
+    /// Whether to read the binary data without memory mapping it, which
+    /// cannot interfere with (or be crashed by) acquisition software that
+    /// is still writing the dataset. By default, safe mode is used when
+    /// the dataset is locked (see [TimsTofPath::lock_state]).
+    pub fn with_safe_mode(&self, safe_mode: bool) -> Self {
+        Self {
+            safe_mode: Some(safe_mode),
+            ..self.clone()
+        }
+    }
+
+    /// Only expose frames whose `Id` is within `frame_range`.
+    pub fn frame_range(&self, frame_range: Range<usize>) -> Self {
+        Self {
+            frame_range: Some(frame_range),
+            ..self.clone()
+        }
+    }
+
+    /// Only expose frames of a single MS level.
+    pub fn ms_level_filter(&self, ms_level: MSLevel) -> Self {
+        Self {
+            ms_level: Some(ms_level),
+            ..self.clone()
+        }
+    }
+
+    /// Attach the labels of a sample sheet to all matching frames.
+    pub fn with_sample_sheet(&self, sample_sheet: SampleSheet) -> Self {
+        Self {
+            sample_sheet: Some(sample_sheet),
+            ..self.clone()
+        }
+    }
+
+    /// How corrupt frames are handled by [FrameReader::get_or_skip] and
+    /// [FrameReader::get_all_recovered].
+    pub fn with_policy(&self, policy: ReaderPolicy) -> Self {
+        Self {
+            policy,
+            ..self.clone()
+        }
+    }
+
+    /// Whether to only decode the scans of MS2 frames that were isolated
+    /// by the quadrupole. All other scans are empty by construction, so
+    /// cropping them makes decoding (e.g. PASEF) MS2 frames much faster.
+    /// Frames without quadrupole settings are decoded completely.
+    pub fn with_quadrupole_crop(&self, quadrupole_crop: bool) -> Self {
+        Self {
+            quadrupole_crop,
+            ..self.clone()
+        }
+    }
+
+    /// Decode the binary data of `compression_type` with `decompressor`,
+    /// also if timsrust supports that compression type natively.
+    pub fn with_decompressor(
+        &self,
+        compression_type: u8,
+        decompressor: impl BlobDecompressor + 'static,
+    ) -> Self {
+        let mut decompressors = self.decompressors.clone();
+        decompressors.register(compression_type, Arc::new(decompressor));
+        Self {
+            decompressors,
+            ..self.clone()
+        }
+    }
+
+    /// How the intensities of all read frames are normalized into
+    /// [Frame::normalized_intensities]. The raw intensities are kept.
+    ///
+    /// [Frame::normalized_intensities]: crate::ms_data::Frame::normalized_intensities
+    pub fn with_normalization(&self, normalization: NormalizationMode) -> Self {
+        Self {
+            normalization,
+            ..self.clone()
+        }
+    }
+
+    /// Keep up to `capacity_in_bytes` of decoded frames in a [FrameCache]
+    /// that is used by [FrameReader::get_cached].
+    ///
+    /// [FrameCache]: super::FrameCache
+    pub fn with_cache(&self, capacity_in_bytes: usize) -> Self {
+        Self {
+            cache_capacity_in_bytes: Some(capacity_in_bytes),
+            ..self.clone()
+        }
+    }
+
+    /// Report the progress of parallel reads (e.g. [FrameReader::get_all],
+    /// [FrameReader::visit] or [FrameReader::sample]) to `callback`, which
+    /// is a [ProgressCallback] or any `Fn(ReadProgress)`.
+    ///
+    /// [ReadProgress]: super::ReadProgress
+    pub fn with_progress(&self, callback: impl Into<ProgressCallback>) -> Self {
+        Self {
+            progress: Some(callback.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Stop decoding frames in parallel reads once `cancellation` is
+    /// cancelled.
+    pub fn with_cancellation(&self, cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation: Some(cancellation),
+            ..self.clone()
+        }
+    }
+
+    pub fn finalize(self) -> Result<FrameReader, FrameReaderError> {
+        let path = match &self.path {
+            None => return Err(FrameReaderError::NoPath),
+            Some(path) => resolve_builder_path(path)?,
+        };
+        FrameReader::from_builder(path, &self)
+    }
+
+    pub(super) fn uses_safe_mode(&self, path: &TimsTofPath) -> bool {
+        match self.safe_mode {
+            Some(safe_mode) => safe_mode,
+            None => path.lock_state().is_ok_and(|state| state.is_locked()),
+        }
+    }
+
+    pub(super) fn keeps_frame(&self, sql_frame: &SqlFrame) -> bool {
+        let in_range = match &self.frame_range {
+            Some(range) => range.contains(&sql_frame.id),
+            None => true,
+        };
+        let has_level = match self.ms_level {
+            Some(ms_level) => {
+                MSLevel::read_from_msms_type(sql_frame.msms_type) == ms_level
+            },
+            None => true,
+        };
+        in_range & has_level
+    }
+}
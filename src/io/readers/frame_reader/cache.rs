@@ -0,0 +1,187 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use crate::ms_data::Frame;
+
+use super::{FrameReader, FrameReaderError};
+
+/// A size-bounded cache of decoded frames that evicts the least recently
+/// used frames first.
+///
+/// The size of a frame is the number of bytes of its peak data. Frames
+/// that are larger than the capacity of the cache are never cached.
+#[derive(Debug)]
+pub struct FrameCache {
+    capacity_in_bytes: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    frames: HashMap<usize, (Arc<Frame>, u64)>,
+    recency: BTreeMap<u64, usize>,
+    clock: u64,
+    size_in_bytes: usize,
+    hits: usize,
+    misses: usize,
+}
+
+impl FrameCache {
+    pub fn new(capacity_in_bytes: usize) -> Self {
+        Self {
+            capacity_in_bytes,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn capacity_in_bytes(&self) -> usize {
+        self.capacity_in_bytes
+    }
+
+    /// The total size of all cached frames.
+    pub fn size_in_bytes(&self) -> usize {
+        self.lock().size_in_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of lookups that did and did not find a cached frame.
+    pub fn hits_and_misses(&self) -> (usize, usize) {
+        let state = self.lock();
+        (state.hits, state.misses)
+    }
+
+    pub fn get(&self, index: usize) -> Option<Arc<Frame>> {
+        let mut state = self.lock();
+        let tick = state.tick();
+        let Some((frame, last_used)) = state.frames.get_mut(&index) else {
+            state.misses += 1;
+            return None;
+        };
+        let frame = frame.clone();
+        let previous = std::mem::replace(last_used, tick);
+        state.recency.remove(&previous);
+        state.recency.insert(tick, index);
+        state.hits += 1;
+        Some(frame)
+    }
+
+    /// Cache `frame` under `index`, evicting the least recently used
+    /// frames until it fits.
+    pub fn insert(&self, index: usize, frame: Arc<Frame>) {
+        let size = frame_size_in_bytes(&frame);
+        if size > self.capacity_in_bytes {
+            return;
+        }
+        let mut state = self.lock();
+        state.remove(index);
+        while state.size_in_bytes + size > self.capacity_in_bytes {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.remove(oldest);
+        }
+        let tick = state.tick();
+        state.frames.insert(index, (frame, tick));
+        state.recency.insert(tick, index);
+        state.size_in_bytes += size;
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.frames.clear();
+        state.recency.clear();
+        state.size_in_bytes = 0;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // The state is consistent after every operation, so a poisoned
+        // lock can safely be reused.
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl CacheState {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, index: usize) {
+        if let Some((frame, last_used)) = self.frames.remove(&index) {
+            self.recency.remove(&last_used);
+            self.size_in_bytes -= frame_size_in_bytes(&frame);
+        }
+    }
+}
+
+fn frame_size_in_bytes(frame: &Frame) -> usize {
+    std::mem::size_of_val(frame.scan_offsets.as_slice())
+        + std::mem::size_of_val(frame.tof_indices.as_slice())
+        + std::mem::size_of_val(frame.intensities.as_slice())
+        + std::mem::size_of_val(frame.normalized_intensities.as_slice())
+}
+
+impl FrameReader {
+    /// Like [FrameReader::get], but shares decoded frames through the
+    /// [FrameCache] of this reader (see [FrameReaderBuilder::with_cache]).
+    /// Without a cache, every call decodes the frame.
+    ///
+    /// [FrameReaderBuilder::with_cache]: super::FrameReaderBuilder::with_cache
+    pub fn get_cached(
+        &self,
+        index: usize,
+    ) -> Result<Arc<Frame>, FrameReaderError> {
+        let Some(cache) = &self.cache else {
+            return Ok(Arc::new(self.get(index)?));
+        };
+        if let Some(frame) = cache.get(index) {
+            return Ok(frame);
+        }
+        let frame = Arc::new(self.get(index)?);
+        cache.insert(index, frame.clone());
+        Ok(frame)
+    }
+
+    pub fn cache(&self) -> Option<&FrameCache> {
+        self.cache.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(peak_count: usize) -> Arc<Frame> {
+        Arc::new(Frame {
+            tof_indices: vec![0; peak_count],
+            intensities: vec![0; peak_count],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = FrameCache::new(70);
+        cache.insert(0, frame(4));
+        cache.insert(1, frame(4));
+        assert_eq!(cache.size_in_bytes(), 64);
+        assert!(cache.get(0).is_some());
+        cache.insert(2, frame(2));
+        assert!(cache.get(1).is_none());
+        assert!(cache.get(0).is_some());
+        assert!(cache.get(2).is_some());
+        assert_eq!(cache.size_in_bytes(), 48);
+        assert_eq!(cache.hits_and_misses(), (3, 1));
+        cache.insert(3, frame(100));
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,443 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use crate::{
+    io::readers::file_readers::tdf_blob_reader::TdfBlob,
+    ms_data::{Frame, FrameQuality},
+};
+
+use super::{FrameReader, FrameReaderError};
+
+/// The peaks of a frame, as produced by a [BlobDecompressor].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecodedFrame {
+    /// The start of every scan in `tof_indices` and `intensities`, followed
+    /// by the total peak count
+    pub scan_offsets: Vec<usize>,
+    pub tof_indices: Vec<u32>,
+    pub intensities: Vec<u32>,
+}
+
+impl DecodedFrame {
+    pub fn scan_count(&self) -> usize {
+        self.scan_offsets.len().saturating_sub(1)
+    }
+
+    pub(super) fn is_consistent(&self) -> bool {
+        (self.scan_offsets.first().is_none_or(|&start| start == 0))
+            & (self.scan_offsets.last().copied().unwrap_or(0)
+                == self.tof_indices.len())
+            & (self.tof_indices.len() == self.intensities.len())
+            & self.scan_offsets.windows(2).all(|x| x[0] <= x[1])
+    }
+
+    /// Drop the peaks of all scans that are not kept.
+    pub fn retain_scans(self, kept_scans: &[bool]) -> Self {
+        let mut decoded = Self {
+            scan_offsets: vec![0],
+            ..Default::default()
+        };
+        for (scan, peaks) in self.scan_offsets.windows(2).enumerate() {
+            if kept_scans.get(scan).copied().unwrap_or(false) {
+                let peaks = peaks[0]..peaks[1];
+                decoded
+                    .tof_indices
+                    .extend_from_slice(&self.tof_indices[peaks.clone()]);
+                decoded
+                    .intensities
+                    .extend_from_slice(&self.intensities[peaks]);
+            }
+            decoded.scan_offsets.push(decoded.tof_indices.len());
+        }
+        decoded
+    }
+}
+
+/// Decodes the binary data of frames with a `TimsCompressionType`.
+///
+/// Blobs are framed as in all TDF binary files: a header with the byte
+/// count and the scan count, followed by the data that is passed to
+/// [BlobDecompressor::decompress]. Truncated binary files are detected
+/// from this framing for every decompressor (see
+/// [FrameReader::truncation]).
+pub trait BlobDecompressor: Debug + Send + Sync {
+    /// Decode the data of a blob whose header holds `scan_count`.
+    ///
+    /// Inconsistent data should be reported as
+    /// [BlobDecompressorError::CorruptData], so that
+    /// [ReaderPolicy](super::ReaderPolicy) can recover the frame.
+    fn decompress(
+        &self,
+        data: &[u8],
+        scan_count: usize,
+    ) -> Result<DecodedFrame, BlobDecompressorError>;
+
+    /// Decode only the peaks of the scans that are kept; all other scans
+    /// are empty. Used to crop MS2 frames to their quadrupole windows.
+    fn decompress_scans(
+        &self,
+        data: &[u8],
+        scan_count: usize,
+        kept_scans: &[bool],
+    ) -> Result<DecodedFrame, BlobDecompressorError> {
+        let decoded = self.decompress(data, scan_count)?;
+        if !decoded.is_consistent() {
+            return Err(BlobDecompressorError::CorruptData);
+        }
+        Ok(decoded.retain_scans(kept_scans))
+    }
+
+    /// Decode the scans of a corrupt blob up to the first inconsistency;
+    /// all later scans are empty. `None` if this format cannot be decoded
+    /// partially, in which case corrupt frames are dropped by
+    /// [ReaderPolicy::Partial](super::ReaderPolicy::Partial).
+    fn decompress_partial(
+        &self,
+        _data: &[u8],
+        _scan_count: usize,
+    ) -> Option<DecodedFrame> {
+        None
+    }
+}
+
+/// The decoder of compression type 2: zstd-compressed, byte-transposed
+/// scan sizes and pairs of TOF deltas and intensities.
+///
+/// [FrameReader] uses it for compression type 2, unless another
+/// decompressor is registered for it (e.g. one that wraps this one).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Type2Decompressor;
+
+impl Type2Decompressor {
+    fn decode(data: &[u8]) -> Result<TdfBlob, BlobDecompressorError> {
+        let bytes = zstd::decode_all(data)
+            .map_err(|_| BlobDecompressorError::Decompression)?;
+        TdfBlob::new(bytes).map_err(|_| BlobDecompressorError::CorruptData)
+    }
+}
+
+impl BlobDecompressor for Type2Decompressor {
+    fn decompress(
+        &self,
+        data: &[u8],
+        _scan_count: usize,
+    ) -> Result<DecodedFrame, BlobDecompressorError> {
+        let values = Self::decode(data)?.decode();
+        let (scan_count, scan_offsets) = read_scan_offsets(&values)?;
+        let peak_count = scan_offsets[scan_count];
+        let peaks = read_peaks(scan_count, peak_count, &values)?;
+        let decoded = DecodedFrame {
+            intensities: peaks.chunks_exact(2).map(|peak| peak[1]).collect(),
+            tof_indices: read_tof_indices(peaks, &scan_offsets)?,
+            scan_offsets,
+        };
+        Ok(decoded)
+    }
+
+    fn decompress_scans(
+        &self,
+        data: &[u8],
+        _scan_count: usize,
+        kept_scans: &[bool],
+    ) -> Result<DecodedFrame, BlobDecompressorError> {
+        let values = Self::decode(data)?.decode();
+        let (scan_count, scan_offsets) = read_scan_offsets(&values)?;
+        let peak_count = scan_offsets[scan_count];
+        let peaks = read_peaks(scan_count, peak_count, &values)?;
+        let mut decoded = DecodedFrame {
+            scan_offsets: Vec::with_capacity(scan_count + 1),
+            ..Default::default()
+        };
+        decoded.scan_offsets.push(0);
+        for (scan, offsets) in scan_offsets.windows(2).enumerate() {
+            if kept_scans.get(scan).copied().unwrap_or(false) {
+                let scan_peaks = &peaks[2 * offsets[0]..2 * offsets[1]];
+                let mut current_sum: u32 = 0;
+                for peak in scan_peaks.chunks_exact(2) {
+                    current_sum = next_tof_sum(current_sum, peak[0])?;
+                    decoded.tof_indices.push(current_sum - 1);
+                    decoded.intensities.push(peak[1]);
+                }
+            }
+            decoded.scan_offsets.push(decoded.intensities.len());
+        }
+        Ok(decoded)
+    }
+
+    fn decompress_partial(
+        &self,
+        data: &[u8],
+        _scan_count: usize,
+    ) -> Option<DecodedFrame> {
+        decode_partial(&Self::decode(data).ok()?)
+    }
+}
+
+/// The scan count and the start of every scan (followed by the peak count)
+/// of decoded type 2 values.
+fn read_scan_offsets(
+    values: &[u32],
+) -> Result<(usize, Vec<usize>), BlobDecompressorError> {
+    let scan_count =
+        *values.first().ok_or(BlobDecompressorError::CorruptData)? as usize;
+    let peak_count = values
+        .len()
+        .checked_sub(scan_count)
+        .ok_or(BlobDecompressorError::CorruptData)?
+        / 2;
+    let scan_sizes = values
+        .get(1..scan_count)
+        .ok_or(BlobDecompressorError::CorruptData)?;
+    let mut scan_offsets: Vec<usize> = Vec::with_capacity(scan_count + 1);
+    scan_offsets.push(0);
+    let mut offset = 0;
+    for &scan_size in scan_sizes {
+        offset += (scan_size / 2) as usize;
+        scan_offsets.push(offset);
+    }
+    scan_offsets.push(peak_count);
+    if scan_offsets.windows(2).any(|x| x[0] > x[1]) {
+        return Err(BlobDecompressorError::CorruptData);
+    }
+    Ok((scan_count, scan_offsets))
+}
+
+/// The interleaved TOF index deltas and intensities of all peaks.
+fn read_peaks(
+    scan_count: usize,
+    peak_count: usize,
+    values: &[u32],
+) -> Result<&[u32], BlobDecompressorError> {
+    values
+        .get(scan_count..scan_count + 2 * peak_count)
+        .ok_or(BlobDecompressorError::CorruptData)
+}
+
+fn read_tof_indices(
+    peaks: &[u32],
+    scan_offsets: &[usize],
+) -> Result<Vec<u32>, BlobDecompressorError> {
+    let mut tof_indices: Vec<u32> = Vec::with_capacity(peaks.len() / 2);
+    for scan in scan_offsets.windows(2) {
+        let mut current_sum: u32 = 0;
+        for peak in peaks[2 * scan[0]..2 * scan[1]].chunks_exact(2) {
+            current_sum = next_tof_sum(current_sum, peak[0])?;
+            tof_indices.push(current_sum - 1);
+        }
+    }
+    Ok(tof_indices)
+}
+
+/// Add a TOF delta to the running sum of a scan. The sum is the TOF index
+/// plus one, so it overflowing or being 0 means that the frame is corrupt.
+fn next_tof_sum(
+    current_sum: u32,
+    delta: u32,
+) -> Result<u32, BlobDecompressorError> {
+    current_sum
+        .checked_add(delta)
+        .filter(|&sum| sum > 0)
+        .ok_or(BlobDecompressorError::CorruptData)
+}
+
+/// Decode the scans of a type 2 blob up to the first inconsistency. All
+/// scans after it are empty.
+fn decode_partial(blob: &TdfBlob) -> Option<DecodedFrame> {
+    let scan_count = blob.get(0)? as usize;
+    if (scan_count == 0) | (scan_count > blob.len()) {
+        return None;
+    }
+    let peak_count = (blob.len() - scan_count) / 2;
+    let mut decoded = DecodedFrame {
+        scan_offsets: Vec::with_capacity(scan_count + 1),
+        ..Default::default()
+    };
+    decoded.scan_offsets.push(0);
+    let mut corrupt = false;
+    for scan in 0..scan_count {
+        let scan_start = decoded.scan_offsets[scan];
+        let scan_end = if scan + 1 < scan_count {
+            scan_start + blob.get(scan + 1)? as usize / 2
+        } else {
+            peak_count
+        };
+        if corrupt | (scan_end > peak_count) {
+            corrupt = true;
+            decoded.scan_offsets.push(decoded.intensities.len());
+            continue;
+        }
+        let mut current_sum: u32 = 0;
+        for peak in scan_start..scan_end {
+            match next_tof_sum(current_sum, blob.get(scan_count + 2 * peak)?) {
+                Ok(sum) => {
+                    current_sum = sum;
+                    decoded.tof_indices.push(sum - 1);
+                    decoded
+                        .intensities
+                        .push(blob.get(scan_count + 1 + 2 * peak)?);
+                },
+                Err(_) => {
+                    corrupt = true;
+                    break;
+                },
+            }
+        }
+        decoded.scan_offsets.push(decoded.intensities.len());
+    }
+    Some(decoded)
+}
+
+/// Decompressors for compression types, which take precedence over the
+/// formats that are supported natively (2, and 3 with the `timscompress`
+/// feature).
+#[derive(Clone, Debug, Default)]
+pub struct DecompressorRegistry {
+    decompressors: HashMap<u8, Arc<dyn BlobDecompressor>>,
+}
+
+impl DecompressorRegistry {
+    pub fn register(
+        &mut self,
+        compression_type: u8,
+        decompressor: Arc<dyn BlobDecompressor>,
+    ) {
+        self.decompressors.insert(compression_type, decompressor);
+    }
+
+    pub fn get(
+        &self,
+        compression_type: u8,
+    ) -> Option<&Arc<dyn BlobDecompressor>> {
+        self.decompressors.get(&compression_type)
+    }
+
+    /// The registered decompressor of `compression_type`, or the built-in
+    /// one if there is none.
+    pub(super) fn get_or_builtin(
+        &self,
+        compression_type: u8,
+    ) -> Option<Arc<dyn BlobDecompressor>> {
+        match (self.get(compression_type), compression_type) {
+            (Some(decompressor), _) => Some(decompressor.clone()),
+            (None, 2) => Some(Arc::new(Type2Decompressor)),
+            (None, _) => None,
+        }
+    }
+}
+
+impl FrameReader {
+    /// The `TimsCompressionType` of the binary data.
+    pub fn compression_type(&self) -> u8 {
+        self.compression_type
+    }
+
+    pub(super) fn get_with_decompressor(
+        &self,
+        index: usize,
+        decompressor: &dyn BlobDecompressor,
+    ) -> Result<Frame, FrameReaderError> {
+        let mut frame = self.get_frame_without_coordinates(index)?;
+        let offset = self.get_binary_offset(index);
+        let raw_blob = self.tdf_bin_reader.get_raw(offset)?;
+        let scan_count = raw_blob.scan_count;
+        let decoded = match self.quadrupole_scans(&frame, scan_count) {
+            Some(kept_scans) => decompressor.decompress_scans(
+                &raw_blob.data,
+                scan_count,
+                &kept_scans,
+            ),
+            None => decompressor.decompress(&raw_blob.data, scan_count),
+        }
+        .map_err(|error| match error {
+            BlobDecompressorError::CorruptData => {
+                FrameReaderError::CorruptFrame
+            },
+            error => error.into(),
+        })?;
+        if !decoded.is_consistent() {
+            return Err(FrameReaderError::CorruptFrame);
+        }
+        frame.scan_offsets = decoded.scan_offsets;
+        frame.tof_indices = decoded.tof_indices;
+        frame.intensities = decoded.intensities;
+        Ok(frame)
+    }
+
+    /// Decode the scans of a corrupt frame up to the corruption, if its
+    /// decompressor supports it.
+    pub(super) fn get_partial_with_decompressor(
+        &self,
+        index: usize,
+        decompressor: &dyn BlobDecompressor,
+    ) -> Option<Frame> {
+        let mut frame = self.get_frame_without_coordinates(index).ok()?;
+        let raw_blob = self.tdf_bin_reader.get_raw(self.offsets[index]).ok()?;
+        let decoded = decompressor
+            .decompress_partial(&raw_blob.data, raw_blob.scan_count)?;
+        if decoded.intensities.is_empty() | !decoded.is_consistent() {
+            return None;
+        }
+        frame.scan_offsets = decoded.scan_offsets;
+        frame.tof_indices = decoded.tof_indices;
+        frame.intensities = decoded.intensities;
+        frame.quality.insert(FrameQuality::CORRUPT_BLOB);
+        Some(frame)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlobDecompressorError {
+    #[error("Decompression fails")]
+    Decompression,
+    #[error("Data is corrupt")]
+    CorruptData,
+    #[error("{0}")]
+    Other(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retains_scans() {
+        let decoded = DecodedFrame {
+            scan_offsets: vec![0, 2, 2, 3],
+            tof_indices: vec![1, 5, 3],
+            intensities: vec![10, 50, 30],
+        };
+        assert!(decoded.is_consistent());
+        let retained = decoded.retain_scans(&[false, true, true]);
+        assert_eq!(retained.scan_offsets, vec![0, 0, 0, 1]);
+        assert_eq!(retained.tof_indices, vec![3]);
+        assert_eq!(retained.intensities, vec![30]);
+    }
+
+    /// Byte-transpose u32 values like the zstd decompressed TDF blobs.
+    fn blob(values: &[u32]) -> TdfBlob {
+        let bytes: Vec<[u8; 4]> =
+            values.iter().map(|value| value.to_le_bytes()).collect();
+        let transposed = (0..4)
+            .flat_map(|plane| bytes.iter().map(move |value| value[plane]))
+            .collect();
+        TdfBlob::new(transposed).unwrap()
+    }
+
+    #[test]
+    fn decodes_until_corruption() {
+        // 3 scans with 1, 1 and 1 peak(s); the second has a TOF delta of 0
+        let decoded =
+            decode_partial(&blob(&[3, 2, 2, 5, 10, 0, 20, 7, 30])).unwrap();
+        assert_eq!(decoded.scan_offsets, vec![0, 1, 1, 1]);
+        assert_eq!(decoded.tof_indices, vec![4]);
+        assert_eq!(decoded.intensities, vec![10]);
+    }
+
+    #[test]
+    fn decodes_consistent_blob_completely() {
+        let decoded =
+            decode_partial(&blob(&[2, 2, 5, 10, 3, 20, 7, 30])).unwrap();
+        assert_eq!(decoded.scan_offsets, vec![0, 1, 3]);
+        assert_eq!(decoded.tof_indices, vec![4, 2, 9]);
+        assert_eq!(decoded.intensities, vec![10, 20, 30]);
+    }
+}
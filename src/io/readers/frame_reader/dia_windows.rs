@@ -0,0 +1,214 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use rayon::iter::ParallelIterator;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::ms_data::{Frame, MSLevel, QuadrupoleSettings};
+
+use super::{FrameReader, FrameReaderError};
+
+/// A single quadrupole window of a DIA window group. The window applies to
+/// the scans `scan_start..scan_end`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct DiaWindow {
+    pub window_group: u8,
+    pub scan_start: usize,
+    pub scan_end: usize,
+    pub isolation_mz: f64,
+    pub isolation_width: f64,
+    pub collision_energy: f64,
+}
+
+impl DiaWindow {
+    pub fn lower_mz(&self) -> f64 {
+        self.isolation_mz - self.isolation_width / 2.0
+    }
+
+    pub fn upper_mz(&self) -> f64 {
+        self.isolation_mz + self.isolation_width / 2.0
+    }
+
+    /// Whether a precursor with `mz` at `scan` is isolated by this window.
+    pub fn contains(&self, mz: f64, scan: usize) -> bool {
+        (self.lower_mz() <= mz)
+            & (mz <= self.upper_mz())
+            & (self.scan_start <= scan)
+            & (scan < self.scan_end)
+    }
+}
+
+/// Maps the DIA window groups of a run to their windows and MS2 frames.
+///
+/// Frames are referred to by their 0-based index in the [FrameReader].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiaWindowIndex {
+    windows: Vec<DiaWindow>,
+    frames: BTreeMap<u8, Vec<usize>>,
+}
+
+impl DiaWindowIndex {
+    pub(super) fn new(
+        window_groups: &[Arc<QuadrupoleSettings>],
+        frames: &[Frame],
+    ) -> Self {
+        let mut windows = vec![];
+        let mut group_frames = BTreeMap::new();
+        for quad in window_groups {
+            let window_group = quad.index as u8;
+            group_frames.insert(window_group, vec![]);
+            for i in 0..quad.len() {
+                windows.push(DiaWindow {
+                    window_group,
+                    scan_start: quad.scan_starts[i],
+                    scan_end: quad.scan_ends[i],
+                    isolation_mz: quad.isolation_mz[i],
+                    isolation_width: quad.isolation_width[i],
+                    collision_energy: quad.collision_energy[i],
+                });
+            }
+        }
+        for (index, frame) in frames.iter().enumerate() {
+            if frame.ms_level != MSLevel::MS2 {
+                continue;
+            }
+            if let Some(group) = group_frames.get_mut(&frame.window_group) {
+                group.push(index);
+            }
+        }
+        Self {
+            windows,
+            frames: group_frames,
+        }
+    }
+
+    /// All window groups, in ascending order.
+    pub fn window_groups(&self) -> Vec<u8> {
+        self.frames.keys().copied().collect()
+    }
+
+    /// All windows, sorted by window group.
+    pub fn windows(&self) -> &[DiaWindow] {
+        &self.windows
+    }
+
+    pub fn windows_of_group(&self, window_group: u8) -> Vec<&DiaWindow> {
+        self.windows
+            .iter()
+            .filter(|window| window.window_group == window_group)
+            .collect()
+    }
+
+    /// The MS2 frames that were acquired with a window group.
+    pub fn frames_for_window(&self, window_group: u8) -> &[usize] {
+        self.frames
+            .get(&window_group)
+            .map(|frames| frames.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The first window that isolates a precursor with `mz` at `scan`.
+    pub fn window_for_mz_scan(
+        &self,
+        mz: f64,
+        scan: usize,
+    ) -> Option<&DiaWindow> {
+        self.windows.iter().find(|window| window.contains(mz, scan))
+    }
+}
+
+impl FrameReader {
+    /// The window index of a DIA run, if its windows were loaded (see
+    /// [FrameReaderBuilder::with_dia_windows](super::FrameReaderBuilder::with_dia_windows)).
+    pub fn dia_window_index(&self) -> Option<&DiaWindowIndex> {
+        self.dia_window_index.as_ref()
+    }
+
+    /// Read all MS2 frames of a DIA window group in parallel.
+    pub fn par_iter_window(
+        &self,
+        window_group: u8,
+    ) -> impl ParallelIterator<Item = Result<Frame, FrameReaderError>> + '_
+    {
+        self.parallel_filter(move |frame| {
+            (frame.ms_level == MSLevel::MS2)
+                & (frame.window_group == window_group)
+        })
+    }
+}
+
+/// The minimal number of windows of a window group that form an isolation
+/// ramp.
+const MIN_RAMP_WINDOWS: usize = 3;
+
+/// Whether all window groups are isolation ramps, i.e. at least
+/// [MIN_RAMP_WINDOWS] windows over consecutive scans whose isolation m/z
+/// changes monotonically by less than half of their isolation width.
+/// Diagonal schemes (e.g. synchro-PASEF) are stored as such overlapping
+/// slices in `DiaFrameMsMsWindows`, while the windows of a diaPASEF
+/// staircase are (nearly) adjacent in m/z.
+pub(super) fn is_diagonal(window_groups: &[Arc<QuadrupoleSettings>]) -> bool {
+    let is_ramp = |quad: &Arc<QuadrupoleSettings>| {
+        let slopes: Vec<f64> = quad
+            .isolation_mz
+            .windows(2)
+            .map(|mz| mz[1] - mz[0])
+            .collect();
+        let consecutive = (1..quad.len())
+            .all(|i| quad.scan_starts[i] <= quad.scan_ends[i - 1] + 1);
+        let overlapping = (1..quad.len()).all(|i| {
+            let width =
+                quad.isolation_width[i].min(quad.isolation_width[i - 1]);
+            slopes[i - 1].abs() < width / 2.0
+        });
+        (quad.len() >= MIN_RAMP_WINDOWS)
+            && consecutive
+            && overlapping
+            && (slopes.iter().all(|&slope| slope > 0.0)
+                || slopes.iter().all(|&slope| slope < 0.0))
+    };
+    !window_groups.is_empty() & window_groups.iter().all(is_ramp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_group(
+        scans: &[(usize, usize)],
+        mzs: &[f64],
+    ) -> Arc<QuadrupoleSettings> {
+        Arc::new(QuadrupoleSettings {
+            index: 1,
+            scan_starts: scans.iter().map(|x| x.0).collect(),
+            scan_ends: scans.iter().map(|x| x.1).collect(),
+            isolation_mz: mzs.to_vec(),
+            isolation_width: vec![25.0; mzs.len()],
+            collision_energy: vec![20.0; mzs.len()],
+        })
+    }
+
+    #[test]
+    fn detects_diagonal_windows() {
+        let ramp = window_group(
+            &[(0, 100), (100, 200), (200, 300)],
+            &[900.0, 890.0, 880.0],
+        );
+        assert!(is_diagonal(std::slice::from_ref(&ramp)));
+        let dia = window_group(&[(30, 180), (230, 380)], &[400.0, 600.0]);
+        assert!(!is_diagonal(std::slice::from_ref(&dia)));
+        assert!(!is_diagonal(&[ramp, dia]));
+        let staircase = window_group(
+            &[(0, 100), (100, 200), (200, 300)],
+            &[400.0, 424.0, 448.0],
+        );
+        assert!(!is_diagonal(&[staircase]));
+        let zigzag = window_group(
+            &[(0, 100), (100, 200), (200, 300)],
+            &[900.0, 890.0, 895.0],
+        );
+        assert!(!is_diagonal(&[zigzag]));
+        assert!(!is_diagonal(&[]));
+    }
+}
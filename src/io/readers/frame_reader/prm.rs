@@ -0,0 +1,93 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use crate::{
+    io::readers::file_readers::sql_reader::{
+        prm::{SqlPrmFrameMsMs, SqlPrmTarget},
+        SqlReader, SqlReaderError,
+    },
+    ms_data::{Frame, PrmTarget, QuadrupoleSettings},
+};
+
+use super::FrameReader;
+
+/// The targets of a prm-PASEF run and the isolation windows of each of its
+/// frames, by frame ID.
+#[derive(Debug, Default)]
+pub(super) struct PrmSchedule {
+    pub(super) targets: Vec<Arc<PrmTarget>>,
+    pub(super) quadrupole_settings: HashMap<usize, Arc<QuadrupoleSettings>>,
+    frame_targets: HashMap<usize, Vec<Option<Arc<PrmTarget>>>>,
+}
+
+impl PrmSchedule {
+    pub(super) fn read(sql_reader: &SqlReader) -> Result<Self, SqlReaderError> {
+        let targets: BTreeMap<usize, Arc<PrmTarget>> = sql_reader
+            .read_optional_table::<SqlPrmTarget>(&["PrmTargets"])?
+            .into_iter()
+            .map(|target| {
+                let prm_target = PrmTarget {
+                    id: target.id,
+                    external_id: target.external_id,
+                    rt_in_seconds: target.rt,
+                    im: target.im,
+                    mz: target.mz,
+                    charge: (target.charge > 0).then_some(target.charge),
+                    description: target.description,
+                };
+                (target.id, Arc::new(prm_target))
+            })
+            .collect();
+        let mut frame_windows: BTreeMap<usize, Vec<SqlPrmFrameMsMs>> =
+            BTreeMap::new();
+        for window in sql_reader
+            .read_optional_table::<SqlPrmFrameMsMs>(&["PrmFrameMsMsInfo"])?
+        {
+            frame_windows.entry(window.frame).or_default().push(window);
+        }
+        let mut schedule = Self::default();
+        for (frame, mut windows) in frame_windows {
+            windows.sort_by_key(|window| window.scan_start);
+            let quad = QuadrupoleSettings {
+                index: frame,
+                scan_starts: windows.iter().map(|x| x.scan_start).collect(),
+                scan_ends: windows.iter().map(|x| x.scan_end).collect(),
+                isolation_mz: windows.iter().map(|x| x.isolation_mz).collect(),
+                isolation_width: windows
+                    .iter()
+                    .map(|x| x.isolation_width)
+                    .collect(),
+                collision_energy: windows
+                    .iter()
+                    .map(|x| x.collision_energy)
+                    .collect(),
+            };
+            schedule.quadrupole_settings.insert(frame, Arc::new(quad));
+            let frame_targets = windows
+                .iter()
+                .map(|window| targets.get(&window.target).cloned())
+                .collect();
+            schedule.frame_targets.insert(frame, frame_targets);
+        }
+        schedule.targets = targets.into_values().collect();
+        Ok(schedule)
+    }
+
+    pub(super) fn attach_targets(&self, frames: &mut [Frame]) {
+        for frame in frames.iter_mut() {
+            if let Some(targets) = self.frame_targets.get(&frame.index) {
+                frame.prm_targets = targets.clone();
+            }
+        }
+    }
+}
+
+impl FrameReader {
+    /// All targets of a prm-PASEF run, ordered by ID. Empty for all other
+    /// acquisition types.
+    pub fn prm_targets(&self) -> &[Arc<PrmTarget>] {
+        &self.prm_targets
+    }
+}
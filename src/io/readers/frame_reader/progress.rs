@@ -0,0 +1,135 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::FrameReaderError;
+
+/// The progress of a parallel read of many frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadProgress {
+    /// The number of frames that were decoded so far
+    pub completed: usize,
+    /// The number of frames that are read in total
+    pub total: usize,
+}
+
+impl ReadProgress {
+    /// The completed fraction between 0 and 1 (1 if nothing is read).
+    pub fn fraction(&self) -> f64 {
+        match self.total {
+            0 => 1.0,
+            total => self.completed as f64 / total as f64,
+        }
+    }
+}
+
+/// A function that is called after every decoded frame of a parallel read.
+///
+/// It is called from the worker threads, so it should be cheap (e.g. only
+/// update a progress bar).
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(ReadProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(
+        callback: impl Fn(ReadProgress) + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl<F: Fn(ReadProgress) + Send + Sync + 'static> From<F> for ProgressCallback {
+    fn from(callback: F) -> Self {
+        Self::new(callback)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Aborts parallel reads of a [FrameReader] that was configured with it.
+/// All clones share the same state, so a GUI can keep a clone and cancel
+/// a read that runs on another thread.
+///
+/// Frames that were not decoded before cancellation are reported as
+/// [FrameReaderError::Cancelled].
+///
+/// [FrameReader]: super::FrameReader
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Reports the progress of a single parallel read and checks whether it
+/// was cancelled.
+pub(crate) struct ProgressTracker<'a> {
+    callback: Option<&'a ProgressCallback>,
+    cancellation: Option<&'a CancellationToken>,
+    completed: AtomicUsize,
+    total: usize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub(crate) fn new(
+        callback: Option<&'a ProgressCallback>,
+        cancellation: Option<&'a CancellationToken>,
+        total: usize,
+    ) -> Self {
+        Self {
+            callback,
+            cancellation,
+            completed: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    /// Decode a frame with `decode`, unless the read was cancelled.
+    pub(crate) fn track<T>(
+        &self,
+        decode: impl FnOnce() -> Result<T, FrameReaderError>,
+    ) -> Result<T, FrameReaderError> {
+        if self.cancellation.is_some_and(|token| token.is_cancelled()) {
+            return Err(FrameReaderError::Cancelled);
+        }
+        let result = decode();
+        let completed = self.completed.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(callback) = self.callback {
+            (callback.0)(ReadProgress {
+                completed,
+                total: self.total,
+            });
+        }
+        result
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::{
+    io::readers::file_readers::sql_reader::{frames::SqlFrame, SqlReader},
+    ms_data::{Frame, FrameQuality},
+};
+
+/// The maximum relative deviation of the accumulation time of a frame from
+/// the (lower) median of all frames with the same `MsMsType`.
+const ACCUMULATION_TOLERANCE: f64 = 0.1;
+
+const CALIBRATION_COLUMNS: [&str; 2] = ["MzCalibration", "TimsCalibration"];
+
+/// Flag the frame quality checks that can be done without decoding frames.
+///
+/// `selected` holds the index in `sql_frames` of every frame.
+pub(super) fn assign_quality(
+    frames: &mut [Frame],
+    selected: &[usize],
+    sql_frames: &[SqlFrame],
+    sql_reader: &SqlReader,
+    is_maldi: bool,
+) {
+    let medians = median_accumulation_times(sql_frames);
+    let calibrations: Vec<Vec<i64>> = CALIBRATION_COLUMNS
+        .iter()
+        .filter_map(|column| {
+            sql_reader.read_column_from_table(column, "Frames").ok()
+        })
+        .filter(|values: &Vec<i64>| values.len() == sql_frames.len())
+        .collect();
+    for (frame, &index) in frames.iter_mut().zip(selected) {
+        let sql_frame = &sql_frames[index];
+        let accumulation_time = sql_frame.accumulation_time;
+        let median = medians[&sql_frame.msms_type];
+        if accumulation_time.is_nan()
+            | (accumulation_time <= 0.0)
+            | ((accumulation_time - median).abs()
+                > ACCUMULATION_TOLERANCE * median)
+        {
+            frame.quality.insert(FrameQuality::ACCUMULATION_ANOMALY);
+        }
+        let has_position = frame.maldi_info.as_ref().is_some_and(|info| {
+            info.position_x_um.is_some() & info.position_y_um.is_some()
+        });
+        if is_maldi & !has_position {
+            frame.quality.insert(FrameQuality::MISSING_MALDI_POSITION);
+        }
+        if (index > 0)
+            && calibrations
+                .iter()
+                .any(|values| values[index] != values[index - 1])
+        {
+            frame.quality.insert(FrameQuality::CALIBRATION_CHANGE);
+        }
+    }
+}
+
+fn median_accumulation_times(sql_frames: &[SqlFrame]) -> HashMap<u8, f64> {
+    let mut times: HashMap<u8, Vec<f64>> = HashMap::new();
+    for sql_frame in sql_frames {
+        times
+            .entry(sql_frame.msms_type)
+            .or_default()
+            .push(sql_frame.accumulation_time);
+    }
+    times
+        .into_iter()
+        .map(|(msms_type, mut values)| {
+            values.sort_by(|a, b| a.total_cmp(b));
+            (msms_type, values[(values.len() - 1) / 2])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_median_per_msms_type() {
+        let sql_frames: Vec<SqlFrame> =
+            [(0, 100.0), (0, 50.0), (8, 20.0), (0, 100.0)]
+                .into_iter()
+                .map(|(msms_type, accumulation_time)| SqlFrame {
+                    msms_type,
+                    accumulation_time,
+                    ..Default::default()
+                })
+                .collect();
+        let medians = median_accumulation_times(&sql_frames);
+        assert_eq!(medians[&0], 100.0);
+        assert_eq!(medians[&8], 20.0);
+    }
+}
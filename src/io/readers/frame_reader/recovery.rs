@@ -0,0 +1,140 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::ms_data::Frame;
+
+use super::{FrameReader, FrameReaderError};
+
+/// How a [FrameReader] handles frames that cannot be decoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ReaderPolicy {
+    /// Corrupt frames are errors.
+    #[default]
+    Strict,
+    /// Corrupt frames are dropped.
+    SkipCorrupt,
+    /// Corrupt frames keep all scans that could be decoded before the
+    /// corruption. Frames without any decodable data are dropped.
+    Partial,
+}
+
+/// The result of reading a frame with a [ReaderPolicy].
+#[derive(Debug)]
+pub enum FrameRecovery {
+    Complete(Frame),
+    /// Only the first scans could be decoded; the others are empty.
+    Partial(Frame, FrameReaderError),
+    Dropped(FrameReaderError),
+}
+
+impl FrameRecovery {
+    pub fn frame(self) -> Option<Frame> {
+        match self {
+            Self::Complete(frame) | Self::Partial(frame, _) => Some(frame),
+            Self::Dropped(_) => None,
+        }
+    }
+}
+
+/// A frame that could not be read completely.
+#[derive(Debug)]
+pub struct FrameFailure {
+    /// 0-based index of the frame in the [FrameReader]
+    pub index: usize,
+    pub error: FrameReaderError,
+}
+
+/// A summary of reading a run with a [ReaderPolicy].
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    pub complete: usize,
+    pub partial: Vec<FrameFailure>,
+    pub dropped: Vec<FrameFailure>,
+}
+
+impl RecoveryReport {
+    /// The number of frames that were returned (completely or partially).
+    pub fn recovered(&self) -> usize {
+        self.complete + self.partial.len()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.partial.is_empty() & self.dropped.is_empty()
+    }
+}
+
+impl FrameReader {
+    pub fn policy(&self) -> ReaderPolicy {
+        self.policy
+    }
+
+    /// Read a frame according to `policy`.
+    ///
+    /// With [ReaderPolicy::Strict], errors are reported as dropped frames.
+    pub fn get_with_policy(
+        &self,
+        index: usize,
+        policy: ReaderPolicy,
+    ) -> FrameRecovery {
+        let error = match self.get(index) {
+            Ok(frame) => return FrameRecovery::Complete(frame),
+            Err(error) => error,
+        };
+        let partial = match (policy, &self.decompressor) {
+            (ReaderPolicy::Partial, Some(decompressor)) => {
+                self.get_partial_with_decompressor(index, decompressor.as_ref())
+            },
+            _ => None,
+        };
+        match partial {
+            Some(mut frame) => {
+                frame.normalize(self.normalization);
+                FrameRecovery::Partial(frame, error)
+            },
+            None => FrameRecovery::Dropped(error),
+        }
+    }
+
+    /// Read a frame according to the policy of this reader. Frames that
+    /// are dropped (or fail with [ReaderPolicy::Strict]) are `None`.
+    pub fn get_or_skip(&self, index: usize) -> Option<Frame> {
+        self.get_with_policy(index, self.policy).frame()
+    }
+
+    /// Read all frames according to the policy of this reader.
+    ///
+    /// With [ReaderPolicy::Strict], the first error is returned.
+    pub fn get_all_recovered(
+        &self,
+    ) -> Result<(Vec<Frame>, RecoveryReport), FrameReaderError> {
+        let outcomes: Vec<FrameRecovery> = (0..self.len())
+            .into_par_iter()
+            .map(|index| self.get_with_policy(index, self.policy))
+            .collect();
+        let mut frames = Vec::with_capacity(outcomes.len());
+        let mut report = RecoveryReport::default();
+        for (index, outcome) in outcomes.into_iter().enumerate() {
+            match outcome {
+                FrameRecovery::Complete(frame) => {
+                    report.complete += 1;
+                    frames.push(frame);
+                },
+                FrameRecovery::Dropped(error)
+                    if self.policy == ReaderPolicy::Strict =>
+                {
+                    return Err(error);
+                },
+                FrameRecovery::Partial(frame, error) => {
+                    report.partial.push(FrameFailure { index, error });
+                    frames.push(frame);
+                },
+                FrameRecovery::Dropped(error) => {
+                    report.dropped.push(FrameFailure { index, error });
+                },
+            }
+        }
+        Ok((frames, report))
+    }
+}
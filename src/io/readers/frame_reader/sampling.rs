@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::ms_data::{Frame, MSLevel};
+
+use super::{FrameReader, FrameReaderError};
+
+/// How [FrameReader::sample] selects frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplingStrategy {
+    /// `n` frames spread uniformly over the retention time of the run.
+    #[default]
+    UniformRt,
+    /// `n` frames per MS level, each spread uniformly over retention time.
+    ByMsLevel,
+    /// `n` frames per MALDI region (see [MaldiInfo::region]), each spread
+    /// uniformly over the region. Frames without a region form one group.
+    ///
+    /// [MaldiInfo::region]: crate::ms_data::MaldiInfo::region
+    ByRegion,
+}
+
+impl FrameReader {
+    /// Decode a representative subset of frames, e.g. for a quick preview
+    /// of a huge run. Frames are returned in reader order.
+    pub fn sample(
+        &self,
+        n: usize,
+        strategy: SamplingStrategy,
+    ) -> Result<Vec<Frame>, FrameReaderError> {
+        let indices = self.sample_indices(n, strategy);
+        let tracker = self.progress_tracker(indices.len());
+        indices
+            .par_iter()
+            .map(|&index| tracker.track(|| self.get(index)))
+            .collect()
+    }
+
+    /// Like [FrameReader::sample], but stops decoding new frames once
+    /// `budget` has elapsed. Frames are decoded in parallel batches of an
+    /// order that spreads every prefix over the full sample, and the result
+    /// consists of the complete batches that started within the budget, so
+    /// it stays representative of the full sample.
+    pub fn sample_with_budget(
+        &self,
+        n: usize,
+        strategy: SamplingStrategy,
+        budget: Duration,
+    ) -> Result<Vec<Frame>, FrameReaderError> {
+        let start = Instant::now();
+        let indices = progressive_order(self.sample_indices(n, strategy));
+        let mut frames = vec![];
+        for batch in indices.chunks(rayon::current_num_threads()) {
+            if start.elapsed() >= budget {
+                break;
+            }
+            let decoded = batch
+                .par_iter()
+                .map(|&index| self.get(index))
+                .collect::<Result<Vec<Frame>, _>>()?;
+            frames.extend(decoded);
+        }
+        frames.sort_by_key(|frame| frame.index);
+        Ok(frames)
+    }
+
+    /// The sorted indices of the frames that [FrameReader::sample] decodes.
+    pub fn sample_indices(
+        &self,
+        n: usize,
+        strategy: SamplingStrategy,
+    ) -> Vec<usize> {
+        let mut groups: Vec<(Option<&str>, Vec<usize>)> = vec![];
+        for (index, frame) in self.frames.iter().enumerate() {
+            let key = match strategy {
+                SamplingStrategy::UniformRt => None,
+                SamplingStrategy::ByMsLevel => Some(match frame.ms_level {
+                    MSLevel::MS1 => "MS1",
+                    MSLevel::MS2 => "MS2",
+                    MSLevel::Unknown => "Unknown",
+                }),
+                SamplingStrategy::ByRegion => frame
+                    .maldi_info
+                    .as_ref()
+                    .and_then(|maldi_info| maldi_info.region()),
+            };
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, indices)) => indices.push(index),
+                None => groups.push((key, vec![index])),
+            }
+        }
+        let mut selected: Vec<usize> = groups
+            .iter()
+            .flat_map(|(_, indices)| {
+                let rts: Vec<f64> = indices
+                    .iter()
+                    .map(|&index| self.frames[index].rt_in_seconds)
+                    .collect();
+                select_uniform(&rts, n)
+                    .into_iter()
+                    .map(|position| indices[position])
+            })
+            .collect();
+        selected.sort_unstable();
+        selected
+    }
+}
+
+/// Select up to `n` positions from sorted retention times that are closest
+/// to `n` equally spaced time points.
+fn select_uniform(rts: &[f64], n: usize) -> Vec<usize> {
+    if (n == 0) | rts.is_empty() {
+        return vec![];
+    }
+    if n >= rts.len() {
+        return (0..rts.len()).collect();
+    }
+    let first = rts[0];
+    let span = rts[rts.len() - 1] - first;
+    let mut positions: Vec<usize> = (0..n)
+        .map(|k| {
+            let target = first + span * (k as f64 + 0.5) / n as f64;
+            let position = rts.partition_point(|&rt| rt < target);
+            if (position > 0)
+                && ((position == rts.len())
+                    || (target - rts[position - 1] <= rts[position] - target))
+            {
+                position - 1
+            } else {
+                position
+            }
+        })
+        .collect();
+    positions.dedup();
+    positions
+}
+
+/// Reorder items so that every prefix is spread over the full list
+/// (0, n/2, n/4, 3n/4, ...).
+fn progressive_order(items: Vec<usize>) -> Vec<usize> {
+    let mut order = Vec::with_capacity(items.len());
+    let mut taken = vec![false; items.len()];
+    let mut step = items.len().next_power_of_two();
+    while step > 0 {
+        for position in (0..items.len()).step_by(step) {
+            if !taken[position] {
+                taken[position] = true;
+                order.push(items[position]);
+            }
+        }
+        step /= 2;
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ms_data::MaldiInfo;
+
+    #[test]
+    fn selects_uniformly_over_retention_time() {
+        let rts = [0.0, 0.1, 0.2, 5.0, 9.8, 9.9, 10.0];
+        assert_eq!(select_uniform(&rts, 2), vec![2, 4]);
+        assert_eq!(select_uniform(&rts, 10).len(), 7);
+        assert!(select_uniform(&rts, 0).is_empty());
+    }
+
+    #[test]
+    fn progressive_order_covers_all_items() {
+        let order = progressive_order((0..6).collect());
+        assert_eq!(order, vec![0, 4, 2, 1, 3, 5]);
+    }
+
+    #[test]
+    fn parses_maldi_region() {
+        let info = |spot_name: &str| MaldiInfo {
+            spot_name: spot_name.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(info("R00X012Y034").region(), Some("R00"));
+        assert_eq!(info("R12X1Y1").region(), Some("R12"));
+        assert_eq!(info("X1Y1").region(), None);
+        assert_eq!(info("RX1Y1").region(), None);
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::io::readers::file_readers::{
+    sql_reader::frames::SqlFrame, tdf_blob_reader::TdfBlobReader,
+};
+
+use super::FrameReader;
+
+/// Describes a binary file that is shorter than the frame table expects,
+/// e.g. because the acquisition was interrupted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TruncationReport {
+    /// The number of frames in the frame table
+    pub frame_count: usize,
+    /// The number of leading frames whose data is completely stored
+    pub readable_frame_count: usize,
+    /// The `Id` of the last frame that can be read
+    pub last_readable_frame: Option<usize>,
+    /// The size of the binary file in bytes
+    pub bin_file_size: usize,
+    /// The highest binary offset recorded in the frame table
+    pub max_offset: usize,
+}
+
+impl TruncationReport {
+    pub fn dropped_frame_count(&self) -> usize {
+        self.frame_count - self.readable_frame_count
+    }
+}
+
+impl FrameReader {
+    /// Describes the truncation of the binary file if not all frames of
+    /// the frame table could be found in it. Only the readable frames are
+    /// exposed by this reader.
+    pub fn truncation(&self) -> Option<&TruncationReport> {
+        self.truncation.as_ref()
+    }
+}
+
+/// Find the frames at the start of the run whose data is stored completely.
+/// Returns `None` if all frames are readable.
+pub(super) fn detect_truncation(
+    sql_frames: &[SqlFrame],
+    tdf_bin_reader: &TdfBlobReader,
+) -> Option<TruncationReport> {
+    let max_offset = sql_frames.iter().map(|x| x.binary_offset).max()?;
+    if tdf_bin_reader.is_complete(max_offset) {
+        return None;
+    }
+    let readable_frame_count = sql_frames
+        .iter()
+        .position(|frame| !tdf_bin_reader.is_complete(frame.binary_offset))
+        .unwrap_or(sql_frames.len());
+    let last_readable_frame = readable_frame_count
+        .checked_sub(1)
+        .map(|index| sql_frames[index].id);
+    let report = TruncationReport {
+        frame_count: sql_frames.len(),
+        readable_frame_count,
+        last_readable_frame,
+        bin_file_size: tdf_bin_reader.file_size(),
+        max_offset,
+    };
+    Some(report)
+}
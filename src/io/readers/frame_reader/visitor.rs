@@ -0,0 +1,41 @@
+use crate::ms_data::{AcquisitionType, Frame};
+
+/// Run-level information passed to [FrameVisitor::begin_run].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunInfo {
+    pub frame_count: usize,
+    pub acquisition_type: AcquisitionType,
+    pub is_maldi: bool,
+}
+
+/// A user-defined computation that is executed on every frame of a run by
+/// the parallel reading engine of a [FrameReader](super::FrameReader).
+///
+/// The lifecycle of a visit is:
+///
+/// 1. [begin_run](FrameVisitor::begin_run) is called once on the visitor
+///    that was passed to the reader.
+/// 2. The frames are split into at most one chunk per worker thread. Every
+///    chunk obtains its own visitor with [fork](FrameVisitor::fork) and calls
+///    [visit_frame](FrameVisitor::visit_frame) for each frame it decodes.
+///    Frames are borrowed, so no peak data needs to be copied.
+/// 3. All worker visitors are combined with [merge](FrameVisitor::merge)
+///    and finally merged into the original visitor.
+/// 4. [end_run](FrameVisitor::end_run) turns the original visitor into the
+///    output of the visit.
+///
+/// Frames are visited in no particular order.
+pub trait FrameVisitor: Send + Sync {
+    type Output;
+
+    fn begin_run(&mut self, _run_info: &RunInfo) {}
+
+    /// Create an empty worker that shares the configuration of `self`.
+    fn fork(&self) -> Self;
+
+    fn visit_frame(&mut self, frame: &Frame);
+
+    fn merge(&mut self, other: Self);
+
+    fn end_run(self) -> Self::Output;
+}
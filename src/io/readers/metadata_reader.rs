@@ -2,7 +2,7 @@ use std::{collections::HashMap, fmt::Debug, str::FromStr};
 
 use crate::{
     domain_converters::{Frame2RtConverter, Scan2ImConverter, Tof2MzConverter},
-    ms_data::Metadata,
+    ms_data::{GlobalMetadata, Metadata},
 };
 
 use super::{
@@ -41,10 +41,14 @@ impl MetadataReader {
             .cloned()
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
+        let im_converter = get_im_converter(&sql_metadata, &tdf_sql_reader)?;
+        let mz_converter = get_mz_converter(&sql_metadata)?;
+        let global_metadata = GlobalMetadata::from_key_values(sql_metadata);
+        let schema = tdf_sql_reader.detect_schema(&global_metadata)?;
         let metadata = Metadata {
             rt_converter: Frame2RtConverter::from_values(rt_values),
-            im_converter: get_im_converter(&sql_metadata, &tdf_sql_reader)?,
-            mz_converter: get_mz_converter(&sql_metadata)?,
+            im_converter,
+            mz_converter,
             lower_rt: rt_min,
             upper_rt: rt_max,
             lower_im: im_min,
@@ -52,6 +56,8 @@ impl MetadataReader {
             lower_mz: mz_min,
             upper_mz: mz_max,
             compression_type,
+            global_metadata,
+            schema,
         };
         Ok(metadata)
     }
@@ -0,0 +1,180 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::domain_converters::{
+    FrameMobilityCalibration, MobilityCalibration, MobilityCalibrationSegment,
+    Scan2ImConverter, TimsConditions,
+};
+
+use super::{
+    file_readers::sql_reader::{
+        segments::SqlSegment, tims_calibration::SqlTimsCalibration, SqlReader,
+        SqlReaderError,
+    },
+    MetadataReader, MetadataReaderError, TimsTofPathLike,
+};
+
+/// Reads the Scan -> 1/K0 calibration of every frame.
+///
+/// Frames refer to a calibration through the `TimsCalibration` column of
+/// the Frames table and have the TIMS conditions of the `Pressure`, `T1`
+/// and `T2` columns (the temperature is the mean of `T1` and `T2`). The
+/// reference conditions of a calibration are the mean conditions of its
+/// frames in calibration segments (`Segments.IsCalibrationSegment`), or
+/// those of its first frame if it has none.
+///
+/// Every calibration of the `TimsCalibration` table with the linear model
+/// (`ModelType` 1, `1/K0 = C0 + C1 * scan`) has its own converter. Other
+/// models are proprietary and use the linear Scan -> 1/K0 converter of
+/// the acquisition range, as do frames without a calibration. Segments
+/// with their own converter can be set with
+/// [MobilityCalibration::with_segment]. Tables and columns that are not
+/// present are read as empty, in which case no frame is compensated; all
+/// other SQL errors are returned.
+pub struct MobilityCalibrationReader;
+
+/// The `ModelType` of calibrations with `1/K0 = C0 + C1 * scan`.
+const LINEAR_MODEL_TYPE: i64 = 1;
+
+impl MobilityCalibrationReader {
+    // Like MetadataReader, this reader directly returns what it reads
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        path: impl TimsTofPathLike,
+    ) -> Result<MobilityCalibration, MobilityCalibrationReaderError> {
+        let converter = MetadataReader::new(&path)?.im_converter;
+        let sql_reader = SqlReader::open(&path)?;
+        Ok(Self::from_sql_reader(&sql_reader, converter)?)
+    }
+
+    pub(crate) fn from_sql_reader(
+        sql_reader: &SqlReader,
+        converter: Scan2ImConverter,
+    ) -> Result<MobilityCalibration, SqlReaderError> {
+        let ids: Vec<usize> =
+            sql_reader.read_column_from_table("Id", "Frames")?;
+        let calibrations: Vec<Option<usize>> = sql_reader
+            .read_optional_column_from_table::<i64>(
+                "TimsCalibration",
+                "Frames",
+                ids.len(),
+            )?
+            .into_iter()
+            .map(|id| id.map(|id| id as usize))
+            .collect();
+        let optional_column = |column: &str| {
+            sql_reader.read_optional_column_from_table::<f64>(
+                column,
+                "Frames",
+                ids.len(),
+            )
+        };
+        let pressures = optional_column("Pressure")?;
+        let t1 = optional_column("T1")?;
+        let t2 = optional_column("T2")?;
+        let converters: BTreeMap<usize, Scan2ImConverter> = sql_reader
+            .read_optional_table::<SqlTimsCalibration>(&["TimsCalibration"])?
+            .iter()
+            .filter_map(|x| Some((x.id, calibration_converter(x)?)))
+            .collect();
+        let calibration_segments: Vec<SqlSegment> = sql_reader
+            .read_optional_table::<SqlSegment>(&["Segments"])?
+            .into_iter()
+            .filter(|segment| segment.is_calibration_segment)
+            .collect();
+        let mut calibration = MobilityCalibration::new(converter);
+        // The conditions of all frames in calibration segments and the
+        // first frame of every calibration
+        let mut references: BTreeMap<usize, (Vec<TimsConditions>, bool)> =
+            BTreeMap::new();
+        for (index, &frame) in ids.iter().enumerate() {
+            let conditions = pressures[index].map(|pressure| TimsConditions {
+                pressure,
+                temperature: mean_temperature(t1[index], t2[index]),
+            });
+            calibration = calibration.with_frame(
+                frame,
+                FrameMobilityCalibration {
+                    calibration: calibrations[index],
+                    conditions,
+                },
+            );
+            let (Some(id), Some(conditions)) =
+                (calibrations[index], conditions)
+            else {
+                continue;
+            };
+            let in_calibration_segment =
+                calibration_segments.iter().any(|segment| {
+                    (segment.first_frame..=segment.last_frame).contains(&frame)
+                });
+            let (reference, from_segments) = references.entry(id).or_default();
+            if in_calibration_segment & !*from_segments {
+                reference.clear();
+                *from_segments = true;
+            }
+            if in_calibration_segment | reference.is_empty() {
+                reference.push(conditions);
+            }
+        }
+        let ids: BTreeSet<usize> = references
+            .keys()
+            .chain(converters.keys())
+            .copied()
+            .collect();
+        for id in ids {
+            let reference = references
+                .get(&id)
+                .and_then(|(reference, _)| mean_conditions(reference));
+            calibration = calibration.with_segment(
+                id,
+                MobilityCalibrationSegment {
+                    converter: converters
+                        .get(&id)
+                        .copied()
+                        .unwrap_or(converter),
+                    reference,
+                },
+            );
+        }
+        Ok(calibration)
+    }
+}
+
+/// The converter of a calibration, if its model is known.
+fn calibration_converter(
+    calibration: &SqlTimsCalibration,
+) -> Option<Scan2ImConverter> {
+    let [intercept, slope, ..] = calibration.coefficients;
+    ((calibration.model_type == LINEAR_MODEL_TYPE) & (slope != 0.0))
+        .then(|| Scan2ImConverter::from_linear(intercept, slope))
+}
+
+fn mean_temperature(t1: Option<f64>, t2: Option<f64>) -> Option<f64> {
+    match (t1, t2) {
+        (Some(t1), Some(t2)) => Some((t1 + t2) / 2.0),
+        (t1, t2) => t1.or(t2),
+    }
+}
+
+fn mean_conditions(conditions: &[TimsConditions]) -> Option<TimsConditions> {
+    if conditions.is_empty() {
+        return None;
+    }
+    let count = conditions.len() as f64;
+    let temperatures: Vec<f64> =
+        conditions.iter().filter_map(|x| x.temperature).collect();
+    let mean_conditions = TimsConditions {
+        pressure: conditions.iter().map(|x| x.pressure).sum::<f64>() / count,
+        temperature: (temperatures.len() == conditions.len())
+            .then(|| temperatures.iter().sum::<f64>() / count),
+    };
+    Some(mean_conditions)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MobilityCalibrationReaderError {
+    #[error("{0}")]
+    SqlReaderError(#[from] SqlReaderError),
+    #[error("{0}")]
+    MetadataReaderError(#[from] MetadataReaderError),
+}
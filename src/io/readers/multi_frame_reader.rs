@@ -0,0 +1,204 @@
+//! Reading frames of several runs (e.g. fractions or replicates) at once.
+
+use std::path::PathBuf;
+
+use rayon::iter::{
+    IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+};
+
+use crate::ms_data::Frame;
+
+use super::{
+    FrameReader, FrameReaderBuilder, FrameReaderError, TimsTofPath,
+    TimsTofPathError, TimsTofPathLike,
+};
+
+/// A frame together with the run it belongs to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunFrame {
+    /// The position of the run in the [MultiFrameReader]
+    pub run_id: usize,
+    /// The 0-based index of the frame within its run
+    pub index: usize,
+    pub frame: Frame,
+}
+
+/// Reads the frames of several runs as one.
+///
+/// Every run gets a `run_id` (its position in the list of paths) and all
+/// frames get a global index: the frames of run 0 come first, followed by
+/// those of run 1 and so on. All runs are decoded on the same (global)
+/// rayon thread pool.
+#[derive(Debug)]
+pub struct MultiFrameReader {
+    paths: Vec<TimsTofPath>,
+    readers: Vec<FrameReader>,
+    /// The global index of the first frame of every run
+    offsets: Vec<usize>,
+}
+
+impl MultiFrameReader {
+    pub fn new<P: TimsTofPathLike>(
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Self, MultiFrameReaderError> {
+        Self::from_builder(&FrameReader::build(), paths)
+    }
+
+    /// Open all runs with the settings of `builder` (its path is ignored).
+    /// Runs are opened in parallel.
+    pub fn from_builder<P: TimsTofPathLike>(
+        builder: &FrameReaderBuilder,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Self, MultiFrameReaderError> {
+        let paths = paths
+            .into_iter()
+            .map(|path| path.to_timstof_path())
+            .collect::<Result<Vec<TimsTofPath>, _>>()?;
+        if paths.is_empty() {
+            return Err(MultiFrameReaderError::NoRuns);
+        }
+        let readers = paths
+            .par_iter()
+            .map(|path| {
+                builder.with_path(path).finalize().map_err(|source| {
+                    MultiFrameReaderError::RunError {
+                        path: path.as_ref().to_path_buf(),
+                        source,
+                    }
+                })
+            })
+            .collect::<Result<Vec<FrameReader>, _>>()?;
+        let offsets = readers
+            .iter()
+            .scan(0, |offset, reader| {
+                let first = *offset;
+                *offset += reader.len();
+                Some(first)
+            })
+            .collect();
+        let reader = Self {
+            paths,
+            readers,
+            offsets,
+        };
+        Ok(reader)
+    }
+
+    pub fn run_count(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// The total number of frames of all runs.
+    pub fn len(&self) -> usize {
+        self.offsets.last().copied().unwrap_or(0)
+            + self.readers.last().map_or(0, |reader| reader.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn run(&self, run_id: usize) -> Option<&FrameReader> {
+        self.readers.get(run_id)
+    }
+
+    pub fn run_path(&self, run_id: usize) -> Option<&TimsTofPath> {
+        self.paths.get(run_id)
+    }
+
+    pub fn runs(&self) -> impl Iterator<Item = (usize, &FrameReader)> {
+        self.readers.iter().enumerate()
+    }
+
+    /// The run ID and the index within that run of a global frame index.
+    pub fn locate(&self, global_index: usize) -> Option<(usize, usize)> {
+        if global_index >= self.len() {
+            return None;
+        }
+        let run_id = self.offsets.partition_point(|&x| x <= global_index) - 1;
+        Some((run_id, global_index - self.offsets[run_id]))
+    }
+
+    /// The global index of the frame at `index` of run `run_id`.
+    pub fn global_index(&self, run_id: usize, index: usize) -> Option<usize> {
+        let reader = self.readers.get(run_id)?;
+        (index < reader.len()).then(|| self.offsets[run_id] + index)
+    }
+
+    pub fn get(
+        &self,
+        global_index: usize,
+    ) -> Result<RunFrame, MultiFrameReaderError> {
+        let (run_id, index) = self
+            .locate(global_index)
+            .ok_or(MultiFrameReaderError::IndexOutOfBounds(global_index))?;
+        self.get_from_run(run_id, index)
+    }
+
+    pub fn get_from_run(
+        &self,
+        run_id: usize,
+        index: usize,
+    ) -> Result<RunFrame, MultiFrameReaderError> {
+        let reader = self
+            .readers
+            .get(run_id)
+            .ok_or(MultiFrameReaderError::RunOutOfBounds(run_id))?;
+        let frame = reader.get(index).map_err(|source| {
+            MultiFrameReaderError::RunError {
+                path: self.paths[run_id].as_ref().to_path_buf(),
+                source,
+            }
+        })?;
+        Ok(RunFrame {
+            run_id,
+            index,
+            frame,
+        })
+    }
+
+    /// Decode the frames of all runs that satisfy `predicate` in
+    /// parallel. The predicate only has access to frame metadata.
+    pub fn parallel_filter<'a, F: Fn(&Frame) -> bool + Sync + Send + 'a>(
+        &'a self,
+        predicate: F,
+    ) -> impl ParallelIterator<Item = Result<RunFrame, MultiFrameReaderError>> + 'a
+    {
+        let indices: Vec<(usize, usize)> = self
+            .runs()
+            .flat_map(|(run_id, reader)| {
+                (0..reader.len()).map(move |index| (run_id, index))
+            })
+            .filter(|&(run_id, index)| {
+                self.readers[run_id]
+                    .get_frame_without_coordinates(index)
+                    .is_ok_and(|frame| predicate(&frame))
+            })
+            .collect();
+        indices
+            .into_par_iter()
+            .map(move |(run_id, index)| self.get_from_run(run_id, index))
+    }
+
+    /// Decode all frames of all runs in parallel, in global order.
+    pub fn get_all(&self) -> Vec<Result<RunFrame, MultiFrameReaderError>> {
+        self.parallel_filter(|_| true).collect()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MultiFrameReaderError {
+    #[error("{0}")]
+    TimsTofPathError(#[from] TimsTofPathError),
+    #[error("Run {path}: {source}")]
+    RunError {
+        path: PathBuf,
+        source: FrameReaderError,
+    },
+    #[error("No runs provided")]
+    NoRuns,
+    #[error("Run {0} does not exist")]
+    RunOutOfBounds(usize),
+    #[error("Frame {0} does not exist")]
+    IndexOutOfBounds(usize),
+}
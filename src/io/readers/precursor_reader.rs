@@ -14,7 +14,10 @@ use crate::ms_data::Precursor;
 
 #[cfg(feature = "tdf")]
 use super::FrameWindowSplittingConfiguration;
-use super::{TimsTofFileType, TimsTofPath, TimsTofPathError, TimsTofPathLike};
+use super::{
+    builder_path, resolve_builder_path, BuilderPath, TimsTofFileType,
+    TimsTofPathError, TimsTofPathLike,
+};
 
 pub struct PrecursorReader {
     precursor_reader: Box<dyn PrecursorReaderTrait>,
@@ -48,15 +51,14 @@ impl PrecursorReader {
 
 #[derive(Debug, Default, Clone)]
 pub struct PrecursorReaderBuilder {
-    path: Option<TimsTofPath>,
+    path: Option<BuilderPath>,
     #[cfg(feature = "tdf")]
     config: FrameWindowSplittingConfiguration,
 }
 
 impl PrecursorReaderBuilder {
     pub fn with_path(&self, path: impl TimsTofPathLike) -> Self {
-        // TODO
-        let path = Some(path.to_timstof_path().unwrap());
+        let path = Some(builder_path(path));
         Self {
             path,
             ..self.clone()
@@ -77,7 +79,7 @@ impl PrecursorReaderBuilder {
     pub fn finalize(self) -> Result<PrecursorReader, PrecursorReaderError> {
         let path = match self.path {
             None => return Err(PrecursorReaderError::NoPath),
-            Some(path) => path,
+            Some(path) => resolve_builder_path(&path)?,
         };
         let precursor_reader: Box<dyn PrecursorReaderTrait> =
             match path.file_type() {
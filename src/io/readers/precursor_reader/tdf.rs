@@ -6,10 +6,12 @@ use dia::{DIATDFPrecursorReader, DIATDFPrecursorReaderError};
 
 use crate::{
     io::readers::{
-        file_readers::sql_reader::{SqlReader, SqlReaderError},
+        file_readers::sql_reader::{
+            frames::SqlFrame, ReadableSqlTable, SqlReader, SqlReaderError,
+        },
         FrameWindowSplittingConfiguration,
     },
-    ms_data::{AcquisitionType, Precursor},
+    ms_data::{AcquisitionType, MsMsTypeKind, Precursor, ScanMode},
     readers::TimsTofPathLike,
 };
 
@@ -25,21 +27,22 @@ impl TDFPrecursorReader {
         splitting_strategy: FrameWindowSplittingConfiguration,
     ) -> Result<Self, TDFPrecursorReaderError> {
         let tdf_sql_reader = SqlReader::open(&path)?;
-        let sql_frames: Vec<u8> =
-            tdf_sql_reader.read_column_from_table("ScanMode", "Frames")?;
-        let acquisition_type = if sql_frames.iter().any(|&x| x == 8) {
-            AcquisitionType::DDAPASEF
-        } else if sql_frames.iter().any(|&x| x == 9) {
-            AcquisitionType::DIAPASEF
-        } else {
-            AcquisitionType::Unknown
-        };
+        // ScanMode is absent in some schema versions
+        let sql_frames = SqlFrame::from_sql_reader(&tdf_sql_reader)?;
+        let acquisition_type =
+            AcquisitionType::from_frame_types(sql_frames.iter().map(|frame| {
+                (
+                    ScanMode::from_raw(frame.scan_mode),
+                    MsMsTypeKind::from_raw(frame.msms_type),
+                )
+            }));
         let precursor_reader: Box<dyn PrecursorReaderTrait> =
             match acquisition_type {
                 AcquisitionType::DDAPASEF => {
                     Box::new(DDATDFPrecursorReader::new(&path)?)
                 },
-                AcquisitionType::DIAPASEF => Box::new(
+                AcquisitionType::DIAPASEF
+                | AcquisitionType::DiagonalDIAPASEF => Box::new(
                     DIATDFPrecursorReader::new(path, splitting_strategy)?,
                 ),
                 acquisition_type => {
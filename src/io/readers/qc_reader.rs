@@ -0,0 +1,317 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use rusqlite::types::Value;
+
+use crate::ms_data::MsMsTypeKind;
+
+use super::{
+    file_readers::sql_reader::{
+        error_log::SqlErrorLog, frames::SqlFrame, properties::SqlProperty,
+        segments::SqlSegment, ReadableSqlTable, SqlReader, SqlReaderError,
+    },
+    TimsTofPathLike,
+};
+
+/// An acquisition segment, e.g. a calibration segment.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Segment {
+    pub id: usize,
+    /// The `Id` of the first frame of the segment
+    pub first_frame: usize,
+    /// The `Id` of the last frame of the segment
+    pub last_frame: usize,
+    pub is_calibration: bool,
+}
+
+/// The TIMS pressure and the temperatures that were used for pressure
+/// compensation of a frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct PressureCompensation {
+    /// The `Id` of the frame
+    pub frame: usize,
+    pub pressure: Option<f64>,
+    pub t1: Option<f64>,
+    pub t2: Option<f64>,
+}
+
+/// An error that was logged by the acquisition software.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ErrorLogEntry {
+    /// The `Id` of the frame
+    pub frame: usize,
+    pub scan: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum PropertyValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl PropertyValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Real(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// An instrument property that was set for a frame.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FrameProperty {
+    /// The `Id` of the frame
+    pub frame: usize,
+    /// The permanent name of the property (e.g. `TOF_DeviceTempCurrentValue1`)
+    pub name: String,
+    pub value: PropertyValue,
+}
+
+/// Run-level quality control metrics.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct QcReport {
+    pub frame_count: usize,
+    pub ms1_frame_count: usize,
+    pub duration_in_seconds: f64,
+    /// Frames per second
+    pub frame_rate: f64,
+    pub mean_accumulation_time: f64,
+    /// The change of the accumulation time over the run according to a
+    /// linear fit, relative to its mean
+    pub accumulation_time_drift: f64,
+    /// The coefficient of variation of the TIC of MS1 frames
+    pub tic_cv: f64,
+    /// The largest change of the TIC between consecutive MS1 frames,
+    /// relative to the mean TIC
+    pub max_tic_change: f64,
+    pub pressure_range: Option<(f64, f64)>,
+    pub segment_count: usize,
+    pub error_count: usize,
+}
+
+/// Reads the diagnostic tables of a TDF run for quality control.
+///
+/// Tables and columns that are not present are read as empty; all other
+/// SQL errors are returned.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QcReader {
+    frames: Vec<SqlFrame>,
+    tic: Vec<Option<f64>>,
+    segments: Vec<Segment>,
+    pressure_compensation: Vec<PressureCompensation>,
+    error_log: Vec<ErrorLogEntry>,
+    properties: Vec<FrameProperty>,
+}
+
+impl QcReader {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, QcReaderError> {
+        let sql_reader = SqlReader::open(path)?;
+        let frames = SqlFrame::from_sql_reader(&sql_reader)?;
+        let optional_column = |column: &str| {
+            sql_reader.read_optional_column_from_table::<f64>(
+                column,
+                "Frames",
+                frames.len(),
+            )
+        };
+        let tic = optional_column("SummedIntensities")?;
+        let pressures = optional_column("Pressure")?;
+        let t1 = optional_column("T1")?;
+        let t2 = optional_column("T2")?;
+        let pressure_compensation = frames
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                pressures[*index].is_some()
+                    | t1[*index].is_some()
+                    | t2[*index].is_some()
+            })
+            .map(|(index, frame)| PressureCompensation {
+                frame: frame.id,
+                pressure: pressures[index],
+                t1: t1[index],
+                t2: t2[index],
+            })
+            .collect();
+        let segments = sql_reader
+            .read_optional_table::<SqlSegment>(&["Segments"])?
+            .into_iter()
+            .map(|segment| Segment {
+                id: segment.id,
+                first_frame: segment.first_frame,
+                last_frame: segment.last_frame,
+                is_calibration: segment.is_calibration_segment,
+            })
+            .collect();
+        let error_log = sql_reader
+            .read_optional_table::<SqlErrorLog>(&["ErrorLog"])?
+            .into_iter()
+            .map(|entry| ErrorLogEntry {
+                frame: entry.frame,
+                scan: entry.scan,
+                message: entry.message,
+            })
+            .collect();
+        let properties = sql_reader
+            .read_optional_table::<SqlProperty>(&[
+                "Properties",
+                "PropertyDefinitions",
+            ])?
+            .into_iter()
+            .map(|property| FrameProperty {
+                frame: property.frame,
+                name: property.name,
+                value: match property.value {
+                    Value::Null | Value::Blob(_) => PropertyValue::Null,
+                    Value::Integer(value) => PropertyValue::Integer(value),
+                    Value::Real(value) => PropertyValue::Real(value),
+                    Value::Text(value) => PropertyValue::Text(value),
+                },
+            })
+            .collect();
+        let reader = Self {
+            frames,
+            tic,
+            segments,
+            pressure_compensation,
+            error_log,
+            properties,
+        };
+        Ok(reader)
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// The pressure compensation values of all frames that have any.
+    pub fn pressure_compensation(&self) -> &[PressureCompensation] {
+        &self.pressure_compensation
+    }
+
+    pub fn error_log(&self) -> &[ErrorLogEntry] {
+        &self.error_log
+    }
+
+    pub fn properties(&self) -> &[FrameProperty] {
+        &self.properties
+    }
+
+    /// All values of a property with their frame `Id`.
+    pub fn property(&self, name: &str) -> Vec<(usize, &PropertyValue)> {
+        self.properties
+            .iter()
+            .filter(|property| property.name == name)
+            .map(|property| (property.frame, &property.value))
+            .collect()
+    }
+
+    pub fn report(&self) -> QcReport {
+        let rts: Vec<f64> = self.frames.iter().map(|frame| frame.rt).collect();
+        let duration = match (rts.first(), rts.last()) {
+            (Some(first), Some(last)) => last - first,
+            _ => 0.0,
+        };
+        let frame_rate = if duration > 0.0 {
+            (rts.len() - 1) as f64 / duration
+        } else {
+            0.0
+        };
+        let accumulation_times: Vec<f64> = self
+            .frames
+            .iter()
+            .map(|frame| frame.accumulation_time)
+            .collect();
+        let mean_accumulation_time = mean(&accumulation_times);
+        let accumulation_time_drift = if mean_accumulation_time > 0.0 {
+            slope(&rts, &accumulation_times) * duration / mean_accumulation_time
+        } else {
+            0.0
+        };
+        let ms1_tic: Vec<f64> = self
+            .frames
+            .iter()
+            .zip(&self.tic)
+            .filter(|(frame, _)| {
+                MsMsTypeKind::from_raw(frame.msms_type) == MsMsTypeKind::Ms1
+            })
+            .map(|(_, tic)| tic.unwrap_or(0.0))
+            .collect();
+        let mean_tic = mean(&ms1_tic);
+        let (tic_cv, max_tic_change) = if mean_tic > 0.0 {
+            let variance = ms1_tic
+                .iter()
+                .map(|tic| (tic - mean_tic).powi(2))
+                .sum::<f64>()
+                / ms1_tic.len() as f64;
+            let max_change = ms1_tic
+                .windows(2)
+                .map(|x| (x[1] - x[0]).abs())
+                .fold(0.0, f64::max);
+            (variance.sqrt() / mean_tic, max_change / mean_tic)
+        } else {
+            (0.0, 0.0)
+        };
+        let pressures = self
+            .pressure_compensation
+            .iter()
+            .filter_map(|values| values.pressure);
+        let pressure_range = pressures.fold(None, |range, pressure| {
+            let (lower, upper) = range.unwrap_or((pressure, pressure));
+            Some((f64::min(lower, pressure), f64::max(upper, pressure)))
+        });
+        QcReport {
+            frame_count: self.frames.len(),
+            ms1_frame_count: ms1_tic.len(),
+            duration_in_seconds: duration,
+            frame_rate,
+            mean_accumulation_time,
+            accumulation_time_drift,
+            tic_cv,
+            max_tic_change,
+            pressure_range,
+            segment_count: self.segments.len(),
+            error_count: self.error_log.len(),
+        }
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// The slope of a least-squares linear fit of `y` on `x`.
+fn slope(x: &[f64], y: &[f64]) -> f64 {
+    let (x_mean, y_mean) = (mean(x), mean(y));
+    let covariance: f64 = x
+        .iter()
+        .zip(y)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let variance: f64 = x.iter().map(|x| (x - x_mean).powi(2)).sum();
+    if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QcReaderError {
+    #[error("{0}")]
+    SqlReaderError(#[from] SqlReaderError),
+}
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc};
+
+use crate::ms_data::{Frame, SampleLabels};
+
+/// The frame property that a [SampleSheet] is joined on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SampleSheetJoin {
+    /// The full MALDI spot name (e.g. `R00X012Y034`)
+    #[default]
+    SpotName,
+    /// The MALDI region of the spot name (e.g. `R00`)
+    Region,
+}
+
+/// An external table (e.g. a plate map) with labels for spots or regions.
+///
+/// The sheet is a comma separated file with a header row. One column holds
+/// the spot names or regions, all other columns become [SampleLabels].
+/// Every spot name or region can only occur in a single row.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleSheet {
+    join: SampleSheetJoin,
+    columns: Vec<String>,
+    rows: HashMap<String, Arc<SampleLabels>>,
+}
+
+impl SampleSheet {
+    pub fn from_csv(
+        path: impl AsRef<Path>,
+        key_column: &str,
+        join: SampleSheetJoin,
+    ) -> Result<Self, SampleSheetError> {
+        let text = fs::read_to_string(path)?;
+        Self::from_csv_str(&text, key_column, join)
+    }
+
+    pub fn from_csv_str(
+        text: &str,
+        key_column: &str,
+        join: SampleSheetJoin,
+    ) -> Result<Self, SampleSheetError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let header = match lines.next() {
+            Some((_, line)) => split_csv_line(line),
+            None => return Err(SampleSheetError::Empty),
+        };
+        let key_index = header
+            .iter()
+            .position(|column| column == key_column)
+            .ok_or(SampleSheetError::MissingColumn(
+            key_column.to_string(),
+        ))?;
+        let mut rows = HashMap::new();
+        for (line_index, line) in lines {
+            let values = split_csv_line(line);
+            if values.len() != header.len() {
+                return Err(SampleSheetError::InvalidRow(line_index + 1));
+            }
+            let key = values[key_index].clone();
+            let labels = header
+                .iter()
+                .cloned()
+                .zip(values)
+                .enumerate()
+                .filter(|(index, _)| *index != key_index)
+                .map(|(_, label)| label)
+                .collect();
+            let sample_labels = SampleLabels {
+                key: key.clone(),
+                labels,
+            };
+            if rows.insert(key.clone(), Arc::new(sample_labels)).is_some() {
+                return Err(SampleSheetError::DuplicateKey(key));
+            }
+        }
+        let columns = header
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| *index != key_index)
+            .map(|(_, column)| column)
+            .collect();
+        Ok(Self {
+            join,
+            columns,
+            rows,
+        })
+    }
+
+    pub fn join_on(&self) -> SampleSheetJoin {
+        self.join
+    }
+
+    /// The names of all label columns, in the order of the sheet.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The labels of a frame, if its spot name or region is in the sheet.
+    pub fn labels_for(&self, frame: &Frame) -> Option<Arc<SampleLabels>> {
+        let maldi_info = frame.maldi_info.as_ref()?;
+        let key = match self.join {
+            SampleSheetJoin::SpotName => maldi_info.spot_name.as_str(),
+            SampleSheetJoin::Region => maldi_info.region()?,
+        };
+        self.rows.get(key).cloned()
+    }
+
+    /// Attach the labels of the sheet to all frames that match a row.
+    pub fn join(&self, frames: &mut [Frame]) {
+        for frame in frames.iter_mut() {
+            if let Some(labels) = self.labels_for(frame) {
+                frame.sample_labels = Some(labels);
+            }
+        }
+    }
+}
+
+/// Split a line of a CSV file, honoring double quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SampleSheetError {
+    #[error("{0}")]
+    IO(#[from] io::Error),
+    #[error("Sample sheet is empty")]
+    Empty,
+    #[error("Column not found: {0}")]
+    MissingColumn(String),
+    #[error("Row {0} does not match the header")]
+    InvalidRow(usize),
+    #[error("Key occurs in multiple rows: {0}")]
+    DuplicateKey(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ms_data::MaldiInfo;
+
+    fn spot(spot_name: &str) -> Frame {
+        Frame {
+            maldi_info: Some(MaldiInfo {
+                spot_name: spot_name.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn joins_on_region() {
+        let sheet = SampleSheet::from_csv_str(
+            "region,group,\"note\"\nR00,control,\"a, b\"\nR01,treated,\n",
+            "region",
+            SampleSheetJoin::Region,
+        )
+        .unwrap();
+        let mut frames =
+            vec![spot("R00X1Y1"), spot("R01X2Y1"), spot("R02X1Y1")];
+        sheet.join(&mut frames);
+        let labels = frames[0].sample_labels.as_ref().unwrap();
+        assert_eq!(sheet.columns(), ["group", "note"]);
+        assert_eq!(labels.key, "R00");
+        assert_eq!(labels.get("group"), Some("control"));
+        assert_eq!(labels.get("note"), Some("a, b"));
+        assert_eq!(
+            frames[1].sample_labels.as_ref().unwrap().get("note"),
+            Some("")
+        );
+        assert!(frames[2].sample_labels.is_none());
+    }
+
+    #[test]
+    fn rejects_missing_key_column() {
+        let result = SampleSheet::from_csv_str(
+            "spot,group\n",
+            "region",
+            SampleSheetJoin::Region,
+        );
+        assert!(matches!(result, Err(SampleSheetError::MissingColumn(_))));
+    }
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        let result = SampleSheet::from_csv_str(
+            "region,group\nR00,control\nR01,treated\nR00,treated\n",
+            "region",
+            SampleSheetJoin::Region,
+        );
+        assert!(matches!(
+            result,
+            Err(SampleSheetError::DuplicateKey(key)) if key == "R00"
+        ));
+    }
+}
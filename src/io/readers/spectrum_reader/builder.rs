@@ -1,4 +1,7 @@
-use crate::readers::{TimsTofFileType, TimsTofPath, TimsTofPathLike};
+use crate::readers::{
+    builder_path, resolve_builder_path, BuilderPath, TimsTofFileType,
+    TimsTofPathLike,
+};
 
 use super::{
     errors::SpectrumReaderError, SpectrumReader, SpectrumReaderConfig,
@@ -12,14 +15,13 @@ use super::tdf::TDFSpectrumReader;
 
 #[derive(Debug, Default, Clone)]
 pub struct SpectrumReaderBuilder {
-    path: Option<TimsTofPath>,
+    path: Option<BuilderPath>,
     config: SpectrumReaderConfig,
 }
 
 impl SpectrumReaderBuilder {
     pub fn with_path(&self, path: impl TimsTofPathLike) -> Self {
-        // TODO
-        let path = Some(path.to_timstof_path().unwrap());
+        let path = Some(builder_path(path));
         Self {
             path,
             ..self.clone()
@@ -36,7 +38,7 @@ impl SpectrumReaderBuilder {
     pub fn finalize(self) -> Result<SpectrumReader, SpectrumReaderError> {
         let path = match self.path {
             None => return Err(SpectrumReaderError::NoPath),
-            Some(path) => path,
+            Some(path) => resolve_builder_path(&path)?,
         };
         let spectrum_reader: Box<dyn SpectrumReaderTrait> =
             match path.file_type() {
@@ -1,3 +1,5 @@
+use crate::readers::TimsTofPathError;
+
 #[cfg(feature = "minitdf")]
 use super::minitdf::MiniTDFSpectrumReaderError;
 #[cfg(feature = "tdf")]
@@ -13,4 +15,6 @@ pub enum SpectrumReaderError {
     TDFSpectrumReaderError(#[from] TDFSpectrumReaderError),
     #[error("No path provided")]
     NoPath,
+    #[error("{0}")]
+    TimsTofPathError(#[from] TimsTofPathError),
 }
@@ -104,7 +104,8 @@ impl RawSpectrumReader {
                 AcquisitionType::DDAPASEF => Box::new(
                     DDARawSpectrumReader::new(tdf_sql_reader, frame_reader)?,
                 ),
-                AcquisitionType::DIAPASEF => {
+                AcquisitionType::DIAPASEF
+                | AcquisitionType::DiagonalDIAPASEF => {
                     Box::new(DIARawSpectrumReader::new(
                         tdf_sql_reader,
                         frame_reader,
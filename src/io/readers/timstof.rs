@@ -1,4 +1,5 @@
 use std::{
+    ffi::{OsStr, OsString},
     fs, io,
     path::{Path, PathBuf},
 };
@@ -11,29 +12,65 @@ pub enum TimsTofFileType {
     TDF,
 }
 
+/// A TimsTOF dataset: its directory (e.g. `sample.d`) and file type.
+///
+/// The data files are found by (case-insensitive) name within the
+/// directory, unless they were given explicitly (see
+/// [TimsTofPath::from_files]).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TimsTofPath {
     path: PathBuf,
     file_type: TimsTofFileType,
+    tdf_file: Option<PathBuf>,
+    tdf_bin_file: Option<PathBuf>,
 }
 
 impl TimsTofPath {
+    /// Accepts a `.d` directory, a file within it (e.g. `analysis.tdf`) or
+    /// a subdirectory of it. A `*.tdf` file is only read with the
+    /// `*.tdf_bin` of the same name.
     pub fn new(path: impl AsRef<Path>) -> Result<Self, TimsTofPathError> {
         let path = path.as_ref().canonicalize()?;
-        #[cfg(feature = "tdf")]
-        if tdf(&path).is_ok() & tdf_bin(&path).is_ok() {
-            return Ok(Self {
-                path,
-                file_type: TimsTofFileType::TDF,
-            });
+        if path.is_file() {
+            let parent = path
+                .parent()
+                .ok_or_else(|| TimsTofPathError::UnknownType(path.clone()))?;
+            let result = Self::new(parent)?;
+            #[cfg(feature = "tdf")]
+            if (result.file_type == TimsTofFileType::TDF)
+                & has_extension(&path, "tdf")
+            {
+                let Ok(tdf_bin) = tdf_bin_for(&path) else {
+                    let layout = DatasetLayout::inspect(parent)?;
+                    let layout = DatasetLayout {
+                        tdf: Some(path),
+                        tdf_bin: None,
+                        ..layout
+                    };
+                    return Err(TimsTofPathError::MissingCompanions(Box::new(
+                        layout,
+                    )));
+                };
+                return Ok(Self {
+                    tdf_bin_file: Some(tdf_bin),
+                    tdf_file: Some(path),
+                    ..result
+                });
+            }
+            return Ok(result);
         }
-        #[cfg(feature = "minitdf")]
-        if ms2_bin(&path).is_ok() & ms2_parquet(&path).is_ok() {
+        let layout = DatasetLayout::inspect(&path)?;
+        if let Some(file_type) = layout.file_type() {
             return Ok(Self {
                 path,
-                file_type: TimsTofFileType::MiniTDF,
+                file_type,
+                tdf_file: None,
+                tdf_bin_file: None,
             });
         }
+        if !layout.missing_files().is_empty() {
+            return Err(TimsTofPathError::MissingCompanions(Box::new(layout)));
+        }
         match path.parent() {
             Some(parent) => match Self::new(parent) {
                 Ok(result) => Ok(result),
@@ -43,12 +80,39 @@ impl TimsTofPath {
         }
     }
 
+    /// A TDF dataset of an explicit `analysis.tdf` and `analysis.tdf_bin`
+    /// (which may have any name or location).
+    #[cfg(feature = "tdf")]
+    pub fn from_files(
+        tdf: impl AsRef<Path>,
+        tdf_bin: impl AsRef<Path>,
+    ) -> Result<Self, TimsTofPathError> {
+        let tdf = tdf.as_ref().canonicalize()?;
+        let tdf_bin = tdf_bin.as_ref().canonicalize()?;
+        let path = tdf
+            .parent()
+            .ok_or_else(|| TimsTofPathError::UnknownType(tdf.clone()))?
+            .to_path_buf();
+        Ok(Self {
+            path,
+            file_type: TimsTofFileType::TDF,
+            tdf_file: Some(tdf),
+            tdf_bin_file: Some(tdf_bin),
+        })
+    }
+
     pub fn tdf(&self) -> Result<PathBuf, TimsTofPathError> {
-        tdf(self)
+        match &self.tdf_file {
+            Some(tdf) => Ok(tdf.clone()),
+            None => tdf(self),
+        }
     }
 
     pub fn tdf_bin(&self) -> Result<PathBuf, TimsTofPathError> {
-        tdf_bin(self)
+        match &self.tdf_bin_file {
+            Some(tdf_bin) => Ok(tdf_bin.clone()),
+            None => tdf_bin(self),
+        }
     }
 
     pub fn ms2_bin(&self) -> Result<PathBuf, TimsTofPathError> {
@@ -62,32 +126,191 @@ impl TimsTofPath {
     pub fn file_type(&self) -> TimsTofFileType {
         self.file_type
     }
+
+    /// The files of the dataset directory.
+    pub fn layout(&self) -> Result<DatasetLayout, TimsTofPathError> {
+        let mut layout = DatasetLayout::inspect(&self.path)?;
+        layout.tdf = self.tdf().ok().or(layout.tdf);
+        layout.tdf_bin = self.tdf_bin().ok().or(layout.tdf_bin);
+        Ok(layout)
+    }
+}
+
+/// The data and companion files that exist in a dataset directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetLayout {
+    pub directory: PathBuf,
+    /// `analysis.tdf`, or any other `*.tdf` file
+    pub tdf: Option<PathBuf>,
+    /// The `*.tdf_bin` with the name of the `*.tdf`, `analysis.tdf_bin`,
+    /// or any other `*.tdf_bin` file
+    pub tdf_bin: Option<PathBuf>,
+    /// `*.tdf_raw` files with unprocessed data
+    pub tdf_raw: Option<PathBuf>,
+    /// Files with `calib` in their name (e.g. `Calibration.xml`)
+    pub calibration_files: Vec<PathBuf>,
+    pub ms2_bin: Option<PathBuf>,
+    pub ms2_parquet: Option<PathBuf>,
+}
+
+impl DatasetLayout {
+    pub fn inspect(
+        directory: impl AsRef<Path>,
+    ) -> Result<Self, TimsTofPathError> {
+        let directory = directory.as_ref();
+        let mut calibration_files: Vec<PathBuf> = fs::read_dir(directory)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.to_lowercase().contains("calib"))
+            })
+            .collect();
+        calibration_files.sort();
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            tdf: tdf(directory).ok(),
+            tdf_bin: tdf_bin(directory).ok(),
+            tdf_raw: find_extension(directory, ".tdf_raw").ok(),
+            calibration_files,
+            ms2_bin: ms2_bin(directory).ok(),
+            ms2_parquet: ms2_parquet(directory).ok(),
+        })
+    }
+
+    /// The type of dataset that can be read (TDF is preferred), if all
+    /// its files exist and the type is enabled. An `analysis.tdf` without
+    /// `analysis.tdf_bin` next to miniTDF files is read as miniTDF.
+    pub fn file_type(&self) -> Option<TimsTofFileType> {
+        #[cfg(feature = "tdf")]
+        if self.tdf.is_some() & self.tdf_bin.is_some() {
+            return Some(TimsTofFileType::TDF);
+        }
+        #[cfg(feature = "minitdf")]
+        if self.ms2_bin.is_some() & self.ms2_parquet.is_some() {
+            return Some(TimsTofFileType::MiniTDF);
+        }
+        None
+    }
+
+    /// Whether any file of a TDF or miniTDF dataset exists.
+    pub fn has_data_files(&self) -> bool {
+        self.tdf.is_some()
+            | self.tdf_bin.is_some()
+            | self.ms2_bin.is_some()
+            | self.ms2_parquet.is_some()
+    }
+
+    /// The files that are missing to read the partial dataset(s) that
+    /// exist. A missing file is named after the file that is present (e.g.
+    /// `Run.tdf_bin` for `Run.tdf` or `engine.MS2Spectra.ms2.parquet` for
+    /// `engine.ms2.bin`).
+    pub fn missing_files(&self) -> Vec<String> {
+        let mut missing = vec![];
+        match (&self.tdf, &self.tdf_bin) {
+            (Some(tdf), None) => missing.push(companion_name(tdf, "tdf_bin")),
+            (None, Some(tdf_bin)) => {
+                missing.push(companion_name(tdf_bin, "tdf"))
+            },
+            _ => {},
+        }
+        match (&self.ms2_bin, &self.ms2_parquet) {
+            (Some(ms2_bin), None) => missing.push(ms2_companion_name(ms2_bin)),
+            (None, Some(ms2_parquet)) => {
+                missing.push(ms2_companion_name(ms2_parquet))
+            },
+            _ => {},
+        }
+        missing
+    }
+}
+
+/// The name of `path` with its extension replaced by `extension`.
+fn companion_name(path: &Path, extension: &str) -> String {
+    let name = Path::new(path.file_name().unwrap_or_default());
+    name.with_extension(extension)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The suffixes of miniTDF files and their companions, in both the current
+/// and the legacy layout.
+const MS2_COMPANIONS: [(&str, &str); 4] = [
+    ("ms2spectrum.bin", "ms2spectrum.parquet"),
+    ("ms2spectrum.parquet", "ms2spectrum.bin"),
+    ("ms2spectra.ms2.parquet", "ms2.bin"),
+    ("ms2.bin", "MS2Spectra.ms2.parquet"),
+];
+
+/// The name of the companion of a miniTDF file in the same layout, e.g.
+/// `engine.ms2spectrum.parquet` for `engine.ms2spectrum.bin`.
+fn ms2_companion_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    for (suffix, companion) in MS2_COMPANIONS {
+        let Some(stem_len) = name.len().checked_sub(suffix.len()) else {
+            continue;
+        };
+        if name
+            .get(stem_len..)
+            .is_some_and(|x| x.eq_ignore_ascii_case(suffix))
+        {
+            return format!("{}{}", &name[..stem_len], companion);
+        }
+    }
+    name.into_owned()
 }
 
 fn tdf(path: impl AsRef<Path>) -> Result<PathBuf, TimsTofPathError> {
-    find_extension(path, "analysis.tdf")
+    find_extension(&path, "analysis.tdf")
+        .or_else(|_| find_extension(&path, ".tdf"))
 }
 
 fn tdf_bin(path: impl AsRef<Path>) -> Result<PathBuf, TimsTofPathError> {
-    find_extension(path, "analysis.tdf_bin")
+    if let Ok(tdf) = tdf(&path) {
+        if let Ok(tdf_bin) = tdf_bin_for(&tdf) {
+            return Ok(tdf_bin);
+        }
+    }
+    find_extension(&path, "analysis.tdf_bin")
+        .or_else(|_| find_extension(&path, ".tdf_bin"))
+}
+
+/// The `*.tdf_bin` next to a `*.tdf` with the same name, e.g.
+/// `run.tdf_bin` for `run.tdf`.
+fn tdf_bin_for(tdf: &Path) -> Result<PathBuf, TimsTofPathError> {
+    let directory = tdf
+        .parent()
+        .ok_or_else(|| TimsTofPathError::UnknownType(tdf.to_path_buf()))?;
+    let mut name = tdf.file_name().unwrap_or_default().to_os_string();
+    name.push("_bin");
+    let name = name.to_string_lossy().to_lowercase();
+    for entry in fs::read_dir(directory)?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        if file_name == name {
+            return Ok(entry.path());
+        }
+    }
+    Err(TimsTofPathError::Extension(name, directory.to_path_buf()))
+}
+
+#[cfg(feature = "tdf")]
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| x.eq_ignore_ascii_case(extension))
 }
 
+/// Older converters write `<engine>.ms2.bin` and
+/// `<engine>.MS2Spectra.ms2.parquet` instead of the `ms2spectrum` domain.
 fn ms2_bin(path: impl AsRef<Path>) -> Result<PathBuf, TimsTofPathError> {
-    // match find_extension(path, "ms2.bin") {
-    //     Ok(result) => Ok(result),
-    //     Err(_) => find_extension(path, "ms2spectrum.bin"),
-    // }
-    // find_extension(path, "ms2.bin")
-    find_extension(path, "ms2spectrum.bin")
+    find_extension(&path, "ms2spectrum.bin")
+        .or_else(|_| find_extension(&path, "ms2.bin"))
 }
 
 fn ms2_parquet(path: impl AsRef<Path>) -> Result<PathBuf, TimsTofPathError> {
-    // match find_extension(path, "ms2.parquet") {
-    //     Ok(result) => Ok(result),
-    //     Err(_) => find_extension(path, "ms2spectrum.parquet"),
-    // }
-    // find_extension(path, "ms2.parquet")
-    find_extension(path, "ms2spectrum.parquet")
+    find_extension(&path, "ms2spectrum.parquet")
+        .or_else(|_| find_extension(&path, "ms2spectra.ms2.parquet"))
 }
 
 fn find_extension(
@@ -119,13 +342,58 @@ impl AsRef<Path> for TimsTofPath {
     }
 }
 
+/// The path of a reader builder. A path that cannot be resolved is kept
+/// as is, so that the error is reported when the builder is finalized.
+pub(crate) type BuilderPath = Result<TimsTofPath, PathBuf>;
+
+pub(crate) fn builder_path(path: impl TimsTofPathLike) -> BuilderPath {
+    path.to_timstof_path()
+        .map_err(|_| path.as_ref().to_path_buf())
+}
+
+pub(crate) fn resolve_builder_path(
+    path: &BuilderPath,
+) -> Result<TimsTofPath, TimsTofPathError> {
+    match path {
+        Ok(path) => Ok(path.clone()),
+        Err(path) => path.to_timstof_path(),
+    }
+}
+
+/// Anything that can be resolved to a [TimsTofPath].
+///
+/// Implemented for the standard path and string types, for [TimsTofPath]
+/// itself (keeping explicitly given files) and for references to these.
+/// Other `AsRef<Path>` types can be passed with `.as_ref()`.
 pub trait TimsTofPathLike: AsRef<Path> {
     fn to_timstof_path(&self) -> Result<TimsTofPath, TimsTofPathError>;
 }
 
-impl<T: AsRef<Path>> TimsTofPathLike for T {
+macro_rules! impl_timstof_path_like {
+    ($($path:ty),*) => {
+        $(
+            impl TimsTofPathLike for $path {
+                fn to_timstof_path(
+                    &self,
+                ) -> Result<TimsTofPath, TimsTofPathError> {
+                    TimsTofPath::new(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_timstof_path_like!(Path, PathBuf, str, String, OsStr, OsString);
+
+impl TimsTofPathLike for TimsTofPath {
+    fn to_timstof_path(&self) -> Result<TimsTofPath, TimsTofPathError> {
+        Ok(self.clone())
+    }
+}
+
+impl<T: TimsTofPathLike + ?Sized> TimsTofPathLike for &T {
     fn to_timstof_path(&self) -> Result<TimsTofPath, TimsTofPathError> {
-        TimsTofPath::new(&self)
+        (**self).to_timstof_path()
     }
 }
 
@@ -137,4 +405,10 @@ pub enum TimsTofPathError {
     IO(#[from] io::Error),
     #[error("No valid type found for {0}")]
     UnknownType(PathBuf),
+    #[error(
+        "Incomplete dataset {}: missing {}",
+        .0.directory.display(),
+        .0.missing_files().join(", ")
+    )]
+    MissingCompanions(Box<DatasetLayout>),
 }
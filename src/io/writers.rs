@@ -1,3 +1,27 @@
+#[cfg(feature = "convert")]
+mod binary_array;
+#[cfg(feature = "convert")]
+mod imzml;
 mod mgf;
+#[cfg(feature = "convert")]
+mod mzml;
+#[cfg(feature = "convert")]
+mod peak_parquet;
+#[cfg(feature = "tdf")]
+mod run_subsetter;
+#[cfg(feature = "tdf")]
+mod tdf;
 
+#[cfg(feature = "convert")]
+pub use binary_array::BinaryPrecision;
+#[cfg(feature = "convert")]
+pub use imzml::*;
 pub use mgf::*;
+#[cfg(feature = "convert")]
+pub use mzml::*;
+#[cfg(feature = "convert")]
+pub use peak_parquet::*;
+#[cfg(feature = "tdf")]
+pub use run_subsetter::*;
+#[cfg(feature = "tdf")]
+pub use tdf::*;
@@ -0,0 +1,78 @@
+use std::io::Write;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{write::ZlibEncoder, Compression};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The floating point precision of a binary data array.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum BinaryPrecision {
+    F32,
+    #[default]
+    F64,
+}
+
+impl BinaryPrecision {
+    pub(crate) fn cv_param(&self) -> &'static str {
+        match self {
+            Self::F32 => {
+                r#"<cvParam cvRef="MS" accession="MS:1000521" name="32-bit float" value=""/>"#
+            },
+            Self::F64 => {
+                r#"<cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>"#
+            },
+        }
+    }
+
+    pub(crate) fn encode_le(&self, values: &[f64]) -> Vec<u8> {
+        match self {
+            Self::F32 => values
+                .iter()
+                .flat_map(|&value| (value as f32).to_le_bytes())
+                .collect(),
+            Self::F64 => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+        }
+    }
+}
+
+pub(crate) fn compression_cv_param(compress: bool) -> &'static str {
+    if compress {
+        r#"<cvParam cvRef="MS" accession="MS:1000574" name="zlib compression" value=""/>"#
+    } else {
+        r#"<cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>"#
+    }
+}
+
+/// Encode values as (optionally zlib compressed) base64 little-endian
+/// floats, as used in mzML binary data arrays.
+pub(crate) fn encode_binary_array(
+    values: &[f64],
+    precision: BinaryPrecision,
+    compress: bool,
+) -> String {
+    let bytes = precision.encode_le(values);
+    if compress {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder
+            .write_all(&bytes)
+            .expect("Writing to a Vec cannot fail");
+        let compressed =
+            encoder.finish().expect("Writing to a Vec cannot fail");
+        STANDARD.encode(compressed)
+    } else {
+        STANDARD.encode(bytes)
+    }
+}
+
+/// Escape the XML special characters of an attribute value.
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,482 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::{self, Write},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use super::binary_array::{escape_xml, BinaryPrecision};
+use crate::ms_data::{MSLevel, SampleLabels};
+
+/// The spectrum of a single pixel of an imaging run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImagingSpectrum {
+    /// 1-based x coordinate of the pixel
+    pub x: u32,
+    /// 1-based y coordinate of the pixel
+    pub y: u32,
+    pub ms_level: MSLevel,
+    pub mz_values: Vec<f64>,
+    pub intensities: Vec<f64>,
+    /// The bits of the [FrameQuality](crate::FrameQuality) of the frame
+    pub quality: u32,
+    /// Written as `sample` and `sample <column>` user parameters
+    pub sample_labels: Option<Arc<SampleLabels>>,
+}
+
+/// Encoding options of the binary arrays of an imzML file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ImzMLWriterConfig {
+    pub mz_precision: BinaryPrecision,
+    pub intensity_precision: BinaryPrecision,
+}
+
+impl Default for ImzMLWriterConfig {
+    fn default() -> Self {
+        Self {
+            mz_precision: BinaryPrecision::F64,
+            intensity_precision: BinaryPrecision::F32,
+        }
+    }
+}
+
+/// Writes imaging spectra to a processed imzML file and its binary
+/// `.ibd` file. Every spectrum has its own m/z array.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImzMLWriter {
+    config: ImzMLWriterConfig,
+}
+
+impl ImzMLWriter {
+    pub fn new(config: ImzMLWriterConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn write_spectra<W: Write, B: Write>(
+        &self,
+        imzml: W,
+        ibd: B,
+        run_id: &str,
+        spectra: &[ImagingSpectrum],
+    ) -> io::Result<()> {
+        let mut writer = self.stream(ibd, run_id)?;
+        for spectrum in spectra {
+            writer.write_spectrum(spectrum)?;
+        }
+        writer.finish(imzml)
+    }
+
+    /// Write spectra one at a time. Their arrays are written to `ibd`
+    /// immediately, so that only their coordinates and locations are kept
+    /// until [ImzMLStreamWriter::finish] writes the imzML file.
+    pub fn stream<B: Write>(
+        &self,
+        mut ibd: B,
+        run_id: &str,
+    ) -> io::Result<ImzMLStreamWriter<B>> {
+        let uuid = make_uuid(run_id);
+        ibd.write_all(&uuid)?;
+        let writer = ImzMLStreamWriter {
+            config: self.config,
+            ibd,
+            run_id: run_id.to_string(),
+            uuid,
+            offset: uuid.len() as u64,
+            entries: vec![],
+        };
+        Ok(writer)
+    }
+}
+
+/// The location of a binary array in the `.ibd` file.
+#[derive(Clone, Copy, Debug)]
+struct ArrayLocation {
+    offset: u64,
+    byte_count: u64,
+    length: usize,
+}
+
+/// A spectrum whose arrays are written to the `.ibd` file.
+#[derive(Clone, Debug)]
+struct SpectrumEntry {
+    x: u32,
+    y: u32,
+    ms_level: MSLevel,
+    quality: u32,
+    sample_labels: Option<Arc<SampleLabels>>,
+    mz_array: ArrayLocation,
+    intensity_array: ArrayLocation,
+}
+
+/// Writes the spectra of an imzML file one at a time (see
+/// [ImzMLWriter::stream]).
+#[derive(Debug)]
+pub struct ImzMLStreamWriter<B: Write> {
+    config: ImzMLWriterConfig,
+    ibd: B,
+    run_id: String,
+    uuid: [u8; 16],
+    offset: u64,
+    entries: Vec<SpectrumEntry>,
+}
+
+impl<B: Write> ImzMLStreamWriter<B> {
+    pub fn write_spectrum(
+        &mut self,
+        spectrum: &ImagingSpectrum,
+    ) -> io::Result<()> {
+        let mz_bytes = self.config.mz_precision.encode_le(&spectrum.mz_values);
+        let intensity_bytes = self
+            .config
+            .intensity_precision
+            .encode_le(&spectrum.intensities);
+        self.ibd.write_all(&mz_bytes)?;
+        self.ibd.write_all(&intensity_bytes)?;
+        let mz_array = ArrayLocation {
+            offset: self.offset,
+            byte_count: mz_bytes.len() as u64,
+            length: spectrum.mz_values.len(),
+        };
+        let intensity_array = ArrayLocation {
+            offset: mz_array.offset + mz_array.byte_count,
+            byte_count: intensity_bytes.len() as u64,
+            length: spectrum.intensities.len(),
+        };
+        self.offset = intensity_array.offset + intensity_array.byte_count;
+        self.entries.push(SpectrumEntry {
+            x: spectrum.x,
+            y: spectrum.y,
+            ms_level: spectrum.ms_level,
+            quality: spectrum.quality,
+            sample_labels: spectrum.sample_labels.clone(),
+            mz_array,
+            intensity_array,
+        });
+        Ok(())
+    }
+
+    /// Flush the `.ibd` file and write the imzML file of all spectra.
+    pub fn finish<W: Write>(mut self, mut imzml: W) -> io::Result<()> {
+        self.ibd.flush()?;
+        let spectra = &self.entries;
+        let run_id = self.run_id.as_str();
+        let uuid_text: String = self
+            .uuid
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let max_x = spectra.iter().map(|x| x.x).max().unwrap_or(0);
+        let max_y = spectra.iter().map(|x| x.y).max().unwrap_or(0);
+        let mut ms_levels: Vec<MSLevel> = vec![];
+        for spectrum in spectra {
+            if !ms_levels.contains(&spectrum.ms_level) {
+                ms_levels.push(spectrum.ms_level);
+            }
+        }
+        let w = &mut imzml;
+        writeln!(w, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<mzML xmlns="http://psi.hupo.org/ms/mzml" version="1.1.0">"#
+        )?;
+        writeln!(w, r#"  <cvList count="3">"#)?;
+        writeln!(
+            w,
+            r#"    <cv id="MS" fullName="Proteomics Standards Initiative Mass Spectrometry Ontology" URI="https://raw.githubusercontent.com/HUPO-PSI/psi-ms-CV/master/psi-ms.obo"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"    <cv id="UO" fullName="Unit Ontology" URI="https://raw.githubusercontent.com/bio-ontology-research-group/unit-ontology/master/unit.obo"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"    <cv id="IMS" fullName="Imaging MS Ontology" URI="https://raw.githubusercontent.com/imzML/imzML/master/imagingMS.obo"/>"#
+        )?;
+        writeln!(w, r#"  </cvList>"#)?;
+        writeln!(w, r#"  <fileDescription>"#)?;
+        writeln!(w, r#"    <fileContent>"#)?;
+        for ms_level in ms_levels {
+            if let Some(cv_param) = spectrum_type_cv_param(ms_level) {
+                writeln!(w, "      {}", cv_param)?;
+            }
+        }
+        writeln!(
+            w,
+            r#"      <cvParam cvRef="IMS" accession="IMS:1000080" name="universally unique identifier" value="{{{}}}"/>"#,
+            uuid_text
+        )?;
+        writeln!(
+            w,
+            r#"      <cvParam cvRef="IMS" accession="IMS:1000031" name="processed" value=""/>"#
+        )?;
+        writeln!(w, r#"    </fileContent>"#)?;
+        writeln!(w, r#"  </fileDescription>"#)?;
+        writeln!(w, r#"  <referenceableParamGroupList count="2">"#)?;
+        for (id, precision, cv_param) in [
+            (
+                "mzArray",
+                self.config.mz_precision,
+                r#"<cvParam cvRef="MS" accession="MS:1000514" name="m/z array" value="" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>"#,
+            ),
+            (
+                "intensityArray",
+                self.config.intensity_precision,
+                r#"<cvParam cvRef="MS" accession="MS:1000515" name="intensity array" value="" unitCvRef="MS" unitAccession="MS:1000131" unitName="number of detector counts"/>"#,
+            ),
+        ] {
+            writeln!(w, r#"    <referenceableParamGroup id="{}">"#, id)?;
+            writeln!(w, "      {}", cv_param)?;
+            writeln!(w, "      {}", precision.cv_param())?;
+            writeln!(
+                w,
+                r#"      <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>"#
+            )?;
+            writeln!(
+                w,
+                r#"      <cvParam cvRef="IMS" accession="IMS:1000101" name="external data" value="true"/>"#
+            )?;
+            writeln!(w, r#"    </referenceableParamGroup>"#)?;
+        }
+        writeln!(w, r#"  </referenceableParamGroupList>"#)?;
+        writeln!(w, r#"  <softwareList count="1">"#)?;
+        writeln!(
+            w,
+            r#"    <software id="timsrust" version="{}">"#,
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(
+            w,
+            r#"      <cvParam cvRef="MS" accession="MS:1000799" name="custom unreleased software tool" value="timsrust"/>"#
+        )?;
+        writeln!(w, r#"    </software>"#)?;
+        writeln!(w, r#"  </softwareList>"#)?;
+        writeln!(w, r#"  <scanSettingsList count="1">"#)?;
+        writeln!(w, r#"    <scanSettings id="SS1">"#)?;
+        writeln!(
+            w,
+            r#"      <cvParam cvRef="IMS" accession="IMS:1000042" name="max count of pixels x" value="{}"/>"#,
+            max_x
+        )?;
+        writeln!(
+            w,
+            r#"      <cvParam cvRef="IMS" accession="IMS:1000043" name="max count of pixels y" value="{}"/>"#,
+            max_y
+        )?;
+        writeln!(w, r#"    </scanSettings>"#)?;
+        writeln!(w, r#"  </scanSettingsList>"#)?;
+        writeln!(w, r#"  <instrumentConfigurationList count="1">"#)?;
+        writeln!(w, r#"    <instrumentConfiguration id="IC1">"#)?;
+        writeln!(
+            w,
+            r#"      <cvParam cvRef="MS" accession="MS:1000031" name="instrument model" value=""/>"#
+        )?;
+        writeln!(w, r#"    </instrumentConfiguration>"#)?;
+        writeln!(w, r#"  </instrumentConfigurationList>"#)?;
+        writeln!(w, r#"  <dataProcessingList count="1">"#)?;
+        writeln!(w, r#"    <dataProcessing id="DP1">"#)?;
+        writeln!(
+            w,
+            r#"      <processingMethod order="1" softwareRef="timsrust">"#
+        )?;
+        writeln!(
+            w,
+            r#"        <cvParam cvRef="MS" accession="MS:1000544" name="Conversion to mzML" value=""/>"#
+        )?;
+        writeln!(w, r#"      </processingMethod>"#)?;
+        writeln!(w, r#"    </dataProcessing>"#)?;
+        writeln!(w, r#"  </dataProcessingList>"#)?;
+        writeln!(
+            w,
+            r#"  <run id="{}" defaultInstrumentConfigurationRef="IC1">"#,
+            escape_xml(run_id)
+        )?;
+        writeln!(
+            w,
+            r#"    <spectrumList count="{}" defaultDataProcessingRef="DP1">"#,
+            spectra.len()
+        )?;
+        for (index, spectrum) in spectra.iter().enumerate() {
+            writeln!(
+                w,
+                r#"      <spectrum index="{}" id="index={}" defaultArrayLength="0">"#,
+                index, index
+            )?;
+            if let Some(cv_param) = spectrum_type_cv_param(spectrum.ms_level) {
+                writeln!(w, "        {}", cv_param)?;
+                writeln!(
+                    w,
+                    r#"        <cvParam cvRef="MS" accession="MS:1000511" name="ms level" value="{}"/>"#,
+                    ms_level_value(spectrum.ms_level)
+                )?;
+            }
+            writeln!(
+                w,
+                r#"        <userParam name="frame quality" value="{}" type="xsd:unsignedInt"/>"#,
+                spectrum.quality
+            )?;
+            if let Some(labels) = &spectrum.sample_labels {
+                writeln!(
+                    w,
+                    r#"        <userParam name="sample" value="{}" type="xsd:string"/>"#,
+                    escape_xml(&labels.key)
+                )?;
+                for (column, label) in labels.labels.iter() {
+                    writeln!(
+                        w,
+                        r#"        <userParam name="sample {}" value="{}" type="xsd:string"/>"#,
+                        escape_xml(column),
+                        escape_xml(label)
+                    )?;
+                }
+            }
+            writeln!(w, r#"        <scanList count="1">"#)?;
+            writeln!(
+                w,
+                r#"          <cvParam cvRef="MS" accession="MS:1000795" name="no combination" value=""/>"#
+            )?;
+            writeln!(w, r#"          <scan>"#)?;
+            writeln!(
+                w,
+                r#"            <cvParam cvRef="IMS" accession="IMS:1000050" name="position x" value="{}"/>"#,
+                spectrum.x
+            )?;
+            writeln!(
+                w,
+                r#"            <cvParam cvRef="IMS" accession="IMS:1000051" name="position y" value="{}"/>"#,
+                spectrum.y
+            )?;
+            writeln!(w, r#"          </scan>"#)?;
+            writeln!(w, r#"        </scanList>"#)?;
+            writeln!(w, r#"        <binaryDataArrayList count="2">"#)?;
+            for (group, array) in [
+                ("mzArray", spectrum.mz_array),
+                ("intensityArray", spectrum.intensity_array),
+            ] {
+                writeln!(
+                    w,
+                    r#"          <binaryDataArray encodedLength="0">"#
+                )?;
+                writeln!(
+                    w,
+                    r#"            <referenceableParamGroupRef ref="{}"/>"#,
+                    group
+                )?;
+                writeln!(
+                    w,
+                    r#"            <cvParam cvRef="IMS" accession="IMS:1000103" name="external array length" value="{}"/>"#,
+                    array.length
+                )?;
+                writeln!(
+                    w,
+                    r#"            <cvParam cvRef="IMS" accession="IMS:1000104" name="external encoded length" value="{}"/>"#,
+                    array.byte_count
+                )?;
+                writeln!(
+                    w,
+                    r#"            <cvParam cvRef="IMS" accession="IMS:1000102" name="external offset" value="{}"/>"#,
+                    array.offset
+                )?;
+                writeln!(w, r#"            <binary/>"#)?;
+                writeln!(w, r#"          </binaryDataArray>"#)?;
+            }
+            writeln!(w, r#"        </binaryDataArrayList>"#)?;
+            writeln!(w, r#"      </spectrum>"#)?;
+        }
+        writeln!(w, r#"    </spectrumList>"#)?;
+        writeln!(w, r#"  </run>"#)?;
+        writeln!(w, r#"</mzML>"#)?;
+        w.flush()
+    }
+}
+
+/// The spectrum type of an MS level (`None` if it is unknown).
+fn spectrum_type_cv_param(ms_level: MSLevel) -> Option<&'static str> {
+    match ms_level {
+        MSLevel::MS1 => Some(
+            r#"<cvParam cvRef="MS" accession="MS:1000579" name="MS1 spectrum" value=""/>"#,
+        ),
+        MSLevel::MS2 => Some(
+            r#"<cvParam cvRef="MS" accession="MS:1000580" name="MSn spectrum" value=""/>"#,
+        ),
+        MSLevel::Unknown => None,
+    }
+}
+
+fn ms_level_value(ms_level: MSLevel) -> u8 {
+    match ms_level {
+        MSLevel::MS2 => 2,
+        _ => 1,
+    }
+}
+
+/// A random (version 4) UUID that links an imzML file to its `.ibd` file.
+fn make_uuid(run_id: &str) -> [u8; 16] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let mut uuid = [0; 16];
+    for (seed, chunk) in uuid.chunks_mut(8).enumerate() {
+        let mut hasher = DefaultHasher::new();
+        (seed, nanos, run_id, std::process::id()).hash(&mut hasher);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    uuid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_external_offsets() {
+        let spectra = vec![
+            ImagingSpectrum {
+                x: 1,
+                y: 1,
+                ms_level: MSLevel::MS2,
+                mz_values: vec![100.0, 200.0],
+                intensities: vec![1.0, 2.0],
+                quality: 0,
+                sample_labels: None,
+            },
+            ImagingSpectrum {
+                x: 2,
+                y: 1,
+                ms_level: MSLevel::MS2,
+                mz_values: vec![300.0],
+                intensities: vec![3.0],
+                quality: 4,
+                sample_labels: Some(Arc::new(SampleLabels {
+                    key: "R00".to_string(),
+                    labels: [("group".to_string(), "a & b".to_string())]
+                        .into_iter()
+                        .collect(),
+                })),
+            },
+        ];
+        let mut imzml = vec![];
+        let mut ibd = vec![];
+        ImzMLWriter::default()
+            .write_spectra(&mut imzml, &mut ibd, "run", &spectra)
+            .unwrap();
+        assert_eq!(ibd.len(), 16 + 2 * 8 + 2 * 4 + 8 + 4);
+        assert_eq!(ibd[40..48], 300.0f64.to_le_bytes());
+        let imzml = String::from_utf8(imzml).unwrap();
+        assert!(imzml.contains(r#"name="external offset" value="40""#));
+        assert!(imzml.contains(r#"name="max count of pixels x" value="2""#));
+        assert_eq!(imzml.matches(r#"name="MSn spectrum""#).count(), 3);
+        assert_eq!(imzml.matches(r#"name="ms level" value="2""#).count(), 2);
+        assert!(!imzml.contains("MS1 spectrum"));
+        assert!(imzml.contains(r#"name="frame quality" value="4""#));
+        assert_eq!(imzml.matches(r#"name="sample" value="R00""#).count(), 1);
+        assert!(imzml.contains(r#"name="sample group" value="a &amp; b""#));
+    }
+}
@@ -1,9 +1,108 @@
-use crate::Spectrum;
-use std::{fs::File, io::Write, path::Path};
+use crate::{domain_converters::CcsConverter, Spectrum};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
 
-pub struct MGFWriter;
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MGFWriterConfig {
+    /// Annotate every spectrum with the 1/K0 and CCS of its precursor
+    pub annotate_mobility: bool,
+    pub ccs_converter: CcsConverter,
+}
+
+impl Default for MGFWriterConfig {
+    fn default() -> Self {
+        Self {
+            annotate_mobility: true,
+            ccs_converter: CcsConverter::default(),
+        }
+    }
+}
+
+/// Writes MS2 spectra to MGF.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MGFWriter {
+    config: MGFWriterConfig,
+}
 
 impl MGFWriter {
+    pub fn new(config: MGFWriterConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write all spectra with a precursor, titled
+    /// `<run_id>.<precursor>.<precursor>.<charge>`.
+    ///
+    /// The 1/K0 and CCS of a precursor are written as `#ONE_OVER_K0=` and
+    /// `#CCS=` comments in front of its spectrum, since MGF only allows
+    /// comments outside of `BEGIN IONS` and `END IONS`. The CCS is only
+    /// known for precursors with a charge.
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W,
+        run_id: &str,
+        spectra: &[Spectrum],
+    ) -> io::Result<()> {
+        for spectrum in spectra {
+            self.write_spectrum(&mut writer, run_id, spectrum)?;
+        }
+        writer.flush()
+    }
+
+    /// Write a single spectrum as [MGFWriter::write] does, e.g. to stream
+    /// spectra. Spectra without a precursor are skipped.
+    pub fn write_spectrum<W: Write>(
+        &self,
+        writer: &mut W,
+        run_id: &str,
+        spectrum: &Spectrum,
+    ) -> io::Result<()> {
+        let Some(precursor) = spectrum.precursor else {
+            return Ok(());
+        };
+        if self.config.annotate_mobility {
+            writeln!(writer, "#ONE_OVER_K0={:.4}", precursor.im)?;
+            if let Some(charge) = precursor.charge {
+                let ccs = self.config.ccs_converter.ccs(
+                    precursor.im,
+                    precursor.mz,
+                    charge,
+                );
+                writeln!(writer, "#CCS={:.2}", ccs)?;
+            }
+        }
+        writeln!(writer, "BEGIN IONS")?;
+        writeln!(
+            writer,
+            "TITLE={}.{}.{}.{}",
+            run_id,
+            precursor.index,
+            precursor.index,
+            precursor.charge.unwrap_or(0)
+        )?;
+        match precursor.intensity {
+            Some(intensity) => writeln!(
+                writer,
+                "PEPMASS={:.4} {:.0}",
+                precursor.mz, intensity
+            )?,
+            None => writeln!(writer, "PEPMASS={:.4}", precursor.mz)?,
+        }
+        if let Some(charge) = precursor.charge {
+            writeln!(writer, "CHARGE={}+", charge)?;
+        }
+        writeln!(writer, "RTINSECONDS={:.2}", precursor.rt)?;
+        writer.write_all(MGFEntry::write_peaks(spectrum).as_bytes())?;
+        writeln!(writer, "END IONS")?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
     pub fn write_spectra(input_file_path: &str, spectra: &Vec<Spectrum>) {
         let output_file_path = {
             let input_path = Path::new(&input_file_path);
@@ -57,3 +156,50 @@ impl MGFEntry {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ms_data::Precursor;
+
+    #[test]
+    fn writes_annotated_spectra() {
+        let spectrum =
+            |charge: Option<usize>, intensity: Option<f64>| Spectrum {
+                mz_values: vec![100.0, 200.5],
+                intensities: vec![10.0, 20.0],
+                precursor: Some(Precursor {
+                    mz: 500.0,
+                    rt: 12.345,
+                    im: 0.9,
+                    charge,
+                    intensity,
+                    index: 7,
+                    frame_index: 3,
+                }),
+                ..Default::default()
+            };
+        let without_precursor = Spectrum::default();
+        let mut written = vec![];
+        MGFWriter::default()
+            .write(
+                &mut written,
+                "run",
+                &[
+                    spectrum(Some(2), Some(1000.0)),
+                    spectrum(None, None),
+                    without_precursor,
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "#ONE_OVER_K0=0.9000\n#CCS=365.46\nBEGIN IONS\n\
+             TITLE=run.7.7.2\nPEPMASS=500.0000 1000\nCHARGE=2+\n\
+             RTINSECONDS=12.35\n100.0000\t10\n200.5000\t20\nEND IONS\n\n\
+             #ONE_OVER_K0=0.9000\nBEGIN IONS\nTITLE=run.7.7.0\n\
+             PEPMASS=500.0000\nRTINSECONDS=12.35\n100.0000\t10\n\
+             200.5000\t20\nEND IONS\n\n"
+        );
+    }
+}
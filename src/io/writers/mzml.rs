@@ -0,0 +1,355 @@
+use std::io::{self, Write};
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::ms_data::Spectrum;
+
+use super::binary_array::{
+    compression_cv_param, encode_binary_array, escape_xml, BinaryPrecision,
+};
+
+/// Encoding options of the binary arrays of an mzML file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MzMLWriterConfig {
+    pub mz_precision: BinaryPrecision,
+    pub intensity_precision: BinaryPrecision,
+    /// zlib compression makes files smaller, but slower to write and read
+    pub compress: bool,
+}
+
+impl Default for MzMLWriterConfig {
+    fn default() -> Self {
+        Self {
+            mz_precision: BinaryPrecision::F64,
+            intensity_precision: BinaryPrecision::F32,
+            compress: true,
+        }
+    }
+}
+
+/// Writes MS2 spectra to (non-indexed) mzML 1.1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MzMLWriter {
+    config: MzMLWriterConfig,
+}
+
+impl MzMLWriter {
+    pub fn new(config: MzMLWriterConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn write_spectra<W: Write>(
+        &self,
+        writer: W,
+        run_id: &str,
+        spectra: &[Spectrum],
+    ) -> io::Result<()> {
+        let mut writer = self.stream(writer, run_id, spectra.len())?;
+        for spectrum in spectra {
+            writer.write_spectrum(spectrum)?;
+        }
+        writer.finish()
+    }
+
+    /// Write `count` spectra one at a time, without keeping them in memory.
+    /// The header is written immediately, since it contains the number of
+    /// spectra.
+    pub fn stream<W: Write>(
+        &self,
+        mut writer: W,
+        run_id: &str,
+        count: usize,
+    ) -> io::Result<MzMLStreamWriter<W>> {
+        let run_id = escape_xml(run_id);
+        writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<mzML xmlns="http://psi.hupo.org/ms/mzml" version="1.1.0">"#
+        )?;
+        writeln!(writer, r#"  <cvList count="2">"#)?;
+        writeln!(
+            writer,
+            r#"    <cv id="MS" fullName="Proteomics Standards Initiative Mass Spectrometry Ontology" URI="https://raw.githubusercontent.com/HUPO-PSI/psi-ms-CV/master/psi-ms.obo"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"    <cv id="UO" fullName="Unit Ontology" URI="https://raw.githubusercontent.com/bio-ontology-research-group/unit-ontology/master/unit.obo"/>"#
+        )?;
+        writeln!(writer, r#"  </cvList>"#)?;
+        writeln!(writer, r#"  <fileDescription>"#)?;
+        writeln!(writer, r#"    <fileContent>"#)?;
+        writeln!(
+            writer,
+            r#"      <cvParam cvRef="MS" accession="MS:1000580" name="MSn spectrum" value=""/>"#
+        )?;
+        writeln!(writer, r#"    </fileContent>"#)?;
+        writeln!(writer, r#"  </fileDescription>"#)?;
+        writeln!(writer, r#"  <softwareList count="1">"#)?;
+        writeln!(
+            writer,
+            r#"    <software id="timsrust" version="{}">"#,
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(
+            writer,
+            r#"      <cvParam cvRef="MS" accession="MS:1000799" name="custom unreleased software tool" value="timsrust"/>"#
+        )?;
+        writeln!(writer, r#"    </software>"#)?;
+        writeln!(writer, r#"  </softwareList>"#)?;
+        writeln!(writer, r#"  <instrumentConfigurationList count="1">"#)?;
+        writeln!(writer, r#"    <instrumentConfiguration id="IC1">"#)?;
+        writeln!(
+            writer,
+            r#"      <cvParam cvRef="MS" accession="MS:1000031" name="instrument model" value=""/>"#
+        )?;
+        writeln!(writer, r#"    </instrumentConfiguration>"#)?;
+        writeln!(writer, r#"  </instrumentConfigurationList>"#)?;
+        writeln!(writer, r#"  <dataProcessingList count="1">"#)?;
+        writeln!(writer, r#"    <dataProcessing id="DP1">"#)?;
+        writeln!(
+            writer,
+            r#"      <processingMethod order="1" softwareRef="timsrust">"#
+        )?;
+        writeln!(
+            writer,
+            r#"        <cvParam cvRef="MS" accession="MS:1000544" name="Conversion to mzML" value=""/>"#
+        )?;
+        writeln!(writer, r#"      </processingMethod>"#)?;
+        writeln!(writer, r#"    </dataProcessing>"#)?;
+        writeln!(writer, r#"  </dataProcessingList>"#)?;
+        writeln!(
+            writer,
+            r#"  <run id="{}" defaultInstrumentConfigurationRef="IC1">"#,
+            run_id
+        )?;
+        writeln!(
+            writer,
+            r#"    <spectrumList count="{}" defaultDataProcessingRef="DP1">"#,
+            count
+        )?;
+        let writer = MzMLStreamWriter {
+            mzml: *self,
+            writer,
+            count,
+            index: 0,
+        };
+        Ok(writer)
+    }
+
+    fn write_spectrum<W: Write>(
+        &self,
+        writer: &mut W,
+        index: usize,
+        spectrum: &Spectrum,
+    ) -> io::Result<()> {
+        writeln!(
+            writer,
+            r#"      <spectrum index="{}" id="index={}" defaultArrayLength="{}">"#,
+            index,
+            spectrum.index,
+            spectrum.len()
+        )?;
+        writeln!(
+            writer,
+            r#"        <cvParam cvRef="MS" accession="MS:1000511" name="ms level" value="2"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"        <cvParam cvRef="MS" accession="MS:1000580" name="MSn spectrum" value=""/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"        <cvParam cvRef="MS" accession="MS:1000127" name="centroid spectrum" value=""/>"#
+        )?;
+        // The retention time and mobility are only known from the precursor
+        let scan = spectrum.precursor.unwrap_or_default();
+        writeln!(writer, r#"        <scanList count="1">"#)?;
+        writeln!(
+            writer,
+            r#"          <cvParam cvRef="MS" accession="MS:1000795" name="no combination" value=""/>"#
+        )?;
+        writeln!(writer, r#"          <scan>"#)?;
+        writeln!(
+            writer,
+            r#"            <cvParam cvRef="MS" accession="MS:1000016" name="scan start time" value="{}" unitCvRef="UO" unitAccession="UO:0000010" unitName="second"/>"#,
+            scan.rt
+        )?;
+        writeln!(
+            writer,
+            r#"            <cvParam cvRef="MS" accession="MS:1002815" name="inverse reduced ion mobility" value="{}" unitCvRef="MS" unitAccession="MS:1002814" unitName="volt-second per square centimeter"/>"#,
+            scan.im
+        )?;
+        writeln!(writer, r#"          </scan>"#)?;
+        writeln!(writer, r#"        </scanList>"#)?;
+        if let Some(precursor) = &spectrum.precursor {
+            writeln!(writer, r#"        <precursorList count="1">"#)?;
+            writeln!(writer, r#"          <precursor>"#)?;
+            writeln!(writer, r#"            <isolationWindow>"#)?;
+            writeln!(
+                writer,
+                r#"              <cvParam cvRef="MS" accession="MS:1000827" name="isolation window target m/z" value="{}" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>"#,
+                spectrum.isolation_mz
+            )?;
+            for (accession, name) in [
+                ("MS:1000828", "isolation window lower offset"),
+                ("MS:1000829", "isolation window upper offset"),
+            ] {
+                writeln!(
+                    writer,
+                    r#"              <cvParam cvRef="MS" accession="{}" name="{}" value="{}" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>"#,
+                    accession,
+                    name,
+                    spectrum.isolation_width / 2.0
+                )?;
+            }
+            writeln!(writer, r#"            </isolationWindow>"#)?;
+            writeln!(writer, r#"            <selectedIonList count="1">"#)?;
+            writeln!(writer, r#"              <selectedIon>"#)?;
+            writeln!(
+                writer,
+                r#"                <cvParam cvRef="MS" accession="MS:1000744" name="selected ion m/z" value="{}" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>"#,
+                precursor.mz
+            )?;
+            if let Some(charge) = precursor.charge {
+                writeln!(
+                    writer,
+                    r#"                <cvParam cvRef="MS" accession="MS:1000041" name="charge state" value="{}"/>"#,
+                    charge
+                )?;
+            }
+            if let Some(intensity) = precursor.intensity {
+                writeln!(
+                    writer,
+                    r#"                <cvParam cvRef="MS" accession="MS:1000042" name="peak intensity" value="{}" unitCvRef="MS" unitAccession="MS:1000131" unitName="number of detector counts"/>"#,
+                    intensity
+                )?;
+            }
+            writeln!(writer, r#"              </selectedIon>"#)?;
+            writeln!(writer, r#"            </selectedIonList>"#)?;
+            writeln!(writer, r#"            <activation>"#)?;
+            writeln!(
+                writer,
+                r#"              <cvParam cvRef="MS" accession="MS:1000133" name="collision-induced dissociation" value=""/>"#
+            )?;
+            writeln!(
+                writer,
+                r#"              <cvParam cvRef="MS" accession="MS:1000045" name="collision energy" value="{}" unitCvRef="UO" unitAccession="UO:0000266" unitName="electronvolt"/>"#,
+                spectrum.collision_energy
+            )?;
+            writeln!(writer, r#"            </activation>"#)?;
+            writeln!(writer, r#"          </precursor>"#)?;
+            writeln!(writer, r#"        </precursorList>"#)?;
+        }
+        writeln!(writer, r#"        <binaryDataArrayList count="2">"#)?;
+        self.write_binary_array(
+            writer,
+            &spectrum.mz_values,
+            self.config.mz_precision,
+            r#"<cvParam cvRef="MS" accession="MS:1000514" name="m/z array" value="" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>"#,
+        )?;
+        self.write_binary_array(
+            writer,
+            &spectrum.intensities,
+            self.config.intensity_precision,
+            r#"<cvParam cvRef="MS" accession="MS:1000515" name="intensity array" value="" unitCvRef="MS" unitAccession="MS:1000131" unitName="number of detector counts"/>"#,
+        )?;
+        writeln!(writer, r#"        </binaryDataArrayList>"#)?;
+        writeln!(writer, r#"      </spectrum>"#)?;
+        Ok(())
+    }
+
+    fn write_binary_array<W: Write>(
+        &self,
+        writer: &mut W,
+        values: &[f64],
+        precision: BinaryPrecision,
+        array_cv_param: &str,
+    ) -> io::Result<()> {
+        let encoded =
+            encode_binary_array(values, precision, self.config.compress);
+        writeln!(
+            writer,
+            r#"          <binaryDataArray encodedLength="{}">"#,
+            encoded.len()
+        )?;
+        writeln!(writer, "            {}", precision.cv_param())?;
+        writeln!(
+            writer,
+            "            {}",
+            compression_cv_param(self.config.compress)
+        )?;
+        writeln!(writer, "            {}", array_cv_param)?;
+        writeln!(writer, "            <binary>{}</binary>", encoded)?;
+        writeln!(writer, r#"          </binaryDataArray>"#)?;
+        Ok(())
+    }
+}
+
+/// Writes the spectra of an mzML file one at a time (see
+/// [MzMLWriter::stream]).
+#[derive(Debug)]
+pub struct MzMLStreamWriter<W: Write> {
+    mzml: MzMLWriter,
+    writer: W,
+    count: usize,
+    index: usize,
+}
+
+impl<W: Write> MzMLStreamWriter<W> {
+    pub fn write_spectrum(&mut self, spectrum: &Spectrum) -> io::Result<()> {
+        if self.index == self.count {
+            return Err(spectrum_count_error(self.count));
+        }
+        self.mzml
+            .write_spectrum(&mut self.writer, self.index, spectrum)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    /// Close the mzML file. Fails if fewer spectra were written than
+    /// announced.
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.index != self.count {
+            return Err(spectrum_count_error(self.count));
+        }
+        writeln!(self.writer, r#"    </spectrumList>"#)?;
+        writeln!(self.writer, r#"  </run>"#)?;
+        writeln!(self.writer, r#"</mzML>"#)?;
+        self.writer.flush()
+    }
+}
+
+fn spectrum_count_error(count: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("The mzML file has {} spectra", count),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_scans_without_precursor() {
+        let spectrum = Spectrum {
+            mz_values: vec![100.0],
+            intensities: vec![10.0],
+            ..Default::default()
+        };
+        let mut written = vec![];
+        MzMLWriter::default()
+            .write_spectra(&mut written, "run", std::slice::from_ref(&spectrum))
+            .unwrap();
+        let mzml = String::from_utf8(written).unwrap();
+        assert!(mzml.contains(r#"name="scan start time" value="0""#));
+        assert!(mzml.contains(r#"name="inverse reduced ion mobility""#));
+        assert!(!mzml.contains("<precursorList"));
+        let mut writer =
+            MzMLWriter::default().stream(vec![], "run", 2).unwrap();
+        writer.write_spectrum(&spectrum).unwrap();
+        assert!(writer.finish().is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use std::{io::Write, sync::Arc};
+
+use parquet::{
+    basic::{
+        Compression, LogicalType, Repetition, Type as PhysicalType, ZstdLevel,
+    },
+    data_type::{ByteArray, ByteArrayType, DoubleType, Int32Type},
+    errors::ParquetError,
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedFileWriter, SerializedRowGroupWriter},
+    },
+    schema::{parser::parse_message_type, types::Type},
+};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain_converters::{ConvertableDomain, Scan2ImConverter, Tof2MzConverter},
+    ms_data::{Frame, SampleLabels},
+};
+
+const PEAK_SCHEMA: &str = "
+message peaks {
+    REQUIRED INT32 frame (INTEGER(32, false));
+    REQUIRED INT32 scan (INTEGER(32, false));
+    REQUIRED INT32 tof (INTEGER(32, false));
+    REQUIRED INT32 intensity (INTEGER(32, false));
+    REQUIRED INT32 quality (INTEGER(32, false));
+    REQUIRED DOUBLE rt;
+    REQUIRED DOUBLE mz;
+    REQUIRED DOUBLE mobility;
+}
+";
+
+/// The compression codec of the columns of a Parquet file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ParquetCompression {
+    None,
+    /// Fast to write and read
+    #[default]
+    Snappy,
+    /// Smaller files, but slower to write
+    Zstd,
+}
+
+impl ParquetCompression {
+    fn codec(&self) -> Compression {
+        match self {
+            Self::None => Compression::UNCOMPRESSED,
+            Self::Snappy => Compression::SNAPPY,
+            Self::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeakColumns {
+    frame: Vec<u32>,
+    scan: Vec<u32>,
+    tof: Vec<u32>,
+    intensity: Vec<u32>,
+    quality: Vec<u32>,
+    rt: Vec<f64>,
+    mz: Vec<f64>,
+    mobility: Vec<f64>,
+    sample_labels: Vec<Option<Arc<SampleLabels>>>,
+}
+
+/// Writes all peaks of frames as rows of a Parquet table with the unsigned
+/// 32-bit columns `frame, scan, tof, intensity, quality` and the double
+/// columns `rt, mz, mobility`. The `quality` column holds the bits of the
+/// [FrameQuality](crate::FrameQuality) of the frame.
+///
+/// With [PeakParquetWriter::with_sample_labels], every row also has the
+/// optional string columns `sample` (the key of the
+/// [SampleLabels](crate::ms_data::SampleLabels) of its frame) and
+/// `sample_<column>` for all label columns of the sample sheet.
+///
+/// Mobilities are converted with the converter of every frame (see
+/// [Frame::im_converter]) when it has one, and with `im_converter`
+/// otherwise.
+///
+/// Rows are buffered in memory until `row_group_size` peaks are collected.
+pub struct PeakParquetWriter<W: Write + Send> {
+    writer: SerializedFileWriter<W>,
+    mz_converter: Tof2MzConverter,
+    im_converter: Scan2ImConverter,
+    row_group_size: usize,
+    label_columns: Option<Vec<String>>,
+    columns: PeakColumns,
+}
+
+impl<W: Write + Send> PeakParquetWriter<W> {
+    pub fn new(
+        writer: W,
+        mz_converter: Tof2MzConverter,
+        im_converter: Scan2ImConverter,
+        row_group_size: usize,
+        compression: ParquetCompression,
+    ) -> Result<Self, ParquetError> {
+        Self::with_schema(
+            writer,
+            mz_converter,
+            im_converter,
+            row_group_size,
+            compression,
+            None,
+        )
+    }
+
+    /// A writer with additional columns for the labels of a sample sheet
+    /// (see [SampleSheet::columns](crate::readers::SampleSheet::columns)).
+    pub fn with_sample_labels(
+        writer: W,
+        mz_converter: Tof2MzConverter,
+        im_converter: Scan2ImConverter,
+        row_group_size: usize,
+        compression: ParquetCompression,
+        label_columns: &[String],
+    ) -> Result<Self, ParquetError> {
+        Self::with_schema(
+            writer,
+            mz_converter,
+            im_converter,
+            row_group_size,
+            compression,
+            Some(label_columns.to_vec()),
+        )
+    }
+
+    fn with_schema(
+        writer: W,
+        mz_converter: Tof2MzConverter,
+        im_converter: Scan2ImConverter,
+        row_group_size: usize,
+        compression: ParquetCompression,
+        label_columns: Option<Vec<String>>,
+    ) -> Result<Self, ParquetError> {
+        let schema = Arc::new(peak_schema(label_columns.as_deref())?);
+        let properties = Arc::new(
+            WriterProperties::builder()
+                .set_compression(compression.codec())
+                .build(),
+        );
+        let writer = SerializedFileWriter::new(writer, schema, properties)?;
+        Ok(Self {
+            writer,
+            mz_converter,
+            im_converter,
+            row_group_size: row_group_size.max(1),
+            label_columns,
+            columns: PeakColumns::default(),
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: &Frame) -> Result<(), ParquetError> {
+        let im_converter = frame.im_converter.unwrap_or(self.im_converter);
+        for (scan, offsets) in frame.scan_offsets.windows(2).enumerate() {
+            let mobility = im_converter.convert(scan as f64);
+            for peak in offsets[0]..offsets[1] {
+                let tof = frame.tof_indices[peak];
+                self.columns.frame.push(frame.index as u32);
+                self.columns.scan.push(scan as u32);
+                self.columns.tof.push(tof);
+                self.columns.intensity.push(frame.intensities[peak]);
+                self.columns.quality.push(frame.quality.bits());
+                self.columns.rt.push(frame.rt_in_seconds);
+                self.columns.mz.push(self.mz_converter.convert(tof as f64));
+                self.columns.mobility.push(mobility);
+                if self.label_columns.is_some() {
+                    self.columns
+                        .sample_labels
+                        .push(frame.sample_labels.clone());
+                }
+            }
+        }
+        if self.columns.frame.len() >= self.row_group_size {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<(), ParquetError> {
+        self.flush_row_group()?;
+        self.writer.close()?;
+        Ok(())
+    }
+
+    fn flush_row_group(&mut self) -> Result<(), ParquetError> {
+        if self.columns.frame.is_empty() {
+            return Ok(());
+        }
+        let columns = std::mem::take(&mut self.columns);
+        let mut row_group = self.writer.next_row_group()?;
+        for values in [
+            &columns.frame,
+            &columns.scan,
+            &columns.tof,
+            &columns.intensity,
+            &columns.quality,
+        ] {
+            // Unsigned 32-bit columns store the bits of their values as INT32
+            let values: Vec<i32> = values
+                .iter()
+                .map(|&value| i32::from_ne_bytes(value.to_ne_bytes()))
+                .collect();
+            let mut column = row_group
+                .next_column()?
+                .expect("Schema contains all int columns");
+            column
+                .typed::<Int32Type>()
+                .write_batch(&values, None, None)?;
+            column.close()?;
+        }
+        for values in [&columns.rt, &columns.mz, &columns.mobility] {
+            let mut column = row_group
+                .next_column()?
+                .expect("Schema contains all double columns");
+            column
+                .typed::<DoubleType>()
+                .write_batch(values, None, None)?;
+            column.close()?;
+        }
+        if let Some(label_columns) = &self.label_columns {
+            let keys = columns.sample_labels.iter().map(|labels| {
+                labels.as_ref().map(|labels| labels.key.as_str())
+            });
+            write_string_column(&mut row_group, keys)?;
+            for name in label_columns {
+                let labels = columns.sample_labels.iter().map(|labels| {
+                    labels.as_ref().and_then(|labels| labels.get(name))
+                });
+                write_string_column(&mut row_group, labels)?;
+            }
+        }
+        row_group.close()?;
+        Ok(())
+    }
+}
+
+/// The peak schema, optionally with a `sample` column and a `sample_<column>`
+/// column for every label column.
+fn peak_schema(label_columns: Option<&[String]>) -> Result<Type, ParquetError> {
+    let schema = parse_message_type(PEAK_SCHEMA)?;
+    let Some(label_columns) = label_columns else {
+        return Ok(schema);
+    };
+    let mut fields = schema.get_fields().to_vec();
+    let names = std::iter::once("sample".to_string()).chain(
+        label_columns
+            .iter()
+            .map(|column| format!("sample_{}", column)),
+    );
+    for name in names {
+        let field =
+            Type::primitive_type_builder(&name, PhysicalType::BYTE_ARRAY)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(Some(LogicalType::String))
+                .build()?;
+        fields.push(Arc::new(field));
+    }
+    Type::group_type_builder(schema.name())
+        .with_fields(fields)
+        .build()
+}
+
+/// Write the next column of a row group as optional strings.
+fn write_string_column<'a, W: Write + Send>(
+    row_group: &mut SerializedRowGroupWriter<'_, W>,
+    values: impl Iterator<Item = Option<&'a str>>,
+) -> Result<(), ParquetError> {
+    let mut definition_levels = vec![];
+    let mut strings = vec![];
+    for value in values {
+        definition_levels.push(value.is_some() as i16);
+        if let Some(value) = value {
+            strings.push(ByteArray::from(value));
+        }
+    }
+    let mut column = row_group
+        .next_column()?
+        .expect("Schema contains all sample columns");
+    column.typed::<ByteArrayType>().write_batch(
+        &strings,
+        Some(&definition_levels),
+        None,
+    )?;
+    column.close()
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        basic::LogicalType,
+        file::reader::{FileReader, SerializedFileReader},
+        record::Field,
+    };
+
+    use super::*;
+
+    #[test]
+    fn writes_unsigned_columns() {
+        let frame = Frame {
+            index: 3,
+            scan_offsets: vec![0, 1],
+            tof_indices: vec![u32::MAX - 1],
+            intensities: vec![u32::MAX],
+            ..Default::default()
+        };
+        let path = std::env::temp_dir()
+            .join(format!("timsrust_{}_unsigned.parquet", std::process::id()));
+        let mut writer = PeakParquetWriter::new(
+            std::fs::File::create(&path).unwrap(),
+            Tof2MzConverter::default(),
+            Scan2ImConverter::default(),
+            10,
+            ParquetCompression::None,
+        )
+        .unwrap();
+        writer.write_frame(&frame).unwrap();
+        writer.close().unwrap();
+        let reader =
+            SerializedFileReader::new(std::fs::File::open(&path).unwrap())
+                .unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        assert_eq!(
+            schema.column(3).logical_type(),
+            Some(LogicalType::Integer {
+                bit_width: 32,
+                is_signed: false
+            })
+        );
+        let row = reader.get_row_iter(None).unwrap().next().unwrap().unwrap();
+        let fields: Vec<&Field> =
+            row.get_column_iter().map(|(_, field)| field).collect();
+        assert_eq!(fields[0], &Field::UInt(3));
+        assert_eq!(fields[2], &Field::UInt(u32::MAX - 1));
+        assert_eq!(fields[3], &Field::UInt(u32::MAX));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn writes_frame_mobilities() {
+        let frame = Frame {
+            scan_offsets: vec![0, 0, 1],
+            tof_indices: vec![10],
+            intensities: vec![100],
+            im_converter: Some(Scan2ImConverter::from_linear(1.5, -0.01)),
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!(
+            "timsrust_{}_frame_mobilities.parquet",
+            std::process::id()
+        ));
+        let mut writer = PeakParquetWriter::new(
+            std::fs::File::create(&path).unwrap(),
+            Tof2MzConverter::default(),
+            Scan2ImConverter::from_boundaries(0.6, 1.6, 10),
+            10,
+            ParquetCompression::None,
+        )
+        .unwrap();
+        writer.write_frame(&frame).unwrap();
+        writer.close().unwrap();
+        let reader =
+            SerializedFileReader::new(std::fs::File::open(&path).unwrap())
+                .unwrap();
+        let row = reader.get_row_iter(None).unwrap().next().unwrap().unwrap();
+        let mobility = row.get_column_iter().nth(7).unwrap().1;
+        assert_eq!(mobility, &Field::Double(1.5 - 0.01));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rusqlite::{params, Connection};
+
+use crate::{
+    io::readers::{
+        FrameReader, FrameReaderError, TimsTofPath, TimsTofPathError,
+        TimsTofPathLike,
+    },
+    ms_data::{Frame, MSLevel},
+};
+
+const TDF_FILE_NAME: &str = "analysis.tdf";
+const TDF_BIN_FILE_NAME: &str = "analysis.tdf_bin";
+
+type FramePredicate = Arc<dyn Fn(&Frame) -> bool + Send + Sync>;
+
+/// Copies a subset of the frames of a TDF run to a new `.d` directory.
+///
+/// The binary data of the selected frames is copied without decoding it.
+/// All other tables of `analysis.tdf` are copied as well; rows that refer
+/// to frames (through a `Frame` column or the `Parent` of precursors) are
+/// dropped if their frame is not selected. Selected frames get consecutive
+/// `Id`s starting from 1. `Segments` are clipped to their selected frames
+/// and dropped if they have none.
+///
+/// All filters must match for a frame to be selected.
+#[derive(Clone)]
+pub struct RunSubsetter {
+    path: TimsTofPath,
+    filters: Vec<FramePredicate>,
+}
+
+impl RunSubsetter {
+    pub fn new(path: impl TimsTofPathLike) -> Result<Self, RunSubsetterError> {
+        let subsetter = Self {
+            path: path.to_timstof_path()?,
+            filters: vec![],
+        };
+        Ok(subsetter)
+    }
+
+    /// Only keep frames with a retention time (in seconds) within
+    /// `rt_range`.
+    pub fn rt_range(self, rt_range: Range<f64>) -> Self {
+        self.filter(move |frame| rt_range.contains(&frame.rt_in_seconds))
+    }
+
+    pub fn ms_level(self, ms_level: MSLevel) -> Self {
+        self.filter(move |frame| frame.ms_level == ms_level)
+    }
+
+    /// Only keep MALDI frames of a region (e.g. `R00`).
+    pub fn maldi_region(self, region: impl Into<String>) -> Self {
+        let region = region.into();
+        self.filter(move |frame| {
+            frame
+                .maldi_info
+                .as_ref()
+                .and_then(|info| info.region())
+                .is_some_and(|frame_region| frame_region == region)
+        })
+    }
+
+    /// Only keep frames for which `predicate` is true. The predicate is
+    /// called with frames without peaks.
+    pub fn filter(
+        mut self,
+        predicate: impl Fn(&Frame) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filters.push(Arc::new(predicate));
+        self
+    }
+
+    /// The `Id`s of all selected frames in the source run.
+    pub fn frame_ids(&self) -> Result<Vec<usize>, RunSubsetterError> {
+        let reader = FrameReader::new(&self.path)?;
+        let mut ids = vec![];
+        for index in 0..reader.len() {
+            let frame = reader.get_frame_without_coordinates(index)?;
+            if self.filters.iter().all(|filter| filter(&frame)) {
+                ids.push(frame.index);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Write the selected frames to a new `.d` directory at `output` and
+    /// return its path.
+    pub fn write(
+        &self,
+        output: impl AsRef<Path>,
+    ) -> Result<PathBuf, RunSubsetterError> {
+        let output = output.as_ref().to_path_buf();
+        let tdf_path = output.join(TDF_FILE_NAME);
+        if tdf_path.exists() {
+            return Err(RunSubsetterError::AlreadyExists(output));
+        }
+        let frame_ids = self.frame_ids()?;
+        if frame_ids.is_empty() {
+            return Err(RunSubsetterError::NoFrames);
+        }
+        std::fs::create_dir_all(&output)?;
+        std::fs::copy(self.path.tdf()?, &tdf_path)?;
+        let mut connection = Connection::open(&tdf_path)?;
+        let offsets = self.copy_blobs(
+            &connection,
+            &frame_ids,
+            &output.join(TDF_BIN_FILE_NAME),
+        )?;
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(
+            "CREATE TEMP TABLE FrameMap (
+                Old INTEGER PRIMARY KEY,
+                New INTEGER NOT NULL,
+                TimsId INTEGER NOT NULL
+            );",
+        )?;
+        for (new_id, (old_id, offset)) in
+            frame_ids.iter().zip(offsets).enumerate()
+        {
+            transaction.execute(
+                "INSERT INTO FrameMap (Old, New, TimsId) VALUES (?1, ?2, ?3)",
+                params![*old_id as i64, new_id as i64 + 1, offset as i64],
+            )?;
+        }
+        renumber_frames(&transaction)?;
+        transaction.commit()?;
+        connection.execute_batch("VACUUM")?;
+        Ok(output)
+    }
+
+    /// Copy the compressed blobs of all frames to a new binary file and
+    /// return their offsets in it.
+    fn copy_blobs(
+        &self,
+        connection: &Connection,
+        frame_ids: &[usize],
+        bin_path: &Path,
+    ) -> Result<Vec<usize>, RunSubsetterError> {
+        let mut source = File::open(self.path.tdf_bin()?)?;
+        let mut target = BufWriter::new(File::create(bin_path)?);
+        let mut statement =
+            connection.prepare("SELECT TimsId FROM Frames WHERE Id = ?1")?;
+        let mut offsets = Vec::with_capacity(frame_ids.len());
+        let mut offset = 0;
+        let mut blob = vec![];
+        for &frame_id in frame_ids {
+            let source_offset: i64 = statement
+                .query_row(params![frame_id as i64], |row| row.get(0))?;
+            source.seek(SeekFrom::Start(source_offset as u64))?;
+            let mut byte_count = [0; 4];
+            source.read_exact(&mut byte_count)?;
+            blob.resize(u32::from_le_bytes(byte_count) as usize, 0);
+            if blob.len() < byte_count.len() {
+                return Err(RunSubsetterError::CorruptFrame(frame_id));
+            }
+            blob[..4].copy_from_slice(&byte_count);
+            source.read_exact(&mut blob[4..])?;
+            target.write_all(&blob)?;
+            offsets.push(offset);
+            offset += blob.len();
+        }
+        target.flush()?;
+        Ok(offsets)
+    }
+}
+
+/// Drop and renumber all rows that refer to frames according to the
+/// `FrameMap` table. Ids are first negated, so that renumbering never
+/// collides with Ids that are not renumbered yet.
+fn renumber_frames(connection: &Connection) -> Result<(), rusqlite::Error> {
+    connection.execute_batch(
+        "DELETE FROM Frames WHERE Id NOT IN (SELECT Old FROM FrameMap);
+        UPDATE Frames SET
+            TimsId = (SELECT TimsId FROM FrameMap WHERE Old = Frames.Id),
+            Id = -(SELECT New FROM FrameMap WHERE Old = Frames.Id);
+        UPDATE Frames SET Id = -Id;",
+    )?;
+    for table in tables_with_column(connection, "Frame")? {
+        connection.execute_batch(&format!(
+            "DELETE FROM {table} WHERE Frame NOT IN (SELECT Old FROM FrameMap);
+            UPDATE {table} SET
+                Frame = -(SELECT New FROM FrameMap WHERE Old = {table}.Frame);
+            UPDATE {table} SET Frame = -Frame;",
+        ))?;
+    }
+    for table in tables_with_column(connection, "Parent")? {
+        if !["Precursors", "FrameMsMsInfo"].contains(&table.as_str()) {
+            continue;
+        }
+        connection.execute_batch(&format!(
+            "DELETE FROM {table} WHERE Parent IS NOT NULL
+                AND Parent NOT IN (SELECT Old FROM FrameMap);
+            UPDATE {table} SET
+                Parent = (SELECT New FROM FrameMap WHERE Old = {table}.Parent)
+                WHERE Parent IS NOT NULL;",
+        ))?;
+    }
+    if tables_with_column(connection, "FirstFrame")?
+        .iter()
+        .any(|table| table == "Segments")
+    {
+        connection.execute_batch(
+            "DELETE FROM Segments WHERE NOT EXISTS (SELECT 1 FROM FrameMap
+                WHERE Old BETWEEN Segments.FirstFrame AND Segments.LastFrame);
+            UPDATE Segments SET
+                FirstFrame = (SELECT MIN(New) FROM FrameMap
+                    WHERE Old BETWEEN Segments.FirstFrame
+                        AND Segments.LastFrame),
+                LastFrame = (SELECT MAX(New) FROM FrameMap
+                    WHERE Old BETWEEN Segments.FirstFrame
+                        AND Segments.LastFrame);",
+        )?;
+    }
+    if tables_with_column(connection, "Precursor")?
+        .iter()
+        .any(|table| table == "PasefFrameMsMsInfo")
+    {
+        connection.execute_batch(
+            "DELETE FROM PasefFrameMsMsInfo
+                WHERE Precursor NOT IN (SELECT Id FROM Precursors);",
+        )?;
+    }
+    Ok(())
+}
+
+/// The names of all (non-temporary) tables that have a column `column`.
+fn tables_with_column(
+    connection: &Connection,
+    column: &str,
+) -> Result<Vec<String>, rusqlite::Error> {
+    let mut statement = connection.prepare(
+        "SELECT m.name FROM sqlite_master AS m
+            JOIN pragma_table_info(m.name) AS p
+            WHERE m.type = 'table' AND p.name = ?1",
+    )?;
+    let tables = statement
+        .query_map(params![column], |row| row.get::<_, String>(0))?
+        .collect::<Result<HashSet<String>, _>>()?;
+    let mut tables: Vec<String> = tables.into_iter().collect();
+    tables.sort();
+    Ok(tables)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RunSubsetterError {
+    #[error("{0}")]
+    IO(#[from] io::Error),
+    #[error("{0}")]
+    SqlError(#[from] rusqlite::Error),
+    #[error("{0}")]
+    TimsTofPathError(#[from] TimsTofPathError),
+    #[error("{0}")]
+    FrameReaderError(#[from] FrameReaderError),
+    #[error("Run already exists: {0}")]
+    AlreadyExists(PathBuf),
+    #[error("No frames match the filters")]
+    NoFrames,
+    #[error("Binary data of frame {0} is corrupt")]
+    CorruptFrame(usize),
+}
@@ -0,0 +1,255 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{params, Connection};
+
+use crate::ms_data::{Frame, GlobalMetadata, MaldiInfo};
+
+const TDF_FILE_NAME: &str = "analysis.tdf";
+const TDF_BIN_FILE_NAME: &str = "analysis.tdf_bin";
+const HEADER_SIZE: usize = 2 * std::mem::size_of::<u32>();
+const ZSTD_LEVEL: i32 = 1;
+
+/// GlobalMetadata keys that are needed to read the written run back.
+const REQUIRED_KEYS: [&str; 6] = [
+    "AcquisitionSoftware",
+    "MzAcqRangeLower",
+    "MzAcqRangeUpper",
+    "OneOverK0AcqRangeLower",
+    "OneOverK0AcqRangeUpper",
+    "DigitizerNumSamples",
+];
+
+const SCHEMA: &str = "
+CREATE TABLE GlobalMetadata (
+    Key TEXT PRIMARY KEY,
+    Value TEXT
+);
+CREATE TABLE Frames (
+    Id INTEGER PRIMARY KEY,
+    Time REAL NOT NULL,
+    ScanMode INTEGER NOT NULL,
+    MsMsType INTEGER NOT NULL,
+    TimsId INTEGER,
+    MaxIntensity INTEGER NOT NULL,
+    SummedIntensities INTEGER NOT NULL,
+    NumScans INTEGER NOT NULL,
+    NumPeaks INTEGER NOT NULL,
+    AccumulationTime REAL
+);
+";
+
+const MALDI_SCHEMA: &str = "
+CREATE TABLE MaldiFrameInfo (
+    Frame INTEGER PRIMARY KEY,
+    SpotName TEXT,
+    XIndexPos INTEGER NOT NULL,
+    YIndexPos INTEGER NOT NULL,
+    PositionX REAL,
+    PositionY REAL,
+    LaserPower REAL,
+    LaserRepRate REAL,
+    NumLaserShots INTEGER
+);
+";
+
+/// Writes frames to a new `.d` directory with an `analysis.tdf` and an
+/// `analysis.tdf_bin` (compression type 2).
+///
+/// Only the `GlobalMetadata`, `Frames` and `MaldiFrameInfo` tables are
+/// written. Frames get consecutive `Id`s starting from 1 in the order they
+/// are written, regardless of [Frame::index]. Quadrupole settings and
+/// precursors are not written.
+pub struct TdfWriter {
+    path: PathBuf,
+    connection: Connection,
+    bin_writer: BufWriter<File>,
+    bin_offset: usize,
+    frame_count: usize,
+    maldi_info: Vec<(usize, MaldiInfo)>,
+}
+
+impl TdfWriter {
+    /// Create the `.d` directory at `path` and its (empty) files.
+    /// `TimsCompressionType` is always set to 2.
+    pub fn create(
+        path: impl AsRef<Path>,
+        global_metadata: &GlobalMetadata,
+    ) -> Result<Self, TdfWriterError> {
+        let path = path.as_ref().to_path_buf();
+        for key in REQUIRED_KEYS {
+            if global_metadata.get(key).is_none() {
+                return Err(TdfWriterError::MissingKey(key.to_string()));
+            }
+        }
+        let tdf_path = path.join(TDF_FILE_NAME);
+        if tdf_path.exists() {
+            return Err(TdfWriterError::AlreadyExists(path));
+        }
+        std::fs::create_dir_all(&path)?;
+        let bin_writer =
+            BufWriter::new(File::create(path.join(TDF_BIN_FILE_NAME))?);
+        let connection = Connection::open(&tdf_path)?;
+        connection.execute_batch(SCHEMA)?;
+        connection.execute_batch("BEGIN")?;
+        let mut statement = connection.prepare(
+            "INSERT INTO GlobalMetadata (Key, Value) VALUES (?1, ?2)",
+        )?;
+        for (key, value) in global_metadata.raw() {
+            if key != "TimsCompressionType" {
+                statement.execute(params![key, value])?;
+            }
+        }
+        statement.execute(params!["TimsCompressionType", "2"])?;
+        drop(statement);
+        let writer = Self {
+            path,
+            connection,
+            bin_writer,
+            bin_offset: 0,
+            frame_count: 0,
+            maldi_info: vec![],
+        };
+        Ok(writer)
+    }
+
+    /// Append a frame and return the `Id` it was written with.
+    pub fn write_frame(
+        &mut self,
+        frame: &Frame,
+    ) -> Result<usize, TdfWriterError> {
+        let id = self.frame_count + 1;
+        let scan_count = frame.scan_offsets.len().saturating_sub(1);
+        let peak_count = frame.tof_indices.len();
+        if (frame.intensities.len() != peak_count)
+            | (frame.scan_offsets.first().copied().unwrap_or(0) != 0)
+            | (frame.scan_offsets.last().copied().unwrap_or(0) != peak_count)
+            | frame.scan_offsets.windows(2).any(|x| x[0] > x[1])
+        {
+            return Err(TdfWriterError::InvalidFrame(frame.index));
+        }
+        let values = encode_frame(frame)
+            .ok_or(TdfWriterError::InvalidFrame(frame.index))?;
+        let compressed =
+            zstd::encode_all(transpose_bytes(&values).as_slice(), ZSTD_LEVEL)?;
+        let byte_count = (HEADER_SIZE + compressed.len()) as u32;
+        self.bin_writer.write_all(&byte_count.to_le_bytes())?;
+        self.bin_writer
+            .write_all(&(scan_count.max(1) as u32).to_le_bytes())?;
+        self.bin_writer.write_all(&compressed)?;
+        let accumulation_time = match frame.intensity_correction_factor {
+            factor if factor > 0.0 => Some(1.0 / factor),
+            _ => None,
+        };
+        self.connection.execute(
+            "INSERT INTO Frames (Id, Time, ScanMode, MsMsType, TimsId, \
+             MaxIntensity, SummedIntensities, NumScans, NumPeaks, \
+             AccumulationTime) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id as i64,
+                frame.rt_in_seconds,
+                frame.scan_mode,
+                frame.msms_type,
+                self.bin_offset as i64,
+                frame.intensities.iter().max().copied().unwrap_or(0),
+                frame.intensities.iter().map(|&x| x as u64).sum::<u64>() as i64,
+                scan_count.max(1) as i64,
+                peak_count as i64,
+                accumulation_time,
+            ],
+        )?;
+        self.bin_offset += byte_count as usize;
+        self.frame_count += 1;
+        if let Some(maldi_info) = &frame.maldi_info {
+            self.maldi_info.push((id, maldi_info.clone()));
+        }
+        Ok(id)
+    }
+
+    /// Flush all data and return the path of the `.d` directory.
+    pub fn finish(mut self) -> Result<PathBuf, TdfWriterError> {
+        self.bin_writer.flush()?;
+        if !self.maldi_info.is_empty() {
+            self.connection.execute_batch(MALDI_SCHEMA)?;
+            let mut statement = self.connection.prepare(
+                "INSERT INTO MaldiFrameInfo (Frame, SpotName, XIndexPos, \
+                 YIndexPos, PositionX, PositionY, LaserPower, LaserRepRate, \
+                 NumLaserShots) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for (id, maldi) in self.maldi_info.iter() {
+                statement.execute(params![
+                    *id as i64,
+                    maldi.spot_name,
+                    maldi.pixel_x,
+                    maldi.pixel_y,
+                    maldi.position_x_um,
+                    maldi.position_y_um,
+                    maldi.laser_power,
+                    maldi.laser_rep_rate,
+                    maldi.laser_shots,
+                ])?;
+            }
+        }
+        self.connection.execute_batch("COMMIT")?;
+        Ok(self.path)
+    }
+}
+
+/// Encode a frame as the u32 values of a compression type 2 blob: the scan
+/// count, twice the peak count of all but the last scan, and pairs of TOF
+/// deltas and intensities. Peaks are sorted by TOF within each scan.
+/// Returns `None` if the first TOF of a scan does not fit in a delta.
+fn encode_frame(frame: &Frame) -> Option<Vec<u32>> {
+    let scan_count = frame.scan_offsets.len().saturating_sub(1).max(1);
+    let mut values =
+        Vec::with_capacity(scan_count + 2 * frame.tof_indices.len());
+    values.push(scan_count as u32);
+    for scan in frame.scan_offsets.windows(2).take(scan_count - 1) {
+        values.push(2 * (scan[1] - scan[0]) as u32);
+    }
+    for scan in frame.scan_offsets.windows(2) {
+        let mut peaks: Vec<(u32, u32)> = (scan[0]..scan[1])
+            .map(|peak| (frame.tof_indices[peak], frame.intensities[peak]))
+            .collect();
+        peaks.sort_by_key(|&(tof, _)| tof);
+        let mut previous_tof = None;
+        for (tof, intensity) in peaks {
+            let delta = match previous_tof {
+                None => tof.checked_add(1)?,
+                Some(previous) => tof - previous,
+            };
+            previous_tof = Some(tof);
+            values.push(delta);
+            values.push(intensity);
+        }
+    }
+    Some(values)
+}
+
+/// Store the i-th byte of all values together, as expected in TDF blobs.
+fn transpose_bytes(values: &[u32]) -> Vec<u8> {
+    let bytes: Vec<[u8; 4]> =
+        values.iter().map(|value| value.to_le_bytes()).collect();
+    (0..4)
+        .flat_map(|plane| bytes.iter().map(move |value| value[plane]))
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TdfWriterError {
+    #[error("{0}")]
+    IO(#[from] io::Error),
+    #[error("{0}")]
+    SqlError(#[from] rusqlite::Error),
+    #[error("Run already exists: {0}")]
+    AlreadyExists(PathBuf),
+    #[error("Key not found: {0}")]
+    MissingKey(String),
+    #[error("Peaks of frame {0} do not match its scan offsets")]
+    InvalidFrame(usize),
+}
@@ -20,21 +20,56 @@
 //!  in the provided ms2 folder:
 //!     * *.ms2spectrum.bin
 //!     * *.ms2spectrum.parquet
+//!
+//!  Folders written by older converters, containing `*.ms2.bin` and
+//!  `*.MS2Spectra.ms2.parquet`, are read as well, and so are folders where
+//!  these files are paired with an `analysis.tdf` without `analysis.tdf_bin`.
 
+#[cfg(feature = "convert")]
+pub(crate) mod conversion;
+pub(crate) mod data_processing;
 pub(crate) mod domain_converters;
 pub(crate) mod errors;
+#[cfg(feature = "tdf")]
+pub(crate) mod imaging;
 pub(crate) mod io;
 pub(crate) mod ms_data;
 pub(crate) mod utils;
+#[cfg(feature = "tdf")]
+pub(crate) mod validation;
 
+#[cfg(feature = "convert")]
+pub mod convert {
+    //! One-call conversions to mzML, MGF, imzML and Parquet.
+    pub use crate::conversion::*;
+}
 pub mod converters {
     //! Allows conversions between domains (e.g. Time of Flight and m/z)
     pub use crate::domain_converters::*;
 }
+#[cfg(feature = "tdf")]
+pub mod index {
+    //! A persistent m/z and ion mobility index of all peaks in a run.
+    pub use crate::io::index::*;
+}
 pub mod readers {
     //! Readers for all data from Bruker compatible files.
     pub use crate::io::readers::*;
 }
+pub mod processing {
+    //! Processing of raw TimsTOF data (e.g. centroiding)
+    pub use crate::data_processing::*;
+}
+#[cfg(feature = "tdf")]
+pub mod maldi {
+    //! Image extraction for MALDI imaging data.
+    pub use crate::imaging::*;
+}
+#[cfg(feature = "tdf")]
+pub mod validate {
+    //! Frame-by-frame comparison of runs (e.g. to validate writers).
+    pub use crate::validation::*;
+}
 pub mod writers {
     //! Writers to generic file formats.
     pub use crate::io::writers::*;
@@ -1,15 +1,2 @@
 //! Data structures that represent MS data
-
-mod acquisition;
-mod frames;
-mod metadata;
-mod precursors;
-mod quadrupole;
-mod spectra;
-
-pub use acquisition::*;
-pub use frames::*;
-pub use metadata::*;
-pub use precursors::*;
-pub use quadrupole::*;
-pub use spectra::*;
+pub use timsrust_4d_types::ms_data::*;
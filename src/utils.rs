@@ -1 +1,2 @@
+pub mod parallel;
 pub mod vec_utils;
@@ -0,0 +1,10 @@
+/// The minimal length of the rayon splits of a parallel fold over `len`
+/// items, such that there are at most as many splits as threads.
+///
+/// Folds whose accumulators are large (e.g. a forked frame visitor)
+/// allocate one accumulator per split, so bounding the splits bounds the
+/// memory.
+#[cfg(feature = "tdf")]
+pub fn min_split_len(len: usize) -> usize {
+    len.div_ceil(rayon::current_num_threads()).max(1)
+}
@@ -0,0 +1,430 @@
+//! Frame-by-frame comparison of two runs.
+//!
+//! Two runs (or the same run read through two code paths, e.g. a run that
+//! was recompressed or written with a [TdfWriter](crate::io::writers::TdfWriter))
+//! are compared frame by frame in the order of their readers. Peaks are
+//! compared per scan, so that a single missing peak does not shift the
+//! comparison of all following peaks of the frame.
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    io::readers::{
+        FrameReader, FrameReaderError, MetadataReader, MetadataReaderError,
+        TimsTofPathLike,
+    },
+    ms_data::{Frame, Metadata},
+};
+
+/// Tolerances of a [FrameValidator].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValidationConfig {
+    pub tof_tolerance: u32,
+    pub absolute_intensity_tolerance: u32,
+    /// Relative to the largest of both intensities
+    pub relative_intensity_tolerance: f64,
+    /// Retention time in seconds
+    pub rt_tolerance: f64,
+    /// Whether to compare the quadrupole settings and PRM targets, which
+    /// are not part of all written runs
+    pub compare_quadrupole_settings: bool,
+    /// The maximum number of differences that are reported per frame
+    pub max_differences_per_frame: usize,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            tof_tolerance: 0,
+            absolute_intensity_tolerance: 0,
+            relative_intensity_tolerance: 0.0,
+            rt_tolerance: 1e-6,
+            compare_quadrupole_settings: true,
+            max_differences_per_frame: 100,
+        }
+    }
+}
+
+/// A field with a different value in both runs, formatted with [Debug].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataDifference {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+impl MetadataDifference {
+    fn new(
+        field: &'static str,
+        left: &impl std::fmt::Debug,
+        right: &impl std::fmt::Debug,
+    ) -> Self {
+        Self {
+            field,
+            left: format!("{left:?}"),
+            right: format!("{right:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameDifference {
+    ScanCount {
+        left: usize,
+        right: usize,
+    },
+    PeakCount {
+        scan: usize,
+        left: usize,
+        right: usize,
+    },
+    /// `peak` is the index of the peak within its scan
+    TofIndex {
+        scan: usize,
+        peak: usize,
+        left: u32,
+        right: u32,
+    },
+    Intensity {
+        scan: usize,
+        peak: usize,
+        left: u32,
+        right: u32,
+    },
+    Metadata(MetadataDifference),
+}
+
+/// The differences of a single frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameComparison {
+    /// 0-based index of the frame in both readers
+    pub index: usize,
+    pub left_peak_count: usize,
+    pub right_peak_count: usize,
+    /// The largest intensity difference of all compared peaks
+    pub max_intensity_delta: u32,
+    /// All differences, up to [ValidationConfig::max_differences_per_frame]
+    pub differences: Vec<FrameDifference>,
+    /// Whether differences were left out of `differences`
+    pub truncated: bool,
+}
+
+impl FrameComparison {
+    pub fn is_ok(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    fn push(&mut self, difference: FrameDifference, max_differences: usize) {
+        if self.differences.len() < max_differences {
+            self.differences.push(difference);
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+/// The result of comparing two runs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub left_frame_count: usize,
+    pub right_frame_count: usize,
+    /// Run-level differences (empty when readers were compared directly)
+    pub metadata: Vec<MetadataDifference>,
+    /// Only the frames with differences, in order of their index
+    pub frames: Vec<FrameComparison>,
+    /// The largest intensity difference of all compared peaks
+    pub max_intensity_delta: u32,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        (self.left_frame_count == self.right_frame_count)
+            & self.metadata.is_empty()
+            & self.frames.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameValidator {
+    config: ValidationConfig,
+}
+
+impl FrameValidator {
+    pub fn new(config: ValidationConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ValidationConfig {
+        &self.config
+    }
+
+    /// Compare two frames; `index` is only used for the report.
+    pub fn compare_frames(
+        &self,
+        index: usize,
+        left: &Frame,
+        right: &Frame,
+    ) -> FrameComparison {
+        let max_differences = self.config.max_differences_per_frame;
+        let mut comparison = FrameComparison {
+            index,
+            left_peak_count: left.tof_indices.len(),
+            right_peak_count: right.tof_indices.len(),
+            ..Default::default()
+        };
+        for difference in self.compare_frame_metadata(left, right) {
+            comparison.push(FrameDifference::Metadata(difference), usize::MAX);
+        }
+        let left_scans = left.scan_offsets.len().saturating_sub(1);
+        let right_scans = right.scan_offsets.len().saturating_sub(1);
+        if left_scans != right_scans {
+            comparison.push(
+                FrameDifference::ScanCount {
+                    left: left_scans,
+                    right: right_scans,
+                },
+                max_differences,
+            );
+        }
+        for scan in 0..left_scans.min(right_scans) {
+            let left_peaks =
+                left.scan_offsets[scan]..left.scan_offsets[scan + 1];
+            let right_peaks =
+                right.scan_offsets[scan]..right.scan_offsets[scan + 1];
+            if left_peaks.len() != right_peaks.len() {
+                comparison.push(
+                    FrameDifference::PeakCount {
+                        scan,
+                        left: left_peaks.len(),
+                        right: right_peaks.len(),
+                    },
+                    max_differences,
+                );
+                continue;
+            }
+            for (peak, (l, r)) in left_peaks.zip(right_peaks).enumerate() {
+                let (left_tof, right_tof) =
+                    (left.tof_indices[l], right.tof_indices[r]);
+                if left_tof.abs_diff(right_tof) > self.config.tof_tolerance {
+                    comparison.push(
+                        FrameDifference::TofIndex {
+                            scan,
+                            peak,
+                            left: left_tof,
+                            right: right_tof,
+                        },
+                        max_differences,
+                    );
+                }
+                let (left_intensity, right_intensity) =
+                    (left.intensities[l], right.intensities[r]);
+                let delta = left_intensity.abs_diff(right_intensity);
+                comparison.max_intensity_delta =
+                    comparison.max_intensity_delta.max(delta);
+                if !self.intensities_match(left_intensity, right_intensity) {
+                    comparison.push(
+                        FrameDifference::Intensity {
+                            scan,
+                            peak,
+                            left: left_intensity,
+                            right: right_intensity,
+                        },
+                        max_differences,
+                    );
+                }
+            }
+        }
+        comparison
+    }
+
+    fn intensities_match(&self, left: u32, right: u32) -> bool {
+        let delta = left.abs_diff(right);
+        let relative_tolerance =
+            self.config.relative_intensity_tolerance * left.max(right) as f64;
+        (delta <= self.config.absolute_intensity_tolerance)
+            | (delta as f64 <= relative_tolerance)
+    }
+
+    fn compare_frame_metadata(
+        &self,
+        left: &Frame,
+        right: &Frame,
+    ) -> Vec<MetadataDifference> {
+        let mut differences = vec![];
+        macro_rules! compare {
+            ($field:ident) => {
+                if left.$field != right.$field {
+                    differences.push(MetadataDifference::new(
+                        stringify!($field),
+                        &left.$field,
+                        &right.$field,
+                    ));
+                }
+            };
+        }
+        compare!(index);
+        if (left.rt_in_seconds - right.rt_in_seconds).abs()
+            > self.config.rt_tolerance
+        {
+            differences.push(MetadataDifference::new(
+                "rt_in_seconds",
+                &left.rt_in_seconds,
+                &right.rt_in_seconds,
+            ));
+        }
+        compare!(acquisition_type);
+        compare!(ms_level);
+        compare!(msms_type);
+        compare!(scan_mode);
+        if self.config.compare_quadrupole_settings {
+            compare!(quadrupole_settings);
+            compare!(window_group);
+            compare!(prm_targets);
+        }
+        compare!(intensity_correction_factor);
+        compare!(maldi_info);
+        compare!(quality);
+        differences
+    }
+
+    /// Compare all frames of two readers in parallel.
+    pub fn compare_readers(
+        &self,
+        left: &FrameReader,
+        right: &FrameReader,
+    ) -> Result<ValidationReport, ValidationError> {
+        let frames = (0..left.len().min(right.len()))
+            .into_par_iter()
+            .map(|index| {
+                let comparison = self.compare_frames(
+                    index,
+                    &left.get(index)?,
+                    &right.get(index)?,
+                );
+                Ok(comparison)
+            })
+            .collect::<Result<Vec<FrameComparison>, ValidationError>>()?;
+        let report = ValidationReport {
+            left_frame_count: left.len(),
+            right_frame_count: right.len(),
+            max_intensity_delta: frames
+                .iter()
+                .map(|frame| frame.max_intensity_delta)
+                .max()
+                .unwrap_or(0),
+            frames: frames.into_iter().filter(|x| !x.is_ok()).collect(),
+            ..Default::default()
+        };
+        Ok(report)
+    }
+
+    /// Compare the metadata and all frames of two runs.
+    pub fn compare_runs(
+        &self,
+        left: impl TimsTofPathLike,
+        right: impl TimsTofPathLike,
+    ) -> Result<ValidationReport, ValidationError> {
+        let metadata = compare_metadata(
+            &MetadataReader::new(&left)?,
+            &MetadataReader::new(&right)?,
+        );
+        let report = self.compare_readers(
+            &FrameReader::new(&left)?,
+            &FrameReader::new(&right)?,
+        )?;
+        Ok(ValidationReport { metadata, ..report })
+    }
+}
+
+/// The differences of the converters and acquisition ranges of two runs.
+/// The compression type is not compared.
+fn compare_metadata(
+    left: &Metadata,
+    right: &Metadata,
+) -> Vec<MetadataDifference> {
+    let mut differences = vec![];
+    macro_rules! compare {
+        ($field:ident) => {
+            if left.$field != right.$field {
+                differences.push(MetadataDifference::new(
+                    stringify!($field),
+                    &left.$field,
+                    &right.$field,
+                ));
+            }
+        };
+    }
+    compare!(rt_converter);
+    compare!(im_converter);
+    compare!(mz_converter);
+    compare!(lower_rt);
+    compare!(upper_rt);
+    compare!(lower_im);
+    compare!(upper_im);
+    compare!(lower_mz);
+    compare!(upper_mz);
+    differences
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    #[error("{0}")]
+    FrameReaderError(#[from] FrameReaderError),
+    #[error("{0}")]
+    MetadataReaderError(#[from] MetadataReaderError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(tof_indices: Vec<u32>, intensities: Vec<u32>) -> Frame {
+        Frame {
+            scan_offsets: vec![0, 1, tof_indices.len()],
+            tof_indices,
+            intensities,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn compare_with_tolerances() {
+        let left = frame(vec![10, 20, 30], vec![100, 200, 300]);
+        let right = frame(vec![10, 21, 30], vec![100, 210, 300]);
+        let validator = FrameValidator::default();
+        let comparison = validator.compare_frames(0, &left, &right);
+        assert_eq!(
+            comparison.differences,
+            vec![
+                FrameDifference::TofIndex {
+                    scan: 1,
+                    peak: 0,
+                    left: 20,
+                    right: 21
+                },
+                FrameDifference::Intensity {
+                    scan: 1,
+                    peak: 0,
+                    left: 200,
+                    right: 210
+                },
+            ]
+        );
+        assert_eq!(comparison.max_intensity_delta, 10);
+        let validator = FrameValidator::new(ValidationConfig {
+            tof_tolerance: 1,
+            relative_intensity_tolerance: 0.05,
+            ..Default::default()
+        });
+        assert!(validator.compare_frames(0, &left, &right).is_ok());
+        let right = frame(vec![10, 30], vec![100, 300]);
+        assert_eq!(
+            validator.compare_frames(0, &left, &right).differences,
+            vec![FrameDifference::PeakCount {
+                scan: 1,
+                left: 2,
+                right: 1
+            }]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(feature = "tdf")]
+mod tests {
+    use std::path::Path;
+    use timsrust::{
+        processing::binning::{
+            BinAggregation, BinAxis, BinDimension, HeatmapBinner,
+        },
+        readers::{FrameReader, MetadataReader},
+        MSLevel,
+    };
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn tdf_heatmaps() {
+        let file_path = get_local_directory().join("test.d");
+        let metadata = MetadataReader::new(&file_path).unwrap();
+        let reader = FrameReader::new(&file_path).unwrap();
+        let mz = BinAxis::log(metadata.lower_mz, metadata.upper_mz * 1.01, 50);
+        let rt = BinAxis::linear(0.0, 1.0, 10);
+        let heatmap =
+            HeatmapBinner::new(BinDimension::RetentionTime, rt, mz, &metadata)
+                .bin(&reader)
+                .unwrap();
+        assert_eq!(heatmap.values.len(), 500);
+        let frames: Vec<_> = reader
+            .get_all_ms1()
+            .into_iter()
+            .map(|frame| frame.unwrap())
+            .collect();
+        for frame in &frames {
+            let row = rt.bin(frame.rt_in_seconds).unwrap();
+            let tic: f64 = frame.intensities.iter().map(|&x| x as f64).sum();
+            assert_eq!(heatmap.row(row).unwrap().iter().sum::<f64>(), tic);
+        }
+        let total: f64 = frames
+            .iter()
+            .flat_map(|frame| &frame.intensities)
+            .map(|&x| x as f64)
+            .sum();
+        assert_eq!(heatmap.values.iter().sum::<f64>(), total);
+        let im = BinAxis::linear(metadata.lower_im, metadata.upper_im, 20);
+        let heatmap =
+            HeatmapBinner::new(BinDimension::IonMobility, im, mz, &metadata)
+                .with_aggregation(BinAggregation::Max)
+                .with_ms_level(MSLevel::MS2)
+                .bin(&reader)
+                .unwrap();
+        let max = reader
+            .get_all_ms2()
+            .into_iter()
+            .flat_map(|frame| frame.unwrap().intensities)
+            .max()
+            .unwrap();
+        let heatmap_max = heatmap.values.iter().copied().fold(0.0, f64::max);
+        assert_eq!(heatmap_max, max as f64);
+    }
+}
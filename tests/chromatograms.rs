@@ -0,0 +1,187 @@
+#[cfg(feature = "tdf")]
+mod tests {
+    use std::path::Path;
+    use timsrust::{
+        converters::ConvertableDomain,
+        processing::chromatogram::{
+            ChromatogramExtractor, ChromatogramFilter, ChromatogramKind,
+            XicExtractor, XicTarget,
+        },
+        readers::{FrameReader, MetadataReader},
+        Frame, MSLevel, NormalizationMode,
+    };
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn tdf_chromatograms() {
+        let file_path = get_local_directory().join("test.d");
+        let extractor = ChromatogramExtractor::new(&file_path).unwrap();
+        assert!(extractor.uses_frame_table(ChromatogramKind::Tic));
+        let tic = extractor.tic(ChromatogramFilter::All).unwrap();
+        assert_eq!(tic.rt, vec![0.1, 0.2, 0.3, 0.4]);
+        let frames = FrameReader::new(&file_path).unwrap().get_all();
+        let decoded_tic: Vec<f64> = frames
+            .iter()
+            .map(|frame| {
+                let frame = frame.as_ref().unwrap();
+                frame.intensities.iter().map(|&x| x as f64).sum()
+            })
+            .collect();
+        assert_eq!(tic.intensity, decoded_tic);
+        let bpc = extractor
+            .bpc(ChromatogramFilter::MsLevel(MSLevel::MS2))
+            .unwrap();
+        assert_eq!(bpc.rt, vec![0.2, 0.4]);
+        assert_eq!(bpc.intensity, vec![72.0, 272.0]);
+        assert!(extractor
+            .per_window_group(ChromatogramKind::Tic)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn tdf_chromatograms_dia() {
+        let file_path = get_local_directory().join("dia_test.d");
+        let extractor = ChromatogramExtractor::new(&file_path).unwrap();
+        let per_window_group =
+            extractor.per_window_group(ChromatogramKind::Bpc).unwrap();
+        assert!(!per_window_group.is_empty());
+        let ms2 = extractor
+            .bpc(ChromatogramFilter::MsLevel(MSLevel::MS2))
+            .unwrap();
+        let total: usize = per_window_group.values().map(|x| x.len()).sum();
+        assert_eq!(total, ms2.len());
+    }
+
+    #[test]
+    fn tdf_xic() {
+        let file_path = get_local_directory().join("test.d");
+        let metadata = MetadataReader::new(&file_path).unwrap();
+        let frames: Vec<Frame> = FrameReader::new(&file_path)
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        let mz = metadata.mz_converter.convert(40.0);
+        let mut im_target = XicTarget::new(mz, 20_000.0);
+        im_target.im_range = Some(
+            metadata.im_converter.convert(3.0)
+                ..metadata.im_converter.convert(1.0),
+        );
+        // All peaks of the MS2 frame at 0.4 s, in two isolation windows
+        let ms2_mz = metadata.mz_converter.convert(107.0);
+        let mut ms2_target = XicTarget::new(ms2_mz, 1_000_000.0);
+        ms2_target.ms_level = MSLevel::MS2;
+        ms2_target.rt_range = Some(0.3..1.0);
+        let mut isolated_target = ms2_target.clone();
+        isolated_target.isolation_mz = Some(502.5);
+        let targets = vec![
+            XicTarget::new(mz, 20_000.0),
+            im_target,
+            ms2_target,
+            isolated_target,
+        ];
+        let xics = XicExtractor::new(&file_path)
+            .unwrap()
+            .extract(&targets)
+            .unwrap();
+        assert_eq!(xics[0].rt, vec![0.1, 0.3]);
+        assert_eq!(xics[1].rt, vec![0.1, 0.3]);
+        assert_eq!(xics[2].rt, vec![0.4]);
+        for (target, xic) in targets.iter().zip(&xics) {
+            let delta = target.mz * target.tolerance_ppm / 1e6;
+            let expected: Vec<f64> = frames
+                .iter()
+                .filter(|frame| {
+                    (frame.ms_level == target.ms_level)
+                        & target.rt_range.as_ref().is_none_or(|range| {
+                            range.contains(&frame.rt_in_seconds)
+                        })
+                })
+                .map(|frame| {
+                    let mut intensity = 0.0;
+                    for scan in 0..frame.scan_offsets.len() - 1 {
+                        let im = metadata.im_converter.convert(scan as f64);
+                        if let Some(range) = &target.im_range {
+                            if !((range.start <= im) & (im <= range.end)) {
+                                continue;
+                            }
+                        }
+                        if let Some(isolation_mz) = target.isolation_mz {
+                            let quad = &frame.quadrupole_settings;
+                            let Some(window) = quad.window_at_scan(scan) else {
+                                continue;
+                            };
+                            let half_width = quad.isolation_width[window] / 2.0;
+                            if (isolation_mz - quad.isolation_mz[window]).abs()
+                                > half_width
+                            {
+                                continue;
+                            }
+                        }
+                        for peak in frame.scan_offsets[scan]
+                            ..frame.scan_offsets[scan + 1]
+                        {
+                            let peak_mz = metadata
+                                .mz_converter
+                                .convert(frame.tof_indices[peak] as f64);
+                            if (peak_mz - target.mz).abs() <= delta {
+                                intensity += frame.intensities[peak] as f64;
+                            }
+                        }
+                    }
+                    intensity
+                })
+                .collect();
+            assert_eq!(xic.intensity, expected);
+        }
+        assert!(xics[0].intensity.iter().sum::<f64>() > 0.0);
+        assert!(xics[3].intensity[0] > 0.0);
+        assert!(xics[3].intensity[0] < xics[2].intensity[0]);
+        assert!(
+            xics[1].intensity.iter().sum::<f64>()
+                < xics[0].intensity.iter().sum::<f64>()
+        );
+    }
+
+    #[test]
+    fn tdf_normalized_xic() {
+        let file_path = get_local_directory().join("test.d");
+        let metadata = MetadataReader::new(&file_path).unwrap();
+        let frame_reader = FrameReader::build()
+            .with_path(&file_path)
+            .with_normalization(NormalizationMode::Tic)
+            .finalize()
+            .unwrap();
+        let frames: Vec<Frame> = frame_reader
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        // All peaks of every MS1 frame
+        let target = XicTarget::new(metadata.mz_converter.convert(107.0), 1e6);
+        let xics = XicExtractor::from_frame_reader(frame_reader, &metadata)
+            .extract(&[target])
+            .unwrap();
+        let expected: Vec<f64> = frames
+            .iter()
+            .filter(|frame| frame.ms_level == MSLevel::MS1)
+            .map(|frame| {
+                (0..frame.intensities.len())
+                    .map(|index| frame.normalized_intensity(index))
+                    .sum()
+            })
+            .collect();
+        assert_eq!(xics[0].intensity, expected);
+        // Normalized to the TIC, all peaks of a frame sum to 1
+        for intensity in &xics[0].intensity {
+            assert!((intensity - 1.0).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(feature = "cli")]
+mod common;
+
+#[cfg(feature = "cli")]
+mod tests {
+    use super::common::temp_path;
+    use std::{
+        path::Path,
+        process::{Command, Output},
+    };
+    use timsrust::readers::FrameReader;
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    fn timsrust(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_timsrust"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    fn stdout(output: &Output) -> String {
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout.clone()).unwrap()
+    }
+
+    fn test_run() -> String {
+        get_local_directory()
+            .join("test.d")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn cli_info() {
+        let output = stdout(&timsrust(&["info", &test_run()]));
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines.contains(&"acquisition\tDDAPASEF"));
+        assert!(lines.contains(&"frames\t4"));
+        assert!(lines.contains(&"ms2_frames\t2"));
+        assert!(lines.contains(&"rt\t0.1\t0.4"));
+        let output = timsrust(&["info", "missing.d"]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("error:"));
+    }
+
+    #[test]
+    fn cli_convert_and_subset() {
+        let parquet = temp_path("timsrust_cli_test.parquet");
+        let output = timsrust(&[
+            "convert",
+            &test_run(),
+            "--to",
+            "parquet",
+            "--ms-level",
+            "1",
+            "-o",
+            parquet.to_str().unwrap(),
+        ]);
+        assert_eq!(stdout(&output).trim(), parquet.to_str().unwrap());
+        assert!(parquet.exists());
+        let output = timsrust(&[
+            "convert",
+            &test_run(),
+            "--to",
+            "mgf",
+            "--ms-level",
+            "1",
+        ]);
+        assert!(!output.status.success());
+        let subset = temp_path("timsrust_cli_subset_test.d");
+        stdout(&timsrust(&[
+            "subset",
+            &test_run(),
+            subset.to_str().unwrap(),
+            "--ms-level",
+            "2",
+        ]));
+        assert_eq!(FrameReader::new(&subset).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn cli_xic() {
+        let targets = temp_path("timsrust_cli_targets.tsv");
+        std::fs::write(
+            &targets,
+            "id\tmz\trt_end\ttolerance_ppm\nwide\t500.0\t0.2\t1000000\n",
+        )
+        .unwrap();
+        let output = stdout(&timsrust(&[
+            "xic",
+            &test_run(),
+            "--targets",
+            targets.to_str().unwrap(),
+        ]));
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines, vec!["id\trt\tintensity", "wide\t0.1\t110"]);
+        for invalid in ["id\tmass\n", "mz\tms_level\n500.0\t3\n"] {
+            std::fs::write(&targets, invalid).unwrap();
+            let output = timsrust(&[
+                "xic",
+                &test_run(),
+                "--targets",
+                targets.to_str().unwrap(),
+            ]);
+            assert!(!output.status.success());
+        }
+    }
+}
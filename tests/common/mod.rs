@@ -0,0 +1,337 @@
+//! Synthetic TDF runs for integration tests.
+//!
+//! Runs are written from scratch with [TdfWriter] and completed with the
+//! DIA and DDA tables that the readers need, so that every reader path can
+//! be tested without vendor data.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{params, Connection};
+use timsrust::{
+    readers::MetadataReader, writers::TdfWriter, Frame, GlobalMetadata, MSLevel,
+};
+
+pub const DIGITIZER_NUM_SAMPLES: u32 = 1000;
+
+/// A quadrupole window of the `DiaFrameMsMsWindows` table.
+#[derive(Clone, Debug)]
+pub struct SyntheticDiaWindow {
+    pub window_group: u8,
+    pub scan_start: usize,
+    pub scan_end: usize,
+    pub isolation_mz: f64,
+    pub isolation_width: f64,
+    pub collision_energy: f64,
+}
+
+/// A row of the `Precursors` table and the `PasefFrameMsMsInfo` row of
+/// the MS2 frame (by 0-based index of the written frames) that
+/// fragmented it.
+#[derive(Clone, Debug)]
+pub struct SyntheticPrecursor {
+    pub mz: f64,
+    pub charge: usize,
+    pub intensity: f64,
+    pub scan_number: f64,
+    pub parent_frame: usize,
+    pub ms2_frame: usize,
+    pub scan_start: usize,
+    pub scan_end: usize,
+    pub isolation_width: f64,
+    pub collision_energy: f64,
+}
+
+/// A path in the temporary directory that is unique to the test process,
+/// so that tests with the same `name` in other test binaries do not race.
+/// A previous directory at this path is removed.
+pub fn temp_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&path);
+    path
+}
+
+/// Copy `analysis.tdf` and `analysis.tdf_bin` of the run at `source` to
+/// a new directory at [temp_path], e.g. to modify them.
+pub fn copy_run(source: &Path, name: &str) -> PathBuf {
+    let run = temp_path(name);
+    std::fs::create_dir_all(&run).unwrap();
+    for file_name in ["analysis.tdf", "analysis.tdf_bin"] {
+        std::fs::copy(source.join(file_name), run.join(file_name)).unwrap();
+    }
+    run
+}
+
+/// Builds a `.d` directory in the temporary directory.
+#[derive(Clone, Debug, Default)]
+pub struct SyntheticRun {
+    name: String,
+    frames: Vec<Frame>,
+    dia_windows: Vec<SyntheticDiaWindow>,
+    precursors: Vec<SyntheticPrecursor>,
+    sql: Vec<String>,
+    global_metadata: Option<GlobalMetadata>,
+}
+
+impl SyntheticRun {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A run of `frames` (e.g. read from `source`) with the global metadata
+    /// of the run at `source` instead of [global_metadata].
+    pub fn from_source(name: &str, source: &Path, frames: &[Frame]) -> Self {
+        Self {
+            name: name.to_string(),
+            frames: frames.to_vec(),
+            global_metadata: Some(
+                MetadataReader::new(source).unwrap().global_metadata,
+            ),
+            ..Default::default()
+        }
+    }
+
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frames.push(frame);
+        self
+    }
+
+    /// Add a frame with `scan_count` scans of [synthetic_frame].
+    pub fn synthetic_frame(self, msms_type: u8, scan_count: usize) -> Self {
+        let rt = 0.1 * (self.frames.len() + 1) as f64;
+        let seed = self.frames.len() as u32;
+        self.frame(synthetic_frame(msms_type, scan_count, rt, seed))
+    }
+
+    /// Add a frame of [synthetic_frame] with `MsMsType` 9 in a window
+    /// group.
+    pub fn synthetic_dia_frame(
+        self,
+        window_group: u8,
+        scan_count: usize,
+    ) -> Self {
+        let rt = 0.1 * (self.frames.len() + 1) as f64;
+        let seed = self.frames.len() as u32;
+        let frame = Frame {
+            window_group,
+            ..synthetic_frame(9, scan_count, rt, seed)
+        };
+        self.frame(frame)
+    }
+
+    /// A window of the window group of MS2 frames with `MsMsType` 9.
+    pub fn dia_window(mut self, window: SyntheticDiaWindow) -> Self {
+        self.dia_windows.push(window);
+        self
+    }
+
+    pub fn precursor(mut self, precursor: SyntheticPrecursor) -> Self {
+        self.precursors.push(precursor);
+        self
+    }
+
+    /// SQL statements to run on `analysis.tdf` after all tables are
+    /// written, e.g. to add tables that the builder does not cover.
+    pub fn sql(mut self, statements: &str) -> Self {
+        self.sql.push(statements.to_string());
+        self
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Write the run to [temp_path], replacing any previous run with the
+    /// same name.
+    pub fn write(&self) -> PathBuf {
+        let path = temp_path(&self.name);
+        let global_metadata =
+            self.global_metadata.clone().unwrap_or_else(global_metadata);
+        let mut writer = TdfWriter::create(&path, &global_metadata).unwrap();
+        for frame in self.frames.iter() {
+            writer.write_frame(frame).unwrap();
+        }
+        let path = writer.finish().unwrap();
+        let connection = Connection::open(path.join("analysis.tdf")).unwrap();
+        if !self.dia_windows.is_empty() {
+            self.write_dia_tables(&connection);
+        }
+        if !self.precursors.is_empty() {
+            self.write_dda_tables(&connection);
+        }
+        for statements in self.sql.iter() {
+            connection.execute_batch(statements).unwrap();
+        }
+        path
+    }
+
+    fn write_dia_tables(&self, connection: &Connection) {
+        connection
+            .execute_batch(
+                "CREATE TABLE DiaFrameMsMsWindowGroups (Id INTEGER PRIMARY KEY);
+                CREATE TABLE DiaFrameMsMsWindows (
+                    WindowGroup INTEGER NOT NULL,
+                    ScanNumBegin INTEGER NOT NULL,
+                    ScanNumEnd INTEGER NOT NULL,
+                    IsolationMz REAL NOT NULL,
+                    IsolationWidth REAL NOT NULL,
+                    CollisionEnergy REAL NOT NULL
+                );
+                CREATE TABLE DiaFrameMsMsInfo (
+                    Frame INTEGER PRIMARY KEY,
+                    WindowGroup INTEGER NOT NULL
+                );",
+            )
+            .unwrap();
+        let mut groups: Vec<u8> =
+            self.dia_windows.iter().map(|x| x.window_group).collect();
+        groups.sort();
+        groups.dedup();
+        for group in groups {
+            connection
+                .execute(
+                    "INSERT INTO DiaFrameMsMsWindowGroups (Id) VALUES (?1)",
+                    params![group],
+                )
+                .unwrap();
+        }
+        for window in self.dia_windows.iter() {
+            connection
+                .execute(
+                    "INSERT INTO DiaFrameMsMsWindows VALUES \
+                     (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        window.window_group,
+                        window.scan_start as i64,
+                        window.scan_end as i64,
+                        window.isolation_mz,
+                        window.isolation_width,
+                        window.collision_energy,
+                    ],
+                )
+                .unwrap();
+        }
+        for (index, frame) in self.frames.iter().enumerate() {
+            if frame.msms_type == 9 {
+                connection
+                    .execute(
+                        "INSERT INTO DiaFrameMsMsInfo VALUES (?1, ?2)",
+                        params![index as i64 + 1, frame.window_group],
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    fn write_dda_tables(&self, connection: &Connection) {
+        connection
+            .execute_batch(
+                "CREATE TABLE Precursors (
+                    Id INTEGER PRIMARY KEY,
+                    LargestPeakMz REAL NOT NULL,
+                    AverageMz REAL NOT NULL,
+                    MonoisotopicMz REAL,
+                    Charge INTEGER,
+                    ScanNumber REAL NOT NULL,
+                    Intensity REAL NOT NULL,
+                    Parent INTEGER
+                );
+                CREATE TABLE PasefFrameMsMsInfo (
+                    Frame INTEGER NOT NULL,
+                    ScanNumBegin INTEGER NOT NULL,
+                    ScanNumEnd INTEGER NOT NULL,
+                    IsolationMz REAL NOT NULL,
+                    IsolationWidth REAL NOT NULL,
+                    CollisionEnergy REAL,
+                    Precursor INTEGER
+                );",
+            )
+            .unwrap();
+        for (index, precursor) in self.precursors.iter().enumerate() {
+            connection
+                .execute(
+                    "INSERT INTO Precursors VALUES \
+                     (?1, ?2, ?2, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        index as i64 + 1,
+                        precursor.mz,
+                        precursor.charge as i64,
+                        precursor.scan_number,
+                        precursor.intensity,
+                        precursor.parent_frame as i64 + 1,
+                    ],
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "INSERT INTO PasefFrameMsMsInfo VALUES \
+                     (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        precursor.ms2_frame as i64 + 1,
+                        precursor.scan_start as i64,
+                        precursor.scan_end as i64,
+                        precursor.mz,
+                        precursor.isolation_width,
+                        precursor.collision_energy,
+                        index as i64 + 1,
+                    ],
+                )
+                .unwrap();
+        }
+    }
+}
+
+/// Global metadata with the keys that the metadata reader requires.
+pub fn global_metadata() -> GlobalMetadata {
+    let raw: HashMap<String, String> = [
+        ("AcquisitionSoftware", "timsTOF"),
+        ("MzAcqRangeLower", "100.0"),
+        ("MzAcqRangeUpper", "1000.0"),
+        ("OneOverK0AcqRangeLower", "0.5"),
+        ("OneOverK0AcqRangeUpper", "1.5"),
+        ("DigitizerNumSamples", "1000"),
+        ("SampleName", "synthetic"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+    GlobalMetadata::from_key_values(raw)
+}
+
+/// A deterministic frame whose scan `i` has `(i + seed) % 4` peaks with
+/// increasing TOF indices. TOF indices stay below [DIGITIZER_NUM_SAMPLES]
+/// for up to 300 scans and small seeds.
+pub fn synthetic_frame(
+    msms_type: u8,
+    scan_count: usize,
+    rt_in_seconds: f64,
+    seed: u32,
+) -> Frame {
+    let mut frame = Frame {
+        scan_offsets: vec![0],
+        rt_in_seconds,
+        msms_type,
+        // PASEF MS2 frames use the same value for both columns
+        scan_mode: msms_type,
+        ms_level: MSLevel::read_from_msms_type(msms_type),
+        intensity_correction_factor: 1.0 / 100.0,
+        ..Default::default()
+    };
+    for scan in 0..scan_count as u32 {
+        for peak in 0..(scan + seed) % 4 {
+            let tof = 10 + 2 * scan + 50 * peak + seed;
+            frame.tof_indices.push(tof);
+            frame.intensities.push(1 + scan + 10 * peak + seed);
+        }
+        frame.scan_offsets.push(frame.tof_indices.len());
+    }
+    frame
+}
@@ -0,0 +1,250 @@
+#[cfg(feature = "convert")]
+mod common;
+
+#[cfg(feature = "convert")]
+mod tests {
+    use std::{fs::File, path::Path};
+
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::Field,
+    };
+    use timsrust::{
+        convert::{
+            to_imzml, to_mgf, to_mzml, to_parquet, ConversionError,
+            ImzMLOptions, MgfOptions, MzMLOptions, ParquetOptions,
+        },
+        readers::{
+            CancellationToken, FrameReader, FrameReaderError, PrecursorReader,
+            SampleSheet, SampleSheetJoin, SpectrumReader,
+        },
+        MaldiInfo,
+    };
+
+    use crate::common::{synthetic_frame, temp_path, SyntheticRun};
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn tdf_to_mzml() {
+        let file_path = get_local_directory().join("test.d");
+        let output = temp_path("timsrust_test.mzML");
+        let options = MzMLOptions {
+            output: Some(output.clone()),
+            ..Default::default()
+        };
+        assert_eq!(to_mzml(&file_path, &options).unwrap(), output);
+        let spectrum_count = SpectrumReader::new(&file_path).unwrap().len();
+        let mzml = std::fs::read_to_string(&output).unwrap();
+        assert!(mzml
+            .contains(&format!(r#"<spectrumList count="{}""#, spectrum_count)));
+        assert_eq!(mzml.matches("<spectrum ").count(), spectrum_count);
+        assert!(mzml.trim_end().ends_with("</mzML>"));
+    }
+
+    #[test]
+    fn tdf_to_mgf() {
+        let file_path = get_local_directory().join("test.d");
+        let output = temp_path("timsrust_test.mgf");
+        let options = MgfOptions {
+            output: Some(output.clone()),
+            ..Default::default()
+        };
+        assert_eq!(to_mgf(&file_path, &options).unwrap(), output);
+        let precursors = PrecursorReader::new(&file_path).unwrap();
+        let mgf = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(mgf.matches("BEGIN IONS").count(), precursors.len());
+        assert_eq!(mgf.matches("END IONS").count(), precursors.len());
+        assert_eq!(mgf.matches("#ONE_OVER_K0=").count(), precursors.len());
+        let charged = (0..precursors.len())
+            .filter(|&index| precursors.get(index).unwrap().charge.is_some())
+            .count();
+        assert_eq!(mgf.matches("#CCS=").count(), charged);
+        assert_eq!(mgf.matches("CHARGE=").count(), charged);
+        assert!(
+            mgf.contains("TITLE=test.1.1.2\nPEPMASS=500.0000 10\nCHARGE=2+")
+        );
+    }
+
+    #[test]
+    fn tdf_to_parquet() {
+        let file_path = get_local_directory().join("test.d");
+        let output = temp_path("timsrust_test.parquet");
+        let options = ParquetOptions {
+            output: Some(output.clone()),
+            row_group_size: 50,
+            ..Default::default()
+        };
+        to_parquet(&file_path, &options).unwrap();
+        let reader =
+            SerializedFileReader::new(File::open(output).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 136);
+        assert!(reader.metadata().num_row_groups() > 1);
+        let schema = reader.metadata().file_metadata().schema_descr();
+        assert!(schema.columns().iter().any(|x| x.name() == "quality"));
+    }
+
+    #[test]
+    fn tdf_to_imzml_requires_maldi() {
+        let file_path = get_local_directory().join("test.d");
+        let options = ImzMLOptions {
+            output: Some(temp_path("timsrust_test.imzML")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            to_imzml(&file_path, &options),
+            Err(ConversionError::NotMaldi)
+        ));
+    }
+
+    #[test]
+    fn tdf_to_parquet_cancelled() {
+        let file_path = get_local_directory().join("test.d");
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let options = ParquetOptions {
+            output: Some(temp_path("timsrust_cancelled_test.parquet")),
+            cancellation: Some(cancellation),
+            ..Default::default()
+        };
+        assert!(matches!(
+            to_parquet(&file_path, &options),
+            Err(ConversionError::FrameReaderError(
+                FrameReaderError::Cancelled
+            ))
+        ));
+    }
+
+    #[test]
+    fn maldi_repeated_pixel_to_imzml() {
+        let mut run = SyntheticRun::new("timsrust_repeated_pixel_test.d");
+        for (seed, x) in [1, 1, 2].into_iter().enumerate() {
+            let mut frame = synthetic_frame(0, 10, seed as f64, seed as u32);
+            frame.maldi_info = Some(MaldiInfo {
+                spot_name: format!("R00X00{}Y001", x),
+                pixel_x: x,
+                pixel_y: 1,
+                ..Default::default()
+            });
+            run = run.frame(frame);
+        }
+        let run = run.write();
+        let output = temp_path("timsrust_repeated_pixel.imzML");
+        let options = ImzMLOptions {
+            output: Some(output.clone()),
+            centroiding: None,
+            ..Default::default()
+        };
+        to_imzml(&run, &options).unwrap();
+        let imzml = std::fs::read_to_string(output).unwrap();
+        assert_eq!(imzml.matches("<spectrum ").count(), 2);
+        assert_eq!(imzml.matches(r#"name="position x" value="1""#).count(), 1);
+    }
+
+    #[test]
+    fn maldi_sample_sheet_to_parquet_and_imzml() {
+        let mut run = SyntheticRun::new("timsrust_sample_sheet_test.d");
+        for (seed, (x, y)) in [(1, 1), (2, 1), (1, 2)].into_iter().enumerate() {
+            let mut frame = synthetic_frame(0, 10, seed as f64, seed as u32);
+            frame.maldi_info = Some(MaldiInfo {
+                spot_name: format!("R0{}X00{}Y00{}", y - 1, x, y),
+                pixel_x: x,
+                pixel_y: y,
+                ..Default::default()
+            });
+            run = run.frame(frame);
+        }
+        let peak_counts: Vec<usize> = run
+            .frames()
+            .iter()
+            .map(|frame| frame.tof_indices.len())
+            .collect();
+        let run = run.write();
+        let sheet = SampleSheet::from_csv_str(
+            "region,group\nR00,control\n",
+            "region",
+            SampleSheetJoin::Region,
+        )
+        .unwrap();
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_sample_sheet(sheet.clone())
+            .finalize()
+            .unwrap();
+        let groups: Vec<Option<String>> = (0..reader.len())
+            .map(|index| {
+                let labels = reader.get(index).unwrap().sample_labels?;
+                labels.get("group").map(|group| group.to_string())
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                Some("control".to_string()),
+                Some("control".to_string()),
+                None
+            ]
+        );
+
+        let output = temp_path("timsrust_sample_sheet.parquet");
+        let options = ParquetOptions {
+            output: Some(output.clone()),
+            sample_sheet: Some(sheet.clone()),
+            ..Default::default()
+        };
+        to_parquet(&run, &options).unwrap();
+        let parquet =
+            SerializedFileReader::new(File::open(output).unwrap()).unwrap();
+        let mut labeled_peaks = 0;
+        for row in parquet.get_row_iter(None).unwrap() {
+            let row = row.unwrap();
+            let columns: Vec<(&String, &Field)> =
+                row.get_column_iter().collect();
+            let value = |name: &str| {
+                columns
+                    .iter()
+                    .find(|(column, _)| column.as_str() == name)
+                    .map(|(_, field)| (*field).clone())
+                    .unwrap()
+            };
+            match value("sample") {
+                Field::Str(key) => {
+                    assert_eq!(key, "R00");
+                    assert_eq!(
+                        value("sample_group"),
+                        Field::Str("control".to_string())
+                    );
+                    labeled_peaks += 1;
+                },
+                field => {
+                    assert_eq!(field, Field::Null);
+                    assert_eq!(value("sample_group"), Field::Null);
+                },
+            }
+        }
+        assert_eq!(labeled_peaks, peak_counts[0] + peak_counts[1]);
+
+        let output = temp_path("timsrust_sample_sheet.imzML");
+        let options = ImzMLOptions {
+            output: Some(output.clone()),
+            sample_sheet: Some(sheet),
+            ..Default::default()
+        };
+        to_imzml(&run, &options).unwrap();
+        let imzml = std::fs::read_to_string(output).unwrap();
+        assert_eq!(imzml.matches("<spectrum ").count(), 3);
+        assert_eq!(imzml.matches(r#"name="ms level" value="1""#).count(), 3);
+        assert!(!imzml.contains("MSn spectrum"));
+        assert_eq!(imzml.matches(r#"name="sample" value="R00""#).count(), 2);
+        assert_eq!(
+            imzml
+                .matches(r#"name="sample group" value="control""#)
+                .count(),
+            2
+        );
+    }
+}
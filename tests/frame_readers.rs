@@ -1,9 +1,23 @@
+#[cfg(feature = "tdf")]
+mod common;
+
 #[cfg(feature = "tdf")]
 mod tests {
-    use std::{path::Path, sync::Arc};
+    use super::common::{copy_run, temp_path};
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
     use timsrust::{
-        readers::FrameReader, AcquisitionType, Frame, MSLevel,
-        QuadrupoleSettings,
+        index::{IndexEntry, RunIndex, RunIndexConfig, RunIndexError},
+        readers::{
+            CancellationToken, DatasetLayout, DatasetLockState, FrameReader,
+            FrameReaderError, FrameVisitor, MetadataReader, ReadProgress,
+            ReaderPolicy, RunInfo, SamplingStrategy, TimsTofPath,
+            TimsTofPathError,
+        },
+        AcquisitionType, CompactFrame, Frame, FrameQuality, MSLevel,
+        NormalizationMode, QuadrupoleSettings,
     };
 
     fn get_local_directory() -> &'static Path {
@@ -26,33 +40,49 @@ mod tests {
             .into_iter()
             .map(|x| x.unwrap())
             .collect();
+        let im_converter =
+            MetadataReader::new(&file_path).unwrap().im_converter;
         let expected: Vec<Frame> = vec![
             Frame {
                 scan_offsets: vec![0, 1, 3, 6, 10],
                 tof_indices: (0..10).collect(),
                 intensities: (0..10).map(|x| (x + 1) * 2).collect(),
+                normalized_intensities: vec![],
                 index: 1,
                 rt_in_seconds: 0.1,
                 ms_level: MSLevel::MS1,
+                msms_type: 0,
+                scan_mode: 8,
                 quadrupole_settings: Arc::new(QuadrupoleSettings::default()),
                 acquisition_type: AcquisitionType::DDAPASEF,
                 intensity_correction_factor: 1.0 / 100.0,
+                im_converter: Some(im_converter),
                 window_group: 0,
+                prm_targets: vec![],
                 maldi_info: None,
+                sample_labels: None,
+                quality: FrameQuality::default(),
             },
             // Frame::default(),
             Frame {
                 scan_offsets: vec![0, 9, 19, 30, 42],
                 tof_indices: (36..78).collect(),
                 intensities: (36..78).map(|x| (x + 1) * 2).collect(),
+                normalized_intensities: vec![],
                 index: 3,
                 rt_in_seconds: 0.3,
                 ms_level: MSLevel::MS1,
+                msms_type: 0,
+                scan_mode: 8,
                 quadrupole_settings: Arc::new(QuadrupoleSettings::default()),
                 acquisition_type: AcquisitionType::DDAPASEF,
                 intensity_correction_factor: 1.0 / 100.0,
+                im_converter: Some(im_converter),
                 window_group: 0,
+                prm_targets: vec![],
                 maldi_info: None,
+                sample_labels: None,
+                quality: FrameQuality::default(),
             },
             // Frame::default(),
         ];
@@ -75,34 +105,64 @@ mod tests {
             .into_iter()
             .map(|x| x.unwrap())
             .collect();
+        let im_converter =
+            MetadataReader::new(&file_path).unwrap().im_converter;
         let expected: Vec<Frame> = vec![
             // Frame::default(),
             Frame {
                 scan_offsets: vec![0, 5, 11, 18, 26],
                 tof_indices: (10..36).collect(),
                 intensities: (10..36).map(|x| (x + 1) * 2).collect(),
+                normalized_intensities: vec![],
                 index: 2,
                 rt_in_seconds: 0.2,
                 ms_level: MSLevel::MS2,
-                quadrupole_settings: Arc::new(QuadrupoleSettings::default()),
+                msms_type: 8,
+                scan_mode: 8,
+                quadrupole_settings: Arc::new(QuadrupoleSettings {
+                    index: 2,
+                    scan_starts: vec![1, 2],
+                    scan_ends: vec![2, 3],
+                    isolation_mz: vec![501.5, 500.5],
+                    isolation_width: vec![2.0, 2.0],
+                    collision_energy: vec![0.0, 0.0],
+                }),
                 acquisition_type: AcquisitionType::DDAPASEF,
                 intensity_correction_factor: 1.0 / 100.0,
+                im_converter: Some(im_converter),
                 window_group: 0,
+                prm_targets: vec![],
                 maldi_info: None,
+                sample_labels: None,
+                quality: FrameQuality::default(),
             },
             // Frame::default(),
             Frame {
                 scan_offsets: vec![0, 13, 27, 42, 58],
                 tof_indices: (78..136).collect(),
                 intensities: (78..136).map(|x| (x + 1) * 2).collect(),
+                normalized_intensities: vec![],
                 index: 4,
                 rt_in_seconds: 0.4,
                 ms_level: MSLevel::MS2,
-                quadrupole_settings: Arc::new(QuadrupoleSettings::default()),
+                msms_type: 8,
+                scan_mode: 8,
+                quadrupole_settings: Arc::new(QuadrupoleSettings {
+                    index: 4,
+                    scan_starts: vec![1, 2],
+                    scan_ends: vec![2, 3],
+                    isolation_mz: vec![501.5, 502.5],
+                    isolation_width: vec![2.0, 2.0],
+                    collision_energy: vec![0.0, 0.0],
+                }),
                 acquisition_type: AcquisitionType::DDAPASEF,
                 intensity_correction_factor: 1.0 / 100.0,
+                im_converter: Some(im_converter),
                 window_group: 0,
+                prm_targets: vec![],
                 maldi_info: None,
+                sample_labels: None,
+                quality: FrameQuality::default(),
             },
         ];
         for i in 0..expected.len() {
@@ -158,4 +218,626 @@ mod tests {
         assert_eq!(&frames[3].tof_indices.len(), &2765100);
         assert_eq!(&frames[3].intensities.len(), &2765100);
     }
+
+    #[test]
+    fn tdf_reader_dia_window_index() {
+        use rayon::iter::ParallelIterator;
+
+        let file_path = get_local_directory().join("dia_test.d");
+        let reader = FrameReader::new(&file_path).unwrap();
+        let index = reader.dia_window_index().unwrap();
+        assert_eq!(index.window_groups(), vec![1, 2]);
+        assert_eq!(index.windows().len(), 4);
+        assert_eq!(index.windows_of_group(2)[1].isolation_mz, 1000.0);
+        assert_eq!(index.frames_for_window(1), &[1, 2]);
+        assert_eq!(index.frames_for_window(2), &[4, 5]);
+        assert_eq!(index.frames_for_window(3), &[] as &[usize]);
+        let window = index.window_for_mz_scan(622.5, 300).unwrap();
+        assert_eq!(window.window_group, 1);
+        assert_eq!(window.isolation_mz, 600.0);
+        assert_eq!(index.window_for_mz_scan(622.5, 200), None);
+        assert_eq!(
+            index.window_for_mz_scan(810.0, 30).unwrap().window_group,
+            2
+        );
+        let mut frame_ids: Vec<usize> = reader
+            .par_iter_window(2)
+            .map(|frame| frame.unwrap().index)
+            .collect();
+        frame_ids.sort();
+        assert_eq!(frame_ids, vec![5, 6]);
+        let reader =
+            FrameReader::new(get_local_directory().join("test.d")).unwrap();
+        assert_eq!(reader.dia_window_index(), None);
+    }
+
+    #[derive(Default)]
+    struct PeakCounter {
+        frame_count: usize,
+        visited_frames: usize,
+        peak_count: usize,
+        summed_intensity: u64,
+    }
+
+    impl FrameVisitor for PeakCounter {
+        type Output = (usize, usize, u64);
+
+        fn begin_run(&mut self, run_info: &RunInfo) {
+            self.frame_count = run_info.frame_count;
+        }
+
+        fn fork(&self) -> Self {
+            Self::default()
+        }
+
+        fn visit_frame(&mut self, frame: &Frame) {
+            self.visited_frames += 1;
+            self.peak_count += frame.intensities.len();
+            self.summed_intensity +=
+                frame.intensities.iter().map(|&x| x as u64).sum::<u64>();
+        }
+
+        fn merge(&mut self, other: Self) {
+            self.visited_frames += other.visited_frames;
+            self.peak_count += other.peak_count;
+            self.summed_intensity += other.summed_intensity;
+        }
+
+        fn end_run(self) -> Self::Output {
+            assert_eq!(self.frame_count, self.visited_frames);
+            (self.visited_frames, self.peak_count, self.summed_intensity)
+        }
+    }
+
+    #[test]
+    fn tdf_reader_visitor() {
+        let file_name = "test.d";
+        let file_path = get_local_directory()
+            .join(file_name)
+            .to_str()
+            .unwrap()
+            .to_string();
+        let reader = FrameReader::new(&file_path).unwrap();
+        let frames: Vec<Frame> =
+            reader.get_all().into_iter().map(|x| x.unwrap()).collect();
+        let (visited_frames, peak_count, summed_intensity) =
+            reader.visit(PeakCounter::default()).unwrap();
+        assert_eq!(visited_frames, frames.len());
+        assert_eq!(
+            peak_count,
+            frames.iter().map(|x| x.intensities.len()).sum::<usize>()
+        );
+        assert_eq!(
+            summed_intensity,
+            frames
+                .iter()
+                .flat_map(|x| x.intensities.iter())
+                .map(|&x| x as u64)
+                .sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn tdf_run_index() {
+        let source = get_local_directory().join("test.d");
+        let run = copy_run(&source, "timsrust_run_index_test.d");
+        let config = RunIndexConfig {
+            tof_bin_width: 16,
+            scan_bin_width: 2,
+        };
+        let index = RunIndex::open_or_build(&run, config).unwrap();
+        assert!(run.with_extension("d.timsrust_idx").exists());
+        assert_eq!(RunIndex::load(&run).unwrap(), index);
+        assert_eq!(
+            index.query_tof_scan((0, 5), (0, 0)),
+            vec![
+                IndexEntry {
+                    frame_index: 0,
+                    scan: 0,
+                    peak_start: 0,
+                    peak_end: 1,
+                },
+                IndexEntry {
+                    frame_index: 1,
+                    scan: 0,
+                    peak_start: 0,
+                    peak_end: 5,
+                },
+            ]
+        );
+        let frames: Vec<u32> = index
+            .query_tof_scan((48, 48), (0, 3))
+            .iter()
+            .map(|entry| entry.frame_index)
+            .collect();
+        assert_eq!(frames, vec![2, 2]);
+    }
+
+    #[test]
+    fn tdf_run_index_corrupt() {
+        let source = get_local_directory().join("test.d");
+        let run = copy_run(&source, "timsrust_corrupt_index_test.d");
+        let config = RunIndexConfig {
+            tof_bin_width: 16,
+            scan_bin_width: 2,
+        };
+        RunIndex::build(&run, config).unwrap().save(&run).unwrap();
+        let invalid_config = RunIndexConfig {
+            tof_bin_width: 0,
+            ..config
+        };
+        assert!(matches!(
+            RunIndex::open_or_build(&run, invalid_config),
+            Err(RunIndexError::InvalidConfig(_))
+        ));
+        let index_path = run.with_extension("d.timsrust_idx");
+        let bytes = std::fs::read(&index_path).unwrap();
+        // The bin widths start at byte 44 of the 68 byte header, which the
+        // bin offsets follow
+        let corruptions: [fn(&mut Vec<u8>); 4] = [
+            |bytes| bytes[44..48].copy_from_slice(&0u32.to_le_bytes()),
+            |bytes| bytes[68..76].copy_from_slice(&1u64.to_le_bytes()),
+            |bytes| bytes[76..84].copy_from_slice(&u64::MAX.to_le_bytes()),
+            |bytes| bytes.truncate(bytes.len() - 1),
+        ];
+        for corrupt in corruptions {
+            let mut corrupted = bytes.clone();
+            corrupt(&mut corrupted);
+            std::fs::write(&index_path, corrupted).unwrap();
+            assert!(matches!(
+                RunIndex::load(&run),
+                Err(RunIndexError::InvalidFormat)
+            ));
+        }
+    }
+
+    #[test]
+    fn tdf_reader_sample() {
+        let file_path = get_local_directory().join("test.d");
+        let reader = FrameReader::new(&file_path).unwrap();
+        let frames = reader.sample(1, SamplingStrategy::ByMsLevel).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].ms_level, MSLevel::MS1);
+        assert_eq!(frames[1].ms_level, MSLevel::MS2);
+        assert_eq!(
+            reader.sample_indices(10, SamplingStrategy::UniformRt),
+            vec![0, 1, 2, 3]
+        );
+        let frames = reader
+            .sample_with_budget(
+                4,
+                SamplingStrategy::UniformRt,
+                std::time::Duration::ZERO,
+            )
+            .unwrap();
+        assert!(frames.is_empty());
+        let frames = reader
+            .sample_with_budget(
+                4,
+                SamplingStrategy::UniformRt,
+                std::time::Duration::from_secs(60),
+            )
+            .unwrap();
+        let indices: Vec<usize> =
+            frames.iter().map(|frame| frame.index).collect();
+        assert_eq!(indices, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn tdf_reader_builder() {
+        let file_path = get_local_directory().join("test.d");
+        let reader = FrameReader::build()
+            .with_path(&file_path)
+            .ms_level_filter(MSLevel::MS2)
+            .finalize()
+            .unwrap();
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.get(1).unwrap().index, 4);
+        let reader = FrameReader::build()
+            .with_path(&file_path)
+            .frame_range(2..4)
+            .with_maldi(false)
+            .finalize()
+            .unwrap();
+        let indices: Vec<usize> = reader
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap().index)
+            .collect();
+        assert_eq!(indices, vec![2, 3]);
+        let file_path = get_local_directory().join("dia_test.d");
+        let reader = FrameReader::build()
+            .with_path(&file_path)
+            .with_dia_windows(false)
+            .finalize()
+            .unwrap();
+        assert!(reader.get_dia_windows().is_none());
+        assert!(reader.get_all().into_iter().all(|x| x.is_ok()));
+    }
+
+    #[test]
+    fn tdf_reader_locked_dataset() {
+        let source = get_local_directory().join("test.d");
+        let run = copy_run(&source, "timsrust_locked_test.d");
+        assert!(!DatasetLockState::from_path(&run).unwrap().is_locked());
+        std::fs::write(run.join("acquisition.lock"), b"").unwrap();
+        let lock_state = DatasetLockState::from_path(&run).unwrap();
+        assert!(lock_state.is_locked());
+        assert_eq!(lock_state.lock_files.len(), 1);
+        let expected: Vec<Frame> = FrameReader::build()
+            .with_path(&source)
+            .with_safe_mode(false)
+            .finalize()
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        let frames: Vec<Frame> = FrameReader::new(&run)
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(frames, expected);
+    }
+
+    #[test]
+    fn tdf_dataset_layouts() {
+        let source = get_local_directory().join("test.d");
+        let run = temp_path("timsrust_layout_test.d");
+        std::fs::create_dir_all(&run).unwrap();
+        std::fs::copy(source.join("analysis.tdf"), run.join("Run.TDF"))
+            .unwrap();
+        std::fs::copy(source.join("analysis.tdf_bin"), run.join("Run.tdf_bin"))
+            .unwrap();
+        std::fs::write(run.join("Calibration.xml"), b"").unwrap();
+        let layout = DatasetLayout::inspect(&run).unwrap();
+        assert_eq!(layout.tdf, Some(run.join("Run.TDF")));
+        assert_eq!(layout.tdf_bin, Some(run.join("Run.tdf_bin")));
+        assert_eq!(layout.tdf_raw, None);
+        assert_eq!(layout.calibration_files, vec![run.join("Calibration.xml")]);
+        assert!(layout.missing_files().is_empty());
+        let expected: Vec<Frame> = FrameReader::new(&source)
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        let read = |path: TimsTofPath| -> Vec<Frame> {
+            FrameReader::new(path)
+                .unwrap()
+                .get_all()
+                .into_iter()
+                .map(|x| x.unwrap())
+                .collect()
+        };
+        let direct = TimsTofPath::new(run.join("Run.TDF")).unwrap();
+        assert_eq!(
+            direct.tdf().unwrap(),
+            run.join("Run.TDF").canonicalize().unwrap()
+        );
+        assert_eq!(read(direct), expected);
+        let explicit = TimsTofPath::from_files(
+            source.join("analysis.tdf"),
+            run.join("Run.tdf_bin"),
+        )
+        .unwrap();
+        assert_eq!(read(explicit), expected);
+        std::fs::copy(source.join("analysis.tdf"), run.join("Other.tdf"))
+            .unwrap();
+        match TimsTofPath::new(run.join("Other.tdf")) {
+            Err(TimsTofPathError::MissingCompanions(layout)) => {
+                assert_eq!(layout.missing_files(), vec!["Other.tdf_bin"]);
+            },
+            result => panic!("Unexpected result {result:?}"),
+        }
+        std::fs::remove_file(run.join("Other.tdf")).unwrap();
+        std::fs::remove_file(run.join("Run.tdf_bin")).unwrap();
+        match TimsTofPath::new(&run) {
+            Err(TimsTofPathError::MissingCompanions(layout)) => {
+                assert_eq!(layout.missing_files(), vec!["Run.tdf_bin"]);
+            },
+            result => panic!("Unexpected result {result:?}"),
+        }
+        assert!(matches!(
+            FrameReader::new(&run),
+            Err(FrameReaderError::TimsTofPathError(_))
+        ));
+    }
+
+    #[test]
+    fn tdf_reader_builder_invalid_path() {
+        let missing = get_local_directory().join("missing.d");
+        let builder = FrameReader::build().with_path(&missing);
+        assert!(matches!(
+            builder.finalize(),
+            Err(FrameReaderError::TimsTofPathError(_))
+        ));
+        assert!(matches!(
+            FrameReader::new(&missing),
+            Err(FrameReaderError::TimsTofPathError(_))
+        ));
+        assert!(matches!(
+            FrameReader::build().finalize(),
+            Err(FrameReaderError::NoPath)
+        ));
+    }
+
+    #[test]
+    fn tdf_reader_skip_corrupt() {
+        let source = get_local_directory().join("test.d");
+        let run = copy_run(&source, "timsrust_corrupt_test.d");
+        let offset = FrameReader::new(&source).unwrap().get_binary_offset(1);
+        let bin_path = run.join("analysis.tdf_bin");
+        let mut bytes = std::fs::read(&bin_path).unwrap();
+        let byte_count =
+            u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+                as usize;
+        bytes[offset + 8..offset + byte_count].fill(0);
+        std::fs::write(&bin_path, bytes).unwrap();
+        let strict = FrameReader::new(&run).unwrap();
+        assert_eq!(strict.policy(), ReaderPolicy::Strict);
+        assert!(strict.get(1).is_err());
+        assert!(strict.get_or_skip(1).is_none());
+        assert!(strict.get_all_recovered().is_err());
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_policy(ReaderPolicy::SkipCorrupt)
+            .finalize()
+            .unwrap();
+        assert!(reader.get_or_skip(0).is_some());
+        let (frames, report) = reader.get_all_recovered().unwrap();
+        let indices: Vec<usize> = frames.iter().map(|x| x.index).collect();
+        assert_eq!(indices, vec![1, 3, 4]);
+        assert_eq!(report.complete, 3);
+        assert_eq!(report.recovered(), 3);
+        assert!(report.partial.is_empty());
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].index, 1);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn tdf_reader_corrupt_payload() {
+        let source = get_local_directory().join("test.d");
+        let run = copy_run(&source, "timsrust_corrupt_payload_test.d");
+        let full = FrameReader::new(&source).unwrap();
+        let offset = full.get_binary_offset(1);
+        let bin_path = run.join("analysis.tdf_bin");
+        let mut bytes = std::fs::read(&bin_path).unwrap();
+        let byte_count =
+            u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+                as usize;
+        let scan_count: Vec<u8> = bytes[offset + 4..offset + 8].to_vec();
+        // A valid zstd stream whose first TOF delta of the third scan is 0
+        let mut values =
+            zstd::decode_all(&bytes[offset + 8..offset + byte_count]).unwrap();
+        let plane = values.len() / 4;
+        let peak = values[0] as usize + 2 * 11;
+        for byte in 0..4 {
+            values[byte * plane + peak] = 0;
+        }
+        let compressed = zstd::encode_all(values.as_slice(), 0).unwrap();
+        let corrupt_offset = bytes.len();
+        bytes.extend(((compressed.len() + 8) as u32).to_le_bytes());
+        bytes.extend(scan_count);
+        bytes.extend(compressed);
+        std::fs::write(&bin_path, bytes).unwrap();
+        rusqlite::Connection::open(run.join("analysis.tdf"))
+            .unwrap()
+            .execute(
+                "UPDATE Frames SET TimsId = ?1 WHERE Id = 2",
+                [corrupt_offset],
+            )
+            .unwrap();
+        let strict = FrameReader::new(&run).unwrap();
+        assert!(matches!(strict.get(1), Err(FrameReaderError::CorruptFrame)));
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_policy(ReaderPolicy::Partial)
+            .finalize()
+            .unwrap();
+        let (frames, report) = reader.get_all_recovered().unwrap();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(report.complete, 3);
+        assert_eq!(report.partial.len(), 1);
+        assert_eq!(report.partial[0].index, 1);
+        let frame = &frames[1];
+        let expected = full.get(1).unwrap();
+        assert_eq!(frame.scan_offsets, vec![0, 5, 11, 11, 11]);
+        assert_eq!(frame.tof_indices, expected.tof_indices[..11]);
+        assert_eq!(frame.intensities, expected.intensities[..11]);
+        assert!(frame.quality.contains(FrameQuality::CORRUPT_BLOB));
+    }
+
+    #[test]
+    fn tdf_reader_truncated_bin() {
+        let source = get_local_directory().join("test.d");
+        let run = copy_run(&source, "timsrust_truncated_test.d");
+        let full = FrameReader::new(&source).unwrap();
+        assert!(full.truncation().is_none());
+        let bin_path = run.join("analysis.tdf_bin");
+        let bytes = std::fs::read(&bin_path).unwrap();
+        let cut = full.get_binary_offset(2) + 10;
+        std::fs::write(&bin_path, &bytes[..cut]).unwrap();
+        let reader = FrameReader::new(&run).unwrap();
+        let truncation = reader.truncation().unwrap();
+        assert_eq!(truncation.frame_count, 4);
+        assert_eq!(truncation.readable_frame_count, 2);
+        assert_eq!(truncation.dropped_frame_count(), 2);
+        assert_eq!(truncation.last_readable_frame, Some(2));
+        assert_eq!(truncation.bin_file_size, cut);
+        assert_eq!(reader.len(), 2);
+        let frames: Vec<Frame> =
+            reader.get_all().into_iter().map(|x| x.unwrap()).collect();
+        assert_eq!(frames, vec![full.get(0).unwrap(), full.get(1).unwrap()]);
+    }
+
+    #[test]
+    fn tdf_reader_quadrupole_crop() {
+        let file_path = get_local_directory().join("dia_test.d");
+        let reader = FrameReader::new(&file_path).unwrap();
+        let cropped = FrameReader::build()
+            .with_path(&file_path)
+            .with_quadrupole_crop(true)
+            .finalize()
+            .unwrap();
+        for index in 0..reader.len() {
+            let frame = reader.get(index).unwrap();
+            let cropped_frame = cropped.get(index).unwrap();
+            if frame.ms_level == MSLevel::MS1 {
+                assert_eq!(cropped_frame, frame);
+                continue;
+            }
+            let quad = &frame.quadrupole_settings;
+            assert_eq!(
+                cropped_frame.scan_offsets.len(),
+                frame.scan_offsets.len()
+            );
+            for scan in 0..frame.scan_offsets.len() - 1 {
+                let isolated = (0..quad.len()).any(|i| {
+                    (quad.scan_starts[i] <= scan) & (scan < quad.scan_ends[i])
+                });
+                let peaks = |frame: &Frame| {
+                    let range =
+                        frame.scan_offsets[scan]..frame.scan_offsets[scan + 1];
+                    (
+                        frame.tof_indices[range.clone()].to_vec(),
+                        frame.intensities[range].to_vec(),
+                    )
+                };
+                if isolated {
+                    assert_eq!(peaks(&cropped_frame), peaks(&frame));
+                } else {
+                    assert!(peaks(&cropped_frame).0.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tdf_reader_normalization() {
+        let file_name = "test.d";
+        let file_path = get_local_directory().join(file_name);
+        let raw_reader = FrameReader::new(&file_path).unwrap();
+        let reader = FrameReader::build()
+            .with_path(&file_path)
+            .with_normalization(NormalizationMode::Tic)
+            .finalize()
+            .unwrap();
+        assert_eq!(reader.normalization(), NormalizationMode::Tic);
+        for index in 0..reader.len() {
+            let raw_frame = raw_reader.get(index).unwrap();
+            let frame = reader.get(index).unwrap();
+            assert!(raw_frame.normalized_intensities.is_empty());
+            assert_eq!(frame.intensities, raw_frame.intensities);
+            assert_eq!(
+                frame.normalized_intensities.len(),
+                frame.intensities.len()
+            );
+            let total: f32 = frame.normalized_intensities.iter().sum();
+            assert!((total - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn tdf_reader_cache() {
+        let file_name = "test.d";
+        let file_path = get_local_directory().join(file_name);
+        let reader = FrameReader::build()
+            .with_path(&file_path)
+            .with_cache(1 << 20)
+            .finalize()
+            .unwrap();
+        let first = reader.get_cached(1).unwrap();
+        let second = reader.get_cached(1).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(*first, reader.get(1).unwrap());
+        let cache = reader.cache().unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.hits_and_misses(), (1, 1));
+        let uncached = FrameReader::new(&file_path).unwrap();
+        assert!(uncached.cache().is_none());
+        assert_eq!(*uncached.get_cached(1).unwrap(), *first);
+    }
+
+    #[test]
+    fn tdf_reader_progress_and_cancellation() {
+        let file_name = "test.d";
+        let file_path = get_local_directory().join(file_name);
+        let progress = Arc::new(Mutex::new(vec![]));
+        let reported = progress.clone();
+        let cancellation = CancellationToken::new();
+        let reader = FrameReader::build()
+            .with_path(&file_path)
+            .with_progress(move |x: ReadProgress| {
+                reported.lock().unwrap().push(x)
+            })
+            .with_cancellation(cancellation.clone())
+            .finalize()
+            .unwrap();
+        assert!(reader.get_all_ms1().iter().all(|x| x.is_ok()));
+        let mut progress = progress.lock().unwrap().clone();
+        progress.sort_by_key(|x| x.completed);
+        let completed: Vec<usize> =
+            progress.iter().map(|x| x.completed).collect();
+        assert_eq!(completed, vec![1, 2]);
+        assert!(progress.iter().all(|x| x.total == 2));
+        assert_eq!(progress[1].fraction(), 1.0);
+        cancellation.cancel();
+        assert!(reader
+            .get_all()
+            .into_iter()
+            .all(|x| matches!(x, Err(FrameReaderError::Cancelled))));
+        assert!(reader.get(0).is_ok());
+    }
+
+    #[test]
+    fn tdf_reader_shared_between_threads() {
+        let source = get_local_directory().join("test.d");
+        let file_path = copy_run(&source, "timsrust_shared_reader_test.d");
+        let reader = Arc::new(FrameReader::new(&file_path).unwrap());
+        // Frames are still read once the SQLite database is gone
+        std::fs::remove_file(file_path.join("analysis.tdf")).unwrap();
+        let expected = FrameReader::new(&source).unwrap().get_all();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = Arc::clone(&reader);
+                std::thread::spawn(move || {
+                    (0..reader.len())
+                        .rev()
+                        .map(|index| reader.get(index).unwrap())
+                        .collect::<Vec<Frame>>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            let mut frames = handle.join().unwrap();
+            frames.reverse();
+            for (frame, expected) in frames.iter().zip(&expected) {
+                assert_eq!(frame, expected.as_ref().unwrap());
+            }
+            assert_eq!(frames.len(), expected.len());
+        }
+        std::fs::remove_dir_all(&file_path).unwrap();
+    }
+
+    #[test]
+    fn tdf_reader_compact_frames() {
+        let file_name = "test.d";
+        let file_path = get_local_directory().join(file_name);
+        let reader = FrameReader::new(&file_path).unwrap();
+        for frame in reader.get_all() {
+            let frame = frame.unwrap();
+            let compact = CompactFrame::from(&frame);
+            let size = 8 * frame.tof_indices.len()
+                + std::mem::size_of_val(frame.scan_offsets.as_slice());
+            assert!(2 * compact.size_in_bytes() < size);
+            assert!(compact
+                .tof_indices()
+                .eq(frame.tof_indices.iter().copied()));
+            assert_eq!(Frame::from(compact), frame);
+        }
+    }
 }
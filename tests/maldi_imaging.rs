@@ -0,0 +1,190 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::SyntheticRun;
+    use std::path::{Path, PathBuf};
+    use timsrust::{
+        converters::ConvertableDomain,
+        maldi::{IonTarget, LaserNormalization, MaldiImaging, MaldiStats, Roi},
+        readers::{FrameReader, MetadataReader},
+        Frame, MaldiInfo, NormalizationMode,
+    };
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    /// Frames 0 (MS1) and 1 (MS2) of test.d at pixel (1, 1) and frames 2
+    /// and 0 (both MS1) at pixel (2, 1).
+    fn write_imaging_run(name: &str) -> (PathBuf, Vec<Frame>) {
+        let source = get_local_directory().join("test.d");
+        let reader = FrameReader::new(&source).unwrap();
+        let mut frames = vec![];
+        for (index, x) in [(0, 1), (1, 1), (2, 2), (0, 2)] {
+            let mut frame = reader.get(index).unwrap();
+            frame.maldi_info = Some(MaldiInfo {
+                spot_name: format!("R00X{x:03}Y001"),
+                pixel_x: x,
+                pixel_y: 1,
+                ..Default::default()
+            });
+            frames.push(frame);
+        }
+        let run = SyntheticRun::from_source(name, &source, &frames).write();
+        (run, frames)
+    }
+
+    #[test]
+    fn maldi_ion_image() {
+        let (run, frames) = write_imaging_run("timsrust_ion_image_test.d");
+        let metadata = MetadataReader::new(&run).unwrap();
+        let imaging = MaldiImaging::new(&run).unwrap();
+        let tof = frames[0].tof_indices[0];
+        let mz = metadata.mz_converter.convert(tof);
+        let image = imaging.ion_image(&IonTarget::new(mz, 1.0)).unwrap();
+        let expected = |frame: &Frame, scans: std::ops::Range<usize>| {
+            scans
+                .flat_map(|scan| {
+                    frame.scan_offsets[scan]..frame.scan_offsets[scan + 1]
+                })
+                .filter(|&peak| frame.tof_indices[peak] == tof)
+                .map(|peak| frame.intensities[peak] as f64)
+                .sum::<f64>()
+        };
+        let scan_count = frames[0].scan_offsets.len() - 1;
+        let all_scans = 0..scan_count;
+        assert_eq!(image.width, 2);
+        assert_eq!(image.get(1, 1), Some(expected(&frames[0], all_scans)));
+        assert_eq!(
+            image.get(2, 1),
+            Some(
+                expected(&frames[2], 0..scan_count)
+                    + expected(&frames[0], 0..scan_count)
+            )
+        );
+        let scan = (0..scan_count)
+            .find(|&scan| {
+                frames[0].scan_offsets[scan] < frames[0].scan_offsets[scan + 1]
+            })
+            .unwrap();
+        let im = metadata.im_converter.convert(scan as f64);
+        let target =
+            IonTarget::new(mz, 1.0).with_im_range(im - 1e-6..im + 1e-6);
+        let image = imaging.ion_image(&target).unwrap();
+        assert_eq!(image.get(1, 1), Some(expected(&frames[0], scan..scan + 1)));
+    }
+
+    #[test]
+    fn maldi_roi_spectrum() {
+        let (run, frames) = write_imaging_run("timsrust_roi_spectrum_test.d");
+        let imaging = MaldiImaging::new(&run).unwrap();
+        let roi =
+            Roi::Polygon(vec![(0.5, 0.0), (3.0, 0.0), (3.0, 2.0), (0.5, 2.0)]);
+        let spectrum = imaging.roi_spectrum(&roi).unwrap();
+        assert_eq!(spectrum.pixel_count, 2);
+        let total: u64 = [0, 2, 3]
+            .iter()
+            .flat_map(|&index| frames[index].intensities.iter())
+            .map(|&x| x as u64)
+            .sum();
+        let average: f64 = spectrum.intensities.iter().sum();
+        assert!((average - total as f64 / 2.0).abs() < 1e-6);
+        assert!(spectrum.mz_values.windows(2).all(|x| x[0] < x[1]));
+        let roi = Roi::Rectangle {
+            x_min: 2,
+            y_min: 0,
+            x_max: 5,
+            y_max: 1,
+        };
+        assert_eq!(imaging.roi_spectrum(&roi).unwrap().pixel_count, 1);
+    }
+
+    #[test]
+    fn maldi_missing_laser_settings() {
+        let (run, frames) = write_imaging_run("timsrust_laser_shots_test.d");
+        let metadata = MetadataReader::new(&run).unwrap();
+        let imaging = MaldiImaging::new(&run)
+            .unwrap()
+            .with_laser_normalization(LaserNormalization::LaserShots);
+        let mz = metadata.mz_converter.convert(frames[0].tof_indices[0]);
+        let image = imaging.ion_image(&IonTarget::new(mz, 1.0)).unwrap();
+        assert!(image.intensities.iter().all(|&x| x == 0.0));
+        let roi = Roi::Rectangle {
+            x_min: 0,
+            y_min: 0,
+            x_max: 5,
+            y_max: 5,
+        };
+        let spectrum = imaging.roi_spectrum(&roi).unwrap();
+        assert_eq!(spectrum.pixel_count, 0);
+        assert!(spectrum.intensities.is_empty());
+    }
+
+    #[test]
+    fn maldi_normalized_roi_spectrum() {
+        let (run, _) = write_imaging_run("timsrust_normalized_roi_test.d");
+        let metadata = MetadataReader::new(&run).unwrap();
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_normalization(NormalizationMode::Tic)
+            .finalize()
+            .unwrap();
+        let imaging = MaldiImaging::from_frame_reader(
+            reader,
+            metadata.mz_converter,
+            metadata.im_converter,
+        )
+        .unwrap();
+        let roi = Roi::Rectangle {
+            x_min: 0,
+            y_min: 0,
+            x_max: 5,
+            y_max: 5,
+        };
+        let spectrum = imaging.roi_spectrum(&roi).unwrap();
+        // Three MS1 frames that each sum to 1, averaged over two pixels
+        let average: f64 = spectrum.intensities.iter().sum();
+        assert!((average - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn maldi_stats() {
+        let (run, frames) = write_imaging_run("timsrust_maldi_stats_test.d");
+        let stats = MaldiStats::new(&run).unwrap();
+        let tic = |frame: &Frame| {
+            frame.intensities.iter().map(|&x| x as f64).sum::<f64>()
+        };
+        let max = |frame: &Frame| *frame.intensities.iter().max().unwrap();
+        assert_eq!(stats.tic.get(1, 1), Some(tic(&frames[0])));
+        assert_eq!(
+            stats.tic.get(2, 1),
+            Some(tic(&frames[2]) + tic(&frames[0]))
+        );
+        assert_eq!(
+            stats.peak_counts.get(2, 1),
+            Some(
+                (frames[2].intensities.len() + frames[0].intensities.len())
+                    as f64
+            )
+        );
+        assert_eq!(
+            stats.max_intensity.get(2, 1),
+            Some(max(&frames[2]).max(max(&frames[0])) as f64)
+        );
+        let summary = &stats.summary;
+        assert_eq!(summary.frame_count, 4);
+        assert_eq!(summary.acquired_pixels, 2);
+        assert!(summary.missing_pixels.is_empty());
+        assert!(summary.laser_power.is_none());
+        let rts: Vec<f64> = frames.iter().map(|x| x.rt_in_seconds).collect();
+        let duration = rts.iter().copied().fold(f64::MIN, f64::max)
+            - rts.iter().copied().fold(f64::MAX, f64::min);
+        assert!((summary.acquisition_duration - duration).abs() < 1e-9);
+        let source = get_local_directory().join("test.d");
+        assert!(MaldiStats::new(&source).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(feature = "tdf")]
+mod tests {
+    use std::path::Path;
+    use timsrust::readers::MetadataReader;
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn tdf_global_metadata() {
+        let file_path = get_local_directory().join("test.d");
+        let metadata = MetadataReader::new(&file_path).unwrap();
+        let global_metadata = metadata.global_metadata;
+        assert_eq!(global_metadata.sample_name.as_deref(), Some("test"));
+        assert_eq!(
+            global_metadata.acquisition_software.as_deref(),
+            Some("timsTOF")
+        );
+        assert_eq!(global_metadata.mz_acq_range_lower, Some(100.0));
+        assert_eq!(global_metadata.one_over_k0_acq_range_upper, Some(1.5));
+        assert_eq!(global_metadata.instrument_serial_number, None);
+        assert_eq!(global_metadata.get("MaxNumPeaksPerScan"), Some("58"));
+        assert_eq!(
+            global_metadata.get_parsed::<u32>("DigitizerNumSamples"),
+            Some(136)
+        );
+        assert_eq!(global_metadata.raw().len(), 9);
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::SyntheticRun;
+    use rayon::iter::ParallelIterator;
+    use timsrust::{
+        readers::{FrameReader, MultiFrameReader, MultiFrameReaderError},
+        MSLevel,
+    };
+
+    #[test]
+    fn multi_frame_reader() {
+        let first = SyntheticRun::new("timsrust_multi_first_test.d")
+            .synthetic_frame(0, 10)
+            .synthetic_frame(8, 10)
+            .synthetic_frame(0, 10)
+            .write();
+        let second = SyntheticRun::new("timsrust_multi_second_test.d")
+            .synthetic_frame(8, 12)
+            .synthetic_frame(0, 12)
+            .write();
+        let reader = MultiFrameReader::new([&first, &second]).unwrap();
+        assert_eq!(reader.run_count(), 2);
+        assert_eq!(reader.len(), 5);
+        assert_eq!(reader.locate(2), Some((0, 2)));
+        assert_eq!(reader.locate(3), Some((1, 0)));
+        assert_eq!(reader.locate(5), None);
+        assert_eq!(reader.global_index(1, 1), Some(4));
+        assert_eq!(reader.global_index(1, 2), None);
+        let frame = reader.get(4).unwrap();
+        assert_eq!((frame.run_id, frame.index), (1, 1));
+        assert_eq!(
+            frame.frame,
+            FrameReader::new(&second).unwrap().get(1).unwrap()
+        );
+        let all: Vec<_> = reader
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .map(|x| (x.run_id, x.index))
+            .collect();
+        assert_eq!(all, vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1)]);
+        let ms2: Vec<usize> = reader
+            .parallel_filter(|frame| frame.ms_level == MSLevel::MS2)
+            .map(|x| x.unwrap().run_id)
+            .collect();
+        assert_eq!(ms2, vec![0, 1]);
+        assert!(matches!(
+            reader.get(5),
+            Err(MultiFrameReaderError::IndexOutOfBounds(5))
+        ));
+        assert_eq!(reader.run_path(1).unwrap().as_ref(), second.as_path());
+    }
+
+    #[test]
+    fn multi_frame_reader_requires_runs() {
+        let paths: Vec<&str> = vec![];
+        assert!(matches!(
+            MultiFrameReader::new(paths),
+            Err(MultiFrameReaderError::NoRuns)
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::SyntheticRun;
+    use std::path::Path;
+    use timsrust::readers::{PropertyValue, QcReader};
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn qc_report_without_diagnostics() {
+        let reader =
+            QcReader::new(get_local_directory().join("test.d")).unwrap();
+        assert!(reader.segments().is_empty());
+        assert!(reader.error_log().is_empty());
+        assert!(reader.properties().is_empty());
+        assert!(reader.pressure_compensation().is_empty());
+        let report = reader.report();
+        assert_eq!(report.frame_count, 4);
+        assert_eq!(report.ms1_frame_count, 2);
+        assert!((report.frame_rate - 10.0).abs() < 1e-9);
+        assert_eq!(report.mean_accumulation_time, 100.0);
+        assert_eq!(report.accumulation_time_drift, 0.0);
+        // MS1 TICs of 110 and 4830
+        assert!((report.tic_cv - 2360.0 / 2470.0).abs() < 1e-9);
+        assert!((report.max_tic_change - 4720.0 / 2470.0).abs() < 1e-9);
+        assert_eq!(report.pressure_range, None);
+    }
+
+    #[test]
+    fn qc_report_with_diagnostics() {
+        let run = SyntheticRun::new("timsrust_qc_test.d")
+            .synthetic_frame(0, 10)
+            .synthetic_frame(0, 10)
+            .synthetic_frame(0, 10)
+            .sql(
+                "ALTER TABLE Frames ADD COLUMN Pressure REAL;
+                ALTER TABLE Frames ADD COLUMN T1 REAL;
+                ALTER TABLE Frames ADD COLUMN T2 REAL;
+                UPDATE Frames SET Pressure = 2.5 + Id / 10.0, T1 = 25.0;
+                UPDATE Frames SET AccumulationTime = 90.0 + 10.0 * Id;
+                CREATE TABLE Segments (
+                    Id INTEGER PRIMARY KEY,
+                    FirstFrame INTEGER,
+                    LastFrame INTEGER,
+                    IsCalibrationSegment BOOLEAN
+                );
+                INSERT INTO Segments VALUES (1, 1, 1, 1), (2, 2, 3, 0);
+                CREATE TABLE ErrorLog (Frame INTEGER, Scan INTEGER, Message TEXT);
+                INSERT INTO ErrorLog VALUES (2, NULL, 'Spray unstable');
+                CREATE TABLE PropertyDefinitions (
+                    Id INTEGER PRIMARY KEY,
+                    PermanentName TEXT
+                );
+                INSERT INTO PropertyDefinitions VALUES
+                    (1, 'TOF_DeviceTempCurrentValue1'), (2, 'Vacuum_Status');
+                CREATE TABLE Properties (Frame INTEGER, Property INTEGER, Value);
+                INSERT INTO Properties VALUES
+                    (1, 1, 25.5), (3, 1, 26), (1, 2, 'ok');",
+            )
+            .write();
+        let reader = QcReader::new(&run).unwrap();
+        assert_eq!(reader.segments().len(), 2);
+        assert!(reader.segments()[0].is_calibration);
+        assert_eq!(reader.segments()[1].last_frame, 3);
+        assert_eq!(reader.error_log()[0].frame, 2);
+        assert_eq!(reader.error_log()[0].scan, None);
+        assert_eq!(reader.error_log()[0].message, "Spray unstable");
+        let temperatures = reader.property("TOF_DeviceTempCurrentValue1");
+        assert_eq!(temperatures.len(), 2);
+        assert_eq!(temperatures[1], (3, &PropertyValue::Integer(26)));
+        assert_eq!(temperatures[0].1.as_f64(), Some(25.5));
+        assert_eq!(
+            reader.property("Vacuum_Status")[0].1,
+            &PropertyValue::Text("ok".to_string())
+        );
+        assert_eq!(reader.pressure_compensation().len(), 3);
+        assert_eq!(reader.pressure_compensation()[0].t1, Some(25.0));
+        assert_eq!(reader.pressure_compensation()[0].t2, None);
+        let report = reader.report();
+        assert_eq!(report.segment_count, 2);
+        assert_eq!(report.error_count, 1);
+        assert_eq!(report.mean_accumulation_time, 110.0);
+        // 20 ms more over the run, relative to the mean
+        assert!((report.accumulation_time_drift - 20.0 / 110.0).abs() < 1e-9);
+        let (lower, upper) = report.pressure_range.unwrap();
+        assert!((lower - 2.6).abs() < 1e-9);
+        assert!((upper - 2.8).abs() < 1e-9);
+    }
+}
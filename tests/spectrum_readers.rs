@@ -1,8 +1,17 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(all(feature = "minitdf", feature = "tdf"))]
+use common::temp_path;
 use std::path::Path;
+#[cfg(all(feature = "minitdf", feature = "tdf"))]
+use timsrust::readers::TimsTofFileType;
 #[cfg(feature = "tdf")]
 use timsrust::readers::{
     FrameWindowSplittingConfiguration, QuadWindowExpansionStrategy,
 };
+#[cfg(feature = "minitdf")]
+use timsrust::readers::{TimsTofPath, TimsTofPathError};
 use timsrust::{
     readers::{SpectrumProcessingParams, SpectrumReader, SpectrumReaderConfig},
     Precursor, Spectrum,
@@ -69,6 +78,78 @@ fn minitdf_reader() {
     }
 }
 
+#[cfg(feature = "minitdf")]
+#[test]
+fn minitdf_reader_legacy_layout() {
+    // Only contains `*.ms2.bin` and `*.MS2Spectra.ms2.parquet`
+    let file_name = "test_legacy.ms2";
+    let file_path = get_local_directory()
+        .join(file_name)
+        .to_str()
+        .unwrap()
+        .to_string();
+    let reader = SpectrumReader::new(file_path).unwrap();
+    assert_eq!(reader.len(), 3);
+    let spectra: Vec<Spectrum> =
+        reader.get_all().into_iter().map(|x| x.unwrap()).collect();
+    assert_eq!(spectra[0].mz_values, vec![190.10706]);
+    assert_eq!(spectra[0].intensities, vec![350.0]);
+    assert_eq!(spectra[1].mz_values, vec![164.889965, 672.929209]);
+    assert_eq!(spectra[1].intensities, vec![222.0, 2758.0]);
+    assert_eq!(spectra[2].precursor.unwrap().mz, 502.0);
+    assert_eq!(spectra[2].precursor.unwrap().charge, Some(2));
+}
+
+#[cfg(all(feature = "minitdf", feature = "tdf"))]
+#[test]
+fn minitdf_missing_legacy_companion() {
+    let run = temp_path("timsrust_minitdf_missing_legacy.d");
+    std::fs::create_dir_all(&run).unwrap();
+    std::fs::copy(
+        get_local_directory()
+            .join("test_legacy.ms2")
+            .join("converter.ms2.bin"),
+        run.join("converter.ms2.bin"),
+    )
+    .unwrap();
+    match TimsTofPath::new(&run) {
+        Err(TimsTofPathError::MissingCompanions(layout)) => {
+            assert_eq!(
+                layout.missing_files(),
+                vec!["converter.MS2Spectra.ms2.parquet"]
+            );
+        },
+        result => panic!("Unexpected result {result:?}"),
+    }
+}
+
+#[cfg(all(feature = "minitdf", feature = "tdf"))]
+#[test]
+fn minitdf_reader_with_analysis_tdf() {
+    // An `analysis.tdf` without `analysis.tdf_bin` next to miniTDF files
+    let run = temp_path("timsrust_minitdf_with_tdf.d");
+    std::fs::create_dir_all(&run).unwrap();
+    std::fs::copy(
+        get_local_directory().join("test.d").join("analysis.tdf"),
+        run.join("analysis.tdf"),
+    )
+    .unwrap();
+    let source = get_local_directory().join("test2.ms2");
+    for file_name in
+        ["converter.ms2spectrum.bin", "converter.ms2spectrum.parquet"]
+    {
+        std::fs::copy(source.join(file_name), run.join(file_name)).unwrap();
+    }
+    let path = TimsTofPath::new(run.join("analysis.tdf")).unwrap();
+    assert_eq!(path.file_type(), TimsTofFileType::MiniTDF);
+    let reader = SpectrumReader::new(&run).unwrap();
+    let expected = SpectrumReader::new(&source).unwrap();
+    assert_eq!(reader.len(), expected.len());
+    for index in 0..reader.len() {
+        assert_eq!(reader.get(index).unwrap(), expected.get(index).unwrap());
+    }
+}
+
 #[cfg(feature = "tdf")]
 #[test]
 fn tdf_reader_dda() {
@@ -0,0 +1,656 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::{
+        synthetic_frame, temp_path, SyntheticDiaWindow, SyntheticPrecursor,
+        SyntheticRun, DIGITIZER_NUM_SAMPLES,
+    };
+    use timsrust::{
+        converters::{ConvertableDomain, Scan2ImConverter},
+        index::{RunIndex, RunIndexConfig},
+        processing::chromatogram::{
+            ChromatogramExtractor, ChromatogramFilter, ChromatogramKind,
+            XicExtractor, XicTarget,
+        },
+        readers::{
+            BlobDecompressor, BlobDecompressorError, DecodedFrame, FrameReader,
+            MetadataReader, MobilityCalibrationReader, SpectrumReader,
+            Type2Decompressor,
+        },
+        writers::RunSubsetter,
+        AcquisitionType, FrameQuality, MSLevel, MaldiInfo, MsMsTypeKind,
+        ScanMode,
+    };
+
+    fn dia_window(
+        window_group: u8,
+        scans: (usize, usize),
+        isolation_mz: f64,
+    ) -> SyntheticDiaWindow {
+        SyntheticDiaWindow {
+            window_group,
+            scan_start: scans.0,
+            scan_end: scans.1,
+            isolation_mz,
+            isolation_width: 25.0,
+            collision_energy: 30.0,
+        }
+    }
+
+    #[test]
+    fn synthetic_metadata() {
+        let run = SyntheticRun::new("timsrust_synthetic_metadata_test.d")
+            .synthetic_frame(0, 20)
+            .write();
+        let metadata = MetadataReader::new(&run).unwrap();
+        assert_eq!(metadata.compression_type, 2);
+        assert_eq!(metadata.lower_mz, 100.0);
+        assert_eq!(metadata.upper_mz, 1000.0);
+        assert_eq!(
+            metadata
+                .global_metadata
+                .get_parsed::<u32>("DigitizerNumSamples"),
+            Some(DIGITIZER_NUM_SAMPLES)
+        );
+    }
+
+    #[test]
+    fn synthetic_run_index_sparse_ids() {
+        let run = SyntheticRun::new("timsrust_synthetic_sparse_index_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql("UPDATE Frames SET Id = Id * 10;")
+            .write();
+        let config = RunIndexConfig {
+            tof_bin_width: 16,
+            scan_bin_width: 2,
+        };
+        let index = RunIndex::build(&run, config).unwrap();
+        let mut frames: Vec<u32> = index
+            .query_tof_scan((0, u32::MAX), (0, u32::MAX))
+            .iter()
+            .map(|entry| entry.frame_index)
+            .collect();
+        frames.dedup();
+        assert_eq!(frames, vec![0, 1]);
+    }
+
+    #[test]
+    fn synthetic_dia_frames() {
+        let synthetic = SyntheticRun::new("timsrust_synthetic_dia_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_dia_frame(1, 20)
+            .synthetic_dia_frame(2, 20)
+            .dia_window(dia_window(1, (2, 8), 500.0))
+            .dia_window(dia_window(1, (10, 16), 600.0))
+            .dia_window(dia_window(2, (2, 16), 700.0));
+        let run = synthetic.write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(reader.get_acquisition(), AcquisitionType::DIAPASEF);
+        assert_eq!(reader.len(), 3);
+        for (index, expected) in synthetic.frames().iter().enumerate() {
+            let frame = reader.get(index).unwrap();
+            assert_eq!(frame.scan_offsets, expected.scan_offsets);
+            assert_eq!(frame.tof_indices, expected.tof_indices);
+            assert_eq!(frame.intensities, expected.intensities);
+        }
+        let frame = reader.get(1).unwrap();
+        assert_eq!(frame.ms_level, MSLevel::MS2);
+        assert_eq!(frame.window_group, 1);
+        assert_eq!(frame.quadrupole_settings.isolation_mz, vec![500.0, 600.0]);
+        assert_eq!(frame.quadrupole_settings.scan_ends, vec![8, 16]);
+        let frame = reader.get(2).unwrap();
+        assert_eq!(frame.quadrupole_settings.isolation_mz, vec![700.0]);
+    }
+
+    #[test]
+    fn synthetic_dia_staircase() {
+        let run = SyntheticRun::new("timsrust_synthetic_staircase_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_dia_frame(1, 20)
+            .dia_window(dia_window(1, (0, 5), 400.0))
+            .dia_window(dia_window(1, (5, 10), 424.0))
+            .dia_window(dia_window(1, (10, 15), 448.0))
+            .dia_window(dia_window(1, (15, 20), 472.0))
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(reader.get_acquisition(), AcquisitionType::DIAPASEF);
+        let spectra = SpectrumReader::new(&run).unwrap();
+        assert_eq!(spectra.len(), 4);
+    }
+
+    #[test]
+    fn synthetic_dda_spectra() {
+        let run = SyntheticRun::new("timsrust_synthetic_dda_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(8, 20)
+            .precursor(SyntheticPrecursor {
+                mz: 650.0,
+                charge: 2,
+                intensity: 1000.0,
+                scan_number: 6.0,
+                parent_frame: 0,
+                ms2_frame: 1,
+                scan_start: 4,
+                scan_end: 9,
+                isolation_width: 2.0,
+                collision_energy: 25.0,
+            })
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(reader.get_acquisition(), AcquisitionType::DDAPASEF);
+        let quad = reader.get(1).unwrap().quadrupole_settings;
+        assert_eq!(quad.index, 2);
+        assert_eq!(quad.scan_starts, vec![4]);
+        assert_eq!(quad.scan_ends, vec![9]);
+        assert_eq!(quad.isolation_mz, vec![650.0]);
+        assert_eq!(quad.isolation_width, vec![2.0]);
+        assert_eq!(quad.collision_energy, vec![25.0]);
+        assert_eq!(reader.get(0).unwrap().quadrupole_settings.len(), 0);
+        let spectra = SpectrumReader::new(&run).unwrap();
+        assert_eq!(spectra.len(), 1);
+        let spectrum = spectra.get(0).unwrap();
+        assert!(!spectrum.mz_values.is_empty());
+        let precursor = spectrum.precursor.unwrap();
+        assert_eq!(precursor.mz, 650.0);
+        assert_eq!(precursor.charge, Some(2));
+        assert_eq!(precursor.intensity, Some(1000.0));
+        assert_eq!(spectrum.isolation_mz, 650.0);
+        assert_eq!(spectrum.isolation_width, 2.0);
+    }
+
+    #[test]
+    fn synthetic_maldi_frames() {
+        let mut synthetic =
+            SyntheticRun::new("timsrust_synthetic_maldi_test.d");
+        for (index, (x, y)) in [(0, 0), (1, 0), (0, 1)].into_iter().enumerate()
+        {
+            let mut frame =
+                synthetic_frame(0, 10, 0.1 * (index + 1) as f64, index as u32);
+            frame.maldi_info = Some(MaldiInfo {
+                spot_name: format!("R00X{x:03}Y{y:03}"),
+                pixel_x: x,
+                pixel_y: y,
+                laser_shots: Some(100),
+                ..Default::default()
+            });
+            synthetic = synthetic.frame(frame);
+        }
+        let run = synthetic.write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert!(reader.is_maldi());
+        for (index, expected) in synthetic.frames().iter().enumerate() {
+            let frame = reader.get(index).unwrap();
+            assert_eq!(frame.maldi_info, expected.maldi_info);
+        }
+    }
+
+    #[test]
+    fn synthetic_dda_without_scan_mode() {
+        let run = SyntheticRun::new("timsrust_synthetic_no_scan_mode_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(8, 20)
+            .precursor(SyntheticPrecursor {
+                mz: 650.0,
+                charge: 2,
+                intensity: 1000.0,
+                scan_number: 6.0,
+                parent_frame: 0,
+                ms2_frame: 1,
+                scan_start: 4,
+                scan_end: 9,
+                isolation_width: 2.0,
+                collision_energy: 25.0,
+            })
+            .sql("ALTER TABLE Frames DROP COLUMN ScanMode;")
+            .write();
+        let spectra = SpectrumReader::new(&run).unwrap();
+        assert_eq!(spectra.len(), 1);
+        assert_eq!(spectra.get(0).unwrap().precursor.unwrap().mz, 650.0);
+    }
+
+    #[test]
+    fn synthetic_chromatograms_without_summed_intensities() {
+        let run = SyntheticRun::new("timsrust_synthetic_no_tic_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql("ALTER TABLE Frames DROP COLUMN SummedIntensities;")
+            .write();
+        let extractor = ChromatogramExtractor::new(&run).unwrap();
+        assert!(!extractor.uses_frame_table(ChromatogramKind::Tic));
+        assert!(extractor.uses_frame_table(ChromatogramKind::Bpc));
+        let reader = FrameReader::new(&run).unwrap();
+        let expected: Vec<f64> = (0..reader.len())
+            .map(|index| {
+                let frame = reader.get(index).unwrap();
+                frame.intensities.iter().map(|&x| x as f64).sum()
+            })
+            .collect();
+        let tic = extractor.tic(ChromatogramFilter::All).unwrap();
+        assert_eq!(tic.intensity, expected);
+    }
+
+    #[test]
+    fn synthetic_old_schema() {
+        let mut frame = synthetic_frame(0, 10, 0.1, 0);
+        frame.maldi_info = Some(MaldiInfo {
+            spot_name: "R00X000Y000".to_string(),
+            laser_shots: Some(100),
+            ..Default::default()
+        });
+        let run = SyntheticRun::new("timsrust_synthetic_old_schema_test.d")
+            .frame(frame)
+            .sql(
+                "ALTER TABLE Frames DROP COLUMN AccumulationTime;
+                ALTER TABLE MaldiFrameInfo
+                    RENAME COLUMN NumLaserShots TO LaserShots;",
+            )
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        let frame = reader.get(0).unwrap();
+        assert_eq!(frame.intensity_correction_factor, 1.0);
+        assert_eq!(frame.maldi_info.unwrap().laser_shots, Some(100));
+        let schema = MetadataReader::new(&run).unwrap().schema;
+        assert!(schema.has_column("MaldiFrameInfo", "LaserShots"));
+        assert!(!schema.has_column("Frames", "AccumulationTime"));
+        let warnings: Vec<String> =
+            schema.warnings.iter().map(|x| x.to_string()).collect();
+        assert_eq!(
+            warnings,
+            ["Column Frames.AccumulationTime is missing, so its values are defaulted"]
+        );
+    }
+
+    /// Compression type 2 with intensities stored twice as high.
+    #[derive(Debug)]
+    struct HalvingDecompressor;
+
+    impl BlobDecompressor for HalvingDecompressor {
+        fn decompress(
+            &self,
+            data: &[u8],
+            scan_count: usize,
+        ) -> Result<DecodedFrame, BlobDecompressorError> {
+            let mut decoded = Type2Decompressor.decompress(data, scan_count)?;
+            for intensity in decoded.intensities.iter_mut() {
+                *intensity /= 2;
+            }
+            Ok(decoded)
+        }
+    }
+
+    #[test]
+    fn synthetic_custom_compression() {
+        let synthetic = SyntheticRun::new("timsrust_synthetic_custom_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql(
+                "UPDATE GlobalMetadata SET Value = '7' \
+                 WHERE Key = 'TimsCompressionType'",
+            );
+        let run = synthetic.write();
+        assert!(FrameReader::new(&run).is_err());
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_decompressor(7, Type2Decompressor)
+            .finalize()
+            .unwrap();
+        assert_eq!(reader.compression_type(), 7);
+        for (index, expected) in synthetic.frames().iter().enumerate() {
+            let frame = reader.get(index).unwrap();
+            assert_eq!(frame.scan_offsets, expected.scan_offsets);
+            assert_eq!(frame.tof_indices, expected.tof_indices);
+            assert_eq!(frame.intensities, expected.intensities);
+        }
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_decompressor(7, HalvingDecompressor)
+            .finalize()
+            .unwrap();
+        let frame = reader.get(1).unwrap();
+        let expected: Vec<u32> = synthetic.frames()[1]
+            .intensities
+            .iter()
+            .map(|x| x / 2)
+            .collect();
+        assert_eq!(frame.intensities, expected);
+        // Truncation is detected from the framing of the blobs
+        let bin = run.join("analysis.tdf_bin");
+        let size = std::fs::metadata(&bin).unwrap().len();
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&bin)
+            .unwrap()
+            .set_len(size - 1)
+            .unwrap();
+        let reader = FrameReader::build()
+            .with_path(&run)
+            .with_decompressor(7, HalvingDecompressor)
+            .finalize()
+            .unwrap();
+        assert_eq!(reader.truncation().unwrap().readable_frame_count, 1);
+        assert_eq!(reader.len(), 1);
+    }
+
+    #[test]
+    fn synthetic_diagonal_frames() {
+        let run = SyntheticRun::new("timsrust_synthetic_diagonal_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_dia_frame(1, 20)
+            .dia_window(dia_window(1, (0, 5), 900.0))
+            .dia_window(dia_window(1, (5, 10), 890.0))
+            .dia_window(dia_window(1, (10, 15), 880.0))
+            .dia_window(dia_window(1, (15, 20), 870.0))
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(reader.get_acquisition(), AcquisitionType::DiagonalDIAPASEF);
+        let frame = reader.get(1).unwrap();
+        assert_eq!(frame.window_group, 1);
+        let quad = &frame.quadrupole_settings;
+        assert_eq!(quad.window_at_scan(12), Some(2));
+        assert_eq!(quad.isolation_mz[quad.window_at_scan(3).unwrap()], 900.0);
+        assert_eq!(quad.window_at_scan(20), None);
+        assert!(reader.dia_window_index().is_some());
+        assert_eq!(SpectrumReader::new(&run).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn synthetic_prm_frames() {
+        let run = SyntheticRun::new("timsrust_synthetic_prm_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(10, 20)
+            .synthetic_frame(10, 20)
+            .sql(
+                "CREATE TABLE PrmTargets (
+                    Id INTEGER PRIMARY KEY,
+                    ExternalId TEXT,
+                    Time REAL,
+                    OneOverK0 REAL,
+                    MonoisotopicMz REAL,
+                    Charge INTEGER,
+                    Description TEXT
+                );
+                INSERT INTO PrmTargets VALUES
+                    (1, 'PEPTIDE', 12.0, 0.9, 500.25, 2, 'first'),
+                    (2, 'PEPTIDER', 13.0, 1.1, 650.5, NULL, 'second');
+                CREATE TABLE PrmFrameMsMsInfo (
+                    Frame INTEGER,
+                    ScanNumBegin INTEGER,
+                    ScanNumEnd INTEGER,
+                    IsolationMz REAL,
+                    IsolationWidth REAL,
+                    CollisionEnergy REAL,
+                    Target INTEGER
+                );
+                INSERT INTO PrmFrameMsMsInfo VALUES
+                    (2, 10, 18, 650.5, 2.0, 35.0, 2),
+                    (2, 2, 8, 500.25, 2.0, 30.0, 1),
+                    (2, 19, 20, 700.0, 2.0, 35.0, 3),
+                    (3, 2, 8, 500.25, 2.0, 30.0, 1);",
+            )
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(reader.get_acquisition(), AcquisitionType::PRMPASEF);
+        let targets = reader.prm_targets();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].external_id, "PEPTIDE");
+        assert_eq!(targets[0].charge, Some(2));
+        assert_eq!(targets[1].charge, None);
+        assert!(reader.get(0).unwrap().prm_targets.is_empty());
+        let frame = reader.get(1).unwrap();
+        assert_eq!(frame.ms_level, MSLevel::MS2);
+        assert_eq!(
+            frame.quadrupole_settings.isolation_mz,
+            vec![500.25, 650.5, 700.0]
+        );
+        assert_eq!(frame.quadrupole_settings.scan_starts, vec![2, 10, 19]);
+        assert_eq!(frame.prm_targets.len(), 3);
+        assert!(frame.prm_targets[2].is_none());
+        let ids: Vec<usize> =
+            frame.prm_targets.iter().flatten().map(|x| x.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        let frame = reader.get(2).unwrap();
+        assert_eq!(frame.quadrupole_settings.collision_energy, vec![30.0]);
+        assert_eq!(frame.prm_targets.len(), 1);
+    }
+
+    #[test]
+    fn synthetic_mrm_frames() {
+        let run = SyntheticRun::new("timsrust_synthetic_mrm_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(2, 20)
+            .sql("UPDATE Frames SET ScanMode = 2;")
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(reader.get_acquisition(), AcquisitionType::MRM);
+        let frame = reader.get(1).unwrap();
+        assert_eq!(frame.ms_level, MSLevel::MS2);
+        assert_eq!(frame.scan_mode_kind(), ScanMode::Mrm);
+        assert_eq!(frame.scan_mode_kind().raw(), frame.scan_mode);
+        assert_eq!(frame.msms_type_kind(), MsMsTypeKind::Mrm);
+        let frame = reader.get(0).unwrap();
+        assert_eq!(frame.msms_type_kind(), MsMsTypeKind::Ms1);
+    }
+
+    #[test]
+    fn synthetic_calibration_change() {
+        let run = SyntheticRun::new("timsrust_synthetic_calibration_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql(
+                "ALTER TABLE Frames ADD COLUMN MzCalibration INTEGER;
+                ALTER TABLE Frames ADD COLUMN TimsCalibration INTEGER;
+                UPDATE Frames SET MzCalibration = 1, TimsCalibration = 1;
+                UPDATE Frames SET TimsCalibration = 2 WHERE Id = 3;",
+            )
+            .write();
+        let reader = FrameReader::new(&run).unwrap();
+        let changes: Vec<bool> = (0..reader.len())
+            .map(|index| {
+                let quality = reader.get(index).unwrap().quality;
+                quality.contains(FrameQuality::CALIBRATION_CHANGE)
+            })
+            .collect();
+        assert_eq!(changes, vec![false, false, true]);
+    }
+
+    #[test]
+    fn synthetic_mobility_calibration() {
+        let run = SyntheticRun::new("timsrust_synthetic_mobility_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql(
+                "ALTER TABLE Frames ADD COLUMN TimsCalibration INTEGER;
+                ALTER TABLE Frames ADD COLUMN Pressure REAL;
+                UPDATE Frames SET TimsCalibration = 1, Pressure = 2.5;
+                UPDATE Frames SET Pressure = 2.45 WHERE Id = 3;
+                CREATE TABLE Segments (
+                    Id INTEGER PRIMARY KEY,
+                    FirstFrame INTEGER,
+                    LastFrame INTEGER,
+                    IsCalibrationSegment INTEGER
+                );
+                INSERT INTO Segments VALUES (1, 1, 1, 1), (2, 2, 3, 0);",
+            )
+            .write();
+        let im_converter = MetadataReader::new(&run).unwrap().im_converter;
+        let reader = FrameReader::new(&run).unwrap();
+        let calibration = reader.mobility_calibration();
+        assert_eq!(calibration, &MobilityCalibrationReader::new(&run).unwrap());
+        let reference = calibration.segments()[&1].reference.unwrap();
+        assert_eq!(reference.pressure, 2.5);
+        assert_eq!(reader.get(1).unwrap().im_converter, Some(im_converter));
+        let frame = reader.get(2).unwrap();
+        let mobilities = frame.mobilities().unwrap();
+        assert_eq!(mobilities.len(), frame.tof_indices.len());
+        let expected = im_converter.convert(0.0) * 2.5 / 2.45;
+        assert!((frame.scan_mobility(0).unwrap() - expected).abs() < 1e-12);
+        assert!((mobilities[0] - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn synthetic_tims_calibration_models() {
+        let run = SyntheticRun::new("timsrust_synthetic_tims_model_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql(
+                "ALTER TABLE Frames ADD COLUMN TimsCalibration INTEGER;
+                UPDATE Frames SET TimsCalibration = Id;
+                CREATE TABLE TimsCalibration (
+                    Id INTEGER PRIMARY KEY,
+                    ModelType INTEGER,
+                    C0 REAL,
+                    C1 REAL,
+                    C2 REAL
+                );
+                INSERT INTO TimsCalibration VALUES
+                    (1, 1, 1.5, -0.01, 0.0), (2, 2, 1.0, 917.0, 213.0);",
+            )
+            .write();
+        let im_converter = MetadataReader::new(&run).unwrap().im_converter;
+        let reader = FrameReader::new(&run).unwrap();
+        assert_eq!(
+            reader.get(0).unwrap().im_converter,
+            Some(Scan2ImConverter::from_linear(1.5, -0.01))
+        );
+        // Only the linear model is interpreted
+        assert_eq!(reader.get(1).unwrap().im_converter, Some(im_converter));
+    }
+
+    #[test]
+    fn synthetic_calibrated_xic() {
+        let run = SyntheticRun::new("timsrust_synthetic_calibrated_xic_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql(
+                "ALTER TABLE Frames ADD COLUMN TimsCalibration INTEGER;
+                UPDATE Frames SET TimsCalibration = 1 WHERE Id = 1;
+                CREATE TABLE TimsCalibration (
+                    Id INTEGER PRIMARY KEY,
+                    ModelType INTEGER,
+                    C0 REAL,
+                    C1 REAL
+                );
+                INSERT INTO TimsCalibration VALUES (1, 1, 1.5, -0.01);",
+            )
+            .write();
+        let im_range = 1.4435..1.4565;
+        let target = XicTarget {
+            im_range: Some(im_range.clone()),
+            ..XicTarget::new(500.0, 1_000_000.0)
+        };
+        let xic = XicExtractor::new(&run).unwrap().extract(&[target]).unwrap();
+        let reader = FrameReader::new(&run).unwrap();
+        let expected: Vec<f64> = (0..reader.len())
+            .map(|index| {
+                let frame = reader.get(index).unwrap();
+                let im_converter = frame.im_converter.unwrap();
+                frame
+                    .scans()
+                    .filter(|scan| {
+                        im_range.contains(
+                            &im_converter.convert(scan.scan_index as f64),
+                        )
+                    })
+                    .flat_map(|scan| scan.intensities.iter())
+                    .map(|&intensity| intensity as f64)
+                    .sum()
+            })
+            .collect();
+        // Only scan 5 of the first frame is within the range
+        assert!(expected[0] > 0.0);
+        assert_eq!(xic[0].intensity, expected);
+    }
+
+    #[test]
+    fn synthetic_calibrated_run_index() {
+        let run =
+            SyntheticRun::new("timsrust_synthetic_calibrated_index_test.d")
+                .synthetic_frame(0, 20)
+                .synthetic_frame(0, 20)
+                .sql(
+                    "ALTER TABLE Frames ADD COLUMN TimsCalibration INTEGER;
+                UPDATE Frames SET TimsCalibration = 1 WHERE Id = 1;
+                CREATE TABLE TimsCalibration (
+                    Id INTEGER PRIMARY KEY,
+                    ModelType INTEGER,
+                    C0 REAL,
+                    C1 REAL
+                );
+                INSERT INTO TimsCalibration VALUES (1, 1, 1.5, -0.01);",
+                )
+                .write();
+        let config = RunIndexConfig {
+            tof_bin_width: 1,
+            scan_bin_width: 2,
+        };
+        let index = RunIndex::build(&run, config).unwrap();
+        let mz_converter = MetadataReader::new(&run).unwrap().mz_converter;
+        let mz_range = (mz_converter.convert(15.5), mz_converter.convert(16.5));
+        let im_range = (1.466, 1.474);
+        let reader = FrameReader::new(&run).unwrap();
+        let expected: Vec<usize> = (0..reader.len())
+            .filter(|&index| {
+                let frame = reader.get(index).unwrap();
+                let im_converter = frame.im_converter.unwrap();
+                let has_peaks = frame.scans().any(|scan| {
+                    let im = im_converter.convert(scan.scan_index as f64);
+                    (im >= im_range.0)
+                        & (im <= im_range.1)
+                        & scan.tof_indices.iter().any(|&tof_index| {
+                            let mz = mz_converter.convert(tof_index);
+                            (mz >= mz_range.0) & (mz <= mz_range.1)
+                        })
+                });
+                has_peaks
+            })
+            .collect();
+        // Only TOF index 16 in scan 3 of the first frame is within the
+        // ranges, while the acquisition range converter gives scans 0 to 1
+        assert_eq!(expected, vec![0]);
+        assert_eq!(index.frames_for(mz_range, im_range), expected);
+        index.save(&run).unwrap();
+        assert_eq!(RunIndex::load(&run).unwrap(), index);
+    }
+
+    #[test]
+    fn synthetic_subset_segments() {
+        let run = SyntheticRun::new("timsrust_synthetic_segments_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .synthetic_frame(0, 20)
+            .sql(
+                "CREATE TABLE Segments (
+                    Id INTEGER PRIMARY KEY,
+                    FirstFrame INTEGER,
+                    LastFrame INTEGER,
+                    IsCalibrationSegment INTEGER
+                );
+                INSERT INTO Segments VALUES
+                    (1, 1, 1, 1), (2, 2, 3, 0), (3, 4, 4, 0);",
+            )
+            .write();
+        let subset = temp_path("timsrust_synthetic_segments_subset.d");
+        RunSubsetter::new(&run)
+            .unwrap()
+            .rt_range(0.25..0.45)
+            .write(&subset)
+            .unwrap();
+        let connection =
+            rusqlite::Connection::open(subset.join("analysis.tdf")).unwrap();
+        let mut statement = connection
+            .prepare("SELECT Id, FirstFrame, LastFrame FROM Segments")
+            .unwrap();
+        let segments: Vec<(i64, i64, i64)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(segments, vec![(2, 1, 1), (3, 2, 2)]);
+    }
+}
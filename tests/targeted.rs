@@ -0,0 +1,76 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::{SyntheticDiaWindow, SyntheticRun};
+    use timsrust::{
+        converters::ConvertableDomain,
+        processing::targeted::{
+            TargetedConfig, TargetedExtractor, TargetedPrecursor,
+        },
+        readers::MetadataReader,
+    };
+
+    fn dia_window(
+        window_group: u8,
+        scans: (usize, usize),
+        isolation_mz: f64,
+    ) -> SyntheticDiaWindow {
+        SyntheticDiaWindow {
+            window_group,
+            scan_start: scans.0,
+            scan_end: scans.1,
+            isolation_mz,
+            isolation_width: 25.0,
+            collision_energy: 30.0,
+        }
+    }
+
+    #[test]
+    fn synthetic_targeted_extraction() {
+        let run = SyntheticRun::new("timsrust_synthetic_targeted_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_dia_frame(1, 20)
+            .synthetic_dia_frame(2, 20)
+            .dia_window(dia_window(1, (2, 8), 500.0))
+            .dia_window(dia_window(1, (10, 16), 600.0))
+            .dia_window(dia_window(2, (2, 16), 700.0))
+            .write();
+        let metadata = MetadataReader::new(&run).unwrap();
+        let extractor =
+            TargetedExtractor::new(&run, TargetedConfig::default()).unwrap();
+        assert_eq!(extractor.window_index().windows().len(), 3);
+        // Frame 1 has a single peak in scan 12 (TOF 35, intensity 14) and
+        // three peaks in scan 2 (TOF 15 first)
+        let target = |precursor_mz: f64| TargetedPrecursor {
+            id: format!("{precursor_mz}"),
+            precursor_mz,
+            fragment_mz: vec![
+                metadata.mz_converter.convert(35.0),
+                metadata.mz_converter.convert(15.0),
+            ],
+            rt_range: 0.0..1.0,
+            im_range: 0.0..2.0,
+        };
+        let targets = vec![target(605.0), target(300.0), target(690.0)];
+        let groups = extractor.extract(&targets).unwrap();
+        assert_eq!(groups.len(), 2);
+        let group = &groups[0];
+        assert_eq!(group.target, 0);
+        assert_eq!(group.window.window_group, 1);
+        assert_eq!(group.window.scan_start, 10);
+        assert_eq!(group.im_range.end, metadata.im_converter.convert(10.0));
+        let precursor = group.precursor.as_ref().unwrap();
+        assert_eq!(precursor.rt, vec![0.1]);
+        assert_eq!(group.fragments.len(), 2);
+        assert_eq!(group.fragments[0].rt, vec![0.2]);
+        assert_eq!(group.fragments[0].intensity, vec![14.0]);
+        // Outside of the scans of the window
+        assert_eq!(group.fragments[1].intensity, vec![0.0]);
+        let group = &groups[1];
+        assert_eq!(group.target, 2);
+        assert_eq!(group.window.window_group, 2);
+        assert_eq!(group.fragments[0].len(), 1);
+    }
+}
@@ -0,0 +1,193 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::{temp_path, SyntheticRun};
+    use std::path::Path;
+    use timsrust::{
+        readers::{FrameReader, MetadataReader, SpectrumReader},
+        writers::{RunSubsetter, TdfWriter, TdfWriterError},
+        Frame, FrameQuality, MSLevel, MaldiInfo,
+    };
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn tdf_writer_round_trip() {
+        let source = get_local_directory().join("test.d");
+        let frames: Vec<Frame> = FrameReader::new(&source)
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        let run = SyntheticRun::from_source(
+            "timsrust_written_test.d",
+            &source,
+            &frames,
+        )
+        .write();
+        let written: Vec<Frame> = FrameReader::new(&run)
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        // PASEF isolation windows are not written
+        let frames: Vec<Frame> = frames
+            .into_iter()
+            .map(|frame| Frame {
+                quadrupole_settings: Default::default(),
+                ..frame
+            })
+            .collect();
+        assert_eq!(written, frames);
+        let metadata = MetadataReader::new(&run).unwrap();
+        assert_eq!(metadata.compression_type, 2);
+        assert_eq!(
+            metadata.global_metadata.raw(),
+            MetadataReader::new(&source).unwrap().global_metadata.raw()
+        );
+    }
+
+    #[test]
+    fn tdf_writer_invalid_frames() {
+        let source = get_local_directory().join("test.d");
+        let global_metadata =
+            MetadataReader::new(&source).unwrap().global_metadata;
+        let run = temp_path("timsrust_invalid_frames_test.d");
+        let mut writer = TdfWriter::create(&run, &global_metadata).unwrap();
+        let offset_frame = Frame {
+            scan_offsets: vec![1, 2],
+            tof_indices: vec![10],
+            intensities: vec![100],
+            index: 1,
+            ..Default::default()
+        };
+        let overflow_frame = Frame {
+            scan_offsets: vec![0, 1],
+            tof_indices: vec![u32::MAX],
+            intensities: vec![100],
+            index: 2,
+            ..Default::default()
+        };
+        for frame in [offset_frame, overflow_frame] {
+            assert!(matches!(
+                writer.write_frame(&frame),
+                Err(TdfWriterError::InvalidFrame(index)) if index == frame.index
+            ));
+        }
+    }
+
+    #[test]
+    fn tdf_writer_subset_with_maldi() {
+        let source = get_local_directory().join("test.d");
+        let reader = FrameReader::new(&source).unwrap();
+        let mut frames = vec![reader.get(2).unwrap(), reader.get(0).unwrap()];
+        frames[0].maldi_info = Some(MaldiInfo {
+            spot_name: "R00X001Y002".to_string(),
+            pixel_x: 1,
+            pixel_y: 2,
+            laser_shots: Some(200),
+            ..Default::default()
+        });
+        let run = SyntheticRun::from_source(
+            "timsrust_written_maldi_test.d",
+            &source,
+            &frames,
+        )
+        .write();
+        let reader = FrameReader::new(&run).unwrap();
+        assert!(reader.is_maldi());
+        assert_eq!(reader.len(), 2);
+        let frame = reader.get(0).unwrap();
+        assert_eq!(frame.index, 1);
+        assert_eq!(frame.tof_indices, frames[0].tof_indices);
+        assert_eq!(frame.maldi_info, frames[0].maldi_info);
+        assert_eq!(reader.get(1).unwrap().maldi_info, None);
+    }
+
+    #[test]
+    fn run_subsetter_ms_level() {
+        let source = get_local_directory().join("test.d");
+        let reader = FrameReader::new(&source).unwrap();
+        let run = temp_path("timsrust_subset_ms2_test.d");
+        let subsetter =
+            RunSubsetter::new(&source).unwrap().ms_level(MSLevel::MS2);
+        assert_eq!(subsetter.frame_ids().unwrap(), vec![2, 4]);
+        subsetter.write(&run).unwrap();
+        let subset = FrameReader::new(&run).unwrap();
+        assert_eq!(subset.len(), 2);
+        for (index, source_index) in [(0, 1), (1, 3)] {
+            let mut expected = reader.get(source_index).unwrap();
+            expected.index = index + 1;
+            // The precursors of the PASEF isolation windows have an MS1
+            // parent that is not selected
+            expected.quadrupole_settings = Default::default();
+            assert_eq!(subset.get(index).unwrap(), expected);
+        }
+        assert!(subsetter.write(&run).is_err());
+    }
+
+    #[test]
+    fn run_subsetter_rt_range() {
+        let source = get_local_directory().join("test.d");
+        let run = temp_path("timsrust_subset_rt_test.d");
+        RunSubsetter::new(&source)
+            .unwrap()
+            .rt_range(0.25..0.5)
+            .write(&run)
+            .unwrap();
+        let subset = FrameReader::new(&run).unwrap();
+        let rts: Vec<f64> = (0..subset.len())
+            .map(|index| subset.get(index).unwrap().rt_in_seconds)
+            .collect();
+        assert_eq!(rts, vec![0.3, 0.4]);
+        let spectra = SpectrumReader::new(&run).unwrap();
+        assert_eq!(spectra.len(), 1);
+        let precursor = spectra.get(0).unwrap().precursor.unwrap();
+        assert_eq!(precursor.mz, 502.0);
+        assert_eq!(precursor.frame_index, 1);
+    }
+
+    #[test]
+    fn tdf_reader_quality_flags() {
+        let source = get_local_directory().join("test.d");
+        let reader = FrameReader::new(&source).unwrap();
+        let mut frames: Vec<Frame> =
+            reader.get_all().into_iter().map(|x| x.unwrap()).collect();
+        assert!(frames.iter().all(|frame| frame.quality.is_ok()));
+        frames[2].intensity_correction_factor = 1.0 / 200.0;
+        frames[0].maldi_info = Some(MaldiInfo {
+            spot_name: "R00X001Y001".to_string(),
+            position_x_um: Some(0.0),
+            position_y_um: Some(0.0),
+            ..Default::default()
+        });
+        let run = SyntheticRun::from_source(
+            "timsrust_quality_test.d",
+            &source,
+            &frames,
+        )
+        .write();
+        let reader = FrameReader::new(&run).unwrap();
+        let qualities: Vec<FrameQuality> = (0..reader.len())
+            .map(|index| reader.get(index).unwrap().quality)
+            .collect();
+        assert_eq!(
+            qualities,
+            vec![
+                FrameQuality::default(),
+                FrameQuality::MISSING_MALDI_POSITION,
+                FrameQuality::ACCUMULATION_ANOMALY
+                    | FrameQuality::MISSING_MALDI_POSITION,
+                FrameQuality::MISSING_MALDI_POSITION,
+            ]
+        );
+    }
+}
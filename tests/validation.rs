@@ -0,0 +1,84 @@
+#[cfg(feature = "tdf")]
+mod common;
+
+#[cfg(feature = "tdf")]
+mod tests {
+    use super::common::temp_path;
+    use std::path::Path;
+    use timsrust::{
+        readers::{FrameReader, MetadataReader},
+        validate::{
+            FrameDifference, FrameValidator, MetadataDifference,
+            ValidationConfig,
+        },
+        writers::TdfWriter,
+        Frame,
+    };
+
+    fn get_local_directory() -> &'static Path {
+        Path::new(std::file!())
+            .parent()
+            .expect("Failed to get parent directory")
+    }
+
+    #[test]
+    fn validate_writer_round_trip() {
+        let source = get_local_directory().join("test.d");
+        let validator = FrameValidator::default();
+        let report = validator.compare_runs(&source, &source).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.left_frame_count, 4);
+        let mut frames: Vec<Frame> = FrameReader::new(&source)
+            .unwrap()
+            .get_all()
+            .into_iter()
+            .map(|x| x.unwrap())
+            .collect();
+        frames[2].intensities[0] += 1;
+        let run = temp_path("timsrust_validation_test.d");
+        let global_metadata =
+            MetadataReader::new(&source).unwrap().global_metadata;
+        let mut writer = TdfWriter::create(&run, &global_metadata).unwrap();
+        for frame in &frames[..3] {
+            writer.write_frame(frame).unwrap();
+        }
+        writer.finish().unwrap();
+        let report = validator.compare_runs(&source, &run).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.right_frame_count, 3);
+        let fields: Vec<&str> =
+            report.metadata.iter().map(|x| x.field).collect();
+        assert_eq!(fields, vec!["rt_converter", "upper_rt"]);
+        assert_eq!(report.max_intensity_delta, 1);
+        // The isolation windows of DDA frames are not written
+        assert_eq!(report.frames.len(), 2);
+        let frame = &report.frames[0];
+        assert_eq!(frame.index, 1);
+        assert!(matches!(
+            &frame.differences[..],
+            [FrameDifference::Metadata(MetadataDifference {
+                field: "quadrupole_settings",
+                ..
+            })]
+        ));
+        let frame = &report.frames[1];
+        assert_eq!(frame.index, 2);
+        assert_eq!(
+            frame.differences,
+            vec![FrameDifference::Intensity {
+                scan: 0,
+                peak: 0,
+                left: frames[2].intensities[0] - 1,
+                right: frames[2].intensities[0],
+            }]
+        );
+        let validator = FrameValidator::new(ValidationConfig {
+            absolute_intensity_tolerance: 1,
+            compare_quadrupole_settings: false,
+            ..Default::default()
+        });
+        let report = validator.compare_runs(&source, &run).unwrap();
+        assert!(report.frames.is_empty());
+        assert!(!report.is_ok());
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The constant of the Mason-Schamp equation,
+/// `3 e / (16 N0) * sqrt(2 π / k_B)`, for CCS in Å², masses in Da and
+/// 1/K0 in V·s/cm².
+const MASON_SCHAMP_CONSTANT: f64 = 18509.8632163405;
+
+/// Converts the reduced ion mobility (1/K0) of an ion to its collisional
+/// cross section (CCS) in Å² with the Mason-Schamp equation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CcsConverter {
+    /// The mass of the drift gas in Da
+    pub gas_mass: f64,
+    /// The temperature of the drift gas in Kelvin
+    pub temperature: f64,
+}
+
+impl Default for CcsConverter {
+    /// Nitrogen at 305 K, as used by Bruker for timsTOF instruments.
+    fn default() -> Self {
+        Self {
+            gas_mass: 28.013,
+            temperature: 305.0,
+        }
+    }
+}
+
+impl CcsConverter {
+    pub fn ccs(&self, one_over_k0: f64, mz: f64, charge: usize) -> f64 {
+        one_over_k0 * self.factor(mz, charge)
+    }
+
+    pub fn one_over_k0(&self, ccs: f64, mz: f64, charge: usize) -> f64 {
+        ccs / self.factor(mz, charge)
+    }
+
+    fn factor(&self, mz: f64, charge: usize) -> f64 {
+        let mass = mz * charge as f64;
+        let reduced_mass = mass * self.gas_mass / (mass + self.gas_mass);
+        MASON_SCHAMP_CONSTANT * charge as f64
+            / (reduced_mass * self.temperature).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_mobility_to_ccs() {
+        let converter = CcsConverter::default();
+        let ccs = converter.ccs(0.9, 500.0, 2);
+        assert!((ccs - 365.4).abs() < 0.1);
+        assert!((converter.one_over_k0(ccs, 500.0, 2) - 0.9).abs() < 1e-12);
+    }
+}
@@ -1,5 +1,9 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
 /// A converter from Frame -> retention time.
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Frame2RtConverter {
     rt_values: Vec<f64>,
 }
@@ -0,0 +1,197 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::Scan2ImConverter;
+
+const ZERO_CELSIUS_IN_KELVIN: f64 = 273.15;
+
+/// The pressure and temperature in the TIMS tunnel.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TimsConditions {
+    pub pressure: f64,
+    /// Temperature in °C
+    pub temperature: Option<f64>,
+}
+
+impl TimsConditions {
+    /// The factor to convert 1/K0 values that were calibrated at
+    /// `reference` to these conditions.
+    ///
+    /// The reduced mobility is proportional to the pressure and inversely
+    /// proportional to the (absolute) temperature, so 1/K0 at a scan scales
+    /// with `p_ref / p` and `T / T_ref`. The temperature is only
+    /// compensated if both conditions have one.
+    pub fn compensation(&self, reference: &TimsConditions) -> f64 {
+        let pressure = match (self.pressure > 0.0) & (reference.pressure > 0.0)
+        {
+            true => reference.pressure / self.pressure,
+            false => 1.0,
+        };
+        let temperature = match (self.temperature, reference.temperature) {
+            (Some(temperature), Some(reference)) => {
+                (temperature + ZERO_CELSIUS_IN_KELVIN)
+                    / (reference + ZERO_CELSIUS_IN_KELVIN)
+            },
+            _ => 1.0,
+        };
+        pressure * temperature
+    }
+}
+
+/// A calibration segment with the conditions it was calibrated at.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MobilityCalibrationSegment {
+    pub converter: Scan2ImConverter,
+    /// `None` disables pressure compensation of the frames of the segment
+    pub reference: Option<TimsConditions>,
+}
+
+/// The calibration reference and conditions of a single frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FrameMobilityCalibration {
+    /// The id of the calibration segment
+    pub calibration: Option<usize>,
+    pub conditions: Option<TimsConditions>,
+}
+
+/// A converter from Scan -> 1/K0 for every frame of a run.
+///
+/// Frames refer to a calibration segment, which has its own Scan -> 1/K0
+/// converter and the TIMS conditions it was calibrated at. The converter of
+/// a frame is that of its segment, compensated for the difference between
+/// the conditions of the frame and the reference conditions of the
+/// segment. Frames without a (known) segment use the run-wide converter.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MobilityCalibration {
+    converter: Scan2ImConverter,
+    segments: BTreeMap<usize, MobilityCalibrationSegment>,
+    /// By frame id
+    frames: BTreeMap<usize, FrameMobilityCalibration>,
+}
+
+impl MobilityCalibration {
+    /// A calibration where all frames use `converter`.
+    pub fn new(converter: Scan2ImConverter) -> Self {
+        Self {
+            converter,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_segment(
+        mut self,
+        id: usize,
+        segment: MobilityCalibrationSegment,
+    ) -> Self {
+        self.segments.insert(id, segment);
+        self
+    }
+
+    pub fn with_frame(
+        mut self,
+        frame: usize,
+        calibration: FrameMobilityCalibration,
+    ) -> Self {
+        self.frames.insert(frame, calibration);
+        self
+    }
+
+    /// The run-wide converter.
+    pub fn converter(&self) -> Scan2ImConverter {
+        self.converter
+    }
+
+    pub fn segments(&self) -> &BTreeMap<usize, MobilityCalibrationSegment> {
+        &self.segments
+    }
+
+    pub fn frame(&self, frame: usize) -> Option<&FrameMobilityCalibration> {
+        self.frames.get(&frame)
+    }
+
+    /// The Scan -> 1/K0 converter of the frame with `Id` `frame`.
+    pub fn frame_converter(&self, frame: usize) -> Scan2ImConverter {
+        let Some(calibration) = self.frames.get(&frame) else {
+            return self.converter;
+        };
+        let Some(segment) = calibration
+            .calibration
+            .and_then(|calibration| self.segments.get(&calibration))
+        else {
+            return self.converter;
+        };
+        match (calibration.conditions, segment.reference) {
+            (Some(conditions), Some(reference)) => segment
+                .converter
+                .scaled(conditions.compensation(&reference)),
+            _ => segment.converter,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain_converters::ConvertableDomain;
+
+    #[test]
+    fn compensates_pressure_per_segment() {
+        let converter = Scan2ImConverter::from_boundaries(0.6, 1.6, 1000);
+        let conditions = |pressure, temperature| TimsConditions {
+            pressure,
+            temperature,
+        };
+        let calibration = MobilityCalibration::new(converter)
+            .with_segment(
+                1,
+                MobilityCalibrationSegment {
+                    converter,
+                    reference: Some(conditions(2.5, Some(25.0))),
+                },
+            )
+            .with_frame(
+                1,
+                FrameMobilityCalibration {
+                    calibration: Some(1),
+                    conditions: Some(conditions(2.5, Some(25.0))),
+                },
+            )
+            .with_frame(
+                2,
+                FrameMobilityCalibration {
+                    calibration: Some(1),
+                    conditions: Some(conditions(2.45, None)),
+                },
+            )
+            .with_frame(
+                3,
+                FrameMobilityCalibration {
+                    calibration: Some(2),
+                    conditions: Some(conditions(2.0, None)),
+                },
+            );
+        assert_eq!(calibration.frame_converter(1), converter);
+        let compensated = calibration.frame_converter(2);
+        assert!(
+            (compensated.convert(0) - 1.6 * 2.5 / 2.45).abs() < 1e-12,
+            "{}",
+            compensated.convert(0)
+        );
+        assert!((compensated.convert(1000) - 0.6 * 2.5 / 2.45).abs() < 1e-12);
+        // Unknown segments and frames use the run-wide converter
+        assert_eq!(calibration.frame_converter(3), converter);
+        assert_eq!(calibration.frame_converter(4), converter);
+        let warmer = conditions(2.5, Some(35.0));
+        assert!(
+            (warmer.compensation(&conditions(2.5, Some(25.0)))
+                - 308.15 / 298.15)
+                .abs()
+                < 1e-12
+        );
+    }
+}
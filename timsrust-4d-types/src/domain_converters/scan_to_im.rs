@@ -22,6 +22,32 @@ impl Scan2ImConverter {
             scan_slope,
         }
     }
+
+    /// A converter with `1/K0 = intercept + slope * scan`.
+    pub fn from_linear(intercept: f64, slope: f64) -> Self {
+        Self {
+            scan_intercept: intercept,
+            scan_slope: slope,
+        }
+    }
+
+    /// The 1/K0 of scan 0.
+    pub fn intercept(&self) -> f64 {
+        self.scan_intercept
+    }
+
+    /// The change of 1/K0 per scan.
+    pub fn slope(&self) -> f64 {
+        self.scan_slope
+    }
+
+    /// A converter whose 1/K0 values are multiplied by `factor`.
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            scan_intercept: self.scan_intercept * factor,
+            scan_slope: self.scan_slope * factor,
+        }
+    }
 }
 
 impl super::ConvertableDomain for Scan2ImConverter {
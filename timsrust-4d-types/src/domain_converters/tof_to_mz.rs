@@ -25,6 +25,24 @@ impl Tof2MzConverter {
         }
     }
 
+    /// A converter with `sqrt(m/z) = intercept + slope * tof`.
+    pub fn from_linear(intercept: f64, slope: f64) -> Self {
+        Self {
+            tof_intercept: intercept,
+            tof_slope: slope,
+        }
+    }
+
+    /// The square root of the m/z of TOF index 0.
+    pub fn intercept(&self) -> f64 {
+        self.tof_intercept
+    }
+
+    /// The change of the square root of the m/z per TOF index.
+    pub fn slope(&self) -> f64 {
+        self.tof_slope
+    }
+
     pub fn regress_from_pairs(data: &Vec<(f64, u32)>) -> Self {
         let x: Vec<u32> = data.iter().map(|(_, x_val)| *x_val).collect();
         let y: Vec<f64> =
@@ -0,0 +1,11 @@
+//! Core data types of [timsrust](https://docs.rs/timsrust): frames,
+//! spectra, MALDI metadata and domain converters.
+//!
+//! These types are re-exported by timsrust. Depend on this crate directly
+//! to use them without the dependencies that are needed to read files
+//! (e.g. rusqlite and rayon).
+
+pub mod domain_converters;
+pub mod ms_data;
+
+pub use crate::ms_data::*;
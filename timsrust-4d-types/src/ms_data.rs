@@ -0,0 +1,29 @@
+//! Data structures that represent MS data
+
+mod acquisition;
+mod compact;
+mod frames;
+mod matrix;
+mod metadata;
+mod normalization;
+#[cfg(feature = "serialize")]
+mod peak_arrays;
+mod precursors;
+mod prm;
+mod quadrupole;
+mod quality;
+mod scans;
+mod spectra;
+
+pub use acquisition::*;
+pub use compact::*;
+pub use frames::*;
+pub use matrix::*;
+pub use metadata::*;
+pub use normalization::*;
+pub use precursors::*;
+pub use prm::*;
+pub use quadrupole::*;
+pub use quality::*;
+pub use scans::*;
+pub use spectra::*;
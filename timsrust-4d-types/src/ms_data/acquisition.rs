@@ -0,0 +1,199 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use super::MSLevel;
+
+/// The kind of acquisition that was used.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum AcquisitionType {
+    DDAPASEF,
+    DIAPASEF,
+    /// diaPASEF where the isolation window moves with the mobility scans,
+    /// e.g. synchro-PASEF
+    DiagonalDIAPASEF,
+    /// Targeted PASEF with scheduled precursors (MsMsType 10)
+    PRMPASEF,
+    /// MS/MS frames of fixed precursors without PASEF (MsMsType 2 with
+    /// ScanMode 2)
+    MRM,
+    /// Data-dependent MS/MS frames without PASEF (MsMsType 2 with ScanMode 1)
+    AutoMSMS,
+    /// Default value.
+    #[default]
+    Unknown,
+}
+
+impl AcquisitionType {
+    /// Classify a run from the `ScanMode` and `MsMsType` of all its frames.
+    ///
+    /// PASEF MS/MS frames take precedence over MsMsType 2 frames, which are
+    /// classified by their scan mode. Runs without MS/MS frames are
+    /// [AcquisitionType::Unknown].
+    pub fn from_frame_types(
+        frame_types: impl IntoIterator<Item = (ScanMode, MsMsTypeKind)>,
+    ) -> Self {
+        let mut msms_types = vec![];
+        let mut non_pasef_scan_modes = vec![];
+        for (scan_mode, msms_type) in frame_types {
+            if msms_type == MsMsTypeKind::Mrm {
+                non_pasef_scan_modes.push(scan_mode);
+            }
+            msms_types.push(msms_type);
+        }
+        if msms_types.contains(&MsMsTypeKind::DdaPasef) {
+            Self::DDAPASEF
+        } else if msms_types.contains(&MsMsTypeKind::DiaPasef) {
+            Self::DIAPASEF
+        } else if msms_types.contains(&MsMsTypeKind::PrmPasef) {
+            Self::PRMPASEF
+        } else if non_pasef_scan_modes.contains(&ScanMode::Mrm) {
+            Self::MRM
+        } else if non_pasef_scan_modes.contains(&ScanMode::AutoMsMs) {
+            Self::AutoMSMS
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// The `ScanMode` of a frame in the Frames table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ScanMode {
+    #[default]
+    Ms,
+    AutoMsMs,
+    Mrm,
+    InSourceCid,
+    BroadbandCid,
+    DdaPasef,
+    DiaPasef,
+    PrmPasef,
+    Maldi,
+    /// Any other value, as it was stored
+    Unknown(u8),
+}
+
+impl ScanMode {
+    pub fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => Self::Ms,
+            1 => Self::AutoMsMs,
+            2 => Self::Mrm,
+            3 => Self::InSourceCid,
+            4 => Self::BroadbandCid,
+            8 => Self::DdaPasef,
+            9 => Self::DiaPasef,
+            10 => Self::PrmPasef,
+            20 => Self::Maldi,
+            raw => Self::Unknown(raw),
+        }
+    }
+
+    /// The value as it is stored in the Frames table.
+    pub fn raw(&self) -> u8 {
+        match self {
+            Self::Ms => 0,
+            Self::AutoMsMs => 1,
+            Self::Mrm => 2,
+            Self::InSourceCid => 3,
+            Self::BroadbandCid => 4,
+            Self::DdaPasef => 8,
+            Self::DiaPasef => 9,
+            Self::PrmPasef => 10,
+            Self::Maldi => 20,
+            Self::Unknown(raw) => *raw,
+        }
+    }
+}
+
+impl From<u8> for ScanMode {
+    fn from(raw: u8) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+/// The `MsMsType` of a frame in the Frames table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum MsMsTypeKind {
+    #[default]
+    Ms1,
+    /// MS/MS of a fixed precursor over the full frame (`FrameMsMsInfo`),
+    /// used by both MRM and auto-MS/MS
+    Mrm,
+    DdaPasef,
+    DiaPasef,
+    PrmPasef,
+    /// Any other value, as it was stored
+    Unknown(u8),
+}
+
+impl MsMsTypeKind {
+    pub fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => Self::Ms1,
+            2 => Self::Mrm,
+            8 => Self::DdaPasef,
+            9 => Self::DiaPasef,
+            10 => Self::PrmPasef,
+            raw => Self::Unknown(raw),
+        }
+    }
+
+    /// The value as it is stored in the Frames table.
+    pub fn raw(&self) -> u8 {
+        match self {
+            Self::Ms1 => 0,
+            Self::Mrm => 2,
+            Self::DdaPasef => 8,
+            Self::DiaPasef => 9,
+            Self::PrmPasef => 10,
+            Self::Unknown(raw) => *raw,
+        }
+    }
+
+    pub fn ms_level(&self) -> MSLevel {
+        match self {
+            Self::Ms1 => MSLevel::MS1,
+            Self::Unknown(_) => MSLevel::Unknown,
+            _ => MSLevel::MS2,
+        }
+    }
+}
+
+impl From<u8> for MsMsTypeKind {
+    fn from(raw: u8) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_values_round_trip() {
+        for raw in 0..=u8::MAX {
+            assert_eq!(ScanMode::from_raw(raw).raw(), raw);
+            assert_eq!(MsMsTypeKind::from_raw(raw).raw(), raw);
+        }
+        assert_eq!(ScanMode::from_raw(20), ScanMode::Maldi);
+        assert_eq!(MsMsTypeKind::from_raw(5), MsMsTypeKind::Unknown(5));
+    }
+
+    #[test]
+    fn classifies_mixed_mode_runs() {
+        let run = |frame_types: &[(u8, u8)]| {
+            AcquisitionType::from_frame_types(frame_types.iter().map(
+                |&(scan_mode, msms_type)| (scan_mode.into(), msms_type.into()),
+            ))
+        };
+        assert_eq!(run(&[(2, 0), (2, 2)]), AcquisitionType::MRM);
+        assert_eq!(run(&[(1, 0), (1, 2)]), AcquisitionType::AutoMSMS);
+        assert_eq!(run(&[(8, 0), (8, 8)]), AcquisitionType::DDAPASEF);
+        assert_eq!(run(&[(9, 0), (9, 9), (2, 2)]), AcquisitionType::DIAPASEF);
+        assert_eq!(run(&[(0, 0), (20, 0)]), AcquisitionType::Unknown);
+    }
+}
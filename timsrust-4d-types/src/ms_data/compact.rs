@@ -0,0 +1,346 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use super::Frame;
+
+/// A [Frame] with its peaks encoded compactly in memory, for tools that
+/// need to hold many frames at once.
+///
+/// TOF indices are sorted within every scan, so they are stored as the
+/// (zigzag) difference to the previous TOF index of their scan. These
+/// differences, the raw intensities and the number of peaks of every scan
+/// are stored as LEB128 varints, which typically needs 3-4 times less
+/// memory than a [Frame]. Peaks are decoded on the fly by
+/// [CompactFrame::peaks].
+///
+/// Normalized intensities are kept as they are.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CompactFrame {
+    metadata: Frame,
+    peak_count: usize,
+    scan_offset_count: usize,
+    scan_sizes: Vec<u8>,
+    tof_deltas: Vec<u8>,
+    intensities: Vec<u8>,
+}
+
+impl CompactFrame {
+    /// The frame without its scan offsets, TOF indices and intensities.
+    pub fn metadata(&self) -> &Frame {
+        &self.metadata
+    }
+
+    /// The number of peaks.
+    pub fn len(&self) -> usize {
+        self.peak_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peak_count == 0
+    }
+
+    pub fn scan_count(&self) -> usize {
+        self.scan_offset_count.saturating_sub(1)
+    }
+
+    /// The number of bytes of the encoded peaks and normalized intensities.
+    pub fn size_in_bytes(&self) -> usize {
+        self.scan_sizes.len()
+            + self.tof_deltas.len()
+            + self.intensities.len()
+            + std::mem::size_of_val(
+                self.metadata.normalized_intensities.as_slice(),
+            )
+    }
+
+    /// Decode all peaks as `(scan, tof_index, intensity)`.
+    pub fn peaks(&self) -> CompactPeaks<'_> {
+        CompactPeaks {
+            scan_sizes: Varints::new(&self.scan_sizes),
+            tof_deltas: Varints::new(&self.tof_deltas),
+            intensities: Varints::new(&self.intensities),
+            remaining: self.peak_count,
+            remaining_in_scan: 0,
+            scan: 0,
+            next_scan: 0,
+            previous_tof_index: 0,
+        }
+    }
+
+    pub fn tof_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.peaks().map(|(_, tof_index, _)| tof_index)
+    }
+
+    pub fn intensities(&self) -> impl Iterator<Item = u32> + '_ {
+        self.peaks().map(|(_, _, intensity)| intensity)
+    }
+
+    pub fn scan_offsets(&self) -> Vec<usize> {
+        if self.scan_offset_count == 0 {
+            return vec![];
+        }
+        let mut scan_offsets = Vec::with_capacity(self.scan_offset_count);
+        scan_offsets.push(0);
+        let mut offset = 0;
+        for size in Varints::new(&self.scan_sizes) {
+            offset += size as usize;
+            scan_offsets.push(offset);
+        }
+        scan_offsets
+    }
+
+    /// Decode all peaks into a [Frame].
+    pub fn to_frame(&self) -> Frame {
+        let (tof_indices, intensities) = self
+            .peaks()
+            .map(|(_, tof_index, intensity)| (tof_index, intensity))
+            .unzip();
+        Frame {
+            scan_offsets: self.scan_offsets(),
+            tof_indices,
+            intensities,
+            ..self.metadata.clone()
+        }
+    }
+}
+
+impl From<Frame> for CompactFrame {
+    fn from(mut metadata: Frame) -> Self {
+        let scan_offsets = std::mem::take(&mut metadata.scan_offsets);
+        let tof_indices = std::mem::take(&mut metadata.tof_indices);
+        let intensities = std::mem::take(&mut metadata.intensities);
+        let mut compact = Self {
+            metadata,
+            peak_count: tof_indices.len().min(intensities.len()),
+            scan_offset_count: scan_offsets.len(),
+            ..Default::default()
+        };
+        let mut scan_starts = scan_offsets.iter().skip(1).peekable();
+        let mut previous_tof_index = 0;
+        for (index, (&tof_index, &intensity)) in
+            tof_indices.iter().zip(&intensities).enumerate()
+        {
+            while scan_starts.next_if(|&&offset| offset <= index).is_some() {
+                previous_tof_index = 0;
+            }
+            let delta = tof_index as i64 - previous_tof_index as i64;
+            write_varint(&mut compact.tof_deltas, zigzag(delta));
+            write_varint(&mut compact.intensities, intensity as u64);
+            previous_tof_index = tof_index;
+        }
+        for scan in scan_offsets.windows(2) {
+            write_varint(&mut compact.scan_sizes, (scan[1] - scan[0]) as u64);
+        }
+        compact.scan_sizes.shrink_to_fit();
+        compact.tof_deltas.shrink_to_fit();
+        compact.intensities.shrink_to_fit();
+        compact
+    }
+}
+
+impl From<&Frame> for CompactFrame {
+    fn from(frame: &Frame) -> Self {
+        Self::from(frame.clone())
+    }
+}
+
+impl From<&CompactFrame> for Frame {
+    fn from(compact: &CompactFrame) -> Self {
+        compact.to_frame()
+    }
+}
+
+impl From<CompactFrame> for Frame {
+    fn from(compact: CompactFrame) -> Self {
+        let scan_offsets = compact.scan_offsets();
+        let (tof_indices, intensities) = compact
+            .peaks()
+            .map(|(_, tof_index, intensity)| (tof_index, intensity))
+            .unzip();
+        Frame {
+            scan_offsets,
+            tof_indices,
+            intensities,
+            ..compact.metadata
+        }
+    }
+}
+
+/// An iterator over the `(scan, tof_index, intensity)` of all peaks of a
+/// [CompactFrame].
+#[derive(Clone, Debug)]
+pub struct CompactPeaks<'a> {
+    scan_sizes: Varints<'a>,
+    tof_deltas: Varints<'a>,
+    intensities: Varints<'a>,
+    remaining: usize,
+    remaining_in_scan: usize,
+    scan: usize,
+    next_scan: usize,
+    previous_tof_index: u32,
+}
+
+impl Iterator for CompactPeaks<'_> {
+    type Item = (usize, u32, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        while self.remaining_in_scan == 0 {
+            match self.scan_sizes.next() {
+                Some(size) => {
+                    self.scan = self.next_scan;
+                    self.next_scan += 1;
+                    self.remaining_in_scan = size as usize;
+                    self.previous_tof_index = 0;
+                },
+                // Peaks without scan offsets continue the last scan
+                None => self.remaining_in_scan = self.remaining,
+            }
+        }
+        let delta = unzigzag(self.tof_deltas.next()?);
+        let tof_index = (self.previous_tof_index as i64 + delta) as u32;
+        let intensity = self.intensities.next()? as u32;
+        self.previous_tof_index = tof_index;
+        self.remaining -= 1;
+        self.remaining_in_scan -= 1;
+        Some((self.scan, tof_index, intensity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for CompactPeaks<'_> {}
+
+#[derive(Clone, Debug)]
+struct Varints<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Varints<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl Iterator for Varints<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for (index, &byte) in self.bytes.iter().enumerate() {
+            value |= ((byte & 0x7f) as u64) << (7 * index);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[index + 1..];
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Frame {
+        Frame {
+            scan_offsets: vec![0, 3, 3, 5],
+            tof_indices: vec![100, 150, 151, 90, 300_000],
+            intensities: vec![1, 127, 128, 70_000, u32::MAX],
+            index: 4,
+            rt_in_seconds: 12.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_frames() {
+        let frame = frame();
+        let compact = CompactFrame::from(&frame);
+        assert_eq!(compact.len(), 5);
+        assert_eq!(compact.scan_count(), 3);
+        assert_eq!(compact.metadata().index, 4);
+        assert!(compact.metadata().tof_indices.is_empty());
+        assert_eq!(compact.scan_offsets(), frame.scan_offsets);
+        assert_eq!(
+            compact.peaks().collect::<Vec<_>>(),
+            vec![
+                (0, 100, 1),
+                (0, 150, 127),
+                (0, 151, 128),
+                (2, 90, 70_000),
+                (2, 300_000, u32::MAX),
+            ]
+        );
+        assert_eq!(compact.to_frame(), frame);
+        assert_eq!(CompactFrame::from(frame.clone()), compact);
+        assert_eq!(Frame::from(compact), frame);
+        let empty = CompactFrame::from(Frame::default());
+        assert_eq!(empty.to_frame(), Frame::default());
+    }
+
+    #[test]
+    fn encodes_unsorted_tof_indices() {
+        let frame = Frame {
+            scan_offsets: vec![0, 3],
+            tof_indices: vec![10, 5, u32::MAX],
+            intensities: vec![1, 2, 3],
+            ..Default::default()
+        };
+        assert_eq!(CompactFrame::from(&frame).to_frame(), frame);
+        let frame = Frame {
+            scan_offsets: vec![],
+            ..frame
+        };
+        assert_eq!(CompactFrame::from(&frame).to_frame(), frame);
+    }
+
+    #[test]
+    fn counts_only_complete_peaks() {
+        let frame = Frame {
+            scan_offsets: vec![0, 3],
+            tof_indices: vec![10, 20, 30],
+            intensities: vec![1, 2],
+            ..Default::default()
+        };
+        let compact = CompactFrame::from(&frame);
+        assert_eq!(compact.len(), 2);
+        assert_eq!(
+            compact.peaks().collect::<Vec<_>>(),
+            vec![(0, 10, 1), (0, 20, 2)]
+        );
+    }
+
+    #[test]
+    fn encodes_varints() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = vec![];
+            write_varint(&mut bytes, value);
+            assert_eq!(Varints::new(&bytes).collect::<Vec<_>>(), vec![value]);
+        }
+        for value in [0, 1, -1, 63, -64, u32::MAX as i64, -(u32::MAX as i64)] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::domain_converters::Scan2ImConverter;
+
+use super::{
+    AcquisitionType, FrameQuality, MsMsTypeKind, PrmTarget, QuadrupoleSettings,
+    ScanMode,
+};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// MALDI-specific metadata attached to a frame for imaging MS.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MaldiInfo {
+    /// Spot name identifier
+    pub spot_name: String,
+    /// X pixel coordinate (grid index)
+    pub pixel_x: i32,
+    /// Y pixel coordinate (grid index)
+    pub pixel_y: i32,
+    /// X position in micrometers (physical coordinate)
+    pub position_x_um: Option<f64>,
+    /// Y position in micrometers (physical coordinate)
+    pub position_y_um: Option<f64>,
+    /// Laser power setting
+    pub laser_power: Option<f64>,
+    /// Laser repetition rate
+    pub laser_rep_rate: Option<f64>,
+    /// Number of laser shots
+    pub laser_shots: Option<i32>,
+}
+
+impl MaldiInfo {
+    /// The region prefix of the spot name (e.g. `R00` for `R00X012Y034`).
+    pub fn region(&self) -> Option<&str> {
+        let rest = self.spot_name.strip_prefix('R')?;
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        Some(&self.spot_name[..digits + 1])
+    }
+}
+
+/// A frame with all unprocessed data as it was acquired.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Frame {
+    pub scan_offsets: Vec<usize>,
+    #[cfg_attr(feature = "serialize", serde(with = "super::peak_arrays"))]
+    pub tof_indices: Vec<u32>,
+    #[cfg_attr(feature = "serialize", serde(with = "super::peak_arrays"))]
+    pub intensities: Vec<u32>,
+    /// Intensities normalized according to the [NormalizationMode] of the
+    /// reader (see [Frame::normalize]), empty if they are not normalized
+    #[cfg_attr(feature = "serialize", serde(with = "super::peak_arrays"))]
+    pub normalized_intensities: Vec<f32>,
+    pub index: usize,
+    pub rt_in_seconds: f64,
+    pub acquisition_type: AcquisitionType,
+    pub ms_level: MSLevel,
+    /// Raw `MsMsType` value of the Frames table (interpreted as `ms_level`,
+    /// see [Frame::msms_type_kind])
+    pub msms_type: u8,
+    /// Raw `ScanMode` value of the Frames table (see [Frame::scan_mode_kind])
+    pub scan_mode: u8,
+    pub quadrupole_settings: Arc<QuadrupoleSettings>,
+    pub intensity_correction_factor: f64,
+    /// The Scan -> 1/K0 converter of this frame, with its calibration
+    /// segment and pressure compensation applied (see [Frame::mobilities])
+    pub im_converter: Option<Scan2ImConverter>,
+    pub window_group: u8,
+    /// The targets of a prm-PASEF frame, indexed like the windows of its
+    /// quadrupole settings (`None` if a window refers to an unknown target)
+    pub prm_targets: Vec<Option<Arc<PrmTarget>>>,
+    /// MALDI imaging metadata (only present for MALDI-TIMS-MSI data)
+    pub maldi_info: Option<MaldiInfo>,
+    /// Labels joined from an external sample sheet
+    pub sample_labels: Option<Arc<SampleLabels>>,
+    /// Quality checks that failed for this frame
+    pub quality: FrameQuality,
+}
+
+/// A row of an external sample sheet (e.g. a plate map) that was joined
+/// onto frames.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SampleLabels {
+    /// The spot name or region that the row was joined on
+    pub key: String,
+    /// All other columns of the row, by column name
+    pub labels: BTreeMap<String, String>,
+}
+
+impl SampleLabels {
+    pub fn get(&self, column: &str) -> Option<&str> {
+        self.labels.get(column).map(|label| label.as_str())
+    }
+}
+
+impl Frame {
+    pub fn get_corrected_intensity(&self, index: usize) -> f64 {
+        self.intensity_correction_factor * self.intensities[index] as f64
+    }
+
+    /// The normalized intensity of a peak (see [Frame::normalize]), or its
+    /// raw intensity if the frame is not normalized.
+    pub fn normalized_intensity(&self, index: usize) -> f64 {
+        match self.normalized_intensities.get(index) {
+            Some(&intensity) => intensity as f64,
+            None => self.intensities[index] as f64,
+        }
+    }
+
+    /// The typed `ScanMode` of [Frame::scan_mode].
+    pub fn scan_mode_kind(&self) -> ScanMode {
+        ScanMode::from_raw(self.scan_mode)
+    }
+
+    /// The typed `MsMsType` of [Frame::msms_type].
+    pub fn msms_type_kind(&self) -> MsMsTypeKind {
+        MsMsTypeKind::from_raw(self.msms_type)
+    }
+}
+
+/// The MS level used.
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum MSLevel {
+    MS1,
+    MS2,
+    /// Default value.
+    #[default]
+    Unknown,
+}
+
+impl MSLevel {
+    pub fn read_from_msms_type(msms_type: u8) -> MSLevel {
+        MsMsTypeKind::from_raw(msms_type).ms_level()
+    }
+}
@@ -0,0 +1,208 @@
+use std::ops::Range;
+
+use super::Frame;
+
+/// A sparse matrix in compressed sparse row (CSR) layout.
+///
+/// The columns of row `i` are `indices[indptr[i]..indptr[i + 1]]`, sorted
+/// in ascending order, with their values in `data`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CsrMatrix {
+    pub rows: usize,
+    pub columns: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<u32>,
+    pub data: Vec<f64>,
+}
+
+impl CsrMatrix {
+    /// The number of stored values.
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn get(&self, row: usize, column: u32) -> f64 {
+        if row >= self.rows {
+            return 0.0;
+        }
+        let range = self.indptr[row]..self.indptr[row + 1];
+        match self.indices[range.clone()].binary_search(&column) {
+            Ok(position) => self.data[range.start + position],
+            Err(_) => 0.0,
+        }
+    }
+
+    pub fn to_dense(&self) -> DenseMatrix {
+        let mut dense = DenseMatrix::zeros(self.rows, self.columns);
+        for row in 0..self.rows {
+            for peak in self.indptr[row]..self.indptr[row + 1] {
+                let column = self.indices[peak] as usize;
+                dense.values[row * self.columns + column] = self.data[peak];
+            }
+        }
+        dense
+    }
+}
+
+/// A dense matrix stored row-major.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DenseMatrix {
+    pub rows: usize,
+    pub columns: usize,
+    pub values: Vec<f64>,
+}
+
+impl DenseMatrix {
+    pub fn zeros(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            values: vec![0.0; rows * columns],
+        }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<f64> {
+        if (row >= self.rows) | (column >= self.columns) {
+            return None;
+        }
+        Some(self.values[row * self.columns + column])
+    }
+
+    /// The shape as `(rows, columns)`.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+}
+
+impl Frame {
+    /// The raw intensities as a sparse matrix with a row per scan and a
+    /// column per TOF index. The number of columns is one more than the
+    /// largest TOF index of the frame.
+    pub fn to_sparse_csr(&self) -> CsrMatrix {
+        self.to_binned_csr(1)
+    }
+
+    /// Like [Frame::to_sparse_csr], but with `tof_bin_width` consecutive
+    /// TOF indices summed into a single column.
+    pub fn to_binned_csr(&self, tof_bin_width: u32) -> CsrMatrix {
+        let tof_bin_width = tof_bin_width.max(1);
+        let rows = self.scan_offsets.len().saturating_sub(1);
+        let mut matrix = CsrMatrix {
+            rows,
+            columns: 0,
+            indptr: Vec::with_capacity(rows + 1),
+            indices: Vec::with_capacity(self.tof_indices.len()),
+            data: Vec::with_capacity(self.tof_indices.len()),
+        };
+        matrix.indptr.push(0);
+        let mut peaks = vec![];
+        for scan in self.scan_offsets.windows(2) {
+            peaks.clear();
+            peaks.extend((scan[0]..scan[1]).map(|peak| {
+                (
+                    self.tof_indices[peak] / tof_bin_width,
+                    self.intensities[peak] as f64,
+                )
+            }));
+            peaks.sort_by_key(|&(column, _)| column);
+            for &(column, intensity) in peaks.iter() {
+                if matrix.indptr.last() != Some(&matrix.indices.len())
+                    && matrix.indices.last() == Some(&column)
+                {
+                    *matrix.data.last_mut().unwrap() += intensity;
+                    continue;
+                }
+                matrix.indices.push(column);
+                matrix.data.push(intensity);
+                matrix.columns = matrix.columns.max(column as usize + 1);
+            }
+            matrix.indptr.push(matrix.indices.len());
+        }
+        matrix
+    }
+
+    /// The raw intensities as a dense matrix with a row per scan in
+    /// `scan_range` and a column per TOF index in `tof_range`. Peaks
+    /// outside of these ranges are ignored.
+    pub fn to_dense(
+        &self,
+        tof_range: Range<u32>,
+        scan_range: Range<usize>,
+    ) -> DenseMatrix {
+        self.to_binned_dense(tof_range, scan_range, 1)
+    }
+
+    /// Like [Frame::to_dense], but with `tof_bin_width` consecutive TOF
+    /// indices summed into a single column. `tof_bin_range` is given in
+    /// bins, i.e. TOF index divided by `tof_bin_width`.
+    pub fn to_binned_dense(
+        &self,
+        tof_bin_range: Range<u32>,
+        scan_range: Range<usize>,
+        tof_bin_width: u32,
+    ) -> DenseMatrix {
+        let tof_bin_width = tof_bin_width.max(1);
+        let columns = tof_bin_range.len();
+        let mut matrix = DenseMatrix::zeros(scan_range.len(), columns);
+        let last_scan = self.scan_offsets.len().saturating_sub(1);
+        for scan in scan_range.start..scan_range.end.min(last_scan) {
+            let row = scan - scan_range.start;
+            for peak in self.scan_offsets[scan]..self.scan_offsets[scan + 1] {
+                let bin = self.tof_indices[peak] / tof_bin_width;
+                if tof_bin_range.contains(&bin) {
+                    let column = (bin - tof_bin_range.start) as usize;
+                    matrix.values[row * columns + column] +=
+                        self.intensities[peak] as f64;
+                }
+            }
+        }
+        matrix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Frame {
+        Frame {
+            scan_offsets: vec![0, 3, 3, 5],
+            tof_indices: vec![7, 2, 3, 9, 0],
+            intensities: vec![10, 20, 30, 40, 50],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn converts_to_csr() {
+        let csr = frame().to_sparse_csr();
+        assert_eq!(csr.rows, 3);
+        assert_eq!(csr.columns, 10);
+        assert_eq!(csr.indptr, vec![0, 3, 3, 5]);
+        assert_eq!(csr.indices, vec![2, 3, 7, 0, 9]);
+        assert_eq!(csr.data, vec![20.0, 30.0, 10.0, 50.0, 40.0]);
+        assert_eq!(csr.get(2, 9), 40.0);
+        assert_eq!(csr.get(1, 9), 0.0);
+        let binned = frame().to_binned_csr(4);
+        assert_eq!(binned.columns, 3);
+        assert_eq!(binned.indices, vec![0, 1, 0, 2]);
+        assert_eq!(binned.data, vec![50.0, 10.0, 50.0, 40.0]);
+    }
+
+    #[test]
+    fn converts_to_dense() {
+        let frame = frame();
+        let dense = frame.to_dense(2..8, 0..4);
+        assert_eq!(dense.shape(), (4, 6));
+        assert_eq!(dense.get(0, 0), Some(20.0));
+        assert_eq!(dense.get(0, 5), Some(10.0));
+        assert_eq!(dense.get(2, 0), Some(0.0));
+        assert_eq!(dense.values.iter().sum::<f64>(), 60.0);
+        let binned = frame.to_binned_dense(0..3, 2..3, 4);
+        assert_eq!(binned.values, vec![50.0, 0.0, 40.0]);
+        assert_eq!(
+            frame.to_sparse_csr().to_dense(),
+            frame.to_dense(0..10, 0..3)
+        );
+    }
+}
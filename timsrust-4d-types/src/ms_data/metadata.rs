@@ -0,0 +1,140 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+use crate::domain_converters::{
+    Frame2RtConverter, Scan2ImConverter, Tof2MzConverter,
+};
+
+/// Metadata from a single run.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Metadata {
+    pub rt_converter: Frame2RtConverter,
+    pub im_converter: Scan2ImConverter,
+    pub mz_converter: Tof2MzConverter,
+    pub compression_type: u8,
+    pub lower_rt: f64,
+    pub upper_rt: f64,
+    pub lower_im: f64,
+    pub upper_im: f64,
+    pub lower_mz: f64,
+    pub upper_mz: f64,
+    pub global_metadata: GlobalMetadata,
+    pub schema: TdfSchema,
+}
+
+/// The schema of the SQLite database of a run, as detected when it was
+/// read.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct TdfSchema {
+    /// `SchemaVersionMajor` and `SchemaVersionMinor` of the
+    /// `GlobalMetadata` table
+    pub version: Option<(u32, u32)>,
+    /// The columns of every table
+    pub tables: BTreeMap<String, Vec<String>>,
+    /// All optional columns that are missing, so that their fields are
+    /// read with a default value
+    pub warnings: Vec<SchemaWarning>,
+}
+
+impl TdfSchema {
+    pub fn has_column(&self, table: &str, column: &str) -> bool {
+        self.tables
+            .get(table)
+            .is_some_and(|columns| columns.iter().any(|x| x == column))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SchemaWarning {
+    pub table: String,
+    pub column: String,
+}
+
+impl fmt::Display for SchemaWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Column {}.{} is missing, so its values are defaulted",
+            self.table, self.column
+        )
+    }
+}
+
+/// Instrument and acquisition properties from the `GlobalMetadata` table.
+///
+/// Keys that are missing or cannot be parsed are `None`. All key/value
+/// pairs, including unknown keys, are available through
+/// [GlobalMetadata::get].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct GlobalMetadata {
+    pub schema_type: Option<String>,
+    pub schema_version_major: Option<u32>,
+    pub schema_version_minor: Option<u32>,
+    pub instrument_vendor: Option<String>,
+    pub instrument_name: Option<String>,
+    pub instrument_serial_number: Option<String>,
+    pub acquisition_software: Option<String>,
+    pub acquisition_software_version: Option<String>,
+    pub acquisition_date_time: Option<String>,
+    pub operator_name: Option<String>,
+    pub method_name: Option<String>,
+    pub sample_name: Option<String>,
+    pub mz_acq_range_lower: Option<f64>,
+    pub mz_acq_range_upper: Option<f64>,
+    pub one_over_k0_acq_range_lower: Option<f64>,
+    pub one_over_k0_acq_range_upper: Option<f64>,
+    raw: HashMap<String, String>,
+}
+
+impl GlobalMetadata {
+    pub fn from_key_values(raw: HashMap<String, String>) -> Self {
+        let text = |key: &str| raw.get(key).cloned();
+        Self {
+            schema_type: text("SchemaType"),
+            schema_version_major: parse(&raw, "SchemaVersionMajor"),
+            schema_version_minor: parse(&raw, "SchemaVersionMinor"),
+            instrument_vendor: text("InstrumentVendor"),
+            instrument_name: text("InstrumentName"),
+            instrument_serial_number: text("InstrumentSerialNumber"),
+            acquisition_software: text("AcquisitionSoftware"),
+            acquisition_software_version: text("AcquisitionSoftwareVersion"),
+            acquisition_date_time: text("AcquisitionDateTime"),
+            operator_name: text("OperatorName"),
+            method_name: text("MethodName"),
+            sample_name: text("SampleName"),
+            mz_acq_range_lower: parse(&raw, "MzAcqRangeLower"),
+            mz_acq_range_upper: parse(&raw, "MzAcqRangeUpper"),
+            one_over_k0_acq_range_lower: parse(&raw, "OneOverK0AcqRangeLower"),
+            one_over_k0_acq_range_upper: parse(&raw, "OneOverK0AcqRangeUpper"),
+            raw,
+        }
+    }
+
+    /// The raw value of any key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.raw.get(key).map(|value| value.as_str())
+    }
+
+    /// The value of any key, parsed as `T`.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        parse(&self.raw, key)
+    }
+
+    /// All key/value pairs of the `GlobalMetadata` table.
+    pub fn raw(&self) -> &HashMap<String, String> {
+        &self.raw
+    }
+}
+
+fn parse<T: FromStr>(raw: &HashMap<String, String>, key: &str) -> Option<T> {
+    raw.get(key).and_then(|value| value.trim().parse().ok())
+}
@@ -0,0 +1,113 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use super::Frame;
+
+/// How the intensities of a frame are normalized into
+/// [Frame::normalized_intensities].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum NormalizationMode {
+    /// Intensities are not normalized
+    #[default]
+    None,
+    /// Intensities are multiplied by the intensity correction factor,
+    /// i.e. divided by the accumulation time (in ms)
+    AccumulationTime,
+    /// Intensities are divided by the total ion current of the frame, so
+    /// that they sum to 1. Frames with a TIC of 0 are normalized to 0.
+    Tic,
+    /// Intensities are divided by the median intensity of the frame.
+    /// Frames with a median of 0 are normalized to 0.
+    Median,
+}
+
+impl Frame {
+    /// Fill [Frame::normalized_intensities] according to `mode`. With
+    /// [NormalizationMode::None], they are cleared instead.
+    pub fn normalize(&mut self, mode: NormalizationMode) {
+        let factor = match mode {
+            NormalizationMode::None => {
+                self.normalized_intensities = vec![];
+                return;
+            },
+            NormalizationMode::AccumulationTime => {
+                self.intensity_correction_factor
+            },
+            NormalizationMode::Tic => {
+                let tic: f64 = self.intensities.iter().map(|&x| x as f64).sum();
+                inverse(tic)
+            },
+            NormalizationMode::Median => inverse(median(&self.intensities)),
+        };
+        self.normalized_intensities = self
+            .intensities
+            .iter()
+            .map(|&intensity| (intensity as f64 * factor) as f32)
+            .collect();
+    }
+}
+
+/// `1 / value`, or 0 if `value` is 0 (or not finite).
+fn inverse(value: f64) -> f64 {
+    if value.is_finite() && (value != 0.0) {
+        1.0 / value
+    } else {
+        0.0
+    }
+}
+
+fn median(values: &[u32]) -> f64 {
+    let mut values = values.to_vec();
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    match values.len() {
+        0 => f64::NAN,
+        n if n % 2 == 0 => {
+            (values[middle - 1] as f64 + values[middle] as f64) / 2.0
+        },
+        _ => values[middle] as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Frame {
+        Frame {
+            scan_offsets: vec![0, 2, 4],
+            tof_indices: vec![1, 2, 3, 4],
+            intensities: vec![10, 20, 30, 40],
+            intensity_correction_factor: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalize_frame() {
+        let mut frame = frame();
+        frame.normalize(NormalizationMode::AccumulationTime);
+        assert_eq!(frame.normalized_intensities, vec![5.0, 10.0, 15.0, 20.0]);
+        frame.normalize(NormalizationMode::Tic);
+        assert_eq!(frame.normalized_intensities, vec![0.1, 0.2, 0.3, 0.4]);
+        frame.normalize(NormalizationMode::Median);
+        assert_eq!(frame.normalized_intensities, vec![0.4, 0.8, 1.2, 1.6]);
+        frame.normalize(NormalizationMode::None);
+        assert!(frame.normalized_intensities.is_empty());
+    }
+
+    #[test]
+    fn normalize_empty_frame() {
+        let mut frame = Frame {
+            scan_offsets: vec![0, 2],
+            tof_indices: vec![1, 2],
+            intensities: vec![0, 0],
+            ..Default::default()
+        };
+        frame.normalize(NormalizationMode::Tic);
+        assert_eq!(frame.normalized_intensities, vec![0.0, 0.0]);
+        frame.normalize(NormalizationMode::Median);
+        assert_eq!(frame.normalized_intensities, vec![0.0, 0.0]);
+    }
+}
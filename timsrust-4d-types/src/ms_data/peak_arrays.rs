@@ -0,0 +1,151 @@
+//! A compact serde layout for the peak arrays of a [Frame](super::Frame).
+//!
+//! Arrays are serialized as a single byte buffer of little-endian values,
+//! which binary formats such as bincode write (and read) as one block
+//! instead of value by value. Self-describing formats such as JSON write
+//! the bytes as a sequence of numbers; both layouts are accepted when
+//! deserializing.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    Deserializer, Serializer,
+};
+
+/// A fixed-width value that can be stored as little-endian bytes.
+pub(super) trait LeBytes: Sized {
+    const WIDTH: usize;
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>);
+
+    fn from_le_chunk(chunk: &[u8]) -> Self;
+}
+
+impl LeBytes for u32 {
+    const WIDTH: usize = 4;
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn from_le_chunk(chunk: &[u8]) -> Self {
+        Self::from_le_bytes(chunk.try_into().expect("Chunks are 4 bytes"))
+    }
+}
+
+impl LeBytes for f32 {
+    const WIDTH: usize = 4;
+
+    fn extend_le_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn from_le_chunk(chunk: &[u8]) -> Self {
+        Self::from_le_bytes(chunk.try_into().expect("Chunks are 4 bytes"))
+    }
+}
+
+pub(super) fn serialize<T: LeBytes, S: Serializer>(
+    values: &[T],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut bytes = Vec::with_capacity(values.len() * T::WIDTH);
+    for value in values {
+        value.extend_le_bytes(&mut bytes);
+    }
+    serializer.serialize_bytes(&bytes)
+}
+
+pub(super) fn deserialize<'de, T: LeBytes, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    deserializer.deserialize_bytes(LeBytesVisitor(PhantomData))
+}
+
+struct LeBytesVisitor<T>(PhantomData<T>);
+
+impl<T: LeBytes> LeBytesVisitor<T> {
+    fn decode<E: Error>(bytes: &[u8]) -> Result<Vec<T>, E> {
+        if !bytes.len().is_multiple_of(T::WIDTH) {
+            return Err(E::invalid_length(
+                bytes.len(),
+                &"a multiple of the value width",
+            ));
+        }
+        Ok(bytes.chunks_exact(T::WIDTH).map(T::from_le_chunk).collect())
+    }
+}
+
+impl<'de, T: LeBytes> Visitor<'de> for LeBytesVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte buffer of little-endian values")
+    }
+
+    fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Vec<T>, E> {
+        Self::decode(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<Vec<T>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Self::decode(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::{Frame, MaldiInfo, QuadrupoleSettings};
+
+    fn frame() -> Frame {
+        Frame {
+            scan_offsets: vec![0, 2, 3],
+            tof_indices: vec![10, 20, 30],
+            intensities: vec![1, 2, u32::MAX],
+            normalized_intensities: vec![0.5, 1.0, f32::MAX],
+            index: 3,
+            rt_in_seconds: 1.5,
+            quadrupole_settings: Arc::new(QuadrupoleSettings {
+                isolation_mz: vec![500.0],
+                ..Default::default()
+            }),
+            maldi_info: Some(MaldiInfo {
+                spot_name: "R00X001Y002".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        let frame = frame();
+        let bytes = bincode::serialize(&frame).unwrap();
+        assert_eq!(bincode::deserialize::<Frame>(&bytes).unwrap(), frame);
+        let empty = bincode::serialize(&Frame {
+            tof_indices: vec![],
+            intensities: vec![],
+            normalized_intensities: vec![],
+            ..frame
+        })
+        .unwrap();
+        // Every array is a length prefix followed by 4 bytes per value
+        assert_eq!(bytes.len() - empty.len(), 3 * 3 * 4);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let frame = frame();
+        let json = serde_json::to_string(&frame).unwrap();
+        assert_eq!(serde_json::from_str::<Frame>(&json).unwrap(), frame);
+    }
+}
@@ -0,0 +1,19 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// A target of a prm-PASEF run, as listed in the `PrmTargets` table.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct PrmTarget {
+    pub id: usize,
+    /// The name of the target in the method that scheduled it
+    pub external_id: String,
+    /// The scheduled retention time of the target
+    pub rt_in_seconds: f64,
+    /// The scheduled ion mobility (1/K0) of the target
+    pub im: f64,
+    /// The monoisotopic m/z of the target
+    pub mz: f64,
+    pub charge: Option<usize>,
+    pub description: String,
+}
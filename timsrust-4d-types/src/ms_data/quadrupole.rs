@@ -1,7 +1,10 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
 /// The quadrupole settings used for fragmentation.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct QuadrupoleSettings {
     pub index: usize,
     pub scan_starts: Vec<usize>,
@@ -32,4 +35,22 @@ impl QuadrupoleSettings {
     pub fn len(&self) -> usize {
         self.isolation_mz.len()
     }
+
+    /// The index of the window that isolates `scan`, if any. For diagonal
+    /// (e.g. synchro-PASEF) acquisition, this gives the isolation window
+    /// at a specific point of the mobility ramp.
+    pub fn window_at_scan(&self, scan: usize) -> Option<usize> {
+        (0..self.len()).find(|&i| {
+            (self.scan_starts[i] <= scan) & (scan < self.scan_ends[i])
+        })
+    }
+
+    /// Whether the isolation window of `scan` contains `mz`.
+    pub fn isolates(&self, scan: usize, mz: f64) -> bool {
+        self.window_at_scan(scan).is_some_and(|i| {
+            let half_width = self.isolation_width[i] / 2.0;
+            (self.isolation_mz[i] - half_width <= mz)
+                & (mz <= self.isolation_mz[i] + half_width)
+        })
+    }
 }
@@ -0,0 +1,74 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Quality checks that failed for a frame, as bit flags.
+///
+/// An empty set of flags means that all checks passed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FrameQuality(u32);
+
+impl FrameQuality {
+    /// The binary data could only be decoded partially
+    pub const CORRUPT_BLOB: Self = Self(1);
+    /// The accumulation time deviates from the median accumulation time
+    /// of all frames with the same `MsMsType`
+    pub const ACCUMULATION_ANOMALY: Self = Self(1 << 1);
+    /// A frame of a MALDI imaging run without (physical) position
+    pub const MISSING_MALDI_POSITION: Self = Self(1 << 2);
+    /// The m/z or mobility calibration differs from the previous frame
+    pub const CALIBRATION_CHANGE: Self = Self(1 << 3);
+    /// A MALDI frame that could not be normalized by its laser settings
+    /// because they are missing
+    pub const MISSING_LASER_SETTINGS: Self = Self(1 << 4);
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Whether all checks passed.
+    pub fn is_ok(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    pub fn insert(&mut self, flags: Self) {
+        self.0 |= flags.0
+    }
+}
+
+impl std::ops::BitOr for FrameQuality {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combines_flags() {
+        let mut quality = FrameQuality::default();
+        assert!(quality.is_ok());
+        quality.insert(FrameQuality::CORRUPT_BLOB);
+        quality.insert(FrameQuality::CALIBRATION_CHANGE);
+        assert!(!quality.is_ok());
+        assert!(quality.contains(FrameQuality::CORRUPT_BLOB));
+        assert!(!quality.contains(FrameQuality::ACCUMULATION_ANOMALY));
+        assert_eq!(
+            quality,
+            FrameQuality::CORRUPT_BLOB | FrameQuality::CALIBRATION_CHANGE
+        );
+        assert_eq!(quality.bits(), 9);
+    }
+}
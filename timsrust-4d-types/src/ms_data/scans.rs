@@ -0,0 +1,145 @@
+use crate::domain_converters::{ConvertableDomain, Scan2ImConverter};
+
+use super::Frame;
+
+/// The peaks of a single mobility scan of a [Frame].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scan<'a> {
+    pub scan_index: usize,
+    /// The ion mobility (1/K0) of the scan, if a calibration was given
+    pub mobility: Option<f64>,
+    pub tof_indices: &'a [u32],
+    pub intensities: &'a [u32],
+}
+
+impl Scan<'_> {
+    pub fn len(&self) -> usize {
+        self.tof_indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tof_indices.is_empty()
+    }
+}
+
+impl Frame {
+    /// The number of scans, i.e. one less than the number of scan offsets.
+    pub fn scan_count(&self) -> usize {
+        self.scan_offsets.len().saturating_sub(1)
+    }
+
+    /// The scan at `scan_index`, without mobility.
+    pub fn scan(&self, scan_index: usize) -> Option<Scan<'_>> {
+        let start = *self.scan_offsets.get(scan_index)?;
+        let end = *self.scan_offsets.get(scan_index + 1)?;
+        let scan = Scan {
+            scan_index,
+            mobility: None,
+            tof_indices: self.tof_indices.get(start..end)?,
+            intensities: self.intensities.get(start..end)?,
+        };
+        Some(scan)
+    }
+
+    /// All scans in order, including empty ones, without mobility.
+    ///
+    /// Scans whose offsets do not lie within the peaks (e.g. of a corrupt
+    /// frame) are skipped as empty scans, so every scan keeps its index.
+    pub fn scans(&self) -> impl ExactSizeIterator<Item = Scan<'_>> + '_ {
+        (0..self.scan_count()).map(|scan_index| {
+            self.scan(scan_index).unwrap_or(Scan {
+                scan_index,
+                mobility: None,
+                tof_indices: &[],
+                intensities: &[],
+            })
+        })
+    }
+
+    /// Like [Frame::scans], with the mobility of each scan according to
+    /// `im_converter`.
+    pub fn scans_with_mobility<'a>(
+        &'a self,
+        im_converter: &'a Scan2ImConverter,
+    ) -> impl ExactSizeIterator<Item = Scan<'a>> + 'a {
+        self.scans().map(|scan| Scan {
+            mobility: Some(im_converter.convert(scan.scan_index as u32)),
+            ..scan
+        })
+    }
+
+    /// The mobility (1/K0) of the scan at `scan_index` according to the
+    /// calibration of this frame ([Frame::im_converter]).
+    pub fn scan_mobility(&self, scan_index: usize) -> Option<f64> {
+        let im_converter = self.im_converter.as_ref()?;
+        Some(im_converter.convert(scan_index as u32))
+    }
+
+    /// The mobility (1/K0) of every peak according to the calibration of
+    /// this frame ([Frame::im_converter]), computed on demand.
+    pub fn mobilities(&self) -> Option<Vec<f64>> {
+        let im_converter = self.im_converter.as_ref()?;
+        let mut mobilities = Vec::with_capacity(self.tof_indices.len());
+        for (scan_index, offsets) in self.scan_offsets.windows(2).enumerate() {
+            let mobility = im_converter.convert(scan_index as u32);
+            mobilities
+                .extend(std::iter::repeat_n(mobility, offsets[1] - offsets[0]));
+        }
+        Some(mobilities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterate_scans() {
+        let frame = Frame {
+            scan_offsets: vec![0, 2, 2, 3],
+            tof_indices: vec![10, 20, 30],
+            intensities: vec![1, 2, 3],
+            ..Default::default()
+        };
+        let scans: Vec<Scan> = frame.scans().collect();
+        assert_eq!(scans.len(), 3);
+        assert_eq!(scans[0].tof_indices, &[10, 20]);
+        assert!(scans[1].is_empty());
+        assert_eq!(scans[2].intensities, &[3]);
+        assert_eq!(frame.scan(2), Some(scans[2]));
+        assert_eq!(frame.scan(3), None);
+        assert_eq!(Frame::default().scans().count(), 0);
+        let im_converter = Scan2ImConverter::from_boundaries(0.5, 1.5, 3);
+        let mobilities: Vec<f64> = frame
+            .scans_with_mobility(&im_converter)
+            .map(|scan| scan.mobility.unwrap())
+            .collect();
+        assert_eq!(mobilities.len(), 3);
+        assert_eq!(frame.mobilities(), None);
+        let frame = Frame {
+            im_converter: Some(im_converter),
+            ..frame
+        };
+        assert_eq!(
+            frame.mobilities().unwrap(),
+            vec![mobilities[0], mobilities[0], mobilities[2]]
+        );
+        assert_eq!(frame.scan_mobility(1), Some(mobilities[1]));
+        assert!(mobilities[0] > mobilities[2]);
+    }
+
+    #[test]
+    fn skip_scans_outside_peaks() {
+        let frame = Frame {
+            scan_offsets: vec![0, 2, 5, 3],
+            tof_indices: vec![10, 20, 30],
+            intensities: vec![1, 2, 3],
+            ..Default::default()
+        };
+        let scans: Vec<Scan> = frame.scans().collect();
+        assert_eq!(scans.len(), 3);
+        assert_eq!(scans[0].tof_indices, &[10, 20]);
+        assert!(scans[1].is_empty() & scans[2].is_empty());
+        assert_eq!(scans[2].scan_index, 2);
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * C interface of timsrust, provided by the timsrust-ffi crate (e.g.
+ * `cargo build --release -p timsrust-ffi`), which produces a shared
+ * library (libtimsrust_ffi.so, timsrust_ffi.dll, ...).
+ *
+ * Frame indices are 0-based. Functions that can fail return a
+ * TimsrustStatus; timsrust_last_error describes the last failure on the
+ * calling thread.
+ */
+
+#ifndef TIMSRUST_H
+#define TIMSRUST_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum TimsrustStatus {
+    TIMSRUST_OK = 0,
+    TIMSRUST_NULL_POINTER = 1,
+    TIMSRUST_INVALID_PATH = 2,
+    TIMSRUST_READ_ERROR = 3,
+    TIMSRUST_INDEX_OUT_OF_BOUNDS = 4,
+    TIMSRUST_BUFFER_TOO_SMALL = 5,
+    TIMSRUST_NO_MALDI_INFO = 6,
+    /* A bug in timsrust, described by timsrust_last_error */
+    TIMSRUST_PANIC = 7,
+} TimsrustStatus;
+
+typedef struct TimsrustReader TimsrustReader;
+
+typedef struct TimsrustFrameInfo {
+    /* The Id of the frame in the Frames table */
+    size_t id;
+    double rt_in_seconds;
+    /* 1 or 2, or 0 if unknown */
+    uint8_t ms_level;
+    uint8_t msms_type;
+    uint8_t window_group;
+    double intensity_correction_factor;
+} TimsrustFrameInfo;
+
+/* Missing values are NaN (or -1 for laser_shots). */
+typedef struct TimsrustMaldiInfo {
+    int32_t pixel_x;
+    int32_t pixel_y;
+    double position_x_um;
+    double position_y_um;
+    double laser_power;
+    double laser_rep_rate;
+    int32_t laser_shots;
+} TimsrustMaldiInfo;
+
+/* Valid until the next failing call on the calling thread. */
+const char *timsrust_last_error(void);
+
+TimsrustStatus timsrust_open_reader(const char *path, TimsrustReader **reader);
+void timsrust_close_reader(TimsrustReader *reader);
+
+size_t timsrust_frame_count(const TimsrustReader *reader);
+TimsrustStatus timsrust_frame_info(const TimsrustReader *reader, size_t index,
+                                   TimsrustFrameInfo *info);
+
+/*
+ * Decode a frame into the given buffers. The number of scan offsets (scan
+ * count + 1) and peaks are always written; pass empty buffers to query
+ * them (TIMSRUST_BUFFER_TOO_SMALL is returned).
+ */
+TimsrustStatus timsrust_get_frame(const TimsrustReader *reader, size_t index,
+                                  size_t *scan_offsets,
+                                  size_t scan_offsets_capacity,
+                                  uint32_t *tof_indices, uint32_t *intensities,
+                                  size_t peak_capacity,
+                                  size_t *scan_offset_count,
+                                  size_t *peak_count);
+
+TimsrustStatus timsrust_tof_to_mz(const TimsrustReader *reader,
+                                  const uint32_t *tof_indices,
+                                  double *mz_values, size_t count);
+/* Converts scans with the mobility calibration of the frame at index. */
+TimsrustStatus timsrust_scan_to_im(const TimsrustReader *reader, size_t index,
+                                   const uint32_t *scans, double *im_values,
+                                   size_t count);
+
+bool timsrust_is_maldi(const TimsrustReader *reader);
+TimsrustStatus timsrust_maldi_info(const TimsrustReader *reader, size_t index,
+                                   TimsrustMaldiInfo *info);
+/* Writes a NUL-terminated name; length excludes the NUL. */
+TimsrustStatus timsrust_maldi_spot_name(const TimsrustReader *reader,
+                                        size_t index, char *buffer,
+                                        size_t capacity, size_t *length);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TIMSRUST_H */
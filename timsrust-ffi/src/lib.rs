@@ -0,0 +1,488 @@
+//! A C ABI to read frames, e.g. from C++, Python (ctypes) or Julia.
+//!
+//! All functions that can fail return a [TimsrustStatus] and write their
+//! results through caller-provided pointers. A description of the last
+//! error on the calling thread is returned by [timsrust_last_error]. The
+//! header `include/timsrust.h` declares all functions.
+//!
+//! Frames are copied into caller-provided buffers. If a buffer is too
+//! small, nothing is copied and only the required sizes are written, so
+//! that a caller can query them by passing empty buffers first. Decoded
+//! frames are cached, so that this does not decode a frame twice.
+//!
+//! Panics never unwind into the caller: they are reported as
+//! [TimsrustStatus::Panic] (or the documented fallback value of functions
+//! without a status) and described by [timsrust_last_error].
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use timsrust::{
+    converters::ConvertableDomain,
+    readers::{FrameReader, MetadataReader, TimsTofPathLike},
+    MSLevel, Metadata,
+};
+
+/// The number of bytes of decoded frames that a reader keeps.
+const CACHE_CAPACITY_IN_BYTES: usize = 64 << 20;
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// The result of a call through the C ABI.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimsrustStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidPath = 2,
+    ReadError = 3,
+    IndexOutOfBounds = 4,
+    BufferTooSmall = 5,
+    NoMaldiInfo = 6,
+    Panic = 7,
+}
+
+/// An opened run, created by [timsrust_open_reader] and released by
+/// [timsrust_close_reader].
+pub struct TimsrustReader {
+    frame_reader: FrameReader,
+    metadata: Metadata,
+}
+
+/// The metadata of a frame.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimsrustFrameInfo {
+    /// The `Id` of the frame in the `Frames` table
+    pub id: usize,
+    pub rt_in_seconds: f64,
+    /// 1 or 2, or 0 if unknown
+    pub ms_level: u8,
+    pub msms_type: u8,
+    pub window_group: u8,
+    pub intensity_correction_factor: f64,
+}
+
+/// The MALDI metadata of a frame. Missing values are NaN (or -1 for
+/// `laser_shots`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimsrustMaldiInfo {
+    pub pixel_x: i32,
+    pub pixel_y: i32,
+    pub position_x_um: f64,
+    pub position_y_um: f64,
+    pub laser_power: f64,
+    pub laser_rep_rate: f64,
+    pub laser_shots: i32,
+}
+
+fn fail(status: TimsrustStatus, message: impl ToString) -> TimsrustStatus {
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|error| {
+        *error.borrow_mut() = CString::new(message).unwrap_or_default()
+    });
+    status
+}
+
+/// Run the body of an entry point and return `on_panic` if it panics.
+fn guard<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        fail(TimsrustStatus::Panic, panic_message(payload.as_ref()));
+        on_panic
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default(),
+    };
+    format!("Panic: {message}")
+}
+
+/// A description of the last error on the calling thread. The string is
+/// owned by timsrust and valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn timsrust_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ptr())
+}
+
+/// Open the `.d` directory at `path` and write the reader to `reader`.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `reader` must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_open_reader(
+    path: *const c_char,
+    reader: *mut *mut TimsrustReader,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        if path.is_null() | reader.is_null() {
+            return fail(TimsrustStatus::NullPointer, "Path or reader is null");
+        }
+        let Ok(path) = CStr::from_ptr(path).to_str() else {
+            return fail(
+                TimsrustStatus::InvalidPath,
+                "Path is not valid UTF-8",
+            );
+        };
+        let path = match path.to_timstof_path() {
+            Ok(path) => path,
+            Err(error) => return fail(TimsrustStatus::InvalidPath, error),
+        };
+        let frame_reader = match FrameReader::build()
+            .with_path(&path)
+            .with_cache(CACHE_CAPACITY_IN_BYTES)
+            .finalize()
+        {
+            Ok(frame_reader) => frame_reader,
+            Err(error) => return fail(TimsrustStatus::ReadError, error),
+        };
+        let metadata = match MetadataReader::new(&path) {
+            Ok(metadata) => metadata,
+            Err(error) => return fail(TimsrustStatus::ReadError, error),
+        };
+        *reader = Box::into_raw(Box::new(TimsrustReader {
+            frame_reader,
+            metadata,
+        }));
+        TimsrustStatus::Ok
+    })
+}
+
+/// Release a reader. Null is ignored.
+///
+/// # Safety
+///
+/// `reader` must be null or a reader from [timsrust_open_reader] that was
+/// not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_close_reader(reader: *mut TimsrustReader) {
+    guard((), || {
+        if !reader.is_null() {
+            drop(Box::from_raw(reader));
+        }
+    })
+}
+
+/// The number of frames of a run, or 0 if `reader` is null.
+///
+/// # Safety
+///
+/// `reader` must be null or a valid reader.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_frame_count(
+    reader: *const TimsrustReader,
+) -> usize {
+    guard(0, || {
+        reader
+            .as_ref()
+            .map_or(0, |reader| reader.frame_reader.len())
+    })
+}
+
+/// Write the metadata of the frame at (0-based) `index` to `info`.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader and `info` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_frame_info(
+    reader: *const TimsrustReader,
+    index: usize,
+    info: *mut TimsrustFrameInfo,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        let (Some(reader), false) = (reader.as_ref(), info.is_null()) else {
+            return fail(TimsrustStatus::NullPointer, "Reader or info is null");
+        };
+        let Ok(frame) =
+            reader.frame_reader.get_frame_without_coordinates(index)
+        else {
+            return fail(TimsrustStatus::IndexOutOfBounds, "No frame at index");
+        };
+        *info = TimsrustFrameInfo {
+            id: frame.index,
+            rt_in_seconds: frame.rt_in_seconds,
+            ms_level: match frame.ms_level {
+                MSLevel::MS1 => 1,
+                MSLevel::MS2 => 2,
+                MSLevel::Unknown => 0,
+            },
+            msms_type: frame.msms_type,
+            window_group: frame.window_group,
+            intensity_correction_factor: frame.intensity_correction_factor,
+        };
+        TimsrustStatus::Ok
+    })
+}
+
+/// Decode the frame at (0-based) `index` into the given buffers.
+///
+/// The number of scan offsets (the scan count + 1) and peaks are always
+/// written to `scan_offset_count` and `peak_count`. If a buffer is too
+/// small, nothing else is written and [TimsrustStatus::BufferTooSmall] is
+/// returned.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader, the counts must be valid for writes
+/// and all buffers must be valid for writes of their capacity.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn timsrust_get_frame(
+    reader: *const TimsrustReader,
+    index: usize,
+    scan_offsets: *mut usize,
+    scan_offsets_capacity: usize,
+    tof_indices: *mut u32,
+    intensities: *mut u32,
+    peak_capacity: usize,
+    scan_offset_count: *mut usize,
+    peak_count: *mut usize,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        let Some(reader) = reader.as_ref() else {
+            return fail(TimsrustStatus::NullPointer, "Reader is null");
+        };
+        if scan_offset_count.is_null() | peak_count.is_null() {
+            return fail(TimsrustStatus::NullPointer, "Counts are null");
+        }
+        if index >= reader.frame_reader.len() {
+            return fail(TimsrustStatus::IndexOutOfBounds, "No frame at index");
+        }
+        let frame = match reader.frame_reader.get_cached(index) {
+            Ok(frame) => frame,
+            Err(error) => return fail(TimsrustStatus::ReadError, error),
+        };
+        *scan_offset_count = frame.scan_offsets.len();
+        *peak_count = frame.tof_indices.len();
+        if (scan_offsets_capacity < frame.scan_offsets.len())
+            | (peak_capacity < frame.tof_indices.len())
+        {
+            return fail(
+                TimsrustStatus::BufferTooSmall,
+                "Buffers are too small",
+            );
+        }
+        if (scan_offsets.is_null() & !frame.scan_offsets.is_empty())
+            | ((tof_indices.is_null() | intensities.is_null())
+                & !frame.tof_indices.is_empty())
+        {
+            return fail(TimsrustStatus::NullPointer, "Buffers are null");
+        }
+        copy_to(&frame.scan_offsets, scan_offsets);
+        copy_to(&frame.tof_indices, tof_indices);
+        copy_to(&frame.intensities, intensities);
+        TimsrustStatus::Ok
+    })
+}
+
+unsafe fn copy_to<T: Copy>(values: &[T], buffer: *mut T) {
+    if !values.is_empty() {
+        ptr::copy_nonoverlapping(values.as_ptr(), buffer, values.len());
+    }
+}
+
+/// Convert `count` TOF indices to m/z values.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader and both buffers must be valid for
+/// `count` values.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_tof_to_mz(
+    reader: *const TimsrustReader,
+    tof_indices: *const u32,
+    mz_values: *mut f64,
+    count: usize,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        let Some(reader) = reader.as_ref() else {
+            return fail(TimsrustStatus::NullPointer, "Reader is null");
+        };
+        convert(&reader.metadata.mz_converter, tof_indices, mz_values, count)
+    })
+}
+
+/// Convert `count` scan indices of the frame at (0-based) `index` to ion
+/// mobilities (1/K0), with the mobility calibration of that frame.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader and both buffers must be valid for
+/// `count` values.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_scan_to_im(
+    reader: *const TimsrustReader,
+    index: usize,
+    scans: *const u32,
+    im_values: *mut f64,
+    count: usize,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        let Some(reader) = reader.as_ref() else {
+            return fail(TimsrustStatus::NullPointer, "Reader is null");
+        };
+        let Ok(frame) =
+            reader.frame_reader.get_frame_without_coordinates(index)
+        else {
+            return fail(TimsrustStatus::IndexOutOfBounds, "No frame at index");
+        };
+        let im_converter =
+            frame.im_converter.unwrap_or(reader.metadata.im_converter);
+        convert(&im_converter, scans, im_values, count)
+    })
+}
+
+unsafe fn convert(
+    converter: &impl ConvertableDomain,
+    values: *const u32,
+    converted: *mut f64,
+    count: usize,
+) -> TimsrustStatus {
+    if count == 0 {
+        return TimsrustStatus::Ok;
+    }
+    if values.is_null() | converted.is_null() {
+        return fail(TimsrustStatus::NullPointer, "Buffers are null");
+    }
+    let values = slice::from_raw_parts(values, count);
+    let converted = slice::from_raw_parts_mut(converted, count);
+    for (value, converted) in values.iter().zip(converted) {
+        *converted = converter.convert(*value);
+    }
+    TimsrustStatus::Ok
+}
+
+/// Whether the run is a MALDI imaging run, or false if `reader` is null.
+///
+/// # Safety
+///
+/// `reader` must be null or a valid reader.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_is_maldi(
+    reader: *const TimsrustReader,
+) -> bool {
+    guard(false, || {
+        reader
+            .as_ref()
+            .is_some_and(|reader| reader.frame_reader.is_maldi())
+    })
+}
+
+/// Write the MALDI metadata of the frame at (0-based) `index` to `info`.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader and `info` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_maldi_info(
+    reader: *const TimsrustReader,
+    index: usize,
+    info: *mut TimsrustMaldiInfo,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        let (Some(reader), false) = (reader.as_ref(), info.is_null()) else {
+            return fail(TimsrustStatus::NullPointer, "Reader or info is null");
+        };
+        let Ok(frame) =
+            reader.frame_reader.get_frame_without_coordinates(index)
+        else {
+            return fail(TimsrustStatus::IndexOutOfBounds, "No frame at index");
+        };
+        let Some(maldi) = frame.maldi_info else {
+            return fail(
+                TimsrustStatus::NoMaldiInfo,
+                "Frame has no MALDI info",
+            );
+        };
+        *info = TimsrustMaldiInfo {
+            pixel_x: maldi.pixel_x,
+            pixel_y: maldi.pixel_y,
+            position_x_um: maldi.position_x_um.unwrap_or(f64::NAN),
+            position_y_um: maldi.position_y_um.unwrap_or(f64::NAN),
+            laser_power: maldi.laser_power.unwrap_or(f64::NAN),
+            laser_rep_rate: maldi.laser_rep_rate.unwrap_or(f64::NAN),
+            laser_shots: maldi.laser_shots.unwrap_or(-1),
+        };
+        TimsrustStatus::Ok
+    })
+}
+
+/// Write the NUL-terminated MALDI spot name of the frame at (0-based)
+/// `index` to `buffer`. The length of the name (without NUL) is always
+/// written to `length`.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader, `length` must be valid for writes and
+/// `buffer` must be valid for writes of `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn timsrust_maldi_spot_name(
+    reader: *const TimsrustReader,
+    index: usize,
+    buffer: *mut c_char,
+    capacity: usize,
+    length: *mut usize,
+) -> TimsrustStatus {
+    guard(TimsrustStatus::Panic, || {
+        let (Some(reader), false) = (reader.as_ref(), length.is_null()) else {
+            return fail(
+                TimsrustStatus::NullPointer,
+                "Reader or length is null",
+            );
+        };
+        let Ok(frame) =
+            reader.frame_reader.get_frame_without_coordinates(index)
+        else {
+            return fail(TimsrustStatus::IndexOutOfBounds, "No frame at index");
+        };
+        let Some(maldi) = frame.maldi_info else {
+            return fail(
+                TimsrustStatus::NoMaldiInfo,
+                "Frame has no MALDI info",
+            );
+        };
+        let name = maldi.spot_name.as_bytes();
+        *length = name.len();
+        if capacity <= name.len() {
+            return fail(TimsrustStatus::BufferTooSmall, "Buffer is too small");
+        }
+        if buffer.is_null() {
+            return fail(TimsrustStatus::NullPointer, "Buffer is null");
+        }
+        copy_to(name, buffer as *mut u8);
+        *buffer.add(name.len()) = 0;
+        TimsrustStatus::Ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_panics_as_status() {
+        let status = guard(TimsrustStatus::Panic, || -> TimsrustStatus {
+            panic!("Decoding failed")
+        });
+        assert_eq!(status, TimsrustStatus::Panic);
+        let message = unsafe { CStr::from_ptr(timsrust_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "Panic: Decoding failed");
+        assert_eq!(guard(0, || 1), 1);
+    }
+}
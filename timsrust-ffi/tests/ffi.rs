@@ -0,0 +1,156 @@
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+mod tests {
+    use std::{
+        ffi::{CStr, CString},
+        ptr,
+    };
+
+    use super::common::{synthetic_frame, SyntheticRun};
+    use timsrust::{
+        converters::ConvertableDomain, readers::FrameReader, MaldiInfo,
+    };
+    use timsrust_ffi::*;
+
+    fn open(path: &std::path::Path) -> *mut TimsrustReader {
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        let mut reader = ptr::null_mut();
+        let status =
+            unsafe { timsrust_open_reader(path.as_ptr(), &mut reader) };
+        assert_eq!(status, TimsrustStatus::Ok);
+        reader
+    }
+
+    #[test]
+    fn ffi_frames() {
+        let run = SyntheticRun::new("timsrust_ffi_frames_test.d")
+            .synthetic_frame(0, 20)
+            .synthetic_frame(8, 20)
+            .write();
+        let expected = FrameReader::new(&run).unwrap().get(1).unwrap();
+        let reader = open(&run);
+        unsafe {
+            assert_eq!(timsrust_frame_count(reader), 2);
+            let mut info = TimsrustFrameInfo::default();
+            assert_eq!(
+                timsrust_frame_info(reader, 1, &mut info),
+                TimsrustStatus::Ok
+            );
+            assert_eq!(info.id, 2);
+            assert_eq!(info.ms_level, 2);
+            let (mut scan_offset_count, mut peak_count) = (0, 0);
+            let status = timsrust_get_frame(
+                reader,
+                1,
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                &mut scan_offset_count,
+                &mut peak_count,
+            );
+            assert_eq!(status, TimsrustStatus::BufferTooSmall);
+            assert_eq!(scan_offset_count, 21);
+            assert_eq!(peak_count, expected.tof_indices.len());
+            let mut scan_offsets = vec![0; scan_offset_count];
+            let mut tof_indices = vec![0; peak_count];
+            let mut intensities = vec![0; peak_count];
+            let status = timsrust_get_frame(
+                reader,
+                1,
+                scan_offsets.as_mut_ptr(),
+                scan_offsets.len(),
+                tof_indices.as_mut_ptr(),
+                intensities.as_mut_ptr(),
+                peak_count,
+                &mut scan_offset_count,
+                &mut peak_count,
+            );
+            assert_eq!(status, TimsrustStatus::Ok);
+            assert_eq!(scan_offsets, expected.scan_offsets);
+            assert_eq!(tof_indices, expected.tof_indices);
+            assert_eq!(intensities, expected.intensities);
+            let mut mz_values = vec![0.0; peak_count];
+            let status = timsrust_tof_to_mz(
+                reader,
+                tof_indices.as_ptr(),
+                mz_values.as_mut_ptr(),
+                peak_count,
+            );
+            assert_eq!(status, TimsrustStatus::Ok);
+            assert!(mz_values.iter().all(|&mz| mz >= 100.0));
+            let scans = [0, 3];
+            let mut im_values = [0.0; 2];
+            let status = timsrust_scan_to_im(
+                reader,
+                1,
+                scans.as_ptr(),
+                im_values.as_mut_ptr(),
+                scans.len(),
+            );
+            assert_eq!(status, TimsrustStatus::Ok);
+            assert_eq!(
+                im_values[1],
+                expected.im_converter.unwrap().convert(3.0)
+            );
+            assert_eq!(
+                timsrust_frame_info(reader, 2, &mut info),
+                TimsrustStatus::IndexOutOfBounds
+            );
+            assert!(!CStr::from_ptr(timsrust_last_error()).is_empty());
+            assert!(!timsrust_is_maldi(reader));
+            timsrust_close_reader(reader);
+        }
+    }
+
+    #[test]
+    fn ffi_maldi_info() {
+        let mut frame = synthetic_frame(0, 10, 0.1, 0);
+        frame.maldi_info = Some(MaldiInfo {
+            spot_name: "R00X003Y004".to_string(),
+            pixel_x: 3,
+            pixel_y: 4,
+            laser_shots: Some(100),
+            ..Default::default()
+        });
+        let run = SyntheticRun::new("timsrust_ffi_maldi_test.d")
+            .frame(frame)
+            .write();
+        let reader = open(&run);
+        unsafe {
+            assert!(timsrust_is_maldi(reader));
+            let mut info = TimsrustMaldiInfo::default();
+            assert_eq!(
+                timsrust_maldi_info(reader, 0, &mut info),
+                TimsrustStatus::Ok
+            );
+            assert_eq!((info.pixel_x, info.pixel_y), (3, 4));
+            assert_eq!(info.laser_shots, 100);
+            assert!(info.laser_power.is_nan());
+            let mut length = 0;
+            let mut name = vec![0; 4];
+            let status = timsrust_maldi_spot_name(
+                reader,
+                0,
+                name.as_mut_ptr(),
+                name.len(),
+                &mut length,
+            );
+            assert_eq!(status, TimsrustStatus::BufferTooSmall);
+            let mut name = vec![0; length + 1];
+            let status = timsrust_maldi_spot_name(
+                reader,
+                0,
+                name.as_mut_ptr(),
+                name.len(),
+                &mut length,
+            );
+            assert_eq!(status, TimsrustStatus::Ok);
+            let name = CStr::from_ptr(name.as_ptr());
+            assert_eq!(name.to_str().unwrap(), "R00X003Y004");
+            timsrust_close_reader(reader);
+        }
+    }
+}